@@ -3,7 +3,7 @@
 #![windows_subsystem = "console"]
 use std::fmt::Display;
 
-use aer::{log_data, logging};
+use aer::{colors, log_data, logging};
 use aer_upd::data::chocolatey::ChocoVersion;
 use aer_upd::data::{FixVersion, SemVersion};
 #[cfg(feature = "human")]
@@ -11,7 +11,7 @@ use human_panic::setup_panic;
 use lazy_static::lazy_static;
 use log::{error, info};
 use structopt::StructOpt;
-use yansi::{Color, Paint, Style};
+use yansi::{Color, Style};
 
 log_data! {"aer-ver"}
 
@@ -43,14 +43,8 @@ fn main() {
     #[cfg(feature = "human")]
     setup_panic!();
     let args = {
-        let mut args = Arguments::from_args();
-        if std::env::var("NO_COLOR").unwrap_or_default().to_lowercase() == "true" {
-            args.no_color = true;
-        }
-
-        if args.no_color || (cfg!(windows) && !Paint::enable_windows_ascii()) {
-            Paint::disable();
-        }
+        let args = Arguments::from_args();
+        colors::configure_colors(args.no_color);
         args
     };
 
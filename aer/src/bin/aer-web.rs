@@ -5,10 +5,11 @@
 use std::fmt::Display;
 use std::path::PathBuf;
 
-use aer::{log_data, logging, ChecksumType};
+use aer::request::RequestData;
+use aer::{colors, log_data, logging, ChecksumType};
 use aer_upd::data::Url;
 use aer_upd::web::errors::WebError;
-use aer_upd::web::{LinkElement, LinkType, ResponseType, WebRequest, WebResponse};
+use aer_upd::web::{LinkElement, LinkType, MatchTarget, ResponseType, WebRequest, WebResponse};
 #[cfg(feature = "human")]
 use human_bytes::human_bytes;
 #[cfg(feature = "human")]
@@ -16,7 +17,7 @@ use human_panic::setup_panic;
 use lazy_static::lazy_static;
 use log::{error, info, warn};
 use structopt::StructOpt;
-use yansi::{Color, Paint, Style};
+use yansi::{Color, Style};
 
 log_data! { "aer-web" }
 
@@ -37,6 +38,17 @@ struct ParseArguments {
     /// The regular expression to use when parsing the specified `url`.
     #[structopt(long, short)]
     regex: Option<String>,
+
+    /// Match the regular expression against the text of the links instead of
+    /// the link target.
+    #[structopt(long)]
+    match_text: bool,
+
+    /// The css selector to limit the links on the page to, only links
+    /// matching the selector or inside a matching element are parsed (*ie:
+    /// `table.downloads a`*).
+    #[structopt(long, short)]
+    selector: Option<String>,
 }
 
 #[derive(StructOpt)]
@@ -106,6 +118,9 @@ struct Arguments {
     #[structopt(flatten)]
     log: LogData,
 
+    #[structopt(flatten)]
+    request: RequestData,
+
     /// Disable the usage of colors when outputting text to the console.
     #[structopt(long, global = true)]
     no_color: bool,
@@ -115,20 +130,14 @@ fn main() {
     #[cfg(feature = "human")]
     setup_panic!();
     let args = {
-        let mut args = Arguments::from_args();
-        if std::env::var("NO_COLOR").unwrap_or_default().to_lowercase() == "true" {
-            args.no_color = true;
-        }
-
-        if args.no_color || (cfg!(windows) && !Paint::enable_windows_ascii()) {
-            Paint::disable();
-        }
+        let args = Arguments::from_args();
+        colors::configure_colors(args.no_color);
         args
     };
 
     logging::setup_logging(&args.log).expect("Unable to configure logging of the application!");
 
-    let request = WebRequest::create();
+    let request = args.request.create_request();
     match args.cmd {
         Commands::Parse(args) => parse_cmd(request, args),
         Commands::Download(args) => download_cmd(request, args),
@@ -136,7 +145,13 @@ fn main() {
 }
 
 fn parse_cmd(request: WebRequest, args: ParseArguments) {
-    match parse_website(request, args.url, args.regex) {
+    let target = if args.match_text {
+        MatchTarget::Text
+    } else {
+        MatchTarget::Link
+    };
+
+    match parse_website(request, args.url, args.regex, args.selector, target) {
         Ok((parent, links)) => {
             info!(
                 "Successfully parsed '{}'",
@@ -199,11 +214,16 @@ fn parse_website(
     request: WebRequest,
     url: Url,
     regex: Option<String>,
+    selector: Option<String>,
+    target: MatchTarget,
 ) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
-    let response = request.get_html_response(url.as_str())?;
+    let mut response = request.get_html_response(url.as_str())?;
+    if let Some(ref selector) = selector {
+        response = response.with_link_selector(selector);
+    }
 
     if let Some(ref regex) = regex {
-        response.read(Some(regex))
+        response.read_matching(Some(regex), target)
     } else {
         response.read(None)
     }
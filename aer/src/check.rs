@@ -0,0 +1,210 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the check of wether a newer version is available for a package,
+//! without running the rest of the update process. Only the parse urls of the
+//! package are requested, no files are downloaded and none of the package
+//! files are changed.
+
+use std::cmp::Ordering;
+use std::path::Path;
+
+use aer_upd::data::*;
+use aer_upd::updaters::chocolatey::get_nuspec_version;
+use aer_upd::updaters::scoop::get_manifest_version;
+use aer_upd::validators;
+use aer_upd::web::WebRequest;
+use log::info;
+use serde::Serialize;
+
+use crate::errors::UpdateError;
+use crate::update::{find_version, get_manifest_path, get_nuspec_path, UpdateOptions};
+
+/// The versions that was found by a single updater of a package.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckedVersion {
+    /// The name of the updater the versions was found by.
+    pub updater: &'static str,
+    /// The version in the nuspec file or manifest of the package, or [None]
+    /// if the version could not be read.
+    pub current_version: Option<String>,
+    /// The latest version that was found, or [None] if no version was found.
+    pub latest_version: Option<String>,
+    /// Wether the latest version is newer than the current version.
+    pub update_available: bool,
+}
+
+/// Finds the latest version of the specified package data for every updater,
+/// and compares it against the current version of the package, where
+/// `package_dir` is the directory that the paths in the package data are
+/// relative to.
+///
+/// ## Errors
+///
+/// Returns an error when a regex of the package could not be compiled, or
+/// when the parse urls of the package could not be read.
+pub fn check_package(
+    data: &PackageData,
+    package_dir: &Path,
+    request: &WebRequest,
+    options: &UpdateOptions,
+) -> Result<Vec<CheckedVersion>, UpdateError> {
+    if let Some(violation) = validators::find_invalid_regexes(data).into_iter().next() {
+        return Err(UpdateError::PackageRegex {
+            id: data.metadata().id().to_owned(),
+            violation,
+        });
+    }
+
+    let mut results = vec![];
+
+    if data.updater().has_chocolatey() {
+        let choco = data.updater().chocolatey();
+        let nuspec = get_nuspec_path(package_dir, data, &choco);
+        let latest = find_version(request, data, package_dir, &choco, options)?;
+        let current = std::fs::read_to_string(&nuspec)
+            .ok()
+            .and_then(|content| get_nuspec_version(&content).ok());
+
+        results.push(checked_version(
+            "chocolatey",
+            current,
+            latest.map(|version| version.to_chocolatey()),
+        ));
+    }
+
+    if data.updater().has_scoop() {
+        let scoop = data.updater().scoop();
+        let manifest = get_manifest_path(package_dir, data, &scoop);
+        let latest = find_version(request, data, package_dir, &scoop.link_data(), options)?;
+        let current = std::fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|content| get_manifest_version(&content).ok());
+
+        results.push(checked_version(
+            "scoop",
+            current,
+            latest.map(|version| version.to_string()),
+        ));
+    }
+
+    Ok(results)
+}
+
+fn checked_version(
+    updater: &'static str,
+    current_version: Option<String>,
+    latest_version: Option<String>,
+) -> CheckedVersion {
+    let update_available =
+        is_update_available(current_version.as_deref(), latest_version.as_deref());
+    if update_available {
+        info!(
+            "The {} version {} is available (current: {})",
+            updater,
+            latest_version.as_deref().unwrap_or_default(),
+            current_version.as_deref().unwrap_or("none")
+        );
+    }
+
+    CheckedVersion {
+        updater,
+        current_version,
+        latest_version,
+        update_available,
+    }
+}
+
+/// Checks wether the latest version is newer than the current version. The
+/// versions are compared as versions when both can be parsed, and are
+/// otherwise treated as newer when they are different. An update is always
+/// available when there is no current version, and never available when no
+/// latest version was found.
+///
+/// ## Examples
+///
+/// ```
+/// use aer::check::is_update_available;
+///
+/// assert!(is_update_available(Some("1.0.0"), Some("1.1.0")));
+/// assert!(!is_update_available(Some("1.1.0"), Some("1.0.0")));
+/// assert!(is_update_available(None, Some("1.0.0")));
+/// assert!(!is_update_available(Some("1.0.0"), None));
+/// ```
+pub fn is_update_available(current_version: Option<&str>, latest_version: Option<&str>) -> bool {
+    match (current_version, latest_version) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(current), Some(latest)) => {
+            match (Versions::parse(current), Versions::parse(latest)) {
+                (Ok(current), Ok(latest)) => match latest.partial_cmp(&current) {
+                    Some(ordering) => ordering == Ordering::Greater,
+                    None => current != latest,
+                },
+                _ => current != latest,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(Some("1.0.0"), Some("1.0.0"), false)]
+    #[case(Some("1.0.0"), Some("1.0.1"), true)]
+    #[case(Some("1.10.0"), Some("1.9.0"), false)]
+    #[case(Some("1.0.0-beta"), Some("1.0.0"), true)]
+    #[case(Some("unknown"), Some("1.0.0"), true)]
+    #[case(None, Some("1.0.0"), true)]
+    #[case(Some("1.0.0"), None, false)]
+    #[case(None, None, false)]
+    fn is_update_available_should_compare_versions(
+        #[case] current: Option<&str>,
+        #[case] latest: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_update_available(current, latest), expected);
+    }
+
+    #[test]
+    fn check_package_should_return_error_on_invalid_regex() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().set_chocolatey({
+            let mut choco = chocolatey::ChocolateyUpdaterData::new();
+            choco.add_regex("arch64", "[invalid");
+            choco
+        });
+
+        let actual = check_package(
+            &data,
+            Path::new("."),
+            &WebRequest::create(),
+            &UpdateOptions::default(),
+        );
+
+        assert!(matches!(actual, Err(UpdateError::PackageRegex { .. })));
+    }
+
+    #[test]
+    fn check_package_should_return_error_without_parse_url() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().set_chocolatey({
+            let mut choco = chocolatey::ChocolateyUpdaterData::new();
+            choco.add_regex("arch64", r"setup-(?P<version>[\d\.]+)\.exe$");
+            choco
+        });
+
+        let actual = check_package(
+            &data,
+            Path::new("."),
+            &WebRequest::create(),
+            &UpdateOptions::default(),
+        );
+
+        assert!(matches!(actual, Err(UpdateError::NoParseUrl { .. })));
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for deciding wether colors should be used when
+//! outputting text to the console.
+
+use yansi::Paint;
+
+/// Enables or disables the usage of colors, based on the `--no-color` flag
+/// and the `NO_COLOR` and `FORCE_COLOR` environment variables.
+pub fn configure_colors(no_color: bool) {
+    let no_color_env = std::env::var("NO_COLOR").ok();
+    let force_color_env = std::env::var("FORCE_COLOR").ok();
+    let enabled = colors_enabled(
+        no_color,
+        no_color_env.as_deref(),
+        force_color_env.as_deref(),
+    );
+
+    if enabled
+        && (!cfg!(windows)
+            || Paint::enable_windows_ascii()
+            || is_forced(force_color_env.as_deref()))
+    {
+        Paint::enable();
+    } else {
+        Paint::disable();
+    }
+}
+
+/// Checks wether colors should be used. The `--no-color` flag always takes
+/// precedence, followed by the `FORCE_COLOR` environment variable and then
+/// the `NO_COLOR` environment variable. Any non-empty value of `NO_COLOR`
+/// disables colors, while `FORCE_COLOR` enables colors for any non-empty
+/// value other than `0` or `false`.
+pub fn colors_enabled(
+    no_color: bool,
+    no_color_env: Option<&str>,
+    force_color_env: Option<&str>,
+) -> bool {
+    if no_color {
+        false
+    } else if force_color_env.map_or(false, |v| !v.is_empty()) {
+        is_forced(force_color_env)
+    } else {
+        no_color_env.map_or(true, |v| v.is_empty())
+    }
+}
+
+fn is_forced(force_color_env: Option<&str>) -> bool {
+    match force_color_env {
+        Some(value) => !(value.is_empty() || value == "0" || value.eq_ignore_ascii_case("false")),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(false, None, None, true)]
+    #[case(false, Some(""), None, true)]
+    #[case(false, Some("true"), None, false)]
+    #[case(false, Some("1"), None, false)]
+    #[case(false, Some("0"), None, false)]
+    #[case(false, Some("1"), Some("1"), true)]
+    #[case(false, None, Some("true"), true)]
+    #[case(false, Some("1"), Some(""), false)]
+    #[case(false, None, Some("0"), false)]
+    #[case(false, None, Some("false"), false)]
+    #[case(true, None, None, false)]
+    #[case(true, None, Some("1"), false)]
+    fn colors_enabled_should_follow_flags_and_environment(
+        #[case] no_color: bool,
+        #[case] no_color_env: Option<&str>,
+        #[case] force_color_env: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            colors_enabled(no_color, no_color_env, force_color_env),
+            expected
+        );
+    }
+}
@@ -0,0 +1,486 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the reading of the configuration file, which holds the defaults
+//! of the global options (*ie: the timeout or the cache directory*) shared by
+//! every invocation of `aer`.
+//!
+//! The configuration file is a `TOML` file where each key is the name of an
+//! option, using underscores instead of dashes. The value of an option is
+//! resolved in the following order, where the first one that is found is
+//! used:
+//!
+//! 1. The option specified on the command line (*ie: `--timeout 60`*).
+//! 2. The environment variable of the option (*ie: `AER_TIMEOUT`*).
+//! 3. The key in the configuration file (*ie: `timeout = 60`*).
+//! 4. The built-in default of the option.
+//!
+//! The configuration file is read from the path specified with `--config`
+//! or the `AER_CONFIG` environment variable, and otherwise from
+//! `aer/config.toml` in the configuration directory of the user (*ie:
+//! `~/.config/aer/config.toml` or `%APPDATA%\aer\config.toml`*) when it
+//! exists.
+//!
+//! Options that weaken the security of requests (*ie:
+//! `--danger-accept-invalid-certs`*) can not be set in the configuration
+//! file, and must be specified for every run.
+//!
+//! ## Examples
+//!
+//! ```toml
+//! timeout = 60
+//! jobs = 4
+//! cache_dir = "/var/cache/aer"
+//! user_agent = "Mozilla/5.0"
+//! proxy = "http://proxy.example.org:8080"
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+use structopt::clap::ArgMatches;
+use toml::Value;
+
+/// The environment variable holding the path to the configuration file.
+pub const CONFIG_ENV: &str = "AER_CONFIG";
+
+/// The keys that are supported in the configuration file, and the
+/// environment variable of the option that each key sets the default of.
+pub const CONFIG_KEYS: &[(&str, &str)] = &[
+    ("accept_language", "AER_ACCEPT_LANGUAGE"),
+    ("cache_dir", "AER_CACHE_DIR"),
+    ("download_jobs", "AER_DOWNLOAD_JOBS"),
+    ("jobs", "AER_JOBS"),
+    ("log_file", "AER_LOG_PATH"),
+    ("log_level", "AER_LOG_LEVEL"),
+    ("max_host_requests", "AER_MAX_HOST_REQUESTS"),
+    ("max_links", "AER_MAX_LINKS"),
+    ("max_redirects", "AER_MAX_REDIRECTS"),
+    ("max_requests", "AER_MAX_REQUESTS"),
+    ("output_dir", "AER_OUTPUT_DIR"),
+    ("proxy", "AER_PROXY"),
+    ("rate_limit", "AER_RATE_LIMIT"),
+    ("retries", "AER_RETRIES"),
+    ("retry_delay", "AER_RETRY_DELAY"),
+    ("timeout", "AER_TIMEOUT"),
+    ("tls_min_version", "AER_TLS_MIN_VERSION"),
+    ("user_agent", "AER_USER_AGENT"),
+];
+
+/// Gets the name of the argument in the parsed arguments and the long name
+/// of the option that the specified key sets the default of, which is the
+/// name of the key using dashes instead of underscores for most keys.
+fn option_names(key: &str) -> (&str, String) {
+    match key {
+        "log_file" => ("path", "log-file".into()),
+        "log_level" => ("level", "log-level".into()),
+        "output_dir" => ("output", "output".into()),
+        _ => (key, key.replace('_', "-")),
+    }
+}
+
+/// Gets the sub commands the option of the specified key is available for,
+/// where the options of most keys are available for every sub command.
+fn option_commands(key: &str) -> &'static [&'static str] {
+    match key {
+        "download_jobs" | "jobs" => &["update"],
+        "output_dir" => &["update", "verify"],
+        _ => &[],
+    }
+}
+
+/// The errors that can occur while reading the configuration file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read.
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The configuration file is not a valid `TOML` file.
+    Parse {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
+    /// The configuration file contains keys that are not supported.
+    UnknownKeys { path: PathBuf, keys: Vec<String> },
+    /// The value of a key is not a string or a number.
+    InvalidValue { path: PathBuf, key: String },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(
+                f,
+                "Unable to read the configuration file '{}': {}",
+                path.display(),
+                error
+            ),
+            Self::Parse { path, error } => write!(
+                f,
+                "Unable to parse the configuration file '{}': {}",
+                path.display(),
+                error
+            ),
+            Self::UnknownKeys { path, keys } => {
+                let supported: Vec<&str> = CONFIG_KEYS.iter().map(|(key, _)| *key).collect();
+                write!(
+                    f,
+                    "The configuration file '{}' contains the unknown key(s) '{}', the \
+                     supported keys are: {}",
+                    path.display(),
+                    keys.join("', '"),
+                    supported.join(", ")
+                )
+            }
+            Self::InvalidValue { path, key } => write!(
+                f,
+                "The value of '{}' in the configuration file '{}' must be a string or a number!",
+                key,
+                path.display()
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Parse { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The defaults of the global options read from a configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFile {
+    /// The path of the configuration file.
+    pub path: PathBuf,
+    values: BTreeMap<&'static str, String>,
+}
+
+impl ConfigFile {
+    /// Reads the configuration file at the specified path.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the file could not be read, is not a valid `TOML`
+    /// file or contains keys that are not supported.
+    pub fn read(path: &Path) -> Result<ConfigFile, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|error| ConfigError::Io {
+            path: path.to_owned(),
+            error,
+        })?;
+
+        Self::parse(path, &content)
+    }
+
+    /// Parses the specified content of the configuration file at the
+    /// specified path.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the content is not valid `TOML`, contains keys
+    /// that are not supported, or the value of a key is not a string or a
+    /// number.
+    pub fn parse(path: &Path, content: &str) -> Result<ConfigFile, ConfigError> {
+        let table = match content.parse::<Value>() {
+            Ok(Value::Table(table)) => table,
+            Ok(_) => unreachable!("a toml document is always a table"),
+            Err(error) => {
+                return Err(ConfigError::Parse {
+                    path: path.to_owned(),
+                    error,
+                })
+            }
+        };
+
+        let unknown: Vec<String> = table
+            .keys()
+            .filter(|key| !CONFIG_KEYS.iter().any(|(name, _)| name == key))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            return Err(ConfigError::UnknownKeys {
+                path: path.to_owned(),
+                keys: unknown,
+            });
+        }
+
+        let mut values = BTreeMap::new();
+        for (name, _) in CONFIG_KEYS {
+            let value = match table.get(*name) {
+                Some(Value::String(value)) => value.clone(),
+                Some(Value::Integer(value)) => value.to_string(),
+                Some(Value::Float(value)) => value.to_string(),
+                Some(_) => {
+                    return Err(ConfigError::InvalidValue {
+                        path: path.to_owned(),
+                        key: (*name).into(),
+                    })
+                }
+                None => continue,
+            };
+            values.insert(*name, value);
+        }
+
+        Ok(ConfigFile {
+            path: path.to_owned(),
+            values,
+        })
+    }
+
+    /// Gets the value of the specified key, if it have been set.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Gets the keys and values of the configuration file for the options
+    /// that are available for the specified sub command, where the option is
+    /// not already set according to `is_set`. The key and the environment
+    /// variable of the option are passed to `is_set`.
+    pub fn defaults<F: Fn(&str, &str) -> bool>(
+        &self,
+        command: Option<&str>,
+        is_set: F,
+    ) -> Vec<(&'static str, &str)> {
+        CONFIG_KEYS
+            .iter()
+            .filter(|(key, _)| {
+                let commands = option_commands(key);
+                commands.is_empty() || command.map_or(false, |command| commands.contains(&command))
+            })
+            .filter(|(key, env)| !is_set(key, env))
+            .filter_map(|(key, _)| self.value(key).map(|value| (*key, value)))
+            .collect()
+    }
+
+    /// Merges the values of the configuration file into the specified
+    /// arguments, where the first argument is the name of the executable and
+    /// `matches` are the arguments parsed without the configuration file. The
+    /// values are added as options (*ie: `--timeout=60`*) for the options
+    /// that are neither specified in the arguments nor with their environment
+    /// variable, before any `--` separator in the arguments.
+    ///
+    /// The returned arguments needs to be parsed again to get the values of
+    /// the configuration file, the environment of the process is not changed
+    /// which means the values are not inherited by the hooks that are run.
+    pub fn merge_args(&self, args: &[OsString], matches: &ArgMatches<'_>) -> Vec<OsString> {
+        let command = matches.subcommand_name();
+        let command_matches = command.and_then(|command| matches.subcommand_matches(command));
+        let is_set = |key: &str, env: &str| {
+            let (arg, _) = option_names(key);
+            std::env::var_os(env).is_some()
+                || matches.occurrences_of(arg) > 0
+                || command_matches.map_or(false, |matches| matches.occurrences_of(arg) > 0)
+        };
+        let defaults = self
+            .defaults(command, is_set)
+            .into_iter()
+            .map(|(key, value)| OsString::from(format!("--{}={}", option_names(key).1, value)));
+        let separator = args
+            .iter()
+            .skip(1)
+            .position(|arg| arg == "--")
+            .map_or(args.len(), |index| index + 1);
+
+        let mut merged = args[..separator].to_vec();
+        merged.extend(defaults);
+        merged.extend_from_slice(&args[separator..]);
+
+        merged
+    }
+}
+
+/// Gets the path of the default configuration file, `aer/config.toml` in the
+/// configuration directory of the user. The file is not required to exist.
+pub fn default_config_path() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty());
+    let dir = if cfg!(windows) {
+        env_dir("APPDATA").map(PathBuf::from)
+    } else {
+        env_dir("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env_dir("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    dir.map(|dir| dir.join("aer").join("config.toml"))
+}
+
+/// Gets the path specified with the `--config` option in the specified
+/// arguments, where the first argument is the name of the executable.
+pub fn config_arg(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--" {
+            break;
+        } else if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    None
+}
+
+/// Finds and reads the configuration file, using the path specified with
+/// `--config` in the arguments, the path in the `AER_CONFIG` environment
+/// variable or the [default path](default_config_path) in that order.
+///
+/// ## Errors
+///
+/// Returns an error if the configuration file could not be read. A missing
+/// file is only an error when the path have been explicitly specified.
+pub fn find_config(args: &[OsString]) -> Result<Option<ConfigFile>, ConfigError> {
+    let explicit = config_arg(args).or_else(|| {
+        std::env::var_os(CONFIG_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    });
+
+    match explicit {
+        Some(path) => ConfigFile::read(&path).map(Some),
+        None => match default_config_path() {
+            Some(path) if path.is_file() => ConfigFile::read(&path).map(Some),
+            _ => Ok(None),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<OsString> {
+        values.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn parse_should_read_supported_values() {
+        let config = ConfigFile::parse(
+            Path::new("config.toml"),
+            "timeout = 60\nrate_limit = 1.5\nuser_agent = \"Mozilla/5.0\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.value("timeout"), Some("60"));
+        assert_eq!(config.value("rate_limit"), Some("1.5"));
+        assert_eq!(config.value("user_agent"), Some("Mozilla/5.0"));
+        assert_eq!(config.value("proxy"), None);
+    }
+
+    #[test]
+    fn parse_should_return_error_on_unknown_keys() {
+        let actual = ConfigFile::parse(Path::new("config.toml"), "timout = 60\njob = 2\n");
+
+        assert!(matches!(
+            actual,
+            Err(ConfigError::UnknownKeys { ref keys, .. }) if keys == &["job", "timout"]
+        ));
+    }
+
+    #[rstest]
+    #[case("jobs = true")]
+    #[case("proxy = [\"http://proxy.example.org\"]")]
+    #[case("[timeout]\nvalue = 60")]
+    fn parse_should_return_error_on_invalid_values(#[case] content: &str) {
+        let actual = ConfigFile::parse(Path::new("config.toml"), content);
+
+        assert!(matches!(actual, Err(ConfigError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn defaults_should_not_include_options_that_are_set() {
+        let config =
+            ConfigFile::parse(Path::new("config.toml"), "retries = 2\ntimeout = 60\n").unwrap();
+
+        let actual = config.defaults(None, |_, env| env == "AER_TIMEOUT");
+
+        assert_eq!(actual, [("retries", "2")]);
+    }
+
+    #[rstest]
+    #[case(Some("update"), &["jobs", "timeout"])]
+    #[case(Some("validate"), &["timeout"])]
+    #[case(None, &["timeout"])]
+    fn defaults_should_only_include_options_of_sub_command(
+        #[case] command: Option<&str>,
+        #[case] expected: &[&str],
+    ) {
+        let config =
+            ConfigFile::parse(Path::new("config.toml"), "jobs = 2\ntimeout = 60\n").unwrap();
+
+        let actual: Vec<&str> = config
+            .defaults(command, |_, _| false)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(&["aer", "update", "a.toml"], &["aer", "update", "a.toml", "--jobs=2", "--timeout=60"])]
+    #[case(&["aer", "update", "-j", "4"], &["aer", "update", "-j", "4", "--timeout=60"])]
+    #[case(&["aer", "--timeout", "5", "update"], &["aer", "--timeout", "5", "update", "--jobs=2"])]
+    #[case(
+        &["aer", "update", "--", "-a"],
+        &["aer", "update", "--jobs=2", "--timeout=60", "--", "-a"]
+    )]
+    fn merge_args_should_add_options_that_are_not_specified(
+        #[case] values: &[&str],
+        #[case] expected: &[&str],
+    ) {
+        use structopt::clap::{App, Arg, SubCommand};
+
+        let app = App::new("aer")
+            .arg(
+                Arg::with_name("timeout")
+                    .long("timeout")
+                    .takes_value(true)
+                    .global(true),
+            )
+            .subcommand(
+                SubCommand::with_name("update")
+                    .arg(
+                        Arg::with_name("jobs")
+                            .long("jobs")
+                            .short("j")
+                            .takes_value(true),
+                    )
+                    .arg(Arg::with_name("files").multiple(true)),
+            );
+        let config =
+            ConfigFile::parse(Path::new("config.toml"), "jobs = 2\ntimeout = 60\n").unwrap();
+        let matches = app.get_matches_from(args(values));
+
+        let actual = config.merge_args(&args(values), &matches);
+
+        assert_eq!(actual, args(expected));
+    }
+
+    #[rstest]
+    #[case(&["aer", "update", "--config", "aer.toml"], Some("aer.toml"))]
+    #[case(&["aer", "--config=aer.toml", "update"], Some("aer.toml"))]
+    #[case(&["aer", "update", "--", "--config", "aer.toml"], None)]
+    #[case(&["aer", "update", "package.aer.toml"], None)]
+    fn config_arg_should_find_path_of_config_option(
+        #[case] values: &[&str],
+        #[case] expected: Option<&str>,
+    ) {
+        let actual = config_arg(&args(values));
+
+        assert_eq!(actual, expected.map(PathBuf::from));
+    }
+}
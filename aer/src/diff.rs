@@ -0,0 +1,233 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the creation of unified diffs between the original and the
+//! updated content of the package files that are changed during an update.
+
+/// The number of unchanged lines that are included before and after every
+/// changed line.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Change<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+impl Change<'_> {
+    fn is_change(&self) -> bool {
+        !matches!(self, Change::Equal(_))
+    }
+}
+
+/// Creates a unified diff of the lines that differ between the original and
+/// the updated content, where `name` is used as the name of the file in the
+/// header of the diff. An empty string is returned when there are no
+/// differences.
+///
+/// ## Examples
+///
+/// ```
+/// use aer::diff::unified_diff;
+///
+/// let diff = unified_diff("test.txt", "version: 1.0.0\n", "version: 1.1.0\n");
+///
+/// assert_eq!(
+///     diff,
+///     "--- test.txt\n+++ test.txt\n@@ -1,1 +1,1 @@\n-version: 1.0.0\n+version: 1.1.0\n"
+/// );
+/// ```
+pub fn unified_diff(name: &str, original: &str, updated: &str) -> String {
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = updated.lines().collect();
+    let changes = diff_lines(&old, &new);
+
+    if !changes.iter().any(Change::is_change) {
+        return String::new();
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", name, name);
+    let mut old_line = 0;
+    let mut new_line = 0;
+    let mut index = 0;
+
+    while let Some(offset) = changes[index..].iter().position(Change::is_change) {
+        let first = index + offset;
+        let start = first.saturating_sub(CONTEXT_LINES).max(index);
+        let end = find_hunk_end(&changes, first);
+
+        for change in &changes[index..start] {
+            if let Change::Equal(_) = change {
+                old_line += 1;
+                new_line += 1;
+            }
+        }
+
+        let hunk = &changes[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|change| !matches!(change, Change::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|change| !matches!(change, Change::Delete(_)))
+            .count();
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk_start(old_line, old_count),
+            old_count,
+            hunk_start(new_line, new_count),
+            new_count
+        ));
+
+        for change in hunk {
+            match change {
+                Change::Equal(line) => output.push_str(&format!(" {}\n", line)),
+                Change::Delete(line) => output.push_str(&format!("-{}\n", line)),
+                Change::Insert(line) => output.push_str(&format!("+{}\n", line)),
+            }
+        }
+
+        old_line += old_count;
+        new_line += new_count;
+        index = end;
+    }
+
+    output
+}
+
+/// Returns the index after the last line of the hunk starting with the change
+/// at `first`. Changes that are separated by less than twice the number of
+/// context lines are combined into the same hunk.
+fn find_hunk_end(changes: &[Change], first: usize) -> usize {
+    let mut index = first;
+
+    loop {
+        while index < changes.len() && changes[index].is_change() {
+            index += 1;
+        }
+
+        let unchanged = changes[index..]
+            .iter()
+            .take_while(|change| !change.is_change())
+            .count();
+
+        if index + unchanged < changes.len() && unchanged <= CONTEXT_LINES * 2 {
+            index += unchanged;
+        } else {
+            return (index + CONTEXT_LINES).min(changes.len());
+        }
+    }
+}
+
+/// Returns the line number a hunk starts at, where hunks without any lines
+/// use the number of the line before the hunk.
+fn hunk_start(lines_before: usize, count: usize) -> usize {
+    if count == 0 {
+        lines_before
+    } else {
+        lines_before + 1
+    }
+}
+
+/// Finds the changes needed to turn the old lines into the new lines, using
+/// the longest common subsequence of the lines that differ.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Change<'a>> {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_lines = &old[prefix..old.len() - suffix];
+    let new_lines = &new[prefix..new.len() - suffix];
+
+    let mut lengths = vec![vec![0usize; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            lengths[i][j] = if old_lines[i] == new_lines[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<Change> = old[..prefix].iter().copied().map(Change::Equal).collect();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old_lines.len() && j < new_lines.len() {
+        if old_lines[i] == new_lines[j] {
+            changes.push(Change::Equal(old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            changes.push(Change::Delete(old_lines[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Insert(new_lines[j]));
+            j += 1;
+        }
+    }
+
+    changes.extend(old_lines[i..].iter().copied().map(Change::Delete));
+    changes.extend(new_lines[j..].iter().copied().map(Change::Insert));
+    changes.extend(old[old.len() - suffix..].iter().copied().map(Change::Equal));
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_should_return_empty_string_without_changes() {
+        let actual = unified_diff("test.txt", "first\nsecond\n", "first\nsecond\n");
+
+        assert_eq!(actual, "");
+    }
+
+    #[test]
+    fn unified_diff_should_include_context_lines() {
+        let original = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let updated = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+
+        let actual = unified_diff("test.txt", original, updated);
+
+        assert_eq!(
+            actual,
+            "--- test.txt\n+++ test.txt\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_should_create_separate_hunks_for_distant_changes() {
+        let original = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let updated = "A\nb\nc\nd\ne\nf\ng\nh\ni\nJ\n";
+
+        let actual = unified_diff("test.txt", original, updated);
+
+        assert_eq!(
+            actual,
+            "--- test.txt\n+++ test.txt\n@@ -1,4 +1,4 @@\n-a\n+A\n b\n c\n d\n@@ -7,4 +7,4 @@\n \
+             g\n h\n i\n-j\n+J\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_should_handle_added_lines() {
+        let actual = unified_diff("test.txt", "", "first\nsecond\n");
+
+        assert_eq!(
+            actual,
+            "--- test.txt\n+++ test.txt\n@@ -0,0 +1,2 @@\n+first\n+second\n"
+        );
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use aer_upd::parsers::errors::ParserError;
+use aer_upd::updaters::errors::UpdaterError;
+use aer_upd::validators::{RegexViolation, RuleSet, Violation};
+use aer_upd::web::errors::WebError;
+
+/// The exit codes used by the `aer` binary, each category of failure have
+/// its own stable exit code to allow scripts and CI pipelines to distinguish
+/// between them.
+///
+/// When multiple package files fail with different categories of errors, the
+/// generic [Failure](ExitCode::Failure) exit code is used.
+///
+/// | Code  | Meaning                                                          |
+/// | ----- | ---------------------------------------------------------------- |
+/// | `0`   | Success, wether any package file was updated or not              |
+/// | `1`   | Failures of different categories, or an uncategorized failure    |
+/// | `2`   | A package file could not be read or parsed                       |
+/// | `3`   | A package file is not valid                                      |
+/// | `4`   | A request or download failed                                     |
+/// | `5`   | No url have been specified to parse                              |
+/// | `6`   | An invalid regex, or a regex without a version in strict mode    |
+/// | `7`   | No update was found, with `--strict` or `--fail-on-no-update`    |
+/// | `8`   | A downloaded file did not match the expected checksum            |
+/// | `9`   | The deadline of the run was exceeded                             |
+/// | `10`  | A newer version is available, with `check --fail-on-update`      |
+/// | `11`  | A regex did not match any of the links, in strict mode           |
+/// | `130` | The process was interrupted                                      |
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitCode {
+    /// All package files was processed successfully (`0`).
+    Success = 0,
+    /// A failure not covered by any of the other categories (`1`).
+    Failure = 1,
+    /// The package file could not be read or parsed (`2`).
+    Parse = 2,
+    /// The package data is not valid (`3`).
+    Validation = 3,
+    /// A request or download failed (`4`).
+    Network = 4,
+    /// No url have been specified to parse (`5`).
+    NoParseUrl = 5,
+    /// A regular expression could not be compiled, or did not capture a
+    /// version in strict mode (`6`).
+    InvalidRegex = 6,
+    /// No update was found when running in strict mode, or none of the
+    /// package files was updated when `--fail-on-no-update` is used (`7`).
+    NoUpdate = 7,
+    /// A downloaded file did not match the expected checksum (`8`).
+    ChecksumMismatch = 8,
+    /// The deadline of the run was exceeded before all package files was
+    /// processed (`9`).
+    Timeout = 9,
+    /// A newer version is available for a package when checking for updates
+    /// with `--fail-on-update` (`10`).
+    UpdateAvailable = 10,
+    /// The regexes of an architecture did not match any of the links found
+    /// when running in strict mode (`11`).
+    NoMatch = 11,
+    /// The process was interrupted (*ie: by pressing `Ctrl-C`*) before all
+    /// package files was processed (`130`).
+    Interrupted = 130,
+}
+
+impl ExitCode {
+    /// Returns the numeric value of the exit code.
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Combines the exit codes of multiple failures into a single exit code.
+    /// When all of the failures have the same exit code, that exit code is
+    /// returned, otherwise [Failure](ExitCode::Failure) is returned.
+    /// [Success](ExitCode::Success) is returned when there are no failures.
+    pub fn combine<I: IntoIterator<Item = ExitCode>>(codes: I) -> ExitCode {
+        let mut codes = codes.into_iter();
+        let first = match codes.next() {
+            Some(code) => code,
+            None => return ExitCode::Success,
+        };
+
+        if codes.all(|code| code == first) {
+            first
+        } else {
+            ExitCode::Failure
+        }
+    }
+
+    /// Exits the current process with the exit code.
+    pub fn exit(self) -> ! {
+        std::process::exit(self.code())
+    }
+}
+
+impl Default for ExitCode {
+    fn default() -> Self {
+        Self::Success
+    }
+}
+
+/// The errors that can occur while running the update process of a single
+/// package file.
+#[derive(Debug)]
+pub enum UpdateError {
+    /// The package file could not be read or parsed.
+    Parse(ParserError),
+    /// The package data is not valid according to the used rule set.
+    Validation {
+        id: String,
+        rule_set: RuleSet,
+        violations: Vec<Violation>,
+    },
+    /// No url to parse have been specified for the package.
+    NoParseUrl { id: String },
+    /// The request to the specified url failed.
+    Request { url: String, error: WebError },
+    /// No published releases was found in the GitHub repository.
+    NoReleases { owner: String, repo: String },
+    /// The regular expression could not be compiled.
+    InvalidRegex { regex: String, error: regex::Error },
+    /// A regular expression in the package data could not be compiled.
+    PackageRegex {
+        id: String,
+        violation: RegexViolation,
+    },
+    /// The file at the specified url could not be downloaded.
+    Download { url: String, message: String },
+    /// The file downloaded from the specified url did not match the expected
+    /// checksum.
+    ChecksumMismatch {
+        url: String,
+        checksum_type: String,
+        expected: String,
+        actual: String,
+    },
+    /// The GitHub api refused the request for the releases of the repository,
+    /// `token_env` is the environment variable the token is read from.
+    GitHubAccessDenied {
+        owner: String,
+        repo: String,
+        token_env: String,
+        rate_limited: bool,
+    },
+    /// The environment variable needed for authentication is not set.
+    Authentication { variable: String },
+    /// No value was found for the specified install script variable.
+    MissingScriptValue { name: String, source: String },
+    /// The package file at the specified path could not be updated.
+    Update { path: PathBuf, error: UpdaterError },
+    /// The file at the specified path could not be read or written.
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The file at the specified path already exists in the output directory,
+    /// only used when collisions are treated as errors.
+    FileExists { path: PathBuf },
+    /// A before or after hook failed.
+    Hook(String),
+    /// The external resolver of a parse url failed, or did not output a valid
+    /// json document.
+    Resolver(String),
+    /// The changed files could not be committed to the git repository.
+    Git(String),
+    /// No update was found for the package, only used in strict mode.
+    NoUpdate { id: String },
+    /// The regex matched a link without a parsable version, only used in
+    /// strict mode.
+    NoVersion { url: String, regex: String },
+    /// The regexes of the specified keys did not match any of the links
+    /// found, only used in strict mode.
+    NoMatch { id: String, keys: Vec<String> },
+    /// The update was not finished before the deadline of the run was
+    /// exceeded.
+    TimedOut,
+}
+
+impl UpdateError {
+    /// Returns the category of the error, used by the json summary to allow
+    /// branching on the kind of failure.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UpdateError::Parse(_) => "parse",
+            UpdateError::Validation { .. } => "validation",
+            UpdateError::NoParseUrl { .. } => "no-url",
+            UpdateError::Request { .. } | UpdateError::NoReleases { .. } => "network",
+            UpdateError::InvalidRegex { .. } | UpdateError::PackageRegex { .. } => "regex",
+            UpdateError::Download { .. } => "download",
+            UpdateError::ChecksumMismatch { .. } => "checksum",
+            UpdateError::Authentication { .. } | UpdateError::GitHubAccessDenied { .. } => {
+                "authentication"
+            }
+            UpdateError::MissingScriptValue { .. } | UpdateError::Update { .. } => "update",
+            UpdateError::Io { .. } => "io",
+            UpdateError::FileExists { .. } => "collision",
+            UpdateError::Hook(_) => "hook",
+            UpdateError::Resolver(_) => "resolver",
+            UpdateError::Git(_) => "git",
+            UpdateError::NoUpdate { .. } => "no-update",
+            UpdateError::NoVersion { .. } => "no-version",
+            UpdateError::NoMatch { .. } => "no-match",
+            UpdateError::TimedOut => "timeout",
+        }
+    }
+
+    /// Returns the exit code that should be used when the process fails
+    /// because of the error.
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            UpdateError::Parse(_) => ExitCode::Parse,
+            UpdateError::Validation { .. } => ExitCode::Validation,
+            UpdateError::Request { .. }
+            | UpdateError::NoReleases { .. }
+            | UpdateError::GitHubAccessDenied { .. }
+            | UpdateError::Download { .. } => ExitCode::Network,
+            UpdateError::NoParseUrl { .. } => ExitCode::NoParseUrl,
+            UpdateError::InvalidRegex { .. }
+            | UpdateError::PackageRegex { .. }
+            | UpdateError::NoVersion { .. } => ExitCode::InvalidRegex,
+            UpdateError::NoUpdate { .. } => ExitCode::NoUpdate,
+            UpdateError::NoMatch { .. } => ExitCode::NoMatch,
+            UpdateError::ChecksumMismatch { .. } => ExitCode::ChecksumMismatch,
+            UpdateError::TimedOut => ExitCode::Timeout,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpdateError::Parse(err) => err.fmt(f),
+            UpdateError::Validation {
+                id,
+                rule_set,
+                violations,
+            } => {
+                let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(
+                    f,
+                    "The package data of '{}' is not valid according to the {} rule set: {}",
+                    id,
+                    rule_set,
+                    violations.join(", ")
+                )
+            }
+            UpdateError::NoParseUrl { id } => {
+                write!(f, "No url have been specified to parse for '{}'!", id)
+            }
+            UpdateError::Request { url, error } => {
+                write!(f, "The request to '{}' failed: {}", url, error)
+            }
+            UpdateError::GitHubAccessDenied {
+                owner,
+                repo,
+                token_env,
+                rate_limited: true,
+            } => write!(
+                f,
+                "The GitHub api rate limit was exceeded when requesting the releases of '{}/{}', \
+                 set a token in the '{}' environment variable to get a higher rate limit!",
+                owner, repo, token_env
+            ),
+            UpdateError::GitHubAccessDenied {
+                owner,
+                repo,
+                token_env,
+                ..
+            } => write!(
+                f,
+                "The GitHub api denied access to the releases of '{}/{}', make sure the token in \
+                 the '{}' environment variable is valid and have access to the repository!",
+                owner, repo, token_env
+            ),
+            UpdateError::NoReleases { owner, repo } => write!(
+                f,
+                "No published releases was found in the GitHub repository '{}/{}'!",
+                owner, repo
+            ),
+            UpdateError::InvalidRegex { regex, error } => {
+                write!(f, "The regex '{}' is not valid: {}", regex, error)
+            }
+            UpdateError::PackageRegex { id, violation } => write!(
+                f,
+                "The package data of '{}' contains an invalid regex: {}",
+                id, violation
+            ),
+            UpdateError::Download { url, message } => {
+                write!(f, "Unable to download '{}': {}", url, message)
+            }
+            UpdateError::ChecksumMismatch {
+                url,
+                checksum_type,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The {} checksum of the file downloaded from '{}' do not match the expected \
+                 checksum (expected: {}, actual: {})",
+                checksum_type, url, expected, actual
+            ),
+            UpdateError::Authentication { variable } => write!(
+                f,
+                "The environment variable '{}' needed for authentication is not set!",
+                variable
+            ),
+            UpdateError::MissingScriptValue { name, source } => write!(
+                f,
+                "No value was found for the install script variable '{}' ({})!",
+                name, source
+            ),
+            UpdateError::Update { path, error } => {
+                write!(f, "Unable to update '{}': {}", path.display(), error)
+            }
+            UpdateError::Io { path, error } => {
+                write!(f, "Unable to access '{}': {}", path.display(), error)
+            }
+            UpdateError::FileExists { path } => write!(
+                f,
+                "The file '{}' already exists in the output directory!",
+                path.display()
+            ),
+            UpdateError::Hook(message) | UpdateError::Resolver(message) => message.fmt(f),
+            UpdateError::Git(message) => {
+                write!(f, "Unable to commit the changed files: {}", message)
+            }
+            UpdateError::NoUpdate { id } => write!(f, "No update was found for '{}'!", id),
+            UpdateError::NoVersion { url, regex } => write!(
+                f,
+                "No version could be parsed from '{}', which was matched by the regex '{}'!",
+                url, regex
+            ),
+            UpdateError::NoMatch { id, keys } => write!(
+                f,
+                "The regexes of '{}' did not match any links for {}, the layout of the page may \
+                 have changed!",
+                id,
+                keys.join(", ")
+            ),
+            UpdateError::TimedOut => {
+                f.write_str("The update was not finished before the deadline was exceeded!")
+            }
+        }
+    }
+}
+
+impl Error for UpdateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UpdateError::Request { error, .. } => Some(error),
+            UpdateError::InvalidRegex { error, .. } => Some(error),
+            UpdateError::PackageRegex { violation, .. } => Some(&violation.error),
+            UpdateError::Update { error, .. } => Some(error),
+            UpdateError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParserError> for UpdateError {
+    fn from(error: ParserError) -> Self {
+        UpdateError::Parse(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(UpdateError::Parse(ParserError::Deserialize("invalid".into())), "parse", ExitCode::Parse)]
+    #[case(UpdateError::NoParseUrl { id: "test".into() }, "no-url", ExitCode::NoParseUrl)]
+    #[case(UpdateError::NoReleases { owner: "owner".into(), repo: "repo".into() }, "network", ExitCode::Network)]
+    #[case(UpdateError::Download { url: "https://example.org".into(), message: "failed".into() }, "download", ExitCode::Network)]
+    #[case(UpdateError::InvalidRegex { regex: "(".into(), error: regex::Regex::new("(").unwrap_err() }, "regex", ExitCode::InvalidRegex)]
+    #[case(UpdateError::NoUpdate { id: "test".into() }, "no-update", ExitCode::NoUpdate)]
+    #[case(UpdateError::NoVersion { url: "https://example.org".into(), regex: "test".into() }, "no-version", ExitCode::InvalidRegex)]
+    #[case(UpdateError::NoMatch { id: "test".into(), keys: vec!["arch64".into()] }, "no-match", ExitCode::NoMatch)]
+    #[case(UpdateError::GitHubAccessDenied { owner: "owner".into(), repo: "repo".into(), token_env: "GITHUB_TOKEN".into(), rate_limited: true }, "authentication", ExitCode::Network)]
+    #[case(UpdateError::FileExists { path: "setup.exe".into() }, "collision", ExitCode::Failure)]
+    #[case(UpdateError::Hook("failed".into()), "hook", ExitCode::Failure)]
+    #[case(UpdateError::Resolver("failed".into()), "resolver", ExitCode::Failure)]
+    #[case(UpdateError::Git("failed".into()), "git", ExitCode::Failure)]
+    #[case(UpdateError::TimedOut, "timeout", ExitCode::Timeout)]
+    fn should_map_error_to_category(
+        #[case] error: UpdateError,
+        #[case] kind: &str,
+        #[case] exit_code: ExitCode,
+    ) {
+        assert_eq!(error.kind(), kind);
+        assert_eq!(error.exit_code(), exit_code);
+    }
+
+    #[rstest]
+    #[case(ExitCode::Success, 0)]
+    #[case(ExitCode::Failure, 1)]
+    #[case(ExitCode::Parse, 2)]
+    #[case(ExitCode::Validation, 3)]
+    #[case(ExitCode::Network, 4)]
+    #[case(ExitCode::NoParseUrl, 5)]
+    #[case(ExitCode::InvalidRegex, 6)]
+    #[case(ExitCode::NoUpdate, 7)]
+    #[case(ExitCode::ChecksumMismatch, 8)]
+    #[case(ExitCode::Timeout, 9)]
+    #[case(ExitCode::UpdateAvailable, 10)]
+    #[case(ExitCode::NoMatch, 11)]
+    #[case(ExitCode::Interrupted, 130)]
+    fn code_should_return_stable_value(#[case] exit_code: ExitCode, #[case] expected: i32) {
+        assert_eq!(exit_code.code(), expected);
+    }
+
+    #[rstest]
+    #[case(vec![], ExitCode::Success)]
+    #[case(vec![ExitCode::Network], ExitCode::Network)]
+    #[case(vec![ExitCode::Parse, ExitCode::Parse], ExitCode::Parse)]
+    #[case(vec![ExitCode::Parse, ExitCode::Network], ExitCode::Failure)]
+    fn combine_should_use_shared_exit_code(
+        #[case] codes: Vec<ExitCode>,
+        #[case] expected: ExitCode,
+    ) {
+        assert_eq!(ExitCode::combine(codes), expected);
+    }
+
+    #[test]
+    fn fmt_should_include_package_id() {
+        let error = UpdateError::NoParseUrl { id: "test".into() };
+
+        assert_eq!(
+            error.to_string(),
+            "No url have been specified to parse for 'test'!"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for committing the files that was changed during the
+//! update of a package to a git repository.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use log::{info, trace};
+
+lazy_static! {
+    /// Only a single git command can modify the index of a repository at the
+    /// same time, as such all git commands are run one after the other even
+    /// when multiple packages are updated concurrently.
+    static ref GIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// The default template of the commit message, where `{{id}}`,
+/// `{{old_version}}` and `{{new_version}}` are replaced with the values of
+/// the updated package.
+pub const DEFAULT_COMMIT_MESSAGE: &str = "({{id}}) {{old_version}} -> {{new_version}}";
+
+/// Stages and commits the specified files in the git repository containing
+/// the `repo` directory. Only the specified files are committed, even if
+/// other files have already been staged.
+///
+/// Returns `false` without creating a commit when none of the files have any
+/// changes.
+pub fn commit_files(repo: &Path, files: &[PathBuf], message: &str) -> Result<bool, String> {
+    let _lock = GIT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let root = get_repository_root(repo)?;
+    let files = files
+        .iter()
+        .map(|file| get_relative_path(&root, file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if files.is_empty() {
+        return Ok(false);
+    }
+
+    run_git(&root, &["add", "--"], &files)?;
+
+    let diff = git_command(&root, &["diff", "--cached", "--quiet", "--"], &files)?;
+    if diff.status.success() {
+        info!("None of the files have any changes, skipping the commit!");
+        return Ok(false);
+    } else if diff.status.code() != Some(1) {
+        return Err(format!(
+            "Unable to check the staged changes: {}",
+            String::from_utf8_lossy(&diff.stderr).trim()
+        ));
+    }
+
+    run_git(&root, &["commit", "-m", message, "--"], &files)?;
+    info!(
+        "Committed {} file(s) to '{}' with the message '{}'",
+        files.len(),
+        root.display(),
+        message
+    );
+
+    Ok(true)
+}
+
+/// Returns the root directory of the git repository containing the
+/// specified directory.
+fn get_repository_root(repo: &Path) -> Result<PathBuf, String> {
+    let output = run_git(repo, &["rev-parse", "--show-toplevel"], &[])?;
+    let root = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+    std::fs::canonicalize(&root)
+        .map_err(|err| format!("Unable to find the repository root '{}': {}", root, err))
+}
+
+fn get_relative_path(root: &Path, file: &Path) -> Result<PathBuf, String> {
+    let file = std::fs::canonicalize(file)
+        .map_err(|err| format!("Unable to find the file '{}': {}", file.display(), err))?;
+
+    file.strip_prefix(root)
+        .map(|file| file.to_owned())
+        .map_err(|_| {
+            format!(
+                "The file '{}' is not inside the repository '{}'!",
+                file.display(),
+                root.display()
+            )
+        })
+}
+
+fn git_command(dir: &Path, args: &[&str], files: &[PathBuf]) -> Result<Output, String> {
+    trace!("Running 'git {}' in '{}'", args.join(" "), dir.display());
+
+    Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .args(files.iter().map(|file| file.as_os_str()))
+        .output()
+        .map_err(|err| format!("Unable to run git: {}", err))
+}
+
+fn run_git(dir: &Path, args: &[&str], files: &[PathBuf]) -> Result<Output, String> {
+    let output = git_command(dir, args, files)?;
+
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(format!(
+            "The command 'git {}' failed: {}",
+            args.first().map_or("", |arg| *arg),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_repository(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("aer-git-tests").join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for args in &[
+            &["init", "-q"][..],
+            &["config", "user.name", "aer"][..],
+            &["config", "user.email", "aer@example.org"][..],
+        ] {
+            run_git(&dir, args, &[]).unwrap();
+        }
+
+        dir
+    }
+
+    fn commit_count(dir: &Path) -> usize {
+        git_command(dir, &["rev-list", "--count", "HEAD"], &[])
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn commit_files_should_commit_changed_files() {
+        let dir = create_repository("changed");
+        let file = dir.join("test.nuspec");
+        std::fs::write(&file, "<version>1.0.0</version>").unwrap();
+
+        let actual = commit_files(&dir, &[file], "(test) 0.9.0 -> 1.0.0").unwrap();
+
+        assert!(actual);
+        assert_eq!(commit_count(&dir), 1);
+    }
+
+    #[test]
+    fn commit_files_should_not_commit_unchanged_files() {
+        let dir = create_repository("unchanged");
+        let file = dir.join("test.nuspec");
+        std::fs::write(&file, "<version>1.0.0</version>").unwrap();
+        commit_files(&dir, &[file.clone()], "(test) 0.9.0 -> 1.0.0").unwrap();
+
+        let actual = commit_files(&dir, &[file], "(test) 1.0.0 -> 1.0.0").unwrap();
+
+        assert!(!actual);
+        assert_eq!(commit_count(&dir), 1);
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the handling of interruptions of the process (*ie: by pressing
+//! `Ctrl-C`*), where the files that are partially downloaded are removed
+//! before the process exits with the [Interrupted](ExitCode::Interrupted)
+//! exit code.
+//!
+//! On unix the `SIGINT` and `SIGTERM` signals are handled, while the console
+//! control events are handled on windows.
+
+use aer_upd::web::partial::remove_partial_files;
+use log::warn;
+
+use crate::errors::ExitCode;
+
+/// Installs the handler that removes the partially downloaded files and
+/// exits the process when the process is interrupted. Files that have
+/// finished downloading are kept.
+pub fn install_handler() {
+    if let Err(err) = imp::install() {
+        warn!("Unable to install the interruption handler: {}", err);
+    }
+}
+
+/// Removes the partially downloaded files and exits the process.
+fn interrupt() -> ! {
+    warn!("The process was interrupted, stopping!");
+
+    for path in remove_partial_files() {
+        warn!("Removed the partially downloaded file '{}'", path.display());
+    }
+
+    ExitCode::Interrupted.exit()
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    /// How often the watcher thread checks if the process have been
+    /// interrupted.
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn handle_signal(_: libc::c_int) {
+        // Only async-signal-safe operations are allowed in a signal handler,
+        // the files are instead removed by the watcher thread.
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    pub(super) fn install() -> io::Result<()> {
+        for signal in &[libc::SIGINT, libc::SIGTERM] {
+            let handler = handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            // SAFETY: The handler only stores to an atomic, which is safe to
+            // do from a signal handler.
+            if unsafe { libc::signal(*signal, handler) } == libc::SIG_ERR {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        thread::Builder::new()
+            .name("interrupt-watcher".into())
+            .spawn(|| loop {
+                if INTERRUPTED.load(Ordering::SeqCst) {
+                    super::interrupt();
+                }
+                thread::sleep(POLL_INTERVAL);
+            })
+            .map(|_| ())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::io;
+
+    use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+    use winapi::um::consoleapi::SetConsoleCtrlHandler;
+    use winapi::um::wincon::{CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT};
+
+    unsafe extern "system" fn handle_event(event: DWORD) -> BOOL {
+        // The handler is called on a separate thread, so the files can be
+        // removed directly.
+        match event {
+            CTRL_C_EVENT | CTRL_BREAK_EVENT | CTRL_CLOSE_EVENT => super::interrupt(),
+            _ => FALSE,
+        }
+    }
+
+    pub(super) fn install() -> io::Result<()> {
+        // SAFETY: The handler is a valid function for the lifetime of the
+        // process.
+        if unsafe { SetConsoleCtrlHandler(Some(handle_event), TRUE) } == FALSE {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::io;
+
+    pub(super) fn install() -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "interruptions can not be handled on this platform",
+        ))
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for maintaining the `latest` pointer next to the
+//! directories that the files of each version of a package are downloaded
+//! to.
+//!
+//! The pointer is a symbolic link on unix, and a text file containing the
+//! name of the directory on windows.
+
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+
+/// The name of the pointer to the directory of the latest version.
+pub const LATEST_POINTER: &str = "latest";
+
+/// Updates the `latest` pointer located next to the specified directory, to
+/// point at the directory. Pointers that are stale or broken are replaced,
+/// while an existing directory with the same name as the pointer is never
+/// replaced.
+///
+/// Returns the path to the pointer.
+pub fn update_latest_pointer(version_dir: &Path) -> io::Result<PathBuf> {
+    let (parent, name) = match (version_dir.parent(), version_dir.file_name()) {
+        (Some(parent), Some(name)) => (parent, name),
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "the directory do not have a parent directory",
+            ))
+        }
+    };
+    let pointer = parent.join(LATEST_POINTER);
+
+    match read_pointer(&pointer) {
+        Ok(Some(target)) if target == Path::new(name) && version_dir.is_dir() => {
+            info!("The pointer '{}' is already up to date", pointer.display());
+            return Ok(pointer);
+        }
+        Ok(Some(target)) => {
+            if !parent.join(&target).is_dir() {
+                warn!(
+                    "The pointer '{}' is broken, as '{}' do not exist!",
+                    pointer.display(),
+                    target.display()
+                );
+            }
+            remove_pointer(&pointer)?;
+        }
+        Ok(None) => {}
+        Err(err) => return Err(err),
+    }
+
+    create_pointer(&pointer, Path::new(name))?;
+    info!(
+        "Updated the pointer '{}' to '{}'",
+        pointer.display(),
+        version_dir.display()
+    );
+
+    Ok(pointer)
+}
+
+/// Reads the target of the pointer, relative to the directory of the
+/// pointer. `None` is returned when the pointer do not exist.
+fn read_pointer(pointer: &Path) -> io::Result<Option<PathBuf>> {
+    let metadata = match std::fs::symlink_metadata(pointer) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    if metadata.file_type().is_symlink() {
+        std::fs::read_link(pointer).map(Some)
+    } else if metadata.is_file() {
+        let content = std::fs::read_to_string(pointer).unwrap_or_default();
+        Ok(Some(PathBuf::from(content.trim())))
+    } else {
+        Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "'{}' already exists and is not a pointer",
+                pointer.display()
+            ),
+        ))
+    }
+}
+
+#[cfg(windows)]
+fn remove_pointer(pointer: &Path) -> io::Result<()> {
+    // Symbolic links to directories on windows need to be removed as
+    // directories.
+    std::fs::remove_file(pointer).or_else(|_| std::fs::remove_dir(pointer))
+}
+
+#[cfg(not(windows))]
+fn remove_pointer(pointer: &Path) -> io::Result<()> {
+    std::fs::remove_file(pointer)
+}
+
+#[cfg(unix)]
+fn create_pointer(pointer: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, pointer)
+}
+
+#[cfg(not(unix))]
+fn create_pointer(pointer: &Path, target: &Path) -> io::Result<()> {
+    std::fs::write(pointer, format!("{}\n", target.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_versions(name: &str, versions: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join("aer-latest-tests").join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        for version in versions {
+            std::fs::create_dir_all(dir.join(version)).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn update_latest_pointer_should_point_to_version_directory() {
+        let dir = create_versions("created", &["1.0.0"]);
+
+        let actual = update_latest_pointer(&dir.join("1.0.0")).unwrap();
+
+        assert_eq!(actual, dir.join(LATEST_POINTER));
+        assert_eq!(read_pointer(&actual).unwrap(), Some(PathBuf::from("1.0.0")));
+    }
+
+    #[test]
+    fn update_latest_pointer_should_replace_stale_pointer() {
+        let dir = create_versions("stale", &["1.0.0", "2.0.0"]);
+        update_latest_pointer(&dir.join("1.0.0")).unwrap();
+
+        let actual = update_latest_pointer(&dir.join("2.0.0")).unwrap();
+
+        assert_eq!(read_pointer(&actual).unwrap(), Some(PathBuf::from("2.0.0")));
+    }
+
+    #[test]
+    fn update_latest_pointer_should_replace_broken_pointer() {
+        let dir = create_versions("broken", &["1.0.0", "2.0.0"]);
+        update_latest_pointer(&dir.join("1.0.0")).unwrap();
+        std::fs::remove_dir(dir.join("1.0.0")).unwrap();
+
+        let actual = update_latest_pointer(&dir.join("2.0.0")).unwrap();
+
+        assert_eq!(read_pointer(&actual).unwrap(), Some(PathBuf::from("2.0.0")));
+    }
+
+    #[test]
+    fn update_latest_pointer_should_not_replace_directories() {
+        let dir = create_versions("directory", &["1.0.0", LATEST_POINTER]);
+
+        let actual = update_latest_pointer(&dir.join("1.0.0"));
+
+        assert_eq!(actual.unwrap_err().kind(), ErrorKind::AlreadyExists);
+        assert!(dir.join(LATEST_POINTER).is_dir());
+    }
+}
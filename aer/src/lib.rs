@@ -1,7 +1,23 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+pub mod check;
+pub mod colors;
+pub mod config;
+pub mod diff;
+pub mod errors;
+pub mod git;
+pub mod interrupt;
+pub mod latest;
 pub mod logging;
+pub mod package_files;
+pub mod progress;
+pub mod report;
+pub mod request;
+pub mod scaffold;
+pub mod sums;
+pub mod update;
+pub mod verify;
 
 use std::fmt::Display;
 use std::fs::File;
@@ -16,7 +32,7 @@ use sha2::digest::generic_array::ArrayLength;
 use sha2::{Digest, Sha256, Sha512};
 use structopt::StructOpt;
 
-#[derive(Debug, PartialEq, StructOpt)]
+#[derive(Debug, Clone, Copy, PartialEq, StructOpt)]
 pub enum ChecksumType {
     Md5,
     Sha1,
@@ -78,6 +94,55 @@ impl ChecksumType {
     pub fn generate(&self, path: &Path) -> Result<String, std::io::Error> {
         generate_checksum(path, self)
     }
+
+    /// Creates a new hasher for the current checksum type, that can be used
+    /// to calculate the checksum of data as it is being written (*ie: while
+    /// downloading a file*).
+    pub fn hasher(&self) -> ChecksumHasher {
+        match self {
+            ChecksumType::Md5 => ChecksumHasher::Md5(Md5::new()),
+            ChecksumType::Sha1 => ChecksumHasher::Sha1(Sha1::new()),
+            ChecksumType::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+            ChecksumType::Sha512 => ChecksumHasher::Sha512(Sha512::new()),
+        }
+    }
+}
+
+/// Calculates the checksum of all data that is written to it, the resulting
+/// checksum is returned when calling [finalize](ChecksumHasher::finalize).
+pub enum ChecksumHasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl ChecksumHasher {
+    /// Consumes the hasher and returns the checksum of the written data as a
+    /// lowercase hex string.
+    pub fn finalize(self) -> String {
+        match self {
+            ChecksumHasher::Md5(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Sha1(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Sha512(hasher) => format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+impl Write for ChecksumHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ChecksumHasher::Md5(hasher) => hasher.write(buf),
+            ChecksumHasher::Sha1(hasher) => hasher.write(buf),
+            ChecksumHasher::Sha256(hasher) => hasher.write(buf),
+            ChecksumHasher::Sha512(hasher) => hasher.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 fn generate_checksum(path: &Path, checksum_type: &ChecksumType) -> Result<String, std::io::Error> {
@@ -197,6 +262,28 @@ mod tests {
         Ok(())
     }
 
+    #[rstest(
+        algorithm,
+        case(ChecksumType::Md5),
+        case(ChecksumType::Sha1),
+        case(ChecksumType::Sha256),
+        case(ChecksumType::Sha512)
+    )]
+    fn hasher_should_generate_same_checksum_as_generate(
+        algorithm: ChecksumType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = PathBuf::from("test-data/checksum-test.bin.txt");
+        let expected = algorithm.generate(&path)?;
+        let mut hasher = algorithm.hasher();
+
+        hasher.write_all(&std::fs::read(&path)?)?;
+        let actual = hasher.finalize();
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
     #[test]
     fn generate_should_return_error_on_non_existing_file() {
         let path = PathBuf::from("non-existing");
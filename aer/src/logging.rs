@@ -1,9 +1,16 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt::{Arguments, Display};
 use std::path::Path;
+use std::str::FromStr;
 
-use log::{debug, Level, LevelFilter};
+use lazy_static::lazy_static;
+use log::{debug, Level, LevelFilter, Record};
+use regex::Regex;
+use serde_json::json;
 use yansi::{Color, Paint, Style};
 
 #[macro_export]
@@ -12,35 +19,238 @@ macro_rules! log_data {
         log_data!{env!("CARGO_PKG_NAME")}
     };
     ($app_name:expr) => {
+        log_data!{$app_name, "split"}
+    };
+    ($app_name:expr, $stream:literal) => {
         #[derive(::structopt::StructOpt)]
         pub struct LogData {
-            /// The path to where logs should be written.
-            #[structopt(long = "log", alias = "log-file", env = "AER_LOG_PATH", global = true, parse(from_os_str), default_value = concat!("./", $app_name, ".log"))]
+            /// The path to the file where logs should be written, in addition
+            /// to the console. The file never contains any color codes.
+            #[structopt(long = "log-file", alias = "log", env = "AER_LOG_PATH", global = true, parse(from_os_str), default_value = concat!("./", $app_name, ".log"))]
             pub path: ::std::path::PathBuf,
-            /// The log level to use when outputting to the console.
-            #[structopt(short = "-L", long = "log-level", env = "AER_LOG_LEVEL", global = true, default_value = "info", possible_values = &["trace", "debug", "info", "error" ])]
-            pub level: ::log::LevelFilter,
+            /// The log level to use when outputting to the console, takes
+            /// precedence over `--verbose` and `--quiet`. [default: info]
+            #[structopt(short = "-L", long = "log-level", env = "AER_LOG_LEVEL", global = true, possible_values = &["trace", "debug", "info", "warn", "error", "off" ])]
+            pub level: Option<::log::LevelFilter>,
+            /// Increases the log level used when outputting to the console,
+            /// `-v` outputs debug messages and `-vv` outputs trace messages.
+            #[structopt(short = "v", long = "verbose", global = true, parse(from_occurrences))]
+            pub verbose: u8,
+            /// Decreases the log level used when outputting to the console,
+            /// `-q` only outputs warnings and errors, `-qq` only outputs errors
+            /// and `-qqq` disables the console output.
+            #[structopt(short = "q", long = "quiet", global = true, parse(from_occurrences))]
+            pub quiet: u8,
+            /// The log level to use when outputting to the log file, use `off`
+            /// to disable the log file.
+            #[structopt(long = "log-file-level", env = "AER_LOG_FILE_LEVEL", global = true, default_value = "trace", possible_values = &["trace", "debug", "info", "warn", "error", "off" ])]
+            pub file_level: ::log::LevelFilter,
+            /// The format of the log records, when using `json` every record
+            /// is written as a single line json object to both the console
+            /// and the log file.
+            #[structopt(long = "log-format", env = "AER_LOG_FORMAT", global = true, default_value, possible_values = &["text", "json"])]
+            pub format: crate::logging::LogFormat,
+            /// The stream log records are written to on the console, `split`
+            /// writes warnings and errors to stderr and every other record to
+            /// stdout, while `stderr` and `stdout` writes every record to the
+            /// specified stream.
+            #[structopt(long = "log-stream", env = "AER_LOG_STREAM", global = true, default_value = $stream, possible_values = &["split", "stderr", "stdout"])]
+            pub stream: crate::logging::LogStream,
         }
 
         impl Default for LogData {
             fn default() -> Self {
                 Self {
                     path: ::std::path::PathBuf::from(concat!("./", $app_name, ".log")),
-                    level: ::log::LevelFilter::Info
+                    level: None,
+                    verbose: 0,
+                    quiet: 0,
+                    file_level: ::log::LevelFilter::Trace,
+                    format: crate::logging::LogFormat::Text,
+                    stream: $stream.parse().unwrap(),
                 }
              }
         }
 
         impl crate::logging::LogDataTrait for LogData {
             fn path(&self) -> &::std::path::Path { &self.path }
-            fn level(&self) -> &::log::LevelFilter { &self.level }
+            fn level(&self) -> ::log::LevelFilter {
+                crate::logging::resolve_level(self.level, self.verbose, self.quiet)
+            }
+            fn file_level(&self) -> &::log::LevelFilter { &self.file_level }
+            fn format(&self) -> &crate::logging::LogFormat { &self.format }
+            fn stream(&self) -> crate::logging::LogStream { self.stream }
         }
     };
 }
 
+thread_local! {
+    static PACKAGE_CONTEXT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Sets the identifier of the package that is currently being processed on
+/// the calling thread. Every log line written from this thread will be
+/// prefixed with the identifier until [clear_package_context] is called.
+pub fn set_package_context(id: &str) {
+    PACKAGE_CONTEXT.with(|context| *context.borrow_mut() = Some(id.to_owned()));
+}
+
+/// Removes any package identifier previously set on the calling thread.
+pub fn clear_package_context() {
+    PACKAGE_CONTEXT.with(|context| *context.borrow_mut() = None);
+}
+
+fn package_id() -> Option<String> {
+    PACKAGE_CONTEXT.with(|context| context.borrow().clone())
+}
+
+fn package_prefix() -> String {
+    match package_id() {
+        Some(id) => format!("[{}] ", id),
+        None => String::new(),
+    }
+}
+
+/// The formats that log records can be written as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// Human readable log records, colored when written to the console.
+    Text,
+    /// Newline delimited json records, containing the timestamp, level,
+    /// target, message and the identifier of the package being processed.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("The value is not a supported log format!"),
+        }
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => f.write_str("text"),
+            LogFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+/// The streams that log records can be written to on the console.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogStream {
+    /// Warnings and errors are written to stderr, while every other record is
+    /// written to stdout.
+    Split,
+    /// Every record is written to stderr, keeping stdout free for the output
+    /// of the application.
+    Stderr,
+    /// Every record is written to stdout.
+    Stdout,
+}
+
+impl LogStream {
+    /// Checks wether records with the specified level are written to stderr.
+    pub fn uses_stderr(self, level: Level) -> bool {
+        match self {
+            LogStream::Split => level <= Level::Warn,
+            LogStream::Stderr => true,
+            LogStream::Stdout => false,
+        }
+    }
+
+    fn output(self, level: Level) -> fern::Output {
+        if self.uses_stderr(level) {
+            std::io::stderr().into()
+        } else {
+            std::io::stdout().into()
+        }
+    }
+}
+
+impl Default for LogStream {
+    fn default() -> Self {
+        Self::Split
+    }
+}
+
+impl FromStr for LogStream {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "split" => Ok(LogStream::Split),
+            "stderr" => Ok(LogStream::Stderr),
+            "stdout" => Ok(LogStream::Stdout),
+            _ => Err("The value is not a supported log stream!"),
+        }
+    }
+}
+
+impl Display for LogStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogStream::Split => f.write_str("split"),
+            LogStream::Stderr => f.write_str("stderr"),
+            LogStream::Stdout => f.write_str("stdout"),
+        }
+    }
+}
+
 pub trait LogDataTrait {
     fn path(&self) -> &Path;
-    fn level(&self) -> &LevelFilter;
+    /// The log level to use when outputting to the console.
+    fn level(&self) -> LevelFilter;
+    fn file_level(&self) -> &LevelFilter;
+    fn format(&self) -> &LogFormat;
+    /// The stream to write log records to on the console.
+    fn stream(&self) -> LogStream;
+}
+
+/// Resolves the log level to use when outputting to the console. An explicit
+/// level always takes precedence, otherwise every `verbose` occurrence
+/// increases and every `quiet` occurrence decreases the level by one step
+/// from `info`, with the occurrences cancelling each other out.
+///
+/// ## Examples
+///
+/// ```
+/// use aer::logging::resolve_level;
+/// use log::LevelFilter;
+///
+/// assert_eq!(resolve_level(None, 2, 0), LevelFilter::Trace);
+/// assert_eq!(resolve_level(None, 0, 1), LevelFilter::Warn);
+/// assert_eq!(resolve_level(Some(LevelFilter::Error), 2, 0), LevelFilter::Error);
+/// ```
+pub fn resolve_level(explicit: Option<LevelFilter>, verbose: u8, quiet: u8) -> LevelFilter {
+    const LEVELS: &[LevelFilter] = &[
+        LevelFilter::Off,
+        LevelFilter::Error,
+        LevelFilter::Warn,
+        LevelFilter::Info,
+        LevelFilter::Debug,
+        LevelFilter::Trace,
+    ];
+
+    if let Some(level) = explicit {
+        return level;
+    }
+
+    let index = (3 + verbose as isize - quiet as isize).max(0) as usize;
+
+    LEVELS[index.min(LEVELS.len() - 1)]
 }
 
 #[derive(Copy, Clone)]
@@ -89,81 +299,157 @@ impl Default for Colors {
 pub fn setup_logging<T: LogDataTrait>(log: &T) -> Result<(), Box<dyn std::error::Error>> {
     let colors = Colors::default();
 
-    let cli_dispatch = configure_cli_dispatch(colors, log);
+    let cli_dispatch = configure_cli_dispatch(colors, log, log.stream());
+    let mut dispatch = fern::Dispatch::new().chain(cli_dispatch);
 
+    if log.file_level() != &LevelFilter::Off {
+        dispatch = dispatch.chain(configure_file_dispatch(log)?);
+    }
+
+    dispatch.apply()?;
+
+    debug!("Finished configuring logging");
+
+    Ok(())
+}
+
+fn configure_file_dispatch<T: LogDataTrait>(
+    log: &T,
+) -> Result<fern::Dispatch, Box<dyn std::error::Error>> {
     if log.path().exists() {
         let _ = std::fs::remove_file(log.path());
+    } else if let Some(parent) = log.path().parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
     }
 
-    let mut file_log = fern::Dispatch::new()
-        .format(move |out, message, record| {
+    let mut file_log = if log.format() == &LogFormat::Json {
+        fern::Dispatch::new().format(|out, message, record| {
+            out.finish(format_args!("{}", format_json(message, record)))
+        })
+    } else {
+        fern::Dispatch::new().format(move |out, message, record| {
             out.finish(format_args!(
-                "[{}] {} T[{:?}] [{}] {}:{}: {}",
+                "[{}] {} T[{:?}] [{}] {}:{}: {}{}",
                 chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.6f %:z"),
                 record.level(),
                 std::thread::current().name().unwrap_or("<unnamed>"),
                 record.module_path().unwrap_or("<unnamed>"),
                 record.file().unwrap_or("<unnamed>"),
                 record.line().unwrap_or(0),
-                Paint::wrapping(message).wrap()
+                package_prefix(),
+                strip_colors(&message.to_string())
             ));
         })
-        .level(LevelFilter::Trace);
+    }
+    .level(*log.file_level());
 
     for level in get_levels() {
-        file_log = file_log.level_for(level.0, level.1);
+        file_log = file_log.level_for(level.0, level.1.min(*log.file_level()));
     }
-    file_log = file_log.chain(fern::log_file(log.path())?);
 
-    fern::Dispatch::new()
-        .chain(cli_dispatch)
-        .chain(file_log)
-        .apply()?;
+    Ok(file_log.chain(fern::log_file(log.path())?))
+}
 
-    debug!("Finished configuring logging");
+/// Removes any ansi color codes from the specified message, to prevent color
+/// codes from being written to the log file.
+fn strip_colors(message: &str) -> Cow<'_, str> {
+    lazy_static! {
+        static ref ANSI_CODES: Regex = Regex::new(r"\x1B\[[0-9;]*[A-Za-z]").unwrap();
+    }
 
-    Ok(())
+    ANSI_CODES.replace_all(message, "")
 }
 
-fn configure_cli_dispatch<T: LogDataTrait>(colors: Colors, log: &T) -> fern::Dispatch {
-    let mut cli_info = if log.level() > &LevelFilter::Info {
+/// Formats the specified record as a single line json object.
+fn format_json(message: &Arguments, record: &Record) -> String {
+    json!({
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "thread": std::thread::current().name(),
+        "package": package_id(),
+        "message": strip_colors(&message.to_string()),
+    })
+    .to_string()
+}
+
+fn configure_json_dispatch<T: LogDataTrait>(log: &T, stream: LogStream) -> fern::Dispatch {
+    let mut json_log = fern::Dispatch::new()
+        .format(|out, message, record| out.finish(format_args!("{}", format_json(message, record))))
+        .level(log.level());
+
+    for level in get_levels() {
+        json_log = json_log.level_for(level.0, level.1.min(log.level()));
+    }
+
+    json_log
+        .chain(
+            fern::Dispatch::new()
+                .filter(move |metadata| metadata.level() >= Level::Info)
+                .chain(stream.output(Level::Info)),
+        )
+        .chain(
+            fern::Dispatch::new()
+                .filter(move |metadata| metadata.level() <= Level::Warn)
+                .chain(stream.output(Level::Warn)),
+        )
+}
+
+fn configure_cli_dispatch<T: LogDataTrait>(
+    colors: Colors,
+    log: &T,
+    stream: LogStream,
+) -> fern::Dispatch {
+    if log.format() == &LogFormat::Json {
+        return configure_json_dispatch(log, stream);
+    }
+
+    let mut cli_info = if log.level() > LevelFilter::Info {
         fern::Dispatch::new().format(move |out, message, record| {
             let level = record.level();
             out.finish(format_args!(
-                "[{}]: {}",
+                "[{}]: {}{}",
                 colors.paint_level(level),
+                package_prefix(),
                 colors.paint(&level, message)
             ));
         })
     } else {
         fern::Dispatch::new().format(move |out, message, record| {
-            out.finish(format_args!("{}", colors.paint(&record.level(), message)))
+            out.finish(format_args!(
+                "{}{}",
+                package_prefix(),
+                colors.paint(&record.level(), message)
+            ))
         })
     }
     .filter(move |metadata| metadata.level() >= Level::Info)
-    .level(*log.level());
+    .level(log.level());
 
-    if log.level() > &LevelFilter::Info {
+    if log.level() > LevelFilter::Info {
         for level in get_levels() {
             cli_info = cli_info.level_for(level.0, level.1);
         }
     }
 
-    cli_info = cli_info.chain(std::io::stdout());
+    cli_info = cli_info.chain(stream.output(Level::Info));
 
     fern::Dispatch::new().chain(cli_info).chain(
         fern::Dispatch::new()
             .format(move |out, message, record| {
                 let level = record.level();
                 out.finish(format_args!(
-                    "[{}]: {}",
+                    "[{}]: {}{}",
                     colors.paint_level(level),
+                    package_prefix(),
                     colors.paint(&level, message)
                 ));
             })
             .filter(move |metadata| metadata.level() <= Level::Warn)
-            .level(*log.level())
-            .chain(std::io::stderr()),
+            .level(log.level())
+            .chain(stream.output(Level::Warn)),
     )
 }
 
@@ -176,3 +462,102 @@ fn get_levels() -> &'static [(&'static str, LevelFilter)] {
         ("reqwest::blocking::wait", LevelFilter::Debug),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn format_record(args: Arguments, level: Level, target: &str) -> serde_json::Value {
+        let record = Record::builder()
+            .args(args)
+            .level(level)
+            .target(target)
+            .build();
+
+        serde_json::from_str(&format_json(record.args(), &record)).unwrap()
+    }
+
+    #[rstest]
+    #[case(None, 0, 0, LevelFilter::Info)]
+    #[case(None, 1, 0, LevelFilter::Debug)]
+    #[case(None, 3, 0, LevelFilter::Trace)]
+    #[case(None, 0, 2, LevelFilter::Error)]
+    #[case(None, 0, 5, LevelFilter::Off)]
+    #[case(None, 1, 1, LevelFilter::Info)]
+    #[case(Some(LevelFilter::Warn), 2, 0, LevelFilter::Warn)]
+    fn resolve_level_should_compose_verbose_and_quiet(
+        #[case] explicit: Option<LevelFilter>,
+        #[case] verbose: u8,
+        #[case] quiet: u8,
+        #[case] expected: LevelFilter,
+    ) {
+        assert_eq!(resolve_level(explicit, verbose, quiet), expected);
+    }
+
+    #[rstest]
+    #[case(LogStream::Split, Level::Info, false)]
+    #[case(LogStream::Split, Level::Trace, false)]
+    #[case(LogStream::Split, Level::Warn, true)]
+    #[case(LogStream::Split, Level::Error, true)]
+    #[case(LogStream::Stderr, Level::Info, true)]
+    #[case(LogStream::Stderr, Level::Error, true)]
+    #[case(LogStream::Stdout, Level::Info, false)]
+    #[case(LogStream::Stdout, Level::Error, false)]
+    fn uses_stderr_should_select_stream_by_level(
+        #[case] stream: LogStream,
+        #[case] level: Level,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(stream.uses_stderr(level), expected);
+    }
+
+    #[rstest]
+    #[case("split", LogStream::Split)]
+    #[case("STDERR", LogStream::Stderr)]
+    #[case(" stdout ", LogStream::Stdout)]
+    fn log_stream_should_parse_supported_values(#[case] val: &str, #[case] expected: LogStream) {
+        assert_eq!(val.parse(), Ok(expected));
+    }
+
+    #[test]
+    fn strip_colors_should_remove_ansi_codes() {
+        let message = format!("{} and {}", Paint::red("-old"), Paint::green("+new").bold());
+
+        let actual = strip_colors(&message);
+
+        assert_eq!(actual, "-old and +new");
+    }
+
+    #[test]
+    fn format_json_should_include_package_id() {
+        set_package_context("test-package");
+
+        let actual = format_record(
+            format_args!("Updated to {}", "1.0.0"),
+            Level::Info,
+            "aer::update",
+        );
+        clear_package_context();
+
+        assert_eq!(actual["level"], "INFO");
+        assert_eq!(actual["target"], "aer::update");
+        assert_eq!(actual["package"], "test-package");
+        assert_eq!(actual["message"], "Updated to 1.0.0");
+    }
+
+    #[test]
+    fn format_json_should_use_null_without_package_context() {
+        let actual = format_record(format_args!("Finished"), Level::Debug, "aer");
+
+        assert_eq!(actual["package"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn strip_colors_should_keep_messages_without_colors() {
+        let actual = strip_colors("[test] Updated 'test.nuspec'");
+
+        assert_eq!(actual, "[test] Updated 'test.nuspec'");
+    }
+}
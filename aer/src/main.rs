@@ -1,144 +1,1704 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 #![windows_subsystem = "console"]
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use aer::{log_data, logging};
+use aer::check::{check_package, CheckedVersion};
+use aer::errors::{ExitCode, UpdateError};
+use aer::logging::LogStream;
+use aer::package_files::expand_package_files;
+use aer::progress::ProgressMode;
+use aer::report::{compare_reports, PreviousReport, ReportDelta, ReportedPackage};
+use aer::request::RequestData;
+use aer::scaffold::{create_package_content, create_package_file};
+use aer::update::{
+    describe_parse_urls, get_manifest_path, get_nuspec_path, get_package_dir, update_package,
+    ArchFailurePolicy, CollisionPolicy, DownloadedFile, FileDiff, UpdateOptions,
+    DEFAULT_OUTPUT_TEMPLATE,
+};
+use aer::verify::{verify_package, VerifiedFile};
+use aer::{colors, config, git, interrupt, log_data, logging, ChecksumType};
 use aer_upd::data::*;
-use aer_upd::parsers;
-use aer_upd::web::{WebRequest, WebResponse};
+use aer_upd::parsers::errors::ParserError;
+use aer_upd::parsers::{self, DataFormat};
+use aer_upd::updaters::chocolatey::get_nuspec_version;
+use aer_upd::updaters::scoop::get_manifest_version;
+use aer_upd::validators::{self, RuleSet};
+use aer_upd::web::partial::remove_partial_files;
+use aer_upd::web::WebRequest;
 #[cfg(feature = "human")]
 use human_panic::setup_panic;
-use log::{error, info, trace, warn};
-use regex::Regex;
+use log::{error, info, warn};
+use serde::Serialize;
+use structopt::clap::Shell;
 use structopt::StructOpt;
 use yansi::Paint;
 
-log_data! {}
+log_data! { env!("CARGO_PKG_NAME"), "stderr" }
+
+/// The package file that causes the package data to be read from stdin.
+const STDIN_FILE: &str = "-";
+
+/// How long the update workers are given to stop after the deadline of the
+/// run was exceeded, before they are abandoned.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(5);
 
 #[derive(StructOpt)]
-#[structopt(author = env!("CARGO_PKG_AUTHORS"))]
-struct Arguments {
+struct UpdateArguments {
     /// The files containing the necessary data (metadata+updater data) that
-    /// should be used during the run.
+    /// should be used during the run. Directories are searched recursively for
+    /// supported package files, and glob patterns are expanded. Use `-` to
+    /// read a single package definition from stdin, the paths in the package
+    /// data are then relative to the current directory.
     #[structopt(required = true, parse(from_os_str))]
     package_files: Vec<PathBuf>,
 
+    /// The names of files or directories that should be ignored when
+    /// searching directories or expanding glob patterns, can be specified
+    /// multiple times and may contain wildcards.
+    #[structopt(long, number_of_values = 1)]
+    ignore: Vec<String>,
+
+    /// The format of the package data that is read from stdin.
+    /// [default: detected from the content]
+    #[structopt(long, possible_values = DataFormat::variants_str())]
+    data_format: Option<DataFormat>,
+
+    /// The number of package files that should be updated concurrently.
+    /// [default: the number of available CPUs]
+    #[structopt(long, short, env = "AER_JOBS")]
+    jobs: Option<usize>,
+
+    /// The maximum duration of the entire run (*ie: `30m` or `1h 30m`*).
+    /// When exceeded, the package files that are not finished are reported
+    /// as timed out and the process exits with the exit code `9`, the results
+    /// of the finished package files are kept.
+    #[structopt(long, parse(try_from_str = humantime::parse_duration), env = "AER_DEADLINE")]
+    deadline: Option<Duration>,
+
+    /// The number of files of a single package that should be downloaded
+    /// concurrently. When a download fails the other downloads are allowed to
+    /// finish, before the package is reported as failed.
+    #[structopt(long, default_value = "3", env = "AER_DOWNLOAD_JOBS")]
+    download_jobs: usize,
+
+    /// Include pre-release versions when finding the latest version, even
+    /// for packages that do not allow them.
+    #[structopt(long)]
+    pre: bool,
+
+    /// The directory where the files of each package will be downloaded to.
+    /// Every package will get their own sub directory created from the output
+    /// template. [default: %TEMP%/aer]
+    #[structopt(long, short, parse(from_os_str), env = "AER_OUTPUT_DIR")]
+    output: Option<PathBuf>,
+
+    /// The template of the sub directory in the output directory that the
+    /// files of each package are downloaded to, where `{{id}}` and
+    /// `{{version}}` are replaced with the identifier and the found version
+    /// of the package (*ie: `{{id}}/{{version}}`*). The directory is created
+    /// when the first file is downloaded.
+    #[structopt(long, default_value = DEFAULT_OUTPUT_TEMPLATE, env = "AER_OUTPUT_TEMPLATE")]
+    output_template: String,
+
+    /// The way files that already exist in the output directory are handled.
+    /// `skip` reuses existing files with the same size, `overwrite` always
+    /// downloads the files again and `error` fails the package.
+    #[structopt(long, default_value, possible_values = CollisionPolicy::variants_str())]
+    on_collision: CollisionPolicy,
+
+    /// The way a package is updated when the files of some of the
+    /// architectures could not be downloaded. `all-or-nothing` fails the
+    /// package without changing any package files, while `best-effort`
+    /// updates the architectures that succeeded and warns about the rest.
+    #[structopt(long, default_value, possible_values = ArchFailurePolicy::variants_str())]
+    on_arch_failure: ArchFailurePolicy,
+
+    /// Run the full update of packages where the found version is not newer
+    /// than the current version, and download files even if a file with the
+    /// same name and size already exists in the output directory. By default
+    /// the downloads, script updates and hooks are skipped for unchanged
+    /// packages.
+    #[structopt(long)]
+    force: bool,
+
+    /// The set of rules the package data is validated against before the
+    /// update is started.
+    #[structopt(long, default_value, possible_values = RuleSet::variants_str())]
+    rule_set: RuleSet,
+
+    /// Do not run any of the before or after hooks specified in the package
+    /// files.
+    #[structopt(long)]
+    no_hooks: bool,
+
+    /// The maximum duration each hook is allowed to run (*ie: `30s` or
+    /// `5m`*), before the hook is killed and treated as failed. A timeout
+    /// specified in the package file is used instead when available.
+    #[structopt(
+        long,
+        default_value = "10m",
+        parse(try_from_str = humantime::parse_duration),
+        env = "AER_HOOK_TIMEOUT"
+    )]
+    hook_timeout: Duration,
+
+    /// Output the unified diff of the changes made to the package files. The
+    /// diffs are always outputted during a dry run, and are always included
+    /// in the json output and the report.
+    #[structopt(long)]
+    diff: bool,
+
+    /// Write a checksum file using the specified checksum type (*ie:
+    /// `SHA256SUMS` for `sha256`*) in the directory the files of each package
+    /// are downloaded to. The file lists the checksum and name of every
+    /// downloaded file, in the same format as `sha256sum` and similar tools.
+    #[structopt(long, possible_values = ChecksumType::variants_str())]
+    checksum_file: Option<ChecksumType>,
+
+    /// Treat package files where no update was found as failures, and exit
+    /// with the exit code `7` when none of the package files was updated.
+    /// Links matched by a regex without a parsable version are also treated
+    /// as failures, exiting with the exit code `6`, as are regexes that do not
+    /// match any of the links, exiting with the exit code `11`.
+    #[structopt(long)]
+    strict: bool,
+
+    /// Exit with the exit code `7` when every package file was processed
+    /// successfully, but none of the package files was updated. Unlike
+    /// `--strict`, package files without an update are not treated as
+    /// failures, and failures still use the exit code of the failure.
+    #[structopt(long)]
+    fail_on_no_update: bool,
+
+    /// Only update the package files with the specified package identifier,
+    /// can be specified multiple times.
+    #[structopt(long, number_of_values = 1)]
+    only: Vec<String>,
+
+    /// Skip the package files with the specified package identifier, can be
+    /// specified multiple times.
+    #[structopt(long, number_of_values = 1)]
+    skip: Vec<String>,
+
+    #[structopt(flatten)]
+    tags: TagArguments,
+
+    /// Commit the files that was changed by the update of each package to
+    /// the git repository containing the package file.
+    #[structopt(long)]
+    git_commit: bool,
+
+    /// The directory of the git repository the changed files should be
+    /// committed to. [default: the directory of the package file]
+    #[structopt(long, parse(from_os_str), env = "AER_GIT_REPO")]
+    git_repo: Option<PathBuf>,
+
+    /// The template of the commit message, where `{{id}}`, `{{old_version}}`
+    /// and `{{new_version}}` are replaced with the values of the updated
+    /// package.
+    #[structopt(long, default_value = git::DEFAULT_COMMIT_MESSAGE)]
+    git_message: String,
+
+    /// Write a report summarizing the update of every package file to the
+    /// specified path. The report is written as `json` or `toml` depending
+    /// on the extension of the path, and is written even when some of the
+    /// package files fail.
+    #[structopt(long, parse(from_os_str))]
+    report: Option<PathBuf>,
+
+    /// Compare the results against the report written by a previous run with
+    /// `--report`. The packages that changed, started failing or stopped
+    /// resolving a version since the previous run are included in the
+    /// summary and the report.
+    #[structopt(long, parse(from_os_str))]
+    previous: Option<PathBuf>,
+
+    /// Only exit with a failure exit code when packages fail that did not
+    /// fail in the previous run.
+    #[structopt(long, requires = "previous")]
+    only_new_failures: bool,
+}
+
+#[derive(StructOpt)]
+struct ValidateArguments {
+    /// The files containing the package data that should be validated.
+    /// Directories are searched recursively for supported package files, and
+    /// glob patterns are expanded.
+    #[structopt(required = true, parse(from_os_str))]
+    package_files: Vec<PathBuf>,
+
+    /// The names of files or directories that should be ignored when
+    /// searching directories or expanding glob patterns, can be specified
+    /// multiple times and may contain wildcards.
+    #[structopt(long, number_of_values = 1)]
+    ignore: Vec<String>,
+
+    /// The set of rules the package data should be validated against.
+    #[structopt(long, default_value, possible_values = RuleSet::variants_str())]
+    rule_set: RuleSet,
+}
+
+#[derive(StructOpt)]
+struct VerifyArguments {
+    /// The files containing the package data of the packages that should be
+    /// verified. Directories are searched recursively for supported package
+    /// files, and glob patterns are expanded.
+    #[structopt(required = true, parse(from_os_str))]
+    package_files: Vec<PathBuf>,
+
+    /// The names of files or directories that should be ignored when
+    /// searching directories or expanding glob patterns, can be specified
+    /// multiple times and may contain wildcards.
+    #[structopt(long, number_of_values = 1)]
+    ignore: Vec<String>,
+
+    /// The directory where the files of each package will be downloaded to.
+    /// Every package will get their own sub directory named after the package
+    /// identifier. [default: %TEMP%/aer]
+    #[structopt(long, short, parse(from_os_str), env = "AER_OUTPUT_DIR")]
+    output: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+struct CheckArguments {
+    /// The files containing the package data of the packages that should be
+    /// checked. Directories are searched recursively for supported package
+    /// files, and glob patterns are expanded.
+    #[structopt(required = true, parse(from_os_str))]
+    package_files: Vec<PathBuf>,
+
+    /// The names of files or directories that should be ignored when
+    /// searching directories or expanding glob patterns, can be specified
+    /// multiple times and may contain wildcards.
+    #[structopt(long, number_of_values = 1)]
+    ignore: Vec<String>,
+
+    #[structopt(flatten)]
+    tags: TagArguments,
+
+    /// Include pre-release versions when finding the latest version, even
+    /// for packages that do not allow them.
+    #[structopt(long)]
+    pre: bool,
+
+    /// Exit with the exit code `10` when a newer version is available for
+    /// any of the packages.
+    #[structopt(long)]
+    fail_on_update: bool,
+}
+
+#[derive(StructOpt)]
+struct ListArguments {
+    /// The files containing the package data that should be listed.
+    /// Directories are searched recursively for supported package files, and
+    /// glob patterns are expanded.
+    #[structopt(required = true, parse(from_os_str))]
+    package_files: Vec<PathBuf>,
+
+    /// The names of files or directories that should be ignored when
+    /// searching directories or expanding glob patterns, can be specified
+    /// multiple times and may contain wildcards.
+    #[structopt(long, number_of_values = 1)]
+    ignore: Vec<String>,
+
+    #[structopt(flatten)]
+    tags: TagArguments,
+}
+
+/// The options for selecting packages by the tags in their metadata.
+#[derive(StructOpt)]
+struct TagArguments {
+    /// Only select the packages tagged with the specified tag, can be
+    /// specified multiple times. Packages tagged with any of the specified
+    /// tags are selected, unless `--all-tags` is used. Tags are compared case
+    /// insensitively.
+    #[structopt(long, number_of_values = 1)]
+    tag: Vec<String>,
+
+    /// Only select the packages tagged with every tag specified with `--tag`,
+    /// instead of the packages tagged with any of the tags.
+    #[structopt(long, requires = "tag")]
+    all_tags: bool,
+}
+
+#[derive(StructOpt)]
+struct NewArguments {
+    /// The identifier of the package that should be created.
+    id: String,
+
+    /// The path to the package file that should be created.
+    /// [default: <ID>/<ID>.aer.toml]
+    #[structopt(long, short, parse(from_os_str))]
+    output: Option<PathBuf>,
+
+    /// Overwrite the package file if it already exists.
+    #[structopt(long)]
+    force: bool,
+}
+
+#[derive(StructOpt)]
+struct CompletionsArguments {
+    /// The shell to generate the completion script for.
+    #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+    shell: Shell,
+}
+
+/// The summary of the update of a single package file.
+#[derive(Default, Serialize)]
+struct UpdateResult {
+    file: PathBuf,
+    id: Option<String>,
+    old_version: Option<String>,
+    new_version: Option<String>,
+    changed: bool,
+    /// The files that was downloaded, keyed by the name of the architecture.
+    files: BTreeMap<String, DownloadedFile>,
+    /// The changes that was (*or would be*) made to the package files.
+    diffs: Vec<FileDiff>,
+    /// The architectures where the file could not be downloaded, when the
+    /// package was updated with the architectures that succeeded.
+    failed_files: BTreeMap<String, String>,
+    /// The keys of the regexes that did not match any of the links found.
+    unmatched: Vec<String>,
+    error: Option<String>,
+    /// The category of the error, if the update failed.
+    error_kind: Option<&'static str>,
+    #[serde(skip)]
+    exit_code: ExitCode,
+    /// Wether the update was run without making any changes.
+    dry_run: bool,
+    /// Wether the update was not finished before the deadline of the run was
+    /// exceeded.
+    timed_out: bool,
+    /// The number of milliseconds the update of the package file took.
+    duration_ms: Option<u64>,
+    /// Wether the package file was excluded by the `--only`, `--skip` or
+    /// `--tag` filters.
+    #[serde(skip)]
+    skipped: bool,
+}
+
+impl UpdateResult {
+    /// Gets the parts of the result that are compared against the results of
+    /// a previous run.
+    fn reported(&self) -> ReportedPackage {
+        ReportedPackage {
+            file: self.file.clone(),
+            id: self.id.clone(),
+            new_version: self.new_version.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// The report written to the path specified by `--report`, containing the
+/// results of every package file in the run.
+#[derive(Serialize)]
+struct UpdateReport<'a> {
+    started: String,
+    duration_ms: u64,
+    dry_run: bool,
+    /// The differences against the previous run, when compared against the
+    /// report of a previous run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta: Option<&'a ReportDelta>,
+    packages: &'a [UpdateResult],
+}
+
+/// The formats that the update report can be written as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Json,
+    Toml,
+}
+
+impl ReportFormat {
+    /// Gets the format of the report from the extension of the specified
+    /// path.
+    fn from_path(path: &Path) -> Option<ReportFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "json" => Some(ReportFormat::Json),
+            "toml" => Some(ReportFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// The summary of the verification of a single package file.
+#[derive(Default, Serialize)]
+struct VerifyResult {
+    file: PathBuf,
+    id: Option<String>,
+    files: Vec<VerifiedFile>,
+    error: Option<String>,
+    /// The category of the error, if the package file could not be verified.
+    error_kind: Option<&'static str>,
+    #[serde(skip)]
+    exit_code: ExitCode,
+}
+
+/// The summary of the check of a single package.
+#[derive(Default, Serialize)]
+struct CheckResult {
+    file: PathBuf,
+    id: Option<String>,
+    /// The versions that was found by each updater of the package.
+    versions: Vec<CheckedVersion>,
+    error: Option<String>,
+    /// The category of the error, if the package could not be checked.
+    error_kind: Option<&'static str>,
+    #[serde(skip)]
+    exit_code: ExitCode,
+    /// Wether the package was excluded by the `--tag` filter.
+    #[serde(skip)]
+    skipped: bool,
+}
+
+/// The information about a single package file that is outputted by the
+/// `list` command.
+#[derive(Default, Serialize)]
+struct PackageSummary {
+    file: PathBuf,
+    id: Option<String>,
+    version: Option<String>,
+    parse_url: Option<String>,
+    updaters: Vec<&'static str>,
+    tags: Vec<String>,
+    error: Option<String>,
+    /// Wether the package was excluded by the `--tag` filter.
+    #[serde(skip)]
+    skipped: bool,
+}
+
+/// The formats the result of the update can be outputted as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("The value is not a supported output format!"),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => f.write_str("text"),
+            OutputFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+/// Filters the package files that should be updated by the identifier and
+/// the tags of their package.
+#[derive(Default)]
+struct PackageFilter {
+    only: Vec<String>,
+    skip: Vec<String>,
+    tags: Vec<String>,
+    /// Wether the package must be tagged with every tag, instead of any of
+    /// the tags.
+    all_tags: bool,
+}
+
+impl PackageFilter {
+    /// Creates a filter that only selects packages by their tags.
+    fn with_tags(args: TagArguments) -> PackageFilter {
+        PackageFilter {
+            tags: args.tag,
+            all_tags: args.all_tags,
+            ..PackageFilter::default()
+        }
+    }
+
+    /// Checks wether the package with the specified metadata is selected by
+    /// the `only`, `skip` and `tag` filters. Identifiers and tags are
+    /// compared case insensitively.
+    fn is_selected(&self, metadata: &PackageMetadata) -> bool {
+        let id = metadata.id();
+        let matches = |ids: &[String]| ids.iter().any(|i| i.eq_ignore_ascii_case(id));
+        let tagged = if self.tags.is_empty() {
+            true
+        } else if self.all_tags {
+            self.tags.iter().all(|tag| metadata.has_tag(tag))
+        } else {
+            self.tags.iter().any(|tag| metadata.has_tag(tag))
+        };
+
+        (self.only.is_empty() || matches(&self.only)) && !matches(&self.skip) && tagged
+    }
+}
+
+#[derive(StructOpt)]
+enum Commands {
+    /// Runs the update process for each of the specified package files.
+    ///
+    /// The process exits with `0` on success (*wether any package file was
+    /// updated or not*), `2` on parse errors, `3` on validation errors, `4`
+    /// on network errors, `5` when no url have been specified, `6` on
+    /// invalid regexes, `7` when no update was found in strict mode or with
+    /// `--fail-on-no-update`, `8` when a downloaded file do not match the
+    /// expected checksum, `9` when the deadline of the run was exceeded, `11`
+    /// when a regex did not match any links in strict mode and `130` when the
+    /// process was interrupted. When the package files fail with different
+    /// categories of errors, the process exits with `1`.
+    Update(UpdateArguments),
+    /// Validates each of the specified package files, without running any
+    /// part of the update process.
+    Validate(ValidateArguments),
+    /// Downloads the files published by each of the specified package files
+    /// again, and verifies that the files still have the published checksums.
+    ///
+    /// The urls and checksums are read from the install script and the scoop
+    /// manifest of each package. The process exits with `8` when the checksum
+    /// of any file have changed, and with the exit code of the error when a
+    /// file could not be downloaded.
+    Verify(VerifyArguments),
+    /// Checks wether a newer version is available for each of the specified
+    /// package files, without downloading any files or changing any of the
+    /// package files.
+    ///
+    /// The latest version found by each updater is compared against the
+    /// version in the nuspec file or the scoop manifest of the package. The
+    /// process exits with `10` when `--fail-on-update` is used and any
+    /// package have a newer version available, and with the exit code of the
+    /// error when a package could not be checked.
+    Check(CheckArguments),
+    /// Lists the identifier, current version, parse url and enabled updaters
+    /// of each of the specified package files, without making any web
+    /// requests.
+    List(ListArguments),
+    /// Creates a new package file with placeholder values for the metadata
+    /// and the chocolatey updater.
+    #[structopt(alias = "init")]
+    New(NewArguments),
+    /// Writes the completion script for the specified shell to stdout.
+    Completions(CompletionsArguments),
+}
+
+#[derive(StructOpt)]
+#[structopt(author = env!("CARGO_PKG_AUTHORS"))]
+struct Arguments {
+    #[structopt(subcommand)]
+    cmd: Commands,
+
     #[structopt(flatten)]
     log: LogData,
+
+    #[structopt(flatten)]
+    request: RequestData,
+
+    /// The format to output the result of an update as. When using `json`, the
+    /// summary of all package files is written to stdout while any log
+    /// messages are written to stderr, unless another `--log-stream` is used.
+    #[structopt(long, global = true, default_value, possible_values = &["text", "json"])]
+    format: OutputFormat,
+
+    /// Report what would be done without making any changes. No files are
+    /// downloaded or written, no hooks are run and the changes that would be
+    /// made to the package files are outputted instead.
+    #[structopt(long, global = true)]
+    dry_run: bool,
+
+    /// Disable the usage of colors when outputting text to the console.
+    #[structopt(long, global = true)]
+    no_color: bool,
+
+    /// The configuration file holding the defaults of the global options
+    /// (*ie: `timeout = 60`*), where each key is the name of an option using
+    /// underscores instead of dashes. Options on the command line takes
+    /// precedence over environment variables, which takes precedence over the
+    /// configuration file. [default: `aer/config.toml` in the configuration
+    /// directory of the user]
+    #[structopt(long, global = true, env = "AER_CONFIG", parse(from_os_str))]
+    // The configuration file is read before the arguments are parsed, this
+    // is only used to show the option in the help.
+    #[allow(dead_code)]
+    config: Option<PathBuf>,
 }
 
 fn main() {
     #[cfg(feature = "human")]
     setup_panic!();
-    if cfg!(windows) && !Paint::enable_windows_ascii() {
-        Paint::disable();
+    let raw_args: Vec<OsString> = std::env::args_os().collect();
+    let config = match config::find_config(&raw_args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::Failure.exit();
+        }
+    };
+    let matches = Arguments::clap().get_matches_from(&raw_args);
+    let args = match config {
+        Some(ref config) => Arguments::from_iter(config.merge_args(&raw_args, &matches)),
+        None => Arguments::from_clap(&matches),
+    };
+    colors::configure_colors(args.no_color);
+    logging::setup_logging(&args.log).expect("Unable to configure logging of the application!");
+    interrupt::install_handler();
+    if args.format == OutputFormat::Json && args.log.stream != LogStream::Stderr {
+        warn!(
+            "Log records are written to stdout with '--log-stream {}', and will be mixed with \
+             the json output!",
+            args.log.stream
+        );
+    }
+    if let Some(config) = config {
+        info!(
+            "Using the defaults from the configuration file '{}'",
+            config.path.display()
+        );
     }
 
-    let args = Arguments::from_args();
-    logging::setup_logging(&args.log).expect("Unable to configure logging of the application!");
+    let mut request = args.request;
+    if args.dry_run {
+        // The responses are not cached during a dry run, as that would
+        // require writing them to disk.
+        request.no_cache = true;
+    }
+
+    match args.cmd {
+        Commands::Update(update_args) => update_cmd(
+            request.create_request(),
+            update_args,
+            args.format,
+            args.dry_run,
+        ),
+        Commands::Validate(validate_args) => validate_cmd(validate_args),
+        Commands::Verify(verify_args) => {
+            verify_cmd(request.create_request(), verify_args, args.format)
+        }
+        Commands::Check(check_args) => check_cmd(request.create_request(), check_args, args.format),
+        Commands::List(list_args) => list_cmd(list_args, args.format),
+        Commands::New(new_args) => new_cmd(new_args, args.dry_run),
+        Commands::Completions(completions_args) => completions_cmd(completions_args),
+    }
+}
+
+fn completions_cmd(args: CompletionsArguments) {
+    Arguments::clap().gen_completions_to(
+        env!("CARGO_BIN_NAME"),
+        args.shell,
+        &mut std::io::stdout(),
+    );
+}
+
+fn new_cmd(args: NewArguments, dry_run: bool) {
+    let path = args
+        .output
+        .unwrap_or_else(|| PathBuf::from(&args.id).join(format!("{}.aer.toml", args.id)));
+
+    if dry_run {
+        info!("Content that would be written to '{}':", path.display());
+        for line in create_package_content(&args.id).lines() {
+            info!("{}", Paint::green(format!("+{}", line)));
+        }
+        return;
+    }
+
+    match create_package_file(&path, &args.id, args.force) {
+        Ok(()) => info!("Created the package file '{}'!", path.display()),
+        Err(err) => {
+            error!(
+                "Unable to create the package file '{}': {}",
+                path.display(),
+                err
+            );
+            ExitCode::Failure.exit();
+        }
+    }
+}
+
+fn validate_cmd(args: ValidateArguments) {
+    let package_files = get_package_files(&args.package_files, &args.ignore);
+    let mut failed = vec![];
+    let mut invalid_regexes = vec![];
 
-    // TODO: #11 Run updating on several threads
-    for file in args.package_files {
-        match run_update(&file) {
-            Err(err) => error!("An error occurred during update process: '{}'", err),
-            _ => {
-                todo!()
+    for package_file in &package_files {
+        let (violations, exit_code) = match parsers::read_packages(package_file) {
+            Ok(packages) => {
+                let mut violations = vec![];
+                for data in &packages {
+                    for violation in validators::find_invalid_regexes(data) {
+                        invalid_regexes.push((
+                            package_file,
+                            data.metadata().id().to_owned(),
+                            violation,
+                        ));
+                    }
+
+                    violations.extend(validators::validate(data, args.rule_set).into_iter().map(
+                        |violation| {
+                            if packages.len() > 1 {
+                                format!("{}: {}", data.metadata().id(), violation)
+                            } else {
+                                violation.to_string()
+                            }
+                        },
+                    ));
+                }
+
+                (violations, ExitCode::Validation)
+            }
+            Err(err) => (vec![err.to_string()], ExitCode::Parse),
+        };
+
+        if violations.is_empty() {
+            info!("'{}' is valid!", package_file.display());
+        } else {
+            failed.push(exit_code);
+            error!(
+                "'{}' have {} violation(s):",
+                package_file.display(),
+                violations.len()
+            );
+            for violation in violations {
+                error!("  - {}", violation);
             }
         }
     }
+
+    info!(
+        "Finished validating {} package files: {} valid, {} invalid!",
+        package_files.len(),
+        package_files.len() - failed.len(),
+        failed.len()
+    );
+
+    if !invalid_regexes.is_empty() {
+        error!(
+            "Found {} invalid regex(es) in the package files:",
+            invalid_regexes.len()
+        );
+        for (package_file, id, violation) in invalid_regexes {
+            error!("  - '{}' ({}): {}", package_file.display(), id, violation);
+        }
+    }
+
+    if !failed.is_empty() {
+        ExitCode::combine(failed).exit();
+    }
 }
 
-fn run_update(package_file: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Loading package data from '{}'", "yo");
+fn verify_cmd(request: WebRequest, args: VerifyArguments, format: OutputFormat) {
+    let package_files = get_package_files(&args.package_files, &args.ignore);
+    let options = UpdateOptions {
+        output_dir: args
+            .output
+            .unwrap_or_else(|| std::env::temp_dir().join("aer")),
+        progress: if format == OutputFormat::Json {
+            ProgressMode::Hidden
+        } else {
+            ProgressMode::Bar
+        },
+        ..UpdateOptions::default()
+    };
+    let mut results = vec![];
+
+    for package_file in package_files {
+        let packages: Vec<Result<PackageData, UpdateError>> =
+            match parsers::read_packages(&package_file) {
+                Ok(packages) => packages.into_iter().map(Ok).collect(),
+                Err(err) => vec![Err(UpdateError::from(err))],
+            };
+
+        for package in packages {
+            let mut result = VerifyResult {
+                file: package_file.clone(),
+                ..VerifyResult::default()
+            };
+            let verified = package.and_then(|data| {
+                result.id = Some(data.metadata().id().to_owned());
+                logging::set_package_context(data.metadata().id());
+                verify_package(&data, get_package_dir(&result.file), &request, &options)
+            });
+            logging::clear_package_context();
+
+            match verified {
+                Ok(files) => result.files = files,
+                Err(err) => {
+                    result.error = Some(err.to_string());
+                    result.error_kind = Some(err.kind());
+                    result.exit_code = err.exit_code();
+                }
+            }
+            results.push(result);
+        }
+    }
+
+    let mut exit_codes = vec![];
+    let (mut matching, mut changed, mut failed) = (0, 0, 0);
+
+    for result in &results {
+        if let Some(ref err) = result.error {
+            error!("Verifying '{}' failed: {}", result.file.display(), err);
+            exit_codes.push(result.exit_code);
+            continue;
+        }
+        if result.files.is_empty() {
+            warn!(
+                "No published files was found for '{}'!",
+                result.file.display()
+            );
+        }
+
+        for file in &result.files {
+            if file.matches() {
+                matching += 1;
+                info!("{} ({}): {} matches", file.url, file.arch, file.expected);
+            } else if file.is_changed() {
+                changed += 1;
+                exit_codes.push(file.exit_code);
+                error!(
+                    "{} ({}): The {} checksum have changed from {} to {}!",
+                    file.url,
+                    file.arch,
+                    file.checksum_type,
+                    file.expected,
+                    file.actual.as_deref().unwrap_or_default()
+                );
+            } else {
+                failed += 1;
+                exit_codes.push(file.exit_code);
+                error!(
+                    "{} ({}): {}",
+                    file.url,
+                    file.arch,
+                    file.error.as_deref().unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    if format == OutputFormat::Json {
+        match serde_json::to_string_pretty(&results) {
+            Ok(json) => println!("{}", json),
+            Err(err) => error!("Unable to create the json summary: {}", err),
+        }
+    }
 
-    let data = parsers::read_file(&package_file)?;
     info!(
-        "Successfully loaded package data with identifier '{}'!",
-        data.metadata().id()
+        "Finished verifying {} files of {} package files: {} matching, {} changed, {} failed!",
+        matching + changed + failed,
+        results.len(),
+        matching,
+        changed,
+        failed
     );
 
-    // TODO: #12 Validate data according to specified rule set, default would be
-    // Core
+    if changed > 0 {
+        ExitCode::ChecksumMismatch.exit();
+    }
+    if !exit_codes.is_empty() {
+        ExitCode::combine(exit_codes).exit();
+    }
+}
 
-    // TODO: #13 Run any global before hooks
+fn check_cmd(request: WebRequest, args: CheckArguments, format: OutputFormat) {
+    let package_files = get_package_files(&args.package_files, &args.ignore);
+    let filter = PackageFilter::with_tags(args.tags);
+    let options = UpdateOptions {
+        allow_prerelease: args.pre,
+        ..UpdateOptions::default()
+    };
+    let mut results = vec![];
 
-    let request = WebRequest::create();
+    for package_file in package_files {
+        let packages: Vec<Result<PackageData, UpdateError>> =
+            match parsers::read_packages(&package_file) {
+                Ok(packages) => packages.into_iter().map(Ok).collect(),
+                Err(err) => vec![Err(UpdateError::from(err))],
+            };
+
+        for package in packages {
+            let mut result = CheckResult {
+                file: package_file.clone(),
+                ..CheckResult::default()
+            };
+            let checked = package.and_then(|data| {
+                result.id = Some(data.metadata().id().to_owned());
+                result.skipped = !filter.is_selected(data.metadata());
+                if result.skipped {
+                    return Ok(vec![]);
+                }
+                logging::set_package_context(data.metadata().id());
+                check_package(&data, get_package_dir(&result.file), &request, &options)
+            });
+            logging::clear_package_context();
+
+            match checked {
+                Ok(versions) => result.versions = versions,
+                Err(err) => {
+                    error!("Checking '{}' failed: {}", result.file.display(), err);
+                    result.error = Some(err.to_string());
+                    result.error_kind = Some(err.kind());
+                    result.exit_code = err.exit_code();
+                }
+            }
+            if !result.skipped {
+                results.push(result);
+            }
+        }
+    }
+
+    if format == OutputFormat::Json {
+        match serde_json::to_string_pretty(&results) {
+            Ok(json) => println!("{}", json),
+            Err(err) => error!("Unable to create the json summary: {}", err),
+        }
+    } else {
+        print_check_results(&results);
+    }
+
+    let available = results
+        .iter()
+        .filter(|result| result.versions.iter().any(|v| v.update_available))
+        .count();
+    let failed: Vec<ExitCode> = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .map(|result| result.exit_code)
+        .collect();
+    info!(
+        "Finished checking {} packages: {} with updates available, {} up to date, {} failed!",
+        results.len(),
+        available,
+        results.len() - available - failed.len(),
+        failed.len()
+    );
+
+    if args.fail_on_update && available > 0 {
+        ExitCode::UpdateAvailable.exit();
+    }
+    if !failed.is_empty() {
+        ExitCode::combine(failed).exit();
+    }
+}
+
+/// Outputs the versions found by each updater of the specified check results
+/// as a table to stdout, the packages that could not be checked are not
+/// included.
+fn print_check_results(results: &[CheckResult]) {
+    let rows: Vec<Vec<String>> = results
+        .iter()
+        .filter(|result| result.error.is_none())
+        .flat_map(|result| {
+            let id = result.id.clone().unwrap_or_default();
+            result.versions.iter().map(move |version| {
+                vec![
+                    id.clone(),
+                    version.updater.into(),
+                    version
+                        .current_version
+                        .clone()
+                        .unwrap_or_else(|| "-".into()),
+                    version.latest_version.clone().unwrap_or_else(|| "-".into()),
+                    if version.update_available {
+                        "update available".into()
+                    } else if version.latest_version.is_none() {
+                        "unknown".into()
+                    } else {
+                        "up to date".into()
+                    },
+                ]
+            })
+        })
+        .collect();
+
+    print_table(&["ID", "UPDATER", "CURRENT", "LATEST", "STATUS"], &rows);
+}
+
+fn list_cmd(args: ListArguments, format: OutputFormat) {
+    let package_files = get_package_files(&args.package_files, &args.ignore);
+    let filter = PackageFilter::with_tags(args.tags);
+    let summaries: Vec<PackageSummary> = package_files
+        .into_iter()
+        .flat_map(|file| get_package_summaries(file, &filter))
+        .filter(|summary| !summary.skipped)
+        .collect();
+
+    for summary in &summaries {
+        if let Some(ref err) = summary.error {
+            error!("Unable to load '{}': {}", summary.file.display(), err);
+        }
+    }
+
+    if format == OutputFormat::Json {
+        match serde_json::to_string_pretty(&summaries) {
+            Ok(json) => println!("{}", json),
+            Err(err) => error!("Unable to create the json summary: {}", err),
+        }
+    } else {
+        print_package_summaries(&summaries);
+    }
+
+    if summaries.iter().any(|summary| summary.error.is_some()) {
+        ExitCode::Parse.exit();
+    }
+}
+
+/// Loads every package definition in the specified file, and collects a
+/// summary of each package. A single summary containing the error is returned
+/// when the file could not be loaded, and packages excluded by the specified
+/// filter are marked as skipped.
+fn get_package_summaries(package_file: PathBuf, filter: &PackageFilter) -> Vec<PackageSummary> {
+    match parsers::read_packages(&package_file) {
+        Ok(packages) => packages
+            .iter()
+            .map(|data| {
+                let mut summary = get_package_summary(package_file.clone(), data);
+                summary.skipped = !filter.is_selected(data.metadata());
+                summary
+            })
+            .collect(),
+        Err(err) => vec![PackageSummary {
+            file: package_file,
+            error: Some(err.to_string()),
+            ..PackageSummary::default()
+        }],
+    }
+}
+
+/// Collects the information about the specified package that is available
+/// without making any web requests.
+fn get_package_summary(package_file: PathBuf, data: &PackageData) -> PackageSummary {
+    let mut summary = PackageSummary {
+        file: package_file,
+        ..PackageSummary::default()
+    };
+    summary.id = Some(data.metadata().id().to_owned());
+    summary.tags = data.metadata().tags().to_vec();
 
     if data.updater().has_chocolatey() {
         let choco = data.updater().chocolatey();
-        let (_, urls) = match &choco.parse_url {
-            Some(chocolatey::ChocolateyParseUrl::Url(url)) => {
-                request.get_html_response(url.as_str())?.read(None)?
-            }
-            Some(chocolatey::ChocolateyParseUrl::UrlWithRegex { url, ref regex }) => {
-                info!("Parsing links on '{}' using regex '{}'", url, regex);
-                let (parent, urls) = request.get_html_response(url.as_str())?.read(Some(regex))?;
-                if !urls.is_empty() {
-                    info!("{} links found, using first one to get links!", urls.len());
-                    let url = urls.get(0).unwrap();
-                    info!("Parsing links on '{}'", url.link);
-                    request.get_html_response(url.link.as_str())?.read(None)?
+        let nuspec = get_nuspec_path(get_package_dir(&summary.file), data, &choco);
+        summary.updaters.push("chocolatey");
+        summary.parse_url = choco.parse_url.as_ref().map(describe_parse_urls);
+        summary.version = std::fs::read_to_string(&nuspec)
+            .ok()
+            .and_then(|content| get_nuspec_version(&content).ok());
+    }
+
+    if data.updater().has_scoop() {
+        let scoop = data.updater().scoop();
+        let manifest = get_manifest_path(get_package_dir(&summary.file), data, &scoop);
+        summary.updaters.push("scoop");
+        if summary.parse_url.is_none() {
+            summary.parse_url = scoop.parse_url.as_ref().map(describe_parse_urls);
+        }
+        if summary.version.is_none() {
+            summary.version = std::fs::read_to_string(&manifest)
+                .ok()
+                .and_then(|content| get_manifest_version(&content).ok());
+        }
+    }
+
+    summary
+}
+
+/// Outputs the specified package summaries as a table to stdout, the
+/// package files that could not be loaded are not included.
+fn print_package_summaries(summaries: &[PackageSummary]) {
+    let rows: Vec<Vec<String>> = summaries
+        .iter()
+        .filter(|summary| summary.error.is_none())
+        .map(|summary| {
+            vec![
+                summary.id.clone().unwrap_or_default(),
+                summary.version.clone().unwrap_or_else(|| "-".into()),
+                summary.parse_url.clone().unwrap_or_else(|| "-".into()),
+                if summary.updaters.is_empty() {
+                    "-".into()
+                } else {
+                    summary.updaters.join(", ")
+                },
+                if summary.tags.is_empty() {
+                    "-".into()
+                } else {
+                    summary.tags.join(", ")
+                },
+            ]
+        })
+        .collect();
+
+    print_table(&["ID", "VERSION", "PARSE URL", "UPDATERS", "TAGS"], &rows);
+}
+
+/// Outputs the specified rows as a table to stdout, where every column except
+/// the last is padded to the width of the widest value in the column.
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            rows.iter()
+                .map(|row| row[index].len())
+                .fold(header.len(), usize::max)
+        })
+        .collect();
+    let format_row = |values: Vec<&str>| {
+        let last = values.len() - 1;
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                if index == last {
+                    value.to_string()
                 } else {
-                    (parent, urls)
+                    format!("{:<width$}", value, width = widths[index])
                 }
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+    };
+
+    println!("{}", format_row(headers.to_vec()));
+    for row in rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
+
+fn update_cmd(request: WebRequest, args: UpdateArguments, format: OutputFormat, dry_run: bool) {
+    let started = chrono::Local::now();
+    let report = args
+        .report
+        .map(|path| match ReportFormat::from_path(&path) {
+            Some(report_format) => (path, report_format),
+            None => {
+                error!(
+                    "The report '{}' must have either a json or toml extension!",
+                    path.display()
+                );
+                ExitCode::Failure.exit();
             }
-            _ => {
-                warn!("No url have been specified to parse!");
-                std::process::exit(5);
-            }
+        });
+    let previous = args.previous.map(|path| match read_previous_report(&path) {
+        Ok(previous) => previous,
+        Err(err) => {
+            error!(
+                "Unable to read the previous report '{}': {}",
+                path.display(),
+                err
+            );
+            ExitCode::Failure.exit();
+        }
+    });
+    let package_files = if args
+        .package_files
+        .iter()
+        .any(|file| file == Path::new(STDIN_FILE))
+    {
+        if args.package_files.len() > 1 {
+            error!(
+                "Reading the package data from stdin can not be combined with other package files!"
+            );
+            ExitCode::Failure.exit();
+        }
+        vec![PathBuf::from(STDIN_FILE)]
+    } else {
+        get_package_files(&args.package_files, &args.ignore)
+    };
+    let jobs = args.jobs.unwrap_or_else(num_cpus::get).max(1);
+    let options = UpdateOptions {
+        allow_prerelease: args.pre,
+        output_dir: args
+            .output
+            .unwrap_or_else(|| std::env::temp_dir().join("aer")),
+        output_template: args.output_template,
+        collision: args.on_collision,
+        arch_failure: args.on_arch_failure,
+        force: args.force,
+        dry_run,
+        rule_set: args.rule_set,
+        run_hooks: !args.no_hooks,
+        hook_timeout: args.hook_timeout,
+        strict: args.strict,
+        progress: if format == OutputFormat::Json {
+            ProgressMode::Hidden
+        } else if jobs > 1 && package_files.len() > 1 {
+            ProgressMode::Log
+        } else {
+            ProgressMode::Bar
+        },
+        download_jobs: args.download_jobs.max(1),
+        git_commit: args.git_commit,
+        git_repo: args.git_repo,
+        git_message: args.git_message,
+        show_diff: args.diff,
+        checksum_file: args.checksum_file,
+    };
+    let filter = PackageFilter {
+        only: args.only.clone(),
+        skip: args.skip,
+        ..PackageFilter::with_tags(args.tags)
+    };
+    let results = run_updates(
+        request,
+        package_files,
+        jobs,
+        options,
+        filter,
+        args.data_format,
+        args.deadline,
+    );
+
+    for id in &args.only {
+        if !results
+            .iter()
+            .any(|r| r.id.as_ref().map_or(false, |i| i.eq_ignore_ascii_case(id)))
+        {
+            warn!("No package file with the identifier '{}' was found!", id);
+        }
+    }
+
+    let results: Vec<UpdateResult> = results.into_iter().filter(|r| !r.skipped).collect();
+
+    if results.is_empty() {
+        error!("No packages selected, all package files was excluded by the filters!");
+        ExitCode::Failure.exit();
+    }
+
+    let failed: Vec<&UpdateResult> = results
+        .iter()
+        .filter(|r| r.error.is_some() && !r.timed_out)
+        .collect();
+    let timed_out: Vec<&UpdateResult> = results.iter().filter(|r| r.timed_out).collect();
+    let unchanged = results
+        .iter()
+        .filter(|r| r.error.is_none() && !r.changed)
+        .count();
+
+    for result in &failed {
+        if let Some(ref err) = result.error {
+            error!("Updating '{}' failed: '{}'", result.file.display(), err);
+        }
+    }
+    for result in &timed_out {
+        error!("Updating '{}' timed out!", result.file.display());
+    }
+    for result in results.iter().filter(|r| !r.unmatched.is_empty()) {
+        warn!(
+            "The regexes of '{}' did not match any links for {}!",
+            result.file.display(),
+            result.unmatched.join(", ")
+        );
+    }
+
+    let delta = previous.map(|previous| {
+        let current: Vec<ReportedPackage> = results.iter().map(UpdateResult::reported).collect();
+        compare_reports(&previous.packages, &current)
+    });
+
+    if format == OutputFormat::Json {
+        match serde_json::to_string_pretty(&results) {
+            Ok(json) => println!("{}", json),
+            Err(err) => error!("Unable to create the json summary: {}", err),
+        }
+    }
+
+    if let Some((path, report_format)) = report {
+        let report = UpdateReport {
+            started: started.to_rfc3339(),
+            duration_ms: (chrono::Local::now() - started).num_milliseconds().max(0) as u64,
+            dry_run,
+            delta: delta.as_ref(),
+            packages: &results,
         };
 
-        let mut aarch32 = None;
-        let mut aarch64 = None;
-        let mut others = vec![];
+        match write_report(&path, report_format, &report) {
+            Ok(()) => info!("The report was written to '{}'", path.display()),
+            Err(err) => error!(
+                "Unable to write the report to '{}': {}",
+                path.display(),
+                err
+            ),
+        }
+    }
 
-        for (key, regex) in choco.regexes() {
-            trace!("Filtering {} urls using {}", key, regex);
-            let re = Regex::new(&regex)?;
-            let mut items = urls.iter().filter_map(|link| {
-                let capture = re.captures(link.link.as_str())?;
-                let mut new_link = link.clone();
+    info!(
+        "{}Finished updating {} package files: {} succeeded ({} unchanged), {} failed, {} \
+         timed out!",
+        if dry_run { "DRY RUN: " } else { "" },
+        results.len(),
+        results.len() - failed.len() - timed_out.len(),
+        unchanged,
+        failed.len(),
+        timed_out.len()
+    );
 
-                if let Ok(version) =
-                    Versions::parse(capture.name("version").map(|v| v.as_str()).unwrap_or(""))
-                {
-                    new_link.version = Some(version);
-                }
+    let (failed, timed_out) = match delta {
+        Some(ref delta) => {
+            log_delta(delta);
 
-                Some(new_link)
-            });
-            info!("Parsing urls matching '{}' for {}", regex, key);
-
-            if key.to_lowercase() == "arch32" {
-                info!("Taking first match if found!!");
-                aarch32 = items.next();
-            } else if key.to_lowercase() == "arch64" {
-                info!("Taking first match if found!!");
-                aarch64 = items.next();
+            if args.only_new_failures {
+                let is_new = |result: &&UpdateResult| {
+                    delta.newly_failing.contains(&result.reported().name())
+                };
+                (
+                    failed.into_iter().filter(is_new).collect(),
+                    timed_out.into_iter().filter(is_new).collect(),
+                )
             } else {
-                for link in items {
-                    others.push(link);
-                }
+                (failed, timed_out)
             }
-            if let Some(ref aarch32) = aarch32 {
-                info!("Arch 32: {}", aarch32.link);
-            } else {
-                info!("Arch 32: None")
+        }
+        None => (failed, timed_out),
+    };
+
+    if !timed_out.is_empty() {
+        ExitCode::Timeout.exit();
+    }
+    if !failed.is_empty() {
+        ExitCode::combine(failed.iter().map(|result| result.exit_code)).exit();
+    }
+    if args.fail_on_no_update && results.iter().all(|result| !result.changed) {
+        warn!("None of the package files was updated!");
+        ExitCode::NoUpdate.exit();
+    }
+}
+
+/// Reads the report written by a previous run with `--report`.
+fn read_previous_report(path: &Path) -> Result<PreviousReport, String> {
+    let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    match ReportFormat::from_path(path) {
+        Some(ReportFormat::Json) => serde_json::from_str(&content).map_err(|err| err.to_string()),
+        Some(ReportFormat::Toml) => toml::from_str(&content).map_err(|err| err.to_string()),
+        None => Err("The report must have either a json or toml extension!".into()),
+    }
+}
+
+/// Logs the differences between the current and the previous run.
+fn log_delta(delta: &ReportDelta) {
+    if delta.is_empty() {
+        info!("No differences was found compared to the previous run!");
+        return;
+    }
+
+    if !delta.changed.is_empty() {
+        info!(
+            "Changed since the previous run: {}",
+            delta.changed.join(", ")
+        );
+    }
+    if !delta.fixed.is_empty() {
+        info!("Fixed since the previous run: {}", delta.fixed.join(", "));
+    }
+    if !delta.newly_failing.is_empty() {
+        error!(
+            "Newly failing since the previous run: {}",
+            delta.newly_failing.join(", ")
+        );
+    }
+    if !delta.no_version.is_empty() {
+        warn!(
+            "No version found since the previous run: {}",
+            delta.no_version.join(", ")
+        );
+    }
+}
+
+/// Writes the specified report to the path using the specified format.
+fn write_report(
+    path: &Path,
+    report_format: ReportFormat,
+    report: &UpdateReport,
+) -> Result<(), String> {
+    let content = match report_format {
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(report).map_err(|err| err.to_string())?
+        }
+        ReportFormat::Toml => toml::Value::try_from(report)
+            .and_then(|value| toml::to_string_pretty(&value))
+            .map_err(|err| err.to_string())?,
+    };
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    std::fs::write(path, content).map_err(|err| err.to_string())
+}
+
+/// Expands the specified package file arguments, exiting the process when the
+/// arguments are invalid or no package files was found.
+fn get_package_files(paths: &[PathBuf], ignore: &[String]) -> Vec<PathBuf> {
+    let package_files = match expand_package_files(paths, ignore) {
+        Ok(package_files) => package_files,
+        Err(err) => {
+            error!("Unable to expand the package files: {}", err);
+            ExitCode::Failure.exit();
+        }
+    };
+
+    if package_files.is_empty() {
+        error!("No package files was found!");
+        ExitCode::Failure.exit();
+    }
+
+    package_files
+}
+
+/// Runs the update process for all of the specified package files, using a
+/// pool of `jobs` worker threads. The result for every file is collected and
+/// returned, instead of aborting on the first failure.
+///
+/// When the `deadline` is exceeded, no further package files are started and
+/// the running updates are cancelled before their next file is downloaded or
+/// written. The workers are given a short grace period to stop, any workers
+/// still running after that are abandoned and the files they were
+/// downloading are removed. Every package file without a result is then
+/// reported as timed out.
+fn run_updates(
+    request: WebRequest,
+    package_files: Vec<PathBuf>,
+    jobs: usize,
+    mut options: UpdateOptions,
+    filter: PackageFilter,
+    data_format: Option<DataFormat>,
+    deadline: Option<Duration>,
+) -> Vec<UpdateResult> {
+    let deadline = deadline.map(|deadline| Instant::now() + deadline);
+    let jobs = jobs.min(package_files.len()).max(1);
+    let dry_run = options.dry_run;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    options.cancelled = Some(Arc::clone(&cancelled));
+    let request = Arc::new(request);
+    let options = Arc::new(options);
+    let filter = Arc::new(filter);
+    let queue = Arc::new(Mutex::new(package_files.clone().into_iter()));
+    let (sender, receiver) = mpsc::channel();
+
+    info!("Running the update process using {} worker(s)", jobs);
+
+    let workers: Vec<_> = (0..jobs)
+        .map(|index| {
+            let queue = Arc::clone(&queue);
+            let request = Arc::clone(&request);
+            let options = Arc::clone(&options);
+            let filter = Arc::clone(&filter);
+            let cancelled = Arc::clone(&cancelled);
+            let sender = sender.clone();
+
+            thread::Builder::new()
+                .name(format!("worker-{}", index))
+                .spawn(move || loop {
+                    if cancelled.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let file = match queue.lock().unwrap().next() {
+                        Some(file) => file,
+                        None => break,
+                    };
+
+                    let results = run_file_updates(&request, &file, &options, &filter, data_format);
+
+                    if sender.send(results).is_err() {
+                        break;
+                    }
+                })
+                .expect("Unable to create update worker thread!")
+        })
+        .collect();
+    drop(sender);
+
+    let mut results: Vec<UpdateResult> = vec![];
+
+    loop {
+        let received = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    Err(RecvTimeoutError::Timeout)
+                } else {
+                    receiver.recv_timeout(deadline - now)
+                }
             }
-            if let Some(ref aarch64) = aarch64 {
-                info!("Arch 64: {}", aarch64.link);
-            } else {
-                info!("Arch 64: None");
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok(file_results) => results.extend(file_results),
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                cancelled.store(true, Ordering::SeqCst);
+                error!("The deadline of the run was exceeded, cancelling the remaining updates!");
+
+                let grace = Instant::now() + CANCEL_GRACE_PERIOD;
+                while let Ok(file_results) =
+                    receiver.recv_timeout(grace.saturating_duration_since(Instant::now()))
+                {
+                    results.extend(file_results);
+                }
+                for path in remove_partial_files() {
+                    warn!("Removed the partially downloaded file '{}'", path.display());
+                }
+
+                for file in package_files {
+                    if results.iter().any(|result| result.file == file) {
+                        continue;
+                    }
+                    let err = UpdateError::TimedOut;
+                    results.push(UpdateResult {
+                        file,
+                        error: Some(err.to_string()),
+                        error_kind: Some(err.kind()),
+                        exit_code: err.exit_code(),
+                        dry_run,
+                        timed_out: true,
+                        ..UpdateResult::default()
+                    });
+                }
+
+                return results;
             }
+        }
+    }
+
+    for worker in workers {
+        if worker.join().is_err() {
+            error!("An update worker thread panicked!");
+        }
+    }
+
+    results
+}
+
+/// Reads every package definition in the specified package file, or from
+/// stdin when the package file is `-`.
+fn read_package_data(
+    package_file: &Path,
+    data_format: Option<DataFormat>,
+) -> Result<Vec<PackageData>, ParserError> {
+    if package_file != Path::new(STDIN_FILE) {
+        info!("Loading package data from '{}'", package_file.display());
+        return parsers::read_packages(package_file);
+    }
+
+    info!("Loading package data from stdin");
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(ParserError::Loading)?;
+
+    parsers::read_content_packages(Path::new("<stdin>"), &content, data_format)
+}
+
+/// Loads the specified package file and runs the update process for every
+/// package defined in the file, returning one result for each package. A
+/// single result is returned when the package file could not be loaded.
+fn run_file_updates(
+    request: &WebRequest,
+    package_file: &Path,
+    options: &UpdateOptions,
+    filter: &PackageFilter,
+    data_format: Option<DataFormat>,
+) -> Vec<UpdateResult> {
+    let new_result = || UpdateResult {
+        file: package_file.to_path_buf(),
+        dry_run: options.dry_run,
+        ..UpdateResult::default()
+    };
+    let fail = |result: &mut UpdateResult, err: UpdateError| {
+        result.error = Some(err.to_string());
+        result.error_kind = Some(err.kind());
+        result.exit_code = err.exit_code();
+        result.timed_out = matches!(err, UpdateError::TimedOut);
+    };
+
+    let started = Instant::now();
+    let packages = match read_package_data(package_file, data_format) {
+        Ok(packages) => packages,
+        Err(err) => {
+            let mut result = new_result();
+            fail(&mut result, err.into());
+            result.duration_ms = Some(started.elapsed().as_millis() as u64);
+            return vec![result];
+        }
+    };
+
+    packages
+        .iter()
+        .map(|data| {
+            let started = Instant::now();
+            let mut result = new_result();
+            if let Err(err) = run_update(request, package_file, data, options, filter, &mut result)
             {
-                let others: Vec<&str> = others.iter().map(|o| o.link.as_str()).collect();
-                info!("Others: {:?}", others);
+                fail(&mut result, err);
             }
-        }
+            result.duration_ms = Some(started.elapsed().as_millis() as u64);
+            logging::clear_package_context();
+
+            result
+        })
+        .collect()
+}
+
+/// Runs the update process for the specified package, the information found
+/// during the update is stored in the specified `result`.
+fn run_update(
+    request: &WebRequest,
+    package_file: &Path,
+    data: &PackageData,
+    options: &UpdateOptions,
+    filter: &PackageFilter,
+    result: &mut UpdateResult,
+) -> Result<(), UpdateError> {
+    result.id = Some(data.metadata().id().to_owned());
+    logging::set_package_context(data.metadata().id());
 
-        // TODO: #14 Download architecture files
+    if !filter.is_selected(data.metadata()) {
+        info!(
+            "Skipping '{}' as it is excluded by the filters!",
+            data.metadata().id()
+        );
+        result.skipped = true;
+        return Ok(());
     }
+    info!(
+        "Successfully loaded package data with identifier '{}'!",
+        data.metadata().id()
+    );
+
+    let outcome = update_package(data, get_package_dir(package_file), request, options)?;
+    result.old_version = outcome.old_version;
+    result.new_version = outcome.new_version;
+    result.changed = outcome.changed;
+    result.files = outcome.files;
+    result.diffs = outcome.diffs;
+    result.failed_files = outcome.failed_files;
+    result.unmatched = outcome.unmatched;
 
     Ok(())
 }
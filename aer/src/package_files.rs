@@ -0,0 +1,184 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for expanding the package files passed on the command
+//! line, where each argument can be a path to a single package file, a
+//! directory that is searched recursively for supported package files, or a
+//! glob pattern (*ie: `packages/*/package.aer.toml`*).
+
+use std::path::{Path, PathBuf};
+
+use aer_upd::parsers;
+use glob::{MatchOptions, Pattern, PatternError};
+use log::{trace, warn};
+
+const GLOB_CHARACTERS: &[char] = &['*', '?', '['];
+
+/// Expands the specified paths to the package files they refer to. Hidden
+/// files and directories (*starting with a `.`*), as well as any file or
+/// directory with a name matching one of the `ignore` patterns, are skipped
+/// when searching directories or expanding glob patterns.
+///
+/// Paths that do not exist and do not contain a glob pattern are returned as
+/// they are, to allow the failure to be reported when the file is read.
+pub fn expand_package_files(
+    paths: &[PathBuf],
+    ignore: &[String],
+) -> Result<Vec<PathBuf>, PatternError> {
+    let ignore = ignore
+        .iter()
+        .map(|pattern| Pattern::new(pattern))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut files = vec![];
+
+    for path in paths {
+        let found = if path.is_dir() {
+            find_in_directory(path, &ignore)
+        } else if path.to_string_lossy().contains(GLOB_CHARACTERS) {
+            expand_glob(path, &ignore)?
+        } else {
+            vec![path.clone()]
+        };
+
+        if found.is_empty() {
+            warn!("No package files was found in '{}'!", path.display());
+        }
+
+        for file in found {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn find_in_directory(dir: &Path, ignore: &[Pattern]) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(err) => {
+            warn!("Unable to read the directory '{}': {}", dir.display(), err);
+            return vec![];
+        }
+    };
+    entries.sort();
+
+    let mut files = vec![];
+
+    for entry in entries {
+        if is_excluded(&entry, ignore) {
+            trace!("Skipping '{}'", entry.display());
+        } else if entry.is_dir() {
+            files.extend(find_in_directory(&entry, ignore));
+        } else if parsers::is_supported_file(&entry) {
+            files.push(entry);
+        }
+    }
+
+    files
+}
+
+fn expand_glob(pattern: &Path, ignore: &[Pattern]) -> Result<Vec<PathBuf>, PatternError> {
+    let options = MatchOptions {
+        require_literal_leading_dot: true,
+        ..MatchOptions::new()
+    };
+    let mut files: Vec<PathBuf> = glob::glob_with(&pattern.to_string_lossy(), options)?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| {
+            path.is_file() && !path.iter().any(|name| is_excluded(Path::new(name), ignore))
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+fn is_excluded(path: &Path, ignore: &[Pattern]) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    name.starts_with('.') || ignore.iter().any(|pattern| pattern.matches(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(paths: &[&str], ignore: &[&str]) -> Vec<PathBuf> {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        let ignore: Vec<String> = ignore.iter().map(|i| i.to_string()).collect();
+
+        expand_package_files(&paths, &ignore).unwrap()
+    }
+
+    #[test]
+    fn expand_package_files_should_find_supported_files_in_directories() {
+        let files = find(&["test-data/packages"], &[]);
+
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("test-data/packages/first/package.aer.toml"),
+                PathBuf::from("test-data/packages/second/nested/package.aer.toml"),
+                PathBuf::from("test-data/packages/second/package.aer.toml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_package_files_should_skip_ignored_names() {
+        let files = find(&["test-data/packages"], &["nested"]);
+
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("test-data/packages/first/package.aer.toml"),
+                PathBuf::from("test-data/packages/second/package.aer.toml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_package_files_should_expand_glob_patterns() {
+        let files = find(&["test-data/packages/*/package.aer.toml"], &[]);
+
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("test-data/packages/first/package.aer.toml"),
+                PathBuf::from("test-data/packages/second/package.aer.toml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_package_files_should_keep_explicit_paths_and_remove_duplicates() {
+        let files = find(
+            &[
+                "test-data/non-existing.aer.toml",
+                "test-data/packages/first/package.aer.toml",
+                "test-data/packages/first",
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("test-data/non-existing.aer.toml"),
+                PathBuf::from("test-data/packages/first/package.aer.toml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_package_files_should_error_on_invalid_ignore_pattern() {
+        let actual = expand_package_files(&[], &["[".into()]);
+
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for reporting the progress of downloads, either as a
+//! progress bar when running in a terminal or as periodic log messages.
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
+use log::info;
+use yansi::Paint;
+
+/// The number of bytes between each log message when the total size of the
+/// download is not known.
+const LOG_INTERVAL_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The way the progress of a download is reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressMode {
+    /// Shows a progress bar, falling back to log messages when stderr is not
+    /// a terminal.
+    Bar,
+    /// Outputs a log message for every 10% of the download.
+    Log,
+    /// Do not report any progress.
+    Hidden,
+}
+
+/// A writer that reports the progress of the bytes written to it, before
+/// passing the bytes on to the inner writer.
+pub struct DownloadProgress<W: Write> {
+    inner: W,
+    bar: Option<ProgressBar>,
+    log: bool,
+    name: String,
+    total: Option<u64>,
+    downloaded: u64,
+    logged: u64,
+    started: Instant,
+}
+
+impl<W: Write> DownloadProgress<W> {
+    /// Creates a new progress reporter for the download with the specified
+    /// name, where `total` is the expected size of the download when known
+    /// (*usually from the `Content-Length` header*).
+    pub fn new(inner: W, name: &str, total: Option<u64>, mode: ProgressMode) -> Self {
+        let bar = if mode == ProgressMode::Bar {
+            Some(create_bar(name, total)).filter(|bar| !bar.is_hidden())
+        } else {
+            None
+        };
+
+        DownloadProgress {
+            inner,
+            log: mode == ProgressMode::Log || (mode == ProgressMode::Bar && bar.is_none()),
+            bar,
+            name: name.to_owned(),
+            total: total.filter(|total| *total > 0),
+            downloaded: 0,
+            logged: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Finishes the reporting of the progress, and returns the inner writer.
+    pub fn finish(self) -> W {
+        if let Some(ref bar) = self.bar {
+            bar.finish_and_clear();
+        }
+
+        self.inner
+    }
+
+    fn log_progress(&mut self) {
+        let elapsed = self.started.elapsed();
+        let speed = get_speed(self.downloaded, elapsed);
+
+        match self.total {
+            Some(total) => {
+                let percent = self.downloaded * 100 / total;
+                if percent < self.logged + 10 && self.downloaded < total {
+                    return;
+                }
+                self.logged = percent - percent % 10;
+                let eta = if speed > 0 {
+                    Duration::from_secs(total.saturating_sub(self.downloaded) / speed)
+                } else {
+                    Duration::from_secs(0)
+                };
+
+                info!(
+                    "Downloading '{}': {}% ({}/{}, {}/s, {} remaining)",
+                    self.name,
+                    percent,
+                    HumanBytes(self.downloaded),
+                    HumanBytes(total),
+                    HumanBytes(speed),
+                    HumanDuration(eta)
+                );
+            }
+            None => {
+                if self.downloaded < self.logged + LOG_INTERVAL_BYTES {
+                    return;
+                }
+                self.logged = self.downloaded;
+
+                info!(
+                    "Downloading '{}': {} ({}/s)",
+                    self.name,
+                    HumanBytes(self.downloaded),
+                    HumanBytes(speed)
+                );
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for DownloadProgress<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.downloaded += written as u64;
+
+        if let Some(ref bar) = self.bar {
+            bar.set_position(self.downloaded);
+        } else if self.log {
+            self.log_progress();
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn create_bar(name: &str, total: Option<u64>) -> ProgressBar {
+    let (bar, template) = match total {
+        Some(total) if total > 0 => (
+            ProgressBar::new(total),
+            if Paint::is_enabled() {
+                "{msg} [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})"
+            } else {
+                "{msg} [{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})"
+            },
+        ),
+        _ => (
+            ProgressBar::new_spinner(),
+            "{spinner} {msg} {bytes} ({bytes_per_sec})",
+        ),
+    };
+
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .progress_chars("=> "),
+    );
+    bar.set_message(name.to_owned());
+
+    bar
+}
+
+fn get_speed(downloaded: u64, elapsed: Duration) -> u64 {
+    let millis = elapsed.as_millis() as u64;
+
+    if millis == 0 {
+        0
+    } else {
+        downloaded * 1000 / millis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_should_pass_content_to_inner_writer() {
+        let mut progress = DownloadProgress::new(vec![], "test", Some(6), ProgressMode::Hidden);
+
+        progress.write_all(b"abc").unwrap();
+        progress.write_all(b"def").unwrap();
+
+        assert_eq!(progress.downloaded, 6);
+        assert_eq!(progress.finish(), b"abcdef");
+    }
+
+    #[test]
+    fn new_should_fall_back_to_log_messages_without_terminal() {
+        let progress = DownloadProgress::new(vec![], "test", Some(6), ProgressMode::Bar);
+
+        assert!(progress.log || progress.bar.is_some());
+    }
+
+    #[test]
+    fn new_should_not_report_progress_when_hidden() {
+        let progress = DownloadProgress::new(vec![], "test", None, ProgressMode::Hidden);
+
+        assert!(!progress.log);
+        assert!(progress.bar.is_none());
+    }
+
+    #[test]
+    fn log_progress_should_only_log_every_ten_percent() {
+        let mut progress = DownloadProgress::new(vec![], "test", Some(100), ProgressMode::Log);
+
+        progress.write_all(&[0; 5]).unwrap();
+        assert_eq!(progress.logged, 0);
+        progress.write_all(&[0; 10]).unwrap();
+        assert_eq!(progress.logged, 10);
+        progress.write_all(&[0; 16]).unwrap();
+        assert_eq!(progress.logged, 30);
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the comparison of the results of an update run against the
+//! report that was written by a previous run, to find the packages that
+//! changed or started to break since the previous run.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// The report written by a previous run, only containing the parts of the
+/// report that are needed to compare the runs.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct PreviousReport {
+    #[serde(default)]
+    pub packages: Vec<ReportedPackage>,
+}
+
+/// The result of a single package file in a report.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct ReportedPackage {
+    #[serde(default)]
+    pub file: PathBuf,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub new_version: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ReportedPackage {
+    /// Gets the name of the package, which is the identifier of the package
+    /// when it is known and the path to the package file otherwise. Packages
+    /// are matched against the packages of the other run using the name,
+    /// ignoring the case.
+    pub fn name(&self) -> String {
+        match self.id {
+            Some(ref id) => id.clone(),
+            None => self.file.display().to_string(),
+        }
+    }
+
+    fn failed(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// The differences between the results of the current and the previous run,
+/// where every package is listed using the name returned by
+/// [ReportedPackage::name].
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ReportDelta {
+    /// The packages where a different version was found than in the previous
+    /// run.
+    pub changed: Vec<String>,
+    /// The packages that failed, while they did not fail in the previous run.
+    /// Packages that are not part of the previous run are included when they
+    /// fail.
+    pub newly_failing: Vec<String>,
+    /// The packages that failed in the previous run, while they did not fail
+    /// in the current run.
+    pub fixed: Vec<String>,
+    /// The packages where no version was found, while a version was found in
+    /// the previous run.
+    pub no_version: Vec<String>,
+}
+
+impl ReportDelta {
+    /// Wether there are no differences between the runs worth reporting.
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+            && self.newly_failing.is_empty()
+            && self.fixed.is_empty()
+            && self.no_version.is_empty()
+    }
+}
+
+/// Compares the packages of the current run against the packages of the
+/// previous run. Packages that are only part of the previous run are ignored.
+///
+/// ## Examples
+///
+/// ```
+/// use aer::report::{compare_reports, ReportedPackage};
+///
+/// let previous = vec![ReportedPackage {
+///     id: Some("test-package".into()),
+///     new_version: Some("1.0.0".into()),
+///     ..Default::default()
+/// }];
+/// let current = vec![ReportedPackage {
+///     id: Some("test-package".into()),
+///     error: Some("The web server responded with status 404".into()),
+///     ..Default::default()
+/// }];
+///
+/// let delta = compare_reports(&previous, &current);
+///
+/// assert_eq!(delta.newly_failing, ["test-package"]);
+/// ```
+pub fn compare_reports(previous: &[ReportedPackage], current: &[ReportedPackage]) -> ReportDelta {
+    let previous: HashMap<String, &ReportedPackage> = previous
+        .iter()
+        .map(|package| (package.name().to_lowercase(), package))
+        .collect();
+    let mut delta = ReportDelta::default();
+
+    for package in current {
+        let name = package.name();
+        let before = previous.get(&name.to_lowercase());
+
+        match before {
+            Some(before) if before.failed() && !package.failed() => delta.fixed.push(name.clone()),
+            Some(before) if !before.failed() && package.failed() => {
+                delta.newly_failing.push(name.clone())
+            }
+            None if package.failed() => delta.newly_failing.push(name.clone()),
+            _ => {}
+        }
+
+        if package.failed() {
+            continue;
+        }
+
+        match (
+            before.and_then(|before| before.new_version.as_ref()),
+            &package.new_version,
+        ) {
+            (Some(_), None) => delta.no_version.push(name),
+            (Some(old), Some(new)) if old != new => delta.changed.push(name),
+            _ => {}
+        }
+    }
+
+    delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(id: &str, version: Option<&str>, error: Option<&str>) -> ReportedPackage {
+        ReportedPackage {
+            file: PathBuf::from(format!("{}.aer.toml", id)),
+            id: Some(id.into()),
+            new_version: version.map(Into::into),
+            error: error.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn compare_reports_should_find_differences() {
+        let previous = vec![
+            package("changed", Some("1.0.0"), None),
+            package("failing", Some("1.0.0"), None),
+            package("fixed", None, Some("failed")),
+            package("no-version", Some("1.0.0"), None),
+            package("unchanged", Some("1.0.0"), None),
+            package("still-failing", None, Some("failed")),
+        ];
+        let current = vec![
+            package("changed", Some("1.1.0"), None),
+            package("failing", None, Some("failed")),
+            package("fixed", Some("1.0.0"), None),
+            package("no-version", None, None),
+            package("unchanged", Some("1.0.0"), None),
+            package("still-failing", None, Some("failed")),
+            package("new", None, Some("failed")),
+        ];
+
+        let actual = compare_reports(&previous, &current);
+
+        assert_eq!(
+            actual,
+            ReportDelta {
+                changed: vec!["changed".into()],
+                newly_failing: vec!["failing".into(), "new".into()],
+                fixed: vec!["fixed".into()],
+                no_version: vec!["no-version".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn compare_reports_should_match_packages_without_identifier_by_file() {
+        let mut previous = package("test", Some("1.0.0"), None);
+        previous.id = None;
+        let mut current = package("test", Some("1.0.0"), Some("failed"));
+        current.id = None;
+
+        let actual = compare_reports(&[previous], &[current]);
+
+        assert_eq!(actual.newly_failing, ["test.aer.toml"]);
+    }
+
+    #[test]
+    fn previous_report_should_ignore_unknown_fields() {
+        let report: PreviousReport = toml::from_str(
+            r#"
+            started = "2021-07-01T00:00:00+00:00"
+            duration_ms = 10
+
+            [[packages]]
+            file = "test.aer.toml"
+            id = "test"
+            new_version = "1.0.0"
+            changed = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(report.packages, [package("test", Some("1.0.0"), None)]);
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use aer_upd::data::Url;
+use aer_upd::web::{HostOverride, TlsVersion, WebRequest, WebRequestBuilder};
+use log::warn;
+use structopt::StructOpt;
+
+/// Holds the options that are available for configuring how requests are
+/// made to remote web servers.
+#[derive(Debug, StructOpt)]
+pub struct RequestData {
+    /// The maximum number of times a request should be retried when the
+    /// connection fails or the server responds with a server error.
+    #[structopt(long, env = "AER_RETRIES", global = true, default_value = "2")]
+    pub retries: u32,
+
+    /// The base delay (in milliseconds) to wait before retrying a failed
+    /// request. The delay is doubled for every following attempt.
+    #[structopt(long, env = "AER_RETRY_DELAY", global = true, default_value = "500")]
+    pub retry_delay: u64,
+
+    /// The timeout (in seconds) to use when connecting to a web server, or
+    /// waiting for a web page to be returned.
+    #[structopt(long, env = "AER_TIMEOUT", global = true, default_value = "30")]
+    pub timeout: u64,
+
+    /// The proxy to use for all requests, this overrides any proxy set in the
+    /// `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables.
+    #[structopt(long, env = "AER_PROXY", global = true)]
+    pub proxy: Option<Url>,
+
+    /// The user agent to send with all requests, instead of the default
+    /// `aer/<version>` user agent.
+    #[structopt(long, env = "AER_USER_AGENT", global = true)]
+    pub user_agent: Option<String>,
+
+    /// The languages to send in the `Accept-Language` header of all requests
+    /// (*ie: `en-US,en;q=0.9`*), for web servers that serves localized pages.
+    /// This only changes the header of the requests, and can be overridden
+    /// by each package using the `accept_language` setting of the updater.
+    #[structopt(long, env = "AER_ACCEPT_LANGUAGE", global = true)]
+    pub accept_language: Option<String>,
+
+    /// The directory to cache html responses in, the cached responses are
+    /// used to only download pages that have changed since the last request.
+    /// Defaults to `aer/cache` in the temporary directory.
+    #[structopt(long, env = "AER_CACHE_DIR", global = true, parse(from_os_str))]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Disables the caching of html responses.
+    #[structopt(long, global = true)]
+    pub no_cache: bool,
+
+    /// The maximum number of redirects to follow for a single request.
+    #[structopt(long, env = "AER_MAX_REDIRECTS", global = true, default_value = "10")]
+    pub max_redirects: usize,
+
+    /// Do not follow any redirects, the redirect response is used instead.
+    #[structopt(long, global = true)]
+    pub no_redirects: bool,
+
+    /// The maximum number of links on a html page that are processed, any
+    /// links after the maximum are ignored. Can be overridden by each package
+    /// using the `max_links` setting of the updater.
+    #[structopt(long, env = "AER_MAX_LINKS", global = true, default_value = "5000")]
+    pub max_links: usize,
+
+    /// The maximum number of requests per second to send to a single host,
+    /// hosts are identified by their registrable domain (*ie: `example.org`
+    /// for `www.example.org`*). Use trace logging to see when requests are
+    /// throttled. [default: no limit]
+    #[structopt(long, env = "AER_RATE_LIMIT", global = true)]
+    pub rate_limit: Option<f64>,
+
+    /// The maximum number of requests that can be in progress at the same
+    /// time, across every package that is updated concurrently. [default: no
+    /// limit]
+    #[structopt(long, env = "AER_MAX_REQUESTS", global = true)]
+    pub max_requests: Option<usize>,
+
+    /// The maximum number of requests to a single host that can be in
+    /// progress at the same time, across every package that is updated
+    /// concurrently and independent of the number of jobs. Hosts are
+    /// identified the same way as for the rate limit, use `0` to disable the
+    /// limit.
+    #[structopt(
+        long,
+        env = "AER_MAX_HOST_REQUESTS",
+        global = true,
+        default_value = "4"
+    )]
+    pub max_host_requests: usize,
+
+    /// Connects to the specified address instead of the address resolved by
+    /// DNS for a host, using the `host:address` format (*ie:
+    /// `example.org:127.0.0.1`*). The `Host` header and the name used for
+    /// TLS is still the original host. Can be specified multiple times.
+    #[structopt(long, global = true, number_of_values = 1)]
+    pub resolve: Vec<HostOverride>,
+
+    /// The minimum version of TLS that web servers must support, connections
+    /// to web servers that only support older versions fail. [default: the
+    /// default of the TLS backend]
+    #[structopt(
+        long,
+        env = "AER_TLS_MIN_VERSION",
+        global = true,
+        possible_values = TlsVersion::supported_str()
+    )]
+    pub tls_min_version: Option<TlsVersion>,
+
+    /// DANGER: Accept invalid certificates (*ie: self-signed or expired
+    /// certificates*) of every web server, which allows anyone able to
+    /// intercept the connections to impersonate the web servers. Only use
+    /// this for trusted hosts that can not be reached in any other way. This
+    /// can only be enabled on the command line, and a warning is logged when
+    /// it is used.
+    #[structopt(long, global = true)]
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl RequestData {
+    /// Creates a new web request configured with the current options.
+    pub fn create_request(&self) -> WebRequest {
+        self.create_builder().build()
+    }
+
+    /// Creates a builder for a web request, configured with the current
+    /// options.
+    pub fn create_builder(&self) -> WebRequestBuilder {
+        let mut builder = WebRequestBuilder::new()
+            .retries(self.retries, Duration::from_millis(self.retry_delay))
+            .timeout(Duration::from_secs(self.timeout))
+            .max_links(self.max_links);
+
+        if let Some(ref user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        if let Some(ref accept_language) = self.accept_language {
+            builder = match builder
+                .clone()
+                .headers(vec![("Accept-Language", accept_language.as_str())])
+            {
+                Ok(builder) => builder,
+                Err(err) => {
+                    warn!(
+                        "Ignoring the accept language '{}': {}",
+                        accept_language, err
+                    );
+                    builder
+                }
+            };
+        }
+
+        builder = if self.no_redirects {
+            builder.no_redirects()
+        } else {
+            builder.max_redirects(self.max_redirects)
+        };
+
+        if let Some(rate_limit) = self.rate_limit {
+            builder = builder.rate_limit(rate_limit);
+        }
+
+        if let Some(max_requests) = self.max_requests {
+            builder = builder.max_concurrent_requests(max_requests);
+        }
+
+        if self.max_host_requests > 0 {
+            builder = builder.max_requests_per_host(self.max_host_requests);
+        }
+
+        for host_override in &self.resolve {
+            builder = builder.host_override(host_override.clone());
+        }
+
+        if let Some(version) = self.tls_min_version {
+            builder = match builder.clone().tls_min_version(version) {
+                Ok(builder) => builder,
+                Err(err) => {
+                    warn!("Ignoring the minimum TLS version: {}", err);
+                    builder
+                }
+            };
+        }
+
+        if self.danger_accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs();
+        }
+
+        if !self.no_cache {
+            let cache_dir = self
+                .cache_dir
+                .clone()
+                .unwrap_or_else(|| std::env::temp_dir().join("aer").join("cache"));
+            builder = builder.cache_dir(cache_dir);
+        }
+
+        if let Some(ref proxy) = self.proxy {
+            builder.proxy(proxy.clone())
+        } else {
+            builder
+        }
+    }
+}
+
+impl Default for RequestData {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            retry_delay: 500,
+            timeout: 30,
+            proxy: None,
+            user_agent: None,
+            accept_language: None,
+            cache_dir: None,
+            no_cache: false,
+            max_redirects: 10,
+            no_redirects: false,
+            max_links: 5000,
+            rate_limit: None,
+            max_requests: None,
+            max_host_requests: 4,
+            resolve: vec![],
+            tls_min_version: None,
+            danger_accept_invalid_certs: false,
+        }
+    }
+}
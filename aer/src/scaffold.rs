@@ -0,0 +1,128 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for creating the starter package file of a new
+//! package.
+
+use std::path::Path;
+
+/// The template used when creating a new package file, where `{{id}}` is
+/// replaced with the identifier of the package.
+const PACKAGE_TEMPLATE: &str = r#"# The metadata of the package, used when creating the package.
+[metadata]
+id = "{{id}}"
+# The maintainers of the package, not the authors of the software.
+maintainers = ["{{maintainer}}"]
+# A short summary of the software.
+summary = "TODO: Add a short summary of {{id}}"
+# The homepage of the software.
+project_url = "https://example.org/{{id}}"
+# The license of the software, either as an SPDX expression or a url.
+license = "TODO"
+
+[metadata.chocolatey]
+# The version will be updated by aer.
+version = "0.0.0"
+authors = ["TODO"]
+tags = ["{{id}}"]
+
+[updater.chocolatey]
+# The url of the page containing the links to the files that should be
+# downloaded.
+parse_url = "https://example.org/{{id}}/downloads"
+# The paths to the files that should be updated, relative to this file.
+nuspec = "{{id}}.nuspec"
+install_script = "tools/chocolateyInstall.ps1"
+
+# The regexes used to find the links of each architecture, the version
+# is taken from the `version` capture group.
+[updater.chocolatey.regexes]
+arch64 = '''{{id}}-(?P<version>[\d\.]+)-x64\.exe$'''
+"#;
+
+/// Creates the content of a new package file for a package with the
+/// specified identifier. The created content contain placeholders and
+/// comments explaining each value that need to be changed.
+pub fn create_package_content(id: &str) -> String {
+    let maintainer = std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "TODO".into());
+
+    PACKAGE_TEMPLATE
+        .replace("{{id}}", id)
+        .replace("{{maintainer}}", &maintainer)
+}
+
+/// Writes the content of a new package file for a package with the
+/// specified identifier to the specified path. An existing file is only
+/// overwritten when `force` is set.
+pub fn create_package_file(path: &Path, id: &str, force: bool) -> std::io::Result<()> {
+    if !force && path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "The file already exists, use --force to overwrite it!",
+        ));
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, create_package_content(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aer_upd::parsers;
+
+    use super::*;
+
+    fn get_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join("aer-scaffold-tests")
+            .join(name)
+            .join(format!("{}.aer.toml", name))
+    }
+
+    #[test]
+    fn create_package_file_should_create_parsable_file() {
+        let path = get_path("parsable");
+        let _ = std::fs::remove_file(&path);
+
+        create_package_file(&path, "parsable", false).unwrap();
+        let data = parsers::read_file(&path).unwrap();
+
+        assert_eq!(data.metadata().id(), "parsable");
+    }
+
+    #[test]
+    fn create_package_file_should_not_overwrite_existing_file() {
+        let path = get_path("existing");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "existing").unwrap();
+
+        let actual = create_package_file(&path, "existing", false);
+
+        assert_eq!(
+            actual.unwrap_err().kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "existing");
+    }
+
+    #[test]
+    fn create_package_file_should_overwrite_existing_file_when_forced() {
+        let path = get_path("forced");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "existing").unwrap();
+
+        create_package_file(&path, "forced", true).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            create_package_content("forced")
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for maintaining the checksum file (*ie: `SHA256SUMS`*)
+//! in the directory the files of a package are downloaded to.
+//!
+//! The file uses the same `<checksum>  <file name>` format as the output of
+//! `sha256sum` and similar tools, which allows the downloaded files to be
+//! verified with `sha256sum --check SHA256SUMS`.
+
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use log::info;
+
+use crate::ChecksumType;
+
+/// Gets the name of the checksum file for the specified checksum type (*ie:
+/// `SHA256SUMS` for `sha256`*).
+pub fn sums_file_name(checksum_type: ChecksumType) -> String {
+    format!("{}SUMS", checksum_type.to_string().to_uppercase())
+}
+
+/// Updates the checksum file in the specified directory with the checksums of
+/// the specified files, where each checksum is of the specified type. Entries
+/// for other files that still exist in the directory are kept, and the file
+/// is only written when the content changes.
+///
+/// Returns the path to the checksum file when it was written.
+pub fn update_sums_file(
+    dir: &Path,
+    checksum_type: ChecksumType,
+    files: &[(&Path, String)],
+) -> io::Result<Option<PathBuf>> {
+    let path = dir.join(sums_file_name(checksum_type));
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    let mut entries: BTreeMap<String, String> = existing
+        .lines()
+        .filter_map(parse_line)
+        .filter(|(name, _)| dir.join(name).is_file())
+        .collect();
+    for (file, checksum) in files {
+        if let Some(name) = file.file_name() {
+            entries.insert(name.to_string_lossy().into_owned(), checksum.clone());
+        }
+    }
+
+    let content: String = entries
+        .iter()
+        .map(|(name, checksum)| format!("{}  {}\n", checksum, name))
+        .collect();
+    if content == existing {
+        return Ok(None);
+    }
+
+    std::fs::write(&path, content)?;
+    info!(
+        "Wrote the {} checksums of {} file(s) to '{}'",
+        checksum_type,
+        entries.len(),
+        path.display()
+    );
+
+    Ok(Some(path))
+}
+
+/// Parses a single line of a checksum file, returning the file name and the
+/// checksum. The `*` used by some tools to mark binary files is ignored.
+fn parse_line(line: &str) -> Option<(String, String)> {
+    let mut parts = line.splitn(2, ' ');
+    let checksum = parts.next()?;
+    let name = parts.next()?.strip_prefix(|c| c == ' ' || c == '*')?;
+
+    if checksum.is_empty() || name.is_empty() {
+        None
+    } else {
+        Some((name.to_owned(), checksum.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn create_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), file).unwrap();
+        }
+
+        dir
+    }
+
+    #[rstest]
+    #[case(ChecksumType::Md5, "MD5SUMS")]
+    #[case(ChecksumType::Sha1, "SHA1SUMS")]
+    #[case(ChecksumType::Sha256, "SHA256SUMS")]
+    #[case(ChecksumType::Sha512, "SHA512SUMS")]
+    fn sums_file_name_should_use_standard_names(
+        #[case] checksum_type: ChecksumType,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(sums_file_name(checksum_type), expected);
+    }
+
+    #[test]
+    fn update_sums_file_should_write_sorted_checksums() {
+        let dir = create_dir("aer-tests-sums-write", &["setup-x64.exe", "setup.exe"]);
+        let (x64, x86) = (dir.join("setup-x64.exe"), dir.join("setup.exe"));
+
+        let actual = update_sums_file(
+            &dir,
+            ChecksumType::Sha256,
+            &[
+                (x86.as_path(), "aaaa".into()),
+                (x64.as_path(), "bbbb".into()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(actual, Some(dir.join("SHA256SUMS")));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("SHA256SUMS")).unwrap(),
+            "bbbb  setup-x64.exe\naaaa  setup.exe\n"
+        );
+    }
+
+    #[test]
+    fn update_sums_file_should_keep_entries_of_existing_files() {
+        let dir = create_dir("aer-tests-sums-keep", &["other.zip", "setup.exe"]);
+        std::fs::write(
+            dir.join("SHA256SUMS"),
+            "cccc *other.zip\ndddd  removed.zip\neeee  setup.exe\n",
+        )
+        .unwrap();
+
+        update_sums_file(
+            &dir,
+            ChecksumType::Sha256,
+            &[(dir.join("setup.exe").as_path(), "aaaa".into())],
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("SHA256SUMS")).unwrap(),
+            "cccc  other.zip\naaaa  setup.exe\n"
+        );
+    }
+
+    #[test]
+    fn update_sums_file_should_not_write_unchanged_checksums() {
+        let dir = create_dir("aer-tests-sums-unchanged", &["setup.exe"]);
+        std::fs::write(dir.join("MD5SUMS"), "aaaa  setup.exe\n").unwrap();
+
+        let actual = update_sums_file(
+            &dir,
+            ChecksumType::Md5,
+            &[(dir.join("setup.exe").as_path(), "aaaa".into())],
+        )
+        .unwrap();
+
+        assert_eq!(actual, None);
+    }
+}
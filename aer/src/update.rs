@@ -0,0 +1,4329 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the update process of a single package, from finding the links
+//! of the package to updating the files of the package with the information
+//! that was found.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use aer_upd::data::*;
+use aer_upd::regexes::{expand_template, get_regex};
+use aer_upd::runners::external::run_resolver;
+use aer_upd::runners::hooks::run_hook;
+use aer_upd::updaters::chocolatey::{
+    get_nuspec_version, render_template, update_nuspec_version, update_script_variables,
+    DEFAULT_VERIFICATION_TEMPLATE,
+};
+use aer_upd::updaters::errors::UpdaterError;
+use aer_upd::updaters::scoop::{
+    get_manifest_version, update_manifest as update_manifest_content, ManifestFile,
+};
+use aer_upd::validators::{self, RuleSet};
+use aer_upd::web::errors::WebError;
+use aer_upd::web::github::{self, select_latest_release};
+use aer_upd::web::{
+    dedup_links, part_file_path, HtmlResponse, LinkElement, LinkSource, LinkType, MatchTarget,
+    ResponseType, VersionSelector, WebRequest, WebResponse,
+};
+use log::{error, info, trace, warn};
+use serde::{Deserialize, Serialize, Serializer};
+use yansi::Paint;
+
+use crate::errors::UpdateError;
+use crate::progress::{DownloadProgress, ProgressMode};
+use crate::{check, diff, git, latest, logging, sums, ChecksumType};
+
+/// The default template of the directory inside the output directory that the
+/// files of a package are downloaded to, where `{{id}}` and `{{version}}` are
+/// replaced with the identifier and the found version of the package.
+pub const DEFAULT_OUTPUT_TEMPLATE: &str = "{{id}}";
+
+/// The default maximum duration each hook is allowed to run before it is
+/// killed.
+pub const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// The way a file that already exists in the output directory is handled
+/// when the same file is downloaded again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionPolicy {
+    /// Skip the download when the existing file has the same size as the
+    /// remote file, otherwise the file is downloaded again.
+    Skip,
+    /// Always download the file again, overwriting the existing file.
+    Overwrite,
+    /// Fail the download of the file.
+    Error,
+}
+
+impl Default for CollisionPolicy {
+    fn default() -> Self {
+        Self::Skip
+    }
+}
+
+impl FromStr for CollisionPolicy {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "skip" => Ok(CollisionPolicy::Skip),
+            "overwrite" => Ok(CollisionPolicy::Overwrite),
+            "error" => Ok(CollisionPolicy::Error),
+            _ => Err("The value is not a supported collision policy!"),
+        }
+    }
+}
+
+impl Display for CollisionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollisionPolicy::Skip => f.write_str("skip"),
+            CollisionPolicy::Overwrite => f.write_str("overwrite"),
+            CollisionPolicy::Error => f.write_str("error"),
+        }
+    }
+}
+
+impl CollisionPolicy {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["skip", "overwrite", "error"];
+
+        VARIANTS
+    }
+}
+
+/// The way a package is updated when the files of some, but not all, of the
+/// architectures of the package could not be downloaded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchFailurePolicy {
+    /// Fail the update of the package, without changing any of the package
+    /// files. Any package file that was already changed when a later change
+    /// fails is restored to its original content.
+    AllOrNothing,
+    /// Update the package files with the architectures that was successfully
+    /// downloaded, and warn about the architectures that failed. The values
+    /// of the failed architectures are left unchanged, which means the
+    /// package can reference files of an older version.
+    BestEffort,
+}
+
+impl Default for ArchFailurePolicy {
+    fn default() -> Self {
+        Self::AllOrNothing
+    }
+}
+
+impl FromStr for ArchFailurePolicy {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "all-or-nothing" => Ok(ArchFailurePolicy::AllOrNothing),
+            "best-effort" => Ok(ArchFailurePolicy::BestEffort),
+            _ => Err("The value is not a supported architecture failure policy!"),
+        }
+    }
+}
+
+impl Display for ArchFailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchFailurePolicy::AllOrNothing => f.write_str("all-or-nothing"),
+            ArchFailurePolicy::BestEffort => f.write_str("best-effort"),
+        }
+    }
+}
+
+impl ArchFailurePolicy {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["all-or-nothing", "best-effort"];
+
+        VARIANTS
+    }
+}
+
+/// The options that are used when updating a package.
+#[derive(Debug, Clone)]
+pub struct UpdateOptions {
+    /// Include pre-release versions when finding the latest version, even
+    /// for packages that do not allow them.
+    pub allow_prerelease: bool,
+    /// The directory where the files of each package will be downloaded to.
+    pub output_dir: PathBuf,
+    /// The template of the sub directory of the output directory that the
+    /// files of each package are downloaded to, see
+    /// [DEFAULT_OUTPUT_TEMPLATE].
+    pub output_template: String,
+    /// The way files that already exist in the output directory are handled.
+    pub collision: CollisionPolicy,
+    /// The way a package is updated when only some of the architectures could
+    /// be downloaded.
+    pub arch_failure: ArchFailurePolicy,
+    /// Run the full update even if the found version is not newer than the
+    /// current version of the package, and download files even if a file
+    /// with the same name and size already exists in the output directory
+    /// (*unless collisions are treated as errors*).
+    pub force: bool,
+    /// Only output the changes that would be made, without downloading any
+    /// files or changing any of the package files.
+    pub dry_run: bool,
+    /// The set of rules the package data is validated against before the
+    /// update is started.
+    pub rule_set: RuleSet,
+    /// Wether the hooks specified in the package data should be run.
+    pub run_hooks: bool,
+    /// The maximum duration each hook is allowed to run before it is killed
+    /// and treated as failed, unless the package data specifies a timeout.
+    pub hook_timeout: Duration,
+    /// Treat a package where no update was found, where a regex matched a
+    /// link without a parsable version, or where the regexes of a key did not
+    /// match any of the links, as a failure.
+    pub strict: bool,
+    /// The way the progress of the downloads are reported.
+    pub progress: ProgressMode,
+    /// The number of files of a single package that are downloaded
+    /// concurrently.
+    pub download_jobs: usize,
+    /// Wether the changed files should be committed to a git repository.
+    pub git_commit: bool,
+    /// The directory of the git repository the changed files are committed
+    /// to, defaults to the directory of the package.
+    pub git_repo: Option<PathBuf>,
+    /// The template of the commit message.
+    pub git_message: String,
+    /// Wether the diff of the changes made to the package files should be
+    /// outputted, the diffs are always outputted during a dry run.
+    pub show_diff: bool,
+    /// The type of the checksums written to a checksum file (*ie:
+    /// `SHA256SUMS`*) in the directory the files of a package are downloaded
+    /// to, no checksum file is written when not set.
+    pub checksum_file: Option<ChecksumType>,
+    /// The flag that is set when the run is cancelled (*ie: when the deadline
+    /// of the run is exceeded*). The update of a package then fails as timed
+    /// out before any further files are downloaded or written, restoring the
+    /// package files that was already changed.
+    pub cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl Default for UpdateOptions {
+    fn default() -> Self {
+        Self {
+            allow_prerelease: false,
+            output_dir: std::env::temp_dir().join("aer"),
+            output_template: DEFAULT_OUTPUT_TEMPLATE.into(),
+            collision: CollisionPolicy::default(),
+            arch_failure: ArchFailurePolicy::default(),
+            force: false,
+            dry_run: false,
+            rule_set: RuleSet::default(),
+            run_hooks: true,
+            hook_timeout: DEFAULT_HOOK_TIMEOUT,
+            strict: false,
+            progress: ProgressMode::Hidden,
+            download_jobs: 3,
+            git_commit: false,
+            git_repo: None,
+            git_message: git::DEFAULT_COMMIT_MESSAGE.into(),
+            show_diff: false,
+            checksum_file: None,
+            cancelled: None,
+        }
+    }
+}
+
+/// The result of a successful update of a single package.
+#[derive(Debug, Default, Serialize)]
+pub struct UpdateOutcome {
+    /// The version of the package before the update.
+    pub old_version: Option<String>,
+    /// The version of the package that was found during the update.
+    pub new_version: Option<String>,
+    /// Wether a newer version was found.
+    pub changed: bool,
+    /// The files that was downloaded, keyed by the name of the architecture.
+    pub files: BTreeMap<String, DownloadedFile>,
+    /// The package files that was changed by the update, or that would be
+    /// changed during a dry run.
+    pub modified_files: Vec<PathBuf>,
+    /// The changes made to each of the modified files.
+    pub diffs: Vec<FileDiff>,
+    /// The architectures where the file could not be downloaded, together
+    /// with the reason. Only used when the update continues with the other
+    /// architectures.
+    pub failed_files: BTreeMap<String, String>,
+    /// The keys of the regexes that did not match any of the links found,
+    /// which usually means that the layout of the page have changed. Only
+    /// used when not running in strict mode.
+    pub unmatched: Vec<String>,
+}
+
+/// The changes that was (*or would be*) made to a single package file.
+#[derive(Debug, Serialize)]
+pub struct FileDiff {
+    /// The path of the changed file.
+    pub path: PathBuf,
+    /// The unified diff between the content of the file before and after the
+    /// update.
+    pub diff: String,
+}
+
+impl UpdateOutcome {
+    /// Records the package file at the specified path as modified, outputting
+    /// the diff of the changes when requested by the options. The diff has
+    /// already been outputted during a dry run.
+    fn add_modified_file(&mut self, path: PathBuf, diff: String, options: &UpdateOptions) {
+        if options.show_diff && !options.dry_run {
+            log_diff(&diff);
+        }
+        self.modified_files.push(path.clone());
+        self.diffs.push(FileDiff { path, diff });
+    }
+}
+
+/// Holds the information about a single file that was downloaded during the
+/// update of a package.
+#[derive(Debug, Serialize)]
+pub struct DownloadedFile {
+    /// The link the file was downloaded from.
+    #[serde(rename = "url", serialize_with = "serialize_link")]
+    pub link: LinkElement,
+    /// The path the file was downloaded to.
+    pub path: PathBuf,
+    /// The checksum of the downloaded file.
+    pub checksum: String,
+    /// The type of the checksum of the downloaded file.
+    #[serde(serialize_with = "serialize_display")]
+    pub checksum_type: ChecksumType,
+}
+
+/// The checksum that a downloaded file is expected to have.
+#[derive(Clone)]
+pub(crate) struct ExpectedChecksum {
+    pub(crate) checksum: String,
+    pub(crate) checksum_type: ChecksumType,
+}
+
+/// The links found for each architecture of a package, together with the
+/// request that should be used when downloading the links.
+struct FoundLinks {
+    request: WebRequest,
+    version: Option<Versions>,
+    architectures: BTreeMap<String, LinkElement>,
+    /// The keys of the regexes that did not match any of the links.
+    unmatched: Vec<String>,
+}
+
+/// A single file that should be downloaded for the specified architecture.
+pub(crate) struct Download {
+    pub(crate) arch: String,
+    pub(crate) link: LinkElement,
+    pub(crate) expected: Option<ExpectedChecksum>,
+    /// The content types the file must be served with.
+    pub(crate) content_type: ContentTypeCheck,
+    /// The links that are tried in order when the file could not be
+    /// downloaded from the link.
+    pub(crate) mirrors: Vec<LinkElement>,
+}
+
+/// The content types that a downloaded file is accepted with, any content
+/// type is accepted by default.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ContentTypeCheck {
+    /// The content type the file is expected to be served with, where the
+    /// subtype can be `*`.
+    pub(crate) expected: Option<String>,
+    /// Wether html pages should be rejected.
+    pub(crate) reject_html: bool,
+}
+
+impl ContentTypeCheck {
+    /// Checks wether the specified media type is accepted, returning the
+    /// reason when it is not.
+    fn check(&self, actual: Option<&str>) -> Result<(), String> {
+        let is_html = matches!(actual, Some("text/html") | Some("application/xhtml+xml"));
+        if self.reject_html && is_html {
+            return Err(format!(
+                "The server responded with a html page (content type '{}') instead of a file!",
+                actual.unwrap_or_default()
+            ));
+        }
+
+        let expected = match self.expected {
+            Some(ref expected) => expected.trim().to_lowercase(),
+            None => return Ok(()),
+        };
+        let matches = match (actual, expected.strip_suffix("/*")) {
+            (Some(actual), Some(kind)) => actual.split('/').next() == Some(kind),
+            (Some(actual), None) => actual == expected,
+            (None, _) => false,
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(format!(
+                "The server responded with the content type '{}', while '{}' was expected!",
+                actual.unwrap_or("<none>"),
+                expected
+            ))
+        }
+    }
+}
+
+/// The hooks that are run around the download of every file, together with
+/// the working directory and the environment variables the hooks are run
+/// with.
+#[derive(Default)]
+pub(crate) struct DownloadHooks {
+    pre_download: Vec<Hook>,
+    post_download: Vec<Hook>,
+    work_dir: PathBuf,
+    env: HashMap<String, String>,
+    timeout: Option<Duration>,
+}
+
+/// Runs the update process for the specified package data, where
+/// `package_dir` is the directory that the paths in the package data are
+/// relative to. The package data is validated before any requests are made,
+/// and the update fails on the first regex that can not be compiled.
+///
+/// The outcome of the update is returned instead of being outputted, and the
+/// process is never exited, which allows the update process to be used
+/// outside of the `aer` executable.
+pub fn update_package(
+    data: &PackageData,
+    package_dir: &Path,
+    request: &WebRequest,
+    options: &UpdateOptions,
+) -> Result<UpdateOutcome, UpdateError> {
+    if let Some(violation) = validators::find_invalid_regexes(data).into_iter().next() {
+        return Err(UpdateError::PackageRegex {
+            id: data.metadata().id().to_owned(),
+            violation,
+        });
+    }
+
+    let violations = validators::validate(data, options.rule_set);
+    if !violations.is_empty() {
+        return Err(UpdateError::Validation {
+            id: data.metadata().id().to_owned(),
+            rule_set: options.rule_set,
+            violations,
+        });
+    }
+
+    let mut outcome = UpdateOutcome::default();
+    let hooks = data.updater().hooks();
+    let mut ran_before_hooks = false;
+
+    // Packages without any updaters have nothing that can be unchanged, as
+    // such the hooks are still run for them.
+    let mut processed = !data.updater().has_chocolatey() && !data.updater().has_scoop();
+    if processed {
+        run_before_hooks(&mut ran_before_hooks, data, package_dir, options)?;
+    }
+
+    if data.updater().has_chocolatey() {
+        let choco = data.updater().chocolatey();
+        let nuspec = get_nuspec_path(package_dir, data, &choco);
+        let found = find_links(request, data, package_dir, &choco, options)?;
+        check_unmatched(data, &found, options, &mut outcome)?;
+        let version = found
+            .version
+            .as_ref()
+            .map(|version| version.to_chocolatey());
+        let current_version = std::fs::read_to_string(&nuspec)
+            .ok()
+            .and_then(|content| get_nuspec_version(&content).ok());
+
+        match current_version {
+            Some(current) if is_unchanged(data, &current, version.as_deref(), options) => {
+                outcome.old_version = Some(current.clone());
+                outcome.new_version = Some(current);
+            }
+            _ => {
+                processed = true;
+                run_before_hooks(&mut ran_before_hooks, data, package_dir, options)?;
+                let backup = backup_chocolatey_files(&choco, &nuspec, package_dir, options);
+                let result = update_chocolatey(
+                    found,
+                    data,
+                    &choco,
+                    &nuspec,
+                    package_dir,
+                    options,
+                    &mut outcome,
+                );
+
+                if let (Err(_), Some(backup)) = (&result, backup) {
+                    backup.restore();
+                }
+                result?;
+            }
+        }
+    }
+
+    if data.updater().has_scoop() {
+        let scoop = data.updater().scoop();
+        let link_data = scoop.link_data();
+        let manifest = get_manifest_path(package_dir, data, &scoop);
+        let found = find_links(request, data, package_dir, &link_data, options)?;
+        check_unmatched(data, &found, options, &mut outcome)?;
+        let version = found.version.as_ref().map(|version| version.to_string());
+        let current_version = std::fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|content| get_manifest_version(&content).ok());
+
+        match current_version {
+            Some(current) if is_unchanged(data, &current, version.as_deref(), options) => {
+                outcome.old_version.get_or_insert(current.clone());
+                outcome.new_version.get_or_insert(current);
+            }
+            _ => {
+                processed = true;
+                run_before_hooks(&mut ran_before_hooks, data, package_dir, options)?;
+                let backup = backup_scoop_files(&manifest, options);
+                let result = update_scoop(
+                    found,
+                    data,
+                    &link_data,
+                    manifest,
+                    package_dir,
+                    options,
+                    &mut outcome,
+                );
+
+                if let (Err(_), Some(backup)) = (&result, backup) {
+                    backup.restore();
+                }
+                result?;
+            }
+        }
+    }
+
+    if options.strict && !outcome.changed {
+        return Err(UpdateError::NoUpdate {
+            id: data.metadata().id().to_owned(),
+        });
+    }
+
+    if !processed {
+        return Ok(outcome);
+    }
+
+    if data.updater().latest_pointer() && outcome.changed {
+        update_latest_pointer(data, &outcome, options)?;
+    }
+
+    if options.git_commit && !outcome.modified_files.is_empty() {
+        commit_changes(data, &outcome, package_dir, options)?;
+    }
+
+    if options.run_hooks && !hooks.after.is_empty() {
+        run_after_hooks(&hooks.after, &outcome, data, package_dir, options)?;
+    }
+
+    Ok(outcome)
+}
+
+/// Checks wether the regexes of any key did not match any of the found links,
+/// which is treated as a failure in strict mode. Otherwise the keys are
+/// recorded in the outcome, to be included in the summary of the update.
+fn check_unmatched(
+    data: &PackageData,
+    found: &FoundLinks,
+    options: &UpdateOptions,
+    outcome: &mut UpdateOutcome,
+) -> Result<(), UpdateError> {
+    if found.unmatched.is_empty() {
+        return Ok(());
+    }
+
+    if options.strict {
+        return Err(UpdateError::NoMatch {
+            id: data.metadata().id().to_owned(),
+            keys: found.unmatched.clone(),
+        });
+    }
+
+    for key in &found.unmatched {
+        if !outcome.unmatched.contains(key) {
+            outcome.unmatched.push(key.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks wether the version found for a package is the same as the current
+/// version of the package, in which case the rest of the update is skipped
+/// unless the update is forced.
+fn is_unchanged(
+    data: &PackageData,
+    current_version: &str,
+    version: Option<&str>,
+    options: &UpdateOptions,
+) -> bool {
+    match version {
+        Some(version)
+            if !options.force
+                && !check::is_update_available(Some(current_version), Some(version)) =>
+        {
+            if current_version == version {
+                info!(
+                    "'{}' is unchanged with version {}, skipping the rest of the update!",
+                    data.metadata().id(),
+                    version
+                );
+            } else {
+                info!(
+                    "The found version {} is not newer than the version {} of '{}', skipping \
+                     the rest of the update!",
+                    version,
+                    current_version,
+                    data.metadata().id()
+                );
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Runs the before hooks of a package, unless they have already been run.
+/// The hooks are only run once the package is known to not be unchanged.
+fn run_before_hooks(
+    ran: &mut bool,
+    data: &PackageData,
+    package_dir: &Path,
+    options: &UpdateOptions,
+) -> Result<(), UpdateError> {
+    if *ran || !options.run_hooks {
+        return Ok(());
+    }
+    *ran = true;
+
+    let hooks = data.updater().hooks();
+    let mut env = HashMap::new();
+    env.insert(
+        "AER_PACKAGE_ID".to_string(),
+        data.metadata().id().to_string(),
+    );
+
+    for hook in &hooks.before {
+        if options.dry_run {
+            info!("Would run the before hook '{}'", describe_hook(hook));
+            continue;
+        }
+        run_hook(hook, package_dir, &env, get_hook_timeout(hooks, options)).map_err(|err| {
+            UpdateError::Hook(format!("A before hook failed, aborting update: {}", err))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Updates the install script, verification file and nuspec file of a
+/// chocolatey package, after downloading the files of the found links.
+fn update_chocolatey(
+    found: FoundLinks,
+    data: &PackageData,
+    choco: &chocolatey::ChocolateyUpdaterData,
+    nuspec: &Path,
+    package_dir: &Path,
+    options: &UpdateOptions,
+    outcome: &mut UpdateOutcome,
+) -> Result<(), UpdateError> {
+    let version = found
+        .version
+        .as_ref()
+        .map(|version| version.to_chocolatey());
+    let files = download_files(
+        found,
+        data,
+        choco,
+        version.as_deref(),
+        package_dir,
+        options,
+        &mut outcome.failed_files,
+    )?;
+    check_cancelled(options)?;
+    let script_values =
+        get_script_values(choco, &files, version.as_deref(), &outcome.failed_files)?;
+    let install_script = get_install_script_path(package_dir, choco);
+
+    if !install_script.exists() && choco.install_script.is_none() {
+        info!(
+            "No install script found at '{}', skipping update of script variables!",
+            install_script.display()
+        );
+    } else if !script_values.is_empty() {
+        check_cancelled(options)?;
+        if let Some(diff) = update_install_script(&install_script, &script_values, options.dry_run)?
+        {
+            outcome.add_modified_file(install_script, diff, options);
+        }
+    }
+
+    if let Some(ref verification) = choco.verification {
+        check_cancelled(options)?;
+        let values = get_verification_values(data, &files, version.as_deref());
+        if let Some((path, diff)) =
+            update_verification(verification, package_dir, &values, options.dry_run)?
+        {
+            outcome.add_modified_file(path, diff, options);
+        }
+    }
+
+    outcome.files = files;
+
+    if let Some(version) = version {
+        check_cancelled(options)?;
+        let (old_version, diff) = update_nuspec(nuspec, &version, options.dry_run)?;
+        if let Some(diff) = diff {
+            outcome.add_modified_file(nuspec.to_path_buf(), diff, options);
+        }
+        outcome.changed = old_version != version;
+        outcome.old_version = Some(old_version);
+        outcome.new_version = Some(version);
+    } else {
+        warn!("No version was found, the nuspec file will not be updated!");
+    }
+
+    Ok(())
+}
+
+/// Updates the manifest of a scoop package, after downloading the files of the
+/// found links.
+fn update_scoop(
+    found: FoundLinks,
+    data: &PackageData,
+    link_data: &chocolatey::ChocolateyUpdaterData,
+    manifest: PathBuf,
+    package_dir: &Path,
+    options: &UpdateOptions,
+    outcome: &mut UpdateOutcome,
+) -> Result<(), UpdateError> {
+    let version = found.version.as_ref().map(|version| version.to_string());
+    let files = download_files(
+        found,
+        data,
+        link_data,
+        version.as_deref(),
+        package_dir,
+        options,
+        &mut outcome.failed_files,
+    )?;
+
+    if let Some(version) = version {
+        check_cancelled(options)?;
+        let (old_version, diff) = update_manifest(&manifest, &version, &files, options.dry_run)?;
+        if let Some(diff) = diff {
+            outcome.add_modified_file(manifest, diff, options);
+        }
+        outcome.changed |= old_version != version;
+        outcome.old_version.get_or_insert(old_version);
+        outcome.new_version.get_or_insert(version);
+    } else {
+        warn!("No version was found, the scoop manifest will not be updated!");
+    }
+
+    for (arch, file) in files {
+        outcome.files.entry(arch).or_insert(file);
+    }
+
+    Ok(())
+}
+
+/// Creates a backup of the scoop manifest the same way as
+/// [backup_chocolatey_files].
+fn backup_scoop_files(manifest: &Path, options: &UpdateOptions) -> Option<FileBackup> {
+    if options.dry_run || options.arch_failure != ArchFailurePolicy::AllOrNothing {
+        return None;
+    }
+
+    Some(FileBackup::create(vec![manifest.to_path_buf()]))
+}
+
+/// Creates a backup of the chocolatey package files that can be changed during
+/// the update, which are restored if the update fails. No backup is created
+/// during a dry run, or when the update continues with the architectures that
+/// succeeded.
+fn backup_chocolatey_files(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    nuspec: &Path,
+    package_dir: &Path,
+    options: &UpdateOptions,
+) -> Option<FileBackup> {
+    if options.dry_run || options.arch_failure != ArchFailurePolicy::AllOrNothing {
+        return None;
+    }
+
+    let mut paths = vec![
+        get_install_script_path(package_dir, choco),
+        nuspec.to_path_buf(),
+    ];
+    if let Some(ref verification) = choco.verification {
+        paths.push(get_verification_path(verification, package_dir));
+    }
+
+    Some(FileBackup::create(paths))
+}
+
+/// The content of the package files before any changes was made to them,
+/// which allows the changes to be reverted when a later change fails.
+struct FileBackup {
+    files: Vec<(PathBuf, Option<String>)>,
+}
+
+impl FileBackup {
+    fn create(paths: Vec<PathBuf>) -> FileBackup {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let content = std::fs::read_to_string(&path).ok();
+                (path, content)
+            })
+            .collect();
+
+        FileBackup { files }
+    }
+
+    /// Restores the files that have been changed to their original content,
+    /// and removes the files that did not exist before.
+    fn restore(self) {
+        for (path, original) in self.files {
+            let current = std::fs::read_to_string(&path).ok();
+            if current == original {
+                continue;
+            }
+
+            let result = match original {
+                Some(content) => std::fs::write(&path, content),
+                None => std::fs::remove_file(&path),
+            };
+            match result {
+                Ok(()) => info!("Reverted the changes made to '{}'", path.display()),
+                Err(err) => warn!("Unable to revert '{}': {}", path.display(), err),
+            }
+        }
+    }
+}
+
+/// Runs the after hooks of a package, with the version and downloaded files
+/// of the update available as environment variables.
+fn run_after_hooks(
+    after: &[Hook],
+    outcome: &UpdateOutcome,
+    data: &PackageData,
+    package_dir: &Path,
+    options: &UpdateOptions,
+) -> Result<(), UpdateError> {
+    let timeout = get_hook_timeout(data.updater().hooks(), options);
+    let mut env = HashMap::new();
+    env.insert(
+        "AER_PACKAGE_ID".to_string(),
+        data.metadata().id().to_string(),
+    );
+    if let Some(ref version) = outcome.new_version {
+        env.insert("AER_VERSION".to_string(), version.clone());
+    }
+    let files = std::env::join_paths(outcome.files.values().map(|file| &file.path))
+        .map_err(|err| UpdateError::Hook(format!("Unable to set AER_FILES: {}", err)))?;
+    env.insert(
+        "AER_FILES".to_string(),
+        files.to_string_lossy().into_owned(),
+    );
+
+    for hook in after {
+        if options.dry_run {
+            info!("Would run the after hook '{}'", describe_hook(hook));
+            continue;
+        }
+        run_hook(hook, package_dir, &env, timeout).map_err(UpdateError::Hook)?;
+    }
+
+    Ok(())
+}
+
+/// Describes every url that is parsed, separated by a comma.
+pub fn describe_parse_urls(parse_urls: &chocolatey::ChocolateyParseUrls) -> String {
+    let urls: Vec<String> = parse_urls
+        .entries()
+        .into_iter()
+        .map(|entry| describe_parse_url(&entry.url))
+        .collect();
+
+    urls.join(", ")
+}
+
+/// Returns the url that will be parsed for the specified parse url
+/// configuration.
+pub fn describe_parse_url(parse_url: &chocolatey::ChocolateyParseUrl) -> String {
+    match parse_url {
+        chocolatey::ChocolateyParseUrl::Url(url)
+        | chocolatey::ChocolateyParseUrl::UrlWithRegex { url, .. }
+        | chocolatey::ChocolateyParseUrl::UrlWithSelector { url, .. }
+        | chocolatey::ChocolateyParseUrl::Json { url, .. } => url.to_string(),
+        chocolatey::ChocolateyParseUrl::GitHub { owner, repo, .. } => {
+            format!("https://github.com/{}/{}", owner, repo)
+        }
+        chocolatey::ChocolateyParseUrl::External { command } => command.clone(),
+    }
+}
+
+/// Finds the version of the package by using the specified updater data,
+/// without downloading any of the links that was found.
+pub(crate) fn find_version(
+    request: &WebRequest,
+    data: &PackageData,
+    package_dir: &Path,
+    choco: &chocolatey::ChocolateyUpdaterData,
+    options: &UpdateOptions,
+) -> Result<Option<Versions>, UpdateError> {
+    find_links(request, data, package_dir, choco, options).map(|found| found.version)
+}
+
+/// Finds the links of every architecture by using the specified updater
+/// data. This is shared between every updater kind, with the updater kinds
+/// only differing in the files that the results are written to.
+fn find_links(
+    request: &WebRequest,
+    data: &PackageData,
+    package_dir: &Path,
+    choco: &chocolatey::ChocolateyUpdaterData,
+    options: &UpdateOptions,
+) -> Result<FoundLinks, UpdateError> {
+    let parse_urls = match choco.parse_url {
+        Some(ref parse_urls) => parse_urls,
+        None => {
+            return Err(UpdateError::NoParseUrl {
+                id: data.metadata().id().to_owned(),
+            })
+        }
+    };
+    let request = configure_request(request, choco, parse_urls)?;
+    if let Some(ref name) = choco.regex_preset {
+        match chocolatey::regex_preset(name) {
+            Some(preset) => info!(
+                "Using the '{}' regex preset to find the links of {}",
+                preset.name,
+                data.metadata().id()
+            ),
+            None => warn!("The regex preset '{}' does not exist, ignoring it!", name),
+        }
+    }
+    let preset_data = choco.with_regex_preset();
+    let choco = preset_data.as_ref();
+    let allow_prerelease = options.allow_prerelease || choco.allow_prerelease;
+    let match_target = if choco.match_link_text {
+        MatchTarget::Text
+    } else if choco.decode_link {
+        MatchTarget::DecodedLink
+    } else {
+        MatchTarget::Link
+    };
+    let version_selector = choco
+        .version_selector
+        .as_ref()
+        .map(|selector| VersionSelector {
+            selector: selector.selector.clone(),
+            attribute: selector.attribute.clone(),
+            regex: selector.regex.clone(),
+        });
+    let link_sources: Vec<LinkSource> = choco
+        .link_sources
+        .iter()
+        .map(|source| LinkSource::new(&source.element, &source.attribute))
+        .collect();
+    let mut architectures: Vec<&str> = choco.regexes().keys().map(String::as_str).collect();
+    architectures.sort_unstable();
+    let resolver = ResolverContext {
+        id: data.metadata().id(),
+        architectures,
+        allow_prerelease,
+        work_dir: package_dir,
+        timeout: get_hook_timeout(data.updater().hooks(), options),
+    };
+    let parse_urls = parse_urls.entries();
+    let mut parsed: Vec<(Vec<String>, Vec<LinkElement>)> = Vec::with_capacity(parse_urls.len());
+
+    for entry in &parse_urls {
+        let (parse_url, archs) = (&entry.url, entry.archs.as_slice());
+        let entry_request;
+        let request = if entry.accept_language.is_none() && entry.headers.is_empty() {
+            &request
+        } else {
+            entry_request = configure_entry_request(&request, entry, parse_url)?;
+            &entry_request
+        };
+        let found = match parse_url {
+            chocolatey::ChocolateyParseUrl::UrlWithRegex {
+                url,
+                regex,
+                follow_first: true,
+                follow_highest,
+                arch_regexes,
+            } if !arch_regexes.is_empty() => read_arch_parse_url(
+                request,
+                url,
+                regex,
+                *follow_highest,
+                arch_regexes,
+                match_target,
+                version_selector.as_ref(),
+                &link_sources,
+            )?
+            .into_iter()
+            .map(|(arch, links)| (Some(arch), links))
+            .collect(),
+            _ => vec![(
+                None,
+                read_parse_url(
+                    request,
+                    parse_url,
+                    match_target,
+                    allow_prerelease,
+                    version_selector.as_ref(),
+                    &link_sources,
+                    &resolver,
+                )?,
+            )],
+        };
+
+        for (arch, links) in found {
+            let archs = match arch {
+                Some(arch) if is_used_for(archs, &arch) => vec![arch],
+                Some(_) => continue,
+                None => archs.to_vec(),
+            };
+            if archs.is_empty() {
+                info!(
+                    "'{}' contributed {} links",
+                    describe_parse_url(parse_url),
+                    links.len()
+                );
+            } else {
+                info!(
+                    "'{}' contributed {} links for {}",
+                    describe_parse_url(parse_url),
+                    links.len(),
+                    archs.join(", ")
+                );
+            }
+            parsed.push((archs, links));
+        }
+    }
+
+    let mut architectures = BTreeMap::new();
+    let mut unmatched = vec![];
+    let arch_patterns = if choco.auto_arch {
+        choco.arch_patterns()
+    } else {
+        vec![]
+    };
+
+    for (key, regexes) in choco.regexes() {
+        let regexes = regexes.as_slice();
+        let urls: Vec<LinkElement> = parsed
+            .iter()
+            .filter(|(archs, _)| is_used_for(archs, key))
+            .flat_map(|(_, links)| links.iter().cloned())
+            .collect();
+        let mut items = vec![];
+        let mut any_matched = false;
+
+        for (index, regex) in regexes.iter().enumerate() {
+            let matched = match_links(
+                &urls,
+                key,
+                regex,
+                choco.version_template.as_deref(),
+                match_target,
+                options.strict,
+            )?;
+            // Links excluded as pre-releases or by the version constraints
+            // still count as matched, as the layout of the page is unchanged.
+            any_matched |= !matched.is_empty();
+            items = exclude_prereleases(matched, key, regex, allow_prerelease);
+            let matched = items.len();
+            items.retain(|link| choco.is_version_allowed(link.version.as_ref()));
+            if items.len() < matched {
+                info!(
+                    "{} of the {} links matched for {} was excluded by the version \
+                     constraints",
+                    matched - items.len(),
+                    matched,
+                    key
+                );
+            }
+
+            if !items.is_empty() {
+                trace!(
+                    "Regex {} of {} ('{}') matched {} links for {}",
+                    index + 1,
+                    regexes.len(),
+                    regex,
+                    items.len(),
+                    key
+                );
+                break;
+            }
+        }
+
+        if !any_matched {
+            warn!(
+                "None of the regexes of {} matched any of the {} links, the layout of the page \
+                 may have changed!",
+                key,
+                urls.len()
+            );
+            unmatched.push(key.clone());
+        }
+
+        let arch = chocolatey::architecture_name(key);
+        {
+            let candidates: Vec<&str> = items.iter().map(|l| l.link.as_str()).collect();
+            trace!("Candidates for {}: {:?}", arch, candidates);
+        }
+
+        if choco.auto_arch && !arch_patterns.iter().any(|(name, _)| *name == arch) {
+            detect_architectures(dedup_links(items), &arch_patterns, &mut architectures)?;
+            continue;
+        }
+
+        let selection = choco.link_selection(key);
+        info!("Selecting the {} link matched for {}", selection, arch);
+        match select_link(items, selection) {
+            Some(link) => {
+                info!("{}: {}", arch, link.link);
+                if architectures.insert(arch.clone(), link).is_some() {
+                    warn!(
+                        "Multiple regexes was specified for the {} architecture, only the \
+                         last match will be used!",
+                        arch
+                    );
+                }
+            }
+            None => info!("{}: None", arch),
+        }
+    }
+
+    unmatched.sort();
+
+    Ok(FoundLinks {
+        request,
+        version: get_version(&architectures),
+        architectures,
+        unmatched,
+    })
+}
+
+/// Creates the request used for a single package, by adding the
+/// authentication, user agent, headers and other settings of the package to
+/// the specified request.
+pub(crate) fn configure_request(
+    request: &WebRequest,
+    choco: &chocolatey::ChocolateyUpdaterData,
+    parse_urls: &chocolatey::ChocolateyParseUrls,
+) -> Result<WebRequest, UpdateError> {
+    let request = match choco.authentication {
+        Some(ref authentication) => authenticate_request(request, authentication)?,
+        None => request.clone(),
+    };
+    let request = match choco.user_agent {
+        Some(ref user_agent) => request.with_user_agent(user_agent),
+        None => request,
+    };
+    let request = match choco.max_links {
+        Some(max_links) => request.with_max_links(max_links),
+        None => request,
+    };
+    let url = describe_parse_urls(parse_urls);
+    let request = match choco.accept_language {
+        Some(ref accept_language) => request
+            .with_headers(vec![("Accept-Language", accept_language.as_str())])
+            .map_err(|err| request_error(&url, err))?,
+        None => request,
+    };
+    let request = add_headers(request, &choco.headers, &url)?;
+
+    add_github_token(request, parse_urls)
+}
+
+/// Creates the request used for a single entry of the parse urls, by adding
+/// the accept language and headers of the entry to the request of the package.
+fn configure_entry_request(
+    request: &WebRequest,
+    entry: &chocolatey::ChocolateyParseUrlEntry,
+    parse_url: &chocolatey::ChocolateyParseUrl,
+) -> Result<WebRequest, UpdateError> {
+    let url = describe_parse_url(parse_url);
+    let request = match entry.accept_language {
+        Some(ref accept_language) => request
+            .with_headers(vec![("Accept-Language", accept_language.as_str())])
+            .map_err(|err| request_error(&url, err))?,
+        None => request.clone(),
+    };
+
+    add_headers(request, &entry.headers, &url)
+}
+
+/// Assigns the specified links to the architecture detected from their file
+/// name. The links are expected to be sorted by [dedup_links], which means
+/// the link with the highest version is used for each detected architecture.
+fn detect_architectures(
+    links: Vec<LinkElement>,
+    patterns: &[(String, Vec<String>)],
+    architectures: &mut BTreeMap<String, LinkElement>,
+) -> Result<(), UpdateError> {
+    let mut detected: BTreeMap<String, LinkElement> = BTreeMap::new();
+
+    for link in links {
+        let arch = detect_architecture(&link, patterns)?;
+        trace!("Detected the {} architecture for {}", arch, link.link);
+        detected.entry(arch).or_insert(link);
+    }
+
+    for (arch, link) in detected {
+        info!("{} (detected): {}", arch, link.link);
+        if architectures.insert(arch.clone(), link).is_some() {
+            warn!(
+                "The {} architecture was both detected and matched by a regex, only the last \
+                 match will be used!",
+                arch
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Detects the architecture of the specified link by using the first of the
+/// architecture patterns that match the file name of the link, or the text
+/// of the link when the file name do not match any of them. Links that do
+/// not match any patterns are assigned to the `others` architecture.
+fn detect_architecture(
+    link: &LinkElement,
+    patterns: &[(String, Vec<String>)],
+) -> Result<String, UpdateError> {
+    let file_name = link
+        .link
+        .path_segments()
+        .and_then(|segments| segments.last())
+        .unwrap_or_default();
+    let mut compiled = Vec::with_capacity(patterns.len());
+
+    for (arch, values) in patterns.iter().filter(|(_, values)| !values.is_empty()) {
+        let regex = chocolatey::arch_pattern_regex(values);
+        let re = get_regex(&regex).map_err(|error| UpdateError::InvalidRegex { regex, error })?;
+        compiled.push((arch, re));
+    }
+
+    for value in &[file_name, link.text.as_str()] {
+        if let Some((arch, _)) = compiled.iter().find(|(_, re)| re.is_match(value)) {
+            return Ok((*arch).clone());
+        }
+    }
+
+    Ok(chocolatey::OTHER_ARCHITECTURE.into())
+}
+
+/// Creates the links of the mirrors of the specified architecture, by
+/// rendering the mirror urls with the values of the found link. Mirrors that
+/// do not result in a valid url are skipped.
+fn get_mirror_links(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    arch: &str,
+    link: &LinkElement,
+    version: Option<&str>,
+) -> Vec<LinkElement> {
+    let mut values = HashMap::new();
+    values.insert("url".to_string(), link.link.to_string());
+    values.insert(
+        "file_name".to_string(),
+        link.link
+            .path_segments()
+            .and_then(|segments| segments.last())
+            .unwrap_or_default()
+            .to_string(),
+    );
+    if let Some(version) = version {
+        values.insert("version".to_string(), version.to_string());
+    }
+
+    choco
+        .mirrors(arch)
+        .iter()
+        .filter_map(|mirror| {
+            let url = render_template(mirror, &values)
+                .map_err(|err| err.to_string())
+                .and_then(|url| Url::parse(&url).map_err(|err| err.to_string()));
+
+            match url {
+                Ok(url) => Some(LinkElement {
+                    link: url,
+                    ..link.clone()
+                }),
+                Err(err) => {
+                    warn!(
+                        "The mirror '{}' of the {} file can not be used: {}",
+                        mirror, arch, err
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Downloads the files of the links found by [find_links] to the directory
+/// created from the output template, returning the downloaded files keyed by
+/// the name of their architecture.
+///
+/// When some of the files could not be downloaded, the update fails unless the
+/// [ArchFailurePolicy::BestEffort] policy is used, in which case the failed
+/// architectures are added to `failed` and only the successful files are
+/// returned.
+fn download_files(
+    found: FoundLinks,
+    data: &PackageData,
+    choco: &chocolatey::ChocolateyUpdaterData,
+    version: Option<&str>,
+    package_dir: &Path,
+    options: &UpdateOptions,
+    failed: &mut BTreeMap<String, String>,
+) -> Result<BTreeMap<String, DownloadedFile>, UpdateError> {
+    let hooks = data.updater().hooks();
+    let work_dir = get_work_dir(options, data.metadata().id(), version)?;
+    let checksum_type = get_checksum_type(choco.checksum_type);
+
+    let downloads = found
+        .architectures
+        .into_iter()
+        .map(|(arch, link)| {
+            let expected = choco
+                .expected_checksum(&arch)
+                .map(|expected| ExpectedChecksum {
+                    checksum: expected.checksum.trim().to_lowercase(),
+                    checksum_type: get_checksum_type(
+                        expected.checksum_type.unwrap_or(choco.checksum_type),
+                    ),
+                });
+            let content_type = ContentTypeCheck {
+                expected: choco.expected_content_type(&arch).map(Into::into),
+                reject_html: choco.reject_html,
+            };
+            let mirrors = get_mirror_links(choco, &arch, &link, version);
+            Download {
+                arch,
+                link,
+                expected,
+                content_type,
+                mirrors,
+            }
+        })
+        .collect();
+    let download_hooks = if options.run_hooks {
+        let mut env = HashMap::new();
+        env.insert(
+            "AER_PACKAGE_ID".to_string(),
+            data.metadata().id().to_string(),
+        );
+        DownloadHooks {
+            pre_download: hooks.pre_download.clone(),
+            post_download: hooks.post_download.clone(),
+            work_dir: package_dir.to_path_buf(),
+            env,
+            timeout: get_hook_timeout(hooks, options),
+        }
+    } else {
+        DownloadHooks::default()
+    };
+    let (files, failures) = download_links(
+        &found.request,
+        data.metadata().id(),
+        downloads,
+        &work_dir,
+        checksum_type,
+        options,
+        download_hooks,
+    );
+
+    if !failures.is_empty() {
+        if options.arch_failure == ArchFailurePolicy::AllOrNothing || files.is_empty() {
+            let (_, err) = failures.into_iter().next().unwrap();
+            return Err(err);
+        }
+
+        for (arch, err) in failures {
+            warn!(
+                "The {} file could not be downloaded, only the architectures that succeeded will \
+                 be updated: {}",
+                arch, err
+            );
+            failed.insert(arch, err.to_string());
+        }
+    }
+
+    if let Some(sums_type) = options.checksum_file {
+        write_sums_file(&work_dir, &files, sums_type, options)?;
+    }
+
+    Ok(files)
+}
+
+/// Writes the checksums of the downloaded files to the checksum file in the
+/// directory the files was downloaded to. The checksums are calculated again
+/// when the checksum type of the file is different from the checksum type
+/// used for the package.
+fn write_sums_file(
+    work_dir: &Path,
+    files: &BTreeMap<String, DownloadedFile>,
+    checksum_type: ChecksumType,
+    options: &UpdateOptions,
+) -> Result<(), UpdateError> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    check_cancelled(options)?;
+    if options.dry_run {
+        info!(
+            "Would write the {} checksums of {} file(s) to '{}'",
+            checksum_type,
+            files.len(),
+            work_dir.join(sums::sums_file_name(checksum_type)).display()
+        );
+        return Ok(());
+    }
+
+    let checksums = files
+        .values()
+        .map(|file| {
+            let checksum = if file.checksum_type == checksum_type {
+                file.checksum.clone()
+            } else {
+                checksum_type
+                    .generate(&file.path)
+                    .map_err(|err| io_error(&file.path, err))?
+            };
+            Ok((file.path.as_path(), checksum))
+        })
+        .collect::<Result<Vec<_>, UpdateError>>()?;
+
+    sums::update_sums_file(work_dir, checksum_type, &checksums)
+        .map(|_| ())
+        .map_err(|err| io_error(work_dir, err))
+}
+
+/// Commits the files that was modified by the update to the git repository
+/// specified in the options, or outputs the commit that would be made when
+/// running without making any changes.
+fn commit_changes(
+    data: &PackageData,
+    outcome: &UpdateOutcome,
+    package_dir: &Path,
+    options: &UpdateOptions,
+) -> Result<(), UpdateError> {
+    let files = &outcome.modified_files;
+    let mut values = HashMap::new();
+    values.insert("id".to_string(), data.metadata().id().to_string());
+    values.insert(
+        "old_version".to_string(),
+        outcome.old_version.clone().unwrap_or_default(),
+    );
+    values.insert(
+        "new_version".to_string(),
+        outcome.new_version.clone().unwrap_or_default(),
+    );
+    let message = render_template(&options.git_message, &values)
+        .map_err(|err| UpdateError::Git(format!("Unable to create the commit message: {}", err)))?;
+    let repo = options.git_repo.as_deref().unwrap_or(package_dir);
+    check_cancelled(options)?;
+
+    if options.dry_run {
+        info!(
+            "Would commit {} file(s) to '{}' with the message '{}'",
+            files.len(),
+            repo.display(),
+            message
+        );
+        return Ok(());
+    }
+
+    git::commit_files(repo, files, &message)
+        .map(|_| ())
+        .map_err(UpdateError::Git)
+}
+
+/// Updates the `latest` pointer of the package to point at the directory the
+/// files of the new version was downloaded to. The pointer is only used when
+/// the output template references the version, as the files of every version
+/// are otherwise downloaded to the same directory.
+fn update_latest_pointer(
+    data: &PackageData,
+    outcome: &UpdateOutcome,
+    options: &UpdateOptions,
+) -> Result<(), UpdateError> {
+    let id = data.metadata().id();
+
+    if get_work_dir(options, id, None).is_ok() {
+        warn!(
+            "The output template '{}' do not reference the version, the latest pointer will not \
+             be updated!",
+            options.output_template
+        );
+        return Ok(());
+    }
+
+    let work_dir = get_work_dir(options, id, outcome.new_version.as_deref())?;
+    check_cancelled(options)?;
+
+    if options.dry_run {
+        info!(
+            "Would update the latest pointer to '{}'",
+            work_dir.display()
+        );
+        return Ok(());
+    }
+
+    latest::update_latest_pointer(&work_dir)
+        .map(|_| ())
+        .map_err(|err| io_error(&work_dir, err))
+}
+
+impl DownloadHooks {
+    /// Runs the hooks that should be run before the file for the specified
+    /// architecture is downloaded.
+    fn run_pre_download(&self, arch: &str, url: &str, path: &Path) -> Result<(), UpdateError> {
+        self.run("pre-download", &self.pre_download, arch, url, path)
+    }
+
+    /// Runs the hooks that should be run after the file for the specified
+    /// architecture have been downloaded, the file is deleted if any of the
+    /// hooks fail.
+    fn run_post_download(&self, arch: &str, file: &DownloadedFile) -> Result<(), UpdateError> {
+        let url = file.link.link.as_str();
+
+        self.run("post-download", &self.post_download, arch, url, &file.path)
+            .map_err(|err| {
+                if let Err(err) = std::fs::remove_file(&file.path) {
+                    warn!("Unable to remove '{}': {}", file.path.display(), err);
+                }
+                err
+            })
+    }
+
+    fn run(
+        &self,
+        name: &str,
+        hooks: &[Hook],
+        arch: &str,
+        url: &str,
+        path: &Path,
+    ) -> Result<(), UpdateError> {
+        if hooks.is_empty() {
+            return Ok(());
+        }
+
+        let mut env = self.env.clone();
+        env.insert("AER_ARCH".to_string(), arch.to_string());
+        env.insert("AER_DOWNLOAD_URL".to_string(), url.to_string());
+        env.insert(
+            "AER_DOWNLOAD_PATH".to_string(),
+            path.to_string_lossy().into_owned(),
+        );
+
+        for hook in hooks {
+            run_hook(hook, &self.work_dir, &env, self.timeout).map_err(|err| {
+                UpdateError::Hook(format!("A {} hook failed for '{}': {}", name, url, err))
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the directory the files of a package are downloaded to, by
+/// rendering the output template with the identifier and version of the
+/// package. Empty, `.` and `..` components of the rendered template are
+/// ignored, to keep the directory inside of the output directory.
+///
+/// The template can only reference the version when a version is specified.
+pub fn get_work_dir(
+    options: &UpdateOptions,
+    id: &str,
+    version: Option<&str>,
+) -> Result<PathBuf, UpdateError> {
+    let mut values = HashMap::new();
+    values.insert("id".to_string(), id.to_string());
+    if let Some(version) = version {
+        values.insert("version".to_string(), version.to_string());
+    }
+    let rendered = render_template(&options.output_template, &values)
+        .map_err(|error| update_error(&options.output_dir, error))?;
+
+    Ok(rendered
+        .split(|c| c == '/' || c == '\\')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .fold(options.output_dir.clone(), |path, part| path.join(part)))
+}
+
+/// Returns the directory of the specified package file, which is used as the
+/// base directory of the paths in the package data.
+pub fn get_package_dir(package_file: &Path) -> &Path {
+    match package_file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Returns the path to the nuspec file of the package, defaulting to
+/// `<package id>.nuspec` in the package directory.
+pub fn get_nuspec_path(
+    package_dir: &Path,
+    data: &PackageData,
+    choco: &chocolatey::ChocolateyUpdaterData,
+) -> PathBuf {
+    match choco.nuspec {
+        Some(ref nuspec) => package_dir.join(nuspec),
+        None => package_dir.join(format!("{}.nuspec", data.metadata().id())),
+    }
+}
+
+/// Returns the path to the install script of the package, defaulting to
+/// `tools/chocolateyInstall.ps1` in the package directory.
+pub fn get_install_script_path(
+    package_dir: &Path,
+    choco: &chocolatey::ChocolateyUpdaterData,
+) -> PathBuf {
+    match choco.install_script {
+        Some(ref install_script) => package_dir.join(install_script),
+        None => package_dir.join("tools/chocolateyInstall.ps1"),
+    }
+}
+
+/// Returns the path to the scoop manifest of the package, defaulting to
+/// `<package id>.json` in the package directory.
+pub fn get_manifest_path(
+    package_dir: &Path,
+    data: &PackageData,
+    scoop: &scoop::ScoopUpdaterData,
+) -> PathBuf {
+    match scoop.manifest {
+        Some(ref manifest) => package_dir.join(manifest),
+        None => package_dir.join(format!("{}.json", data.metadata().id())),
+    }
+}
+
+/// Returns the path to the verification file of a chocolatey package,
+/// defaults to `legal/VERIFICATION.txt` relative to the package directory.
+fn get_verification_path(
+    verification: &chocolatey::ChocolateyVerification,
+    package_dir: &Path,
+) -> PathBuf {
+    package_dir.join(
+        verification
+            .path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("legal/VERIFICATION.txt")),
+    )
+}
+
+/// Returns the duration the hooks of a package are allowed to run, where the
+/// timeout of the package data is preferred over the timeout of the options.
+fn get_hook_timeout(hooks: &UpdateHooks, options: &UpdateOptions) -> Option<Duration> {
+    Some(
+        hooks
+            .timeout
+            .map(Duration::from_secs)
+            .unwrap_or(options.hook_timeout),
+    )
+}
+
+/// Returns the command that is run by the specified hook.
+fn describe_hook(hook: &Hook) -> &str {
+    match hook {
+        Hook::Command(command) => command,
+        Hook::PowerShell { powershell } => powershell,
+    }
+}
+
+/// Updates the version in the specified nuspec file, or outputs the changes
+/// that would be made when `dry_run` is set. The version that was in the
+/// nuspec file before the update is returned, together with the diff of the
+/// changes when the file was (*or would be*) changed.
+fn update_nuspec(
+    path: &Path,
+    version: &str,
+    dry_run: bool,
+) -> Result<(String, Option<String>), UpdateError> {
+    let content = std::fs::read_to_string(path).map_err(|error| io_error(path, error))?;
+    let old_version = get_nuspec_version(&content).map_err(|error| update_error(path, error))?;
+
+    let diff = match update_nuspec_version(&content, version)
+        .map_err(|error| update_error(path, error))?
+    {
+        None => {
+            info!(
+                "The nuspec file '{}' is up to date with version {}!",
+                path.display(),
+                version
+            );
+            None
+        }
+        Some(updated) => {
+            let diff = write_changes(path, &content, updated, dry_run)?;
+            if !dry_run {
+                info!("Updated '{}' to version {}!", path.display(), version);
+            }
+            Some(diff)
+        }
+    };
+
+    Ok((old_version, diff))
+}
+
+/// Updates the version, urls and hashes in the specified scoop manifest, or
+/// outputs the changes that would be made when `dry_run` is set. The version
+/// that was in the manifest before the update is returned, together with the
+/// diff of the changes when the file was (*or would be*) changed.
+fn update_manifest(
+    path: &Path,
+    version: &str,
+    files: &BTreeMap<String, DownloadedFile>,
+    dry_run: bool,
+) -> Result<(String, Option<String>), UpdateError> {
+    let content = std::fs::read_to_string(path).map_err(|error| io_error(path, error))?;
+    let old_version = get_manifest_version(&content).map_err(|error| update_error(path, error))?;
+    let files = files
+        .iter()
+        .map(|(arch, file)| {
+            (
+                arch.clone(),
+                ManifestFile {
+                    url: file.link.link.to_string(),
+                    hash: file.checksum.clone(),
+                },
+            )
+        })
+        .collect();
+
+    let diff = match update_manifest_content(&content, version, &files)
+        .map_err(|error| update_error(path, error))?
+    {
+        None => {
+            info!(
+                "The scoop manifest '{}' is up to date with version {}!",
+                path.display(),
+                version
+            );
+            None
+        }
+        Some(updated) => {
+            let diff = write_changes(path, &content, updated, dry_run)?;
+            if !dry_run {
+                info!("Updated '{}' to version {}!", path.display(), version);
+            }
+            Some(diff)
+        }
+    };
+
+    Ok((old_version, diff))
+}
+
+/// Gets the values that should be written to each of the variables in the
+/// install script. When no variables have been specified, the default
+/// variables for the `x86` and `x64` files that was downloaded will be used.
+///
+/// Variables referencing an architecture that is part of the `failed`
+/// architectures are skipped, to keep their current value.
+fn get_script_values(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    files: &BTreeMap<String, DownloadedFile>,
+    version: Option<&str>,
+    failed: &BTreeMap<String, String>,
+) -> Result<HashMap<String, String>, UpdateError> {
+    use chocolatey::{architecture_name, ChocolateyScriptValue};
+
+    let variables = if choco.script_variables.is_empty() {
+        let mut variables = HashMap::new();
+        let arch32 = architecture_name("arch32");
+        let arch64 = architecture_name("arch64");
+        if files.contains_key(&arch32) {
+            variables.insert(
+                "url".to_string(),
+                ChocolateyScriptValue::Url(arch32.clone()),
+            );
+            variables.insert(
+                "checksum".to_string(),
+                ChocolateyScriptValue::Checksum(arch32),
+            );
+        }
+        if files.contains_key(&arch64) {
+            variables.insert(
+                "url64".to_string(),
+                ChocolateyScriptValue::Url(arch64.clone()),
+            );
+            variables.insert(
+                "checksum64".to_string(),
+                ChocolateyScriptValue::Checksum(arch64),
+            );
+        }
+        variables
+    } else {
+        choco.script_variables.clone()
+    };
+
+    let mut values = HashMap::with_capacity(variables.len());
+
+    for (name, source) in variables {
+        if let ChocolateyScriptValue::Url(ref arch) | ChocolateyScriptValue::Checksum(ref arch) =
+            source
+        {
+            if failed.contains_key(arch) {
+                warn!(
+                    "The {} file could not be downloaded, the script variable '{}' will not be \
+                     updated!",
+                    arch, name
+                );
+                continue;
+            }
+        }
+
+        let value = match source {
+            ChocolateyScriptValue::Url(ref arch) => {
+                files.get(arch).map(|f| f.link.link.to_string())
+            }
+            ChocolateyScriptValue::Checksum(ref arch) => {
+                files.get(arch).map(|f| f.checksum.clone())
+            }
+            ChocolateyScriptValue::ChecksumType => {
+                files.values().next().map(|f| f.checksum_type.to_string())
+            }
+            ChocolateyScriptValue::Version => version.map(|v| v.to_owned()),
+        };
+
+        match value {
+            Some(value) => {
+                values.insert(name, value);
+            }
+            None => {
+                return Err(UpdateError::MissingScriptValue {
+                    name,
+                    source: source.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Updates the variables in the specified install script, or outputs the
+/// changes that would be made when `dry_run` is set. Returns the diff of the
+/// changes when the install script was (*or would be*) changed.
+fn update_install_script(
+    path: &Path,
+    values: &HashMap<String, String>,
+    dry_run: bool,
+) -> Result<Option<String>, UpdateError> {
+    let content = std::fs::read_to_string(path).map_err(|error| io_error(path, error))?;
+
+    match update_script_variables(&content, values).map_err(|error| update_error(path, error))? {
+        None => {
+            info!("The install script '{}' is up to date!", path.display());
+            Ok(None)
+        }
+        Some(updated) => {
+            let diff = write_changes(path, &content, updated, dry_run)?;
+            if !dry_run {
+                info!("Updated the variables in '{}'!", path.display());
+            }
+            Ok(Some(diff))
+        }
+    }
+}
+
+/// Gets the values that can be used in the template of the verification file.
+/// The urls and checksums of the downloaded files are available both by the
+/// name of their architecture (*ie: `url_x64`*), and as a list of all files
+/// (`urls` and `checksums`).
+fn get_verification_values(
+    data: &PackageData,
+    files: &BTreeMap<String, DownloadedFile>,
+    version: Option<&str>,
+) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    values.insert("id".to_string(), data.metadata().id().to_string());
+    values.insert(
+        "project_url".to_string(),
+        data.metadata().project_url().to_string(),
+    );
+    if let Some(version) = version {
+        values.insert("version".to_string(), version.to_string());
+    }
+    if let Some(file) = files.values().next() {
+        values.insert("checksum_type".to_string(), file.checksum_type.to_string());
+    }
+
+    let mut urls = String::new();
+    let mut checksums = String::new();
+
+    for (arch, file) in files {
+        let url = file.link.link.to_string();
+        urls.push_str(&format!("  {}: <{}>\n", arch, url));
+        checksums.push_str(&format!("  checksum_{}: {}\n", arch, file.checksum));
+
+        let aliases: &[&str] = match arch.as_str() {
+            "x86" => &["x86", "32"],
+            "x64" => &["x64", "64"],
+            _ => &[arch.as_str()],
+        };
+        for alias in aliases {
+            let separator = if alias.starts_with(char::is_numeric) {
+                ""
+            } else {
+                "_"
+            };
+            values.insert(format!("url{}{}", separator, alias), url.clone());
+            values.insert(
+                format!("checksum{}{}", separator, alias),
+                file.checksum.clone(),
+            );
+        }
+    }
+
+    values.insert("urls".to_string(), urls);
+    values.insert("checksums".to_string(), checksums);
+
+    values
+}
+
+/// Creates or updates the verification file of the package, or outputs the
+/// changes that would be made when `dry_run` is set. The path of the
+/// verification file is returned together with the diff of the changes when
+/// it was (*or would be*) changed.
+fn update_verification(
+    verification: &chocolatey::ChocolateyVerification,
+    package_dir: &Path,
+    values: &HashMap<String, String>,
+    dry_run: bool,
+) -> Result<Option<(PathBuf, String)>, UpdateError> {
+    let template = match verification.template {
+        Some(ref template) => {
+            let template = package_dir.join(template);
+            std::fs::read_to_string(&template).map_err(|error| io_error(&template, error))?
+        }
+        None => DEFAULT_VERIFICATION_TEMPLATE.to_owned(),
+    };
+    let path = get_verification_path(verification, package_dir);
+    let content = render_template(&template, values).map_err(|error| update_error(&path, error))?;
+
+    let original = std::fs::read_to_string(&path).unwrap_or_default();
+    if path.exists() && original == content {
+        info!("The verification file '{}' is up to date!", path.display());
+        return Ok(None);
+    }
+
+    if !dry_run {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|error| io_error(parent, error))?;
+        }
+    }
+    let diff = write_changes(&path, &original, content, dry_run)?;
+    if !dry_run {
+        info!("Updated the verification file '{}'!", path.display());
+    }
+
+    Ok(Some((path, diff)))
+}
+
+/// Writes the updated content to the specified path, or outputs the diff of
+/// the changes that would be made when `dry_run` is set. The diff between the
+/// original and the updated content is returned.
+fn write_changes(
+    path: &Path,
+    original: &str,
+    updated: String,
+    dry_run: bool,
+) -> Result<String, UpdateError> {
+    let diff = diff::unified_diff(&path.display().to_string(), original, &updated);
+
+    if dry_run {
+        info!("Changes that would be made to '{}':", path.display());
+        log_diff(&diff);
+    } else {
+        std::fs::write(path, updated).map_err(|error| io_error(path, error))?;
+    }
+
+    Ok(diff)
+}
+
+/// Fails the update as timed out when the run have been cancelled, which is
+/// checked before each file is downloaded or written.
+fn check_cancelled(options: &UpdateOptions) -> Result<(), UpdateError> {
+    match options.cancelled {
+        Some(ref cancelled) if cancelled.load(Ordering::SeqCst) => Err(UpdateError::TimedOut),
+        _ => Ok(()),
+    }
+}
+
+/// Outputs every line of the specified unified diff, where removed lines are
+/// colored red and added lines are colored green.
+fn log_diff(diff: &str) {
+    for line in diff.lines() {
+        if line.starts_with("---") || line.starts_with("+++") {
+            info!("{}", Paint::new(line).bold());
+        } else if line.starts_with("@@") {
+            info!("{}", Paint::cyan(line));
+        } else if line.starts_with('-') {
+            info!("{}", Paint::red(line));
+        } else if line.starts_with('+') {
+            info!("{}", Paint::green(line));
+        } else {
+            info!("{}", line);
+        }
+    }
+}
+
+/// Downloads the links of every architecture concurrently, using at most
+/// `download_jobs` downloads at the same time. A failed download do not
+/// cancel the other downloads, instead every download is allowed to finish
+/// before the downloaded files and the failures are returned, both keyed by
+/// the name of the architecture.
+fn download_links(
+    request: &WebRequest,
+    id: &str,
+    downloads: Vec<Download>,
+    work_dir: &Path,
+    checksum_type: ChecksumType,
+    options: &UpdateOptions,
+    hooks: DownloadHooks,
+) -> (
+    BTreeMap<String, DownloadedFile>,
+    BTreeMap<String, UpdateError>,
+) {
+    if options.dry_run {
+        let files = downloads
+            .into_iter()
+            .map(|download| {
+                let arch = download.arch.clone();
+                let file = dry_run_download(download, work_dir, checksum_type, &hooks);
+                (arch, file)
+            })
+            .collect();
+        return (files, BTreeMap::new());
+    }
+
+    let jobs = options.download_jobs.min(downloads.len()).max(1);
+    let mut options = options.clone();
+    if jobs > 1 && options.progress == ProgressMode::Bar {
+        options.progress = ProgressMode::Log;
+    }
+    let request = Arc::new(request.clone());
+    let options = Arc::new(options);
+    let hooks = Arc::new(hooks);
+    let work_dir = Arc::new(work_dir.to_path_buf());
+    let queue = Arc::new(Mutex::new(downloads.into_iter()));
+    let (sender, receiver) = mpsc::channel();
+
+    let workers: Vec<_> = (0..jobs)
+        .map(|index| {
+            let queue = Arc::clone(&queue);
+            let request = Arc::clone(&request);
+            let options = Arc::clone(&options);
+            let hooks = Arc::clone(&hooks);
+            let work_dir = Arc::clone(&work_dir);
+            let sender = sender.clone();
+            let id = id.to_owned();
+
+            thread::Builder::new()
+                .name(format!("download-{}", index))
+                .spawn(move || {
+                    logging::set_package_context(&id);
+                    loop {
+                        let download = match queue.lock().unwrap().next() {
+                            Some(download) => download,
+                            None => break,
+                        };
+                        let arch = download.arch.clone();
+
+                        let result = download_link(
+                            &request,
+                            download,
+                            &work_dir,
+                            checksum_type,
+                            &options,
+                            &hooks,
+                        );
+
+                        if sender.send((arch, result)).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .expect("Unable to create download worker thread!")
+        })
+        .collect();
+    drop(sender);
+
+    let mut files = BTreeMap::new();
+    let mut failures = BTreeMap::new();
+
+    for (arch, result) in receiver.iter() {
+        match result {
+            Ok(file) => {
+                files.insert(arch, file);
+            }
+            Err(err) => {
+                error!("Downloading the {} file failed: {}", arch, err);
+                failures.insert(arch, err);
+            }
+        }
+    }
+
+    for worker in workers {
+        if worker.join().is_err() {
+            error!("A download worker thread panicked!");
+        }
+    }
+
+    (files, failures)
+}
+
+/// Creates the information about a file that would have been downloaded,
+/// without downloading the file. The expected checksum is used as the
+/// checksum of the file when available.
+fn dry_run_download(
+    download: Download,
+    work_dir: &Path,
+    checksum_type: ChecksumType,
+    hooks: &DownloadHooks,
+) -> DownloadedFile {
+    let Download { link, expected, .. } = download;
+    let file_name = link
+        .link
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download")
+        .to_owned();
+    let path = work_dir.join(file_name);
+    for hook in &hooks.pre_download {
+        info!("Would run the pre-download hook '{}'", describe_hook(hook));
+    }
+    info!("Would download '{}' to '{}'", link.link, path.display());
+    for hook in &hooks.post_download {
+        info!("Would run the post-download hook '{}'", describe_hook(hook));
+    }
+
+    let (checksum, checksum_type) = match expected {
+        Some(expected) => (expected.checksum, expected.checksum_type),
+        None => (
+            "<not calculated during a dry run>".to_owned(),
+            checksum_type,
+        ),
+    };
+
+    DownloadedFile {
+        link,
+        path,
+        checksum,
+        checksum_type,
+    }
+}
+
+/// Downloads the specified link to the work directory, creating the directory
+/// if it do not exist, and calculates the checksum of the file while it is
+/// downloaded. Files that already exist are handled by the collision policy
+/// of the options, where the download is skipped by default when a file
+/// with the same name and size already exists, unless `force` is set.
+///
+/// When an `expected` checksum is specified, the downloaded file is verified
+/// against it and deleted if the checksums do not match. The file is also
+/// deleted when any of the post-download hooks fail.
+///
+/// When the file could not be downloaded, or do not have the expected
+/// checksum, the mirrors of the download are tried in order until the file is
+/// successfully downloaded from one of them.
+pub(crate) fn download_link(
+    request: &WebRequest,
+    download: Download,
+    work_dir: &Path,
+    checksum_type: ChecksumType,
+    options: &UpdateOptions,
+    hooks: &DownloadHooks,
+) -> Result<DownloadedFile, UpdateError> {
+    let Download {
+        arch,
+        link,
+        expected,
+        content_type,
+        mirrors,
+    } = download;
+    let attempt = |link: LinkElement| {
+        let download = Download {
+            arch: arch.clone(),
+            link,
+            expected: expected.clone(),
+            content_type: content_type.clone(),
+            mirrors: vec![],
+        };
+        download_from_link(request, download, work_dir, checksum_type, options, hooks)
+    };
+    let mut result = attempt(link);
+
+    for mirror in mirrors {
+        match result {
+            Err(ref err)
+                if matches!(
+                    err,
+                    UpdateError::Download { .. } | UpdateError::ChecksumMismatch { .. }
+                ) =>
+            {
+                warn!(
+                    "Downloading the {} file failed, trying the mirror '{}': {}",
+                    arch, mirror.link, err
+                );
+            }
+            _ => break,
+        }
+
+        result = attempt(mirror);
+        if let Ok(ref file) = result {
+            info!(
+                "The {} file was served by the mirror '{}'",
+                arch, file.link.link
+            );
+        }
+    }
+
+    result
+}
+
+/// Downloads the file of a single link, without trying any of the mirrors of
+/// the download.
+fn download_from_link(
+    request: &WebRequest,
+    download: Download,
+    work_dir: &Path,
+    checksum_type: ChecksumType,
+    options: &UpdateOptions,
+    hooks: &DownloadHooks,
+) -> Result<DownloadedFile, UpdateError> {
+    let Download {
+        arch,
+        mut link,
+        expected,
+        content_type,
+        ..
+    } = download;
+    let expected = expected.as_ref();
+    check_cancelled(options)?;
+    std::fs::create_dir_all(work_dir).map_err(|error| io_error(work_dir, error))?;
+    let url = link.link.to_string();
+    let download_error = |message: String| UpdateError::Download {
+        url: url.clone(),
+        message,
+    };
+    let download_url = match link.attributes.get(github::API_URL_ATTRIBUTE) {
+        Some(api_url) if request.has_github_token() => api_url.as_str(),
+        _ => link.link.as_str(),
+    };
+
+    let mut response = match request
+        .get_binary_response(download_url, None, None)
+        .map_err(|error| download_error(error.to_string()))?
+    {
+        ResponseType::New(response, _) => response,
+        ResponseType::Updated(status) => {
+            return Err(download_error(format!(
+                "The server responded with status {}, and no file was available to download!",
+                status
+            )));
+        }
+    };
+    let actual_type = response.content_type();
+    if let Err(message) = content_type.check(actual_type.as_deref()) {
+        warn!(
+            "The server responded with the content type '{}' for '{}'",
+            actual_type.as_deref().unwrap_or("<none>"),
+            url
+        );
+        return Err(download_error(message));
+    }
+    let file_name = response
+        .file_name()
+        .ok_or_else(|| download_error("Unable to find a file name!".into()))?;
+    let path = work_dir.join(&file_name);
+    if options.collision == CollisionPolicy::Error && path.exists() {
+        return Err(UpdateError::FileExists { path });
+    }
+    hooks.run_pre_download(&arch, &url, &path)?;
+
+    if !options.force && options.collision == CollisionPolicy::Skip && path.exists() {
+        let remote_len = response.response().content_length();
+        if remote_len.is_some() && remote_len == path.metadata().ok().map(|m| m.len()) {
+            info!(
+                "The file '{}' already exists with the same size, skipping download!",
+                path.display()
+            );
+            let checksum = checksum_type
+                .generate(&path)
+                .map_err(|error| io_error(&path, error))?;
+            let file = DownloadedFile {
+                link,
+                path,
+                checksum,
+                checksum_type,
+            };
+
+            match expected.map(|expected| verify_checksum(&file, expected)) {
+                Some(Err(err)) => {
+                    warn!("{}, downloading the file again!", err);
+                    link = file.link;
+                }
+                _ => {
+                    log_checksum(&file);
+                    hooks.run_post_download(&arch, &file)?;
+                    return Ok(file);
+                }
+            }
+        }
+    }
+
+    // Downloads that fail because of a temporary network failure are
+    // retried as many times as the requests, where the partially downloaded
+    // file is resumed when the server supports range requests.
+    let total = response.response().content_length();
+    let validator = response.validator();
+    let accepts_ranges = response.accepts_ranges();
+    let part = part_file_path(&path);
+    let mut attempt = 0;
+    let (path, hasher) = loop {
+        response.set_work_dir(work_dir);
+        response.set_complete_length(total);
+        let mut progress =
+            DownloadProgress::new(checksum_type.hasher(), &file_name, total, options.progress);
+        let error = match response.read_with_inspector(Some(file_name.as_str()), &mut progress) {
+            Ok(path) => break (path, progress.finish()),
+            Err(error) => error,
+        };
+
+        if !error.is_transient() || attempt >= request.max_retries() {
+            remove_part_file(&part);
+            return Err(download_error(error.to_string()));
+        }
+        attempt += 1;
+
+        let offset = if accepts_ranges {
+            part.metadata().map_or(0, |metadata| metadata.len())
+        } else {
+            remove_part_file(&part);
+            0
+        };
+        let next = if offset > 0 {
+            warn!(
+                "Downloading '{}' failed after {} bytes: {}, resuming the download!",
+                url, offset, error
+            );
+            request.get_binary_range_response(download_url, offset, validator.as_deref())
+        } else {
+            warn!(
+                "Downloading '{}' failed: {}, retrying the download!",
+                url, error
+            );
+            request.get_binary_response(download_url, None, None)
+        };
+        response = match next {
+            Ok(ResponseType::New(response, _)) => response,
+            Ok(ResponseType::Updated(status)) => {
+                remove_part_file(&part);
+                return Err(download_error(format!(
+                    "The server responded with status {} when resuming the download!",
+                    status
+                )));
+            }
+            Err(error) => {
+                remove_part_file(&part);
+                return Err(download_error(error.to_string()));
+            }
+        };
+    };
+    info!("Downloaded '{}' to '{}'", link.link, path.display());
+
+    let file = DownloadedFile {
+        link,
+        path,
+        checksum: hasher.finalize(),
+        checksum_type,
+    };
+    log_checksum(&file);
+
+    if let Some(expected) = expected {
+        if let Err(err) = verify_checksum(&file, expected) {
+            if let Err(err) = std::fs::remove_file(&file.path) {
+                warn!("Unable to remove '{}': {}", file.path.display(), err);
+            }
+            return Err(err);
+        }
+        info!(
+            "The checksum of '{}' matches the expected checksum!",
+            file.path.display()
+        );
+    }
+
+    hooks.run_post_download(&arch, &file)?;
+
+    Ok(file)
+}
+
+/// Verifies that the checksum of the downloaded file matches the expected
+/// checksum, the checksum is generated again when the expected checksum
+/// uses a different checksum type than the downloaded file.
+fn verify_checksum(file: &DownloadedFile, expected: &ExpectedChecksum) -> Result<(), UpdateError> {
+    let actual = if expected.checksum_type == file.checksum_type {
+        file.checksum.clone()
+    } else {
+        expected
+            .checksum_type
+            .generate(&file.path)
+            .map_err(|error| io_error(&file.path, error))?
+    };
+
+    if actual.eq_ignore_ascii_case(&expected.checksum) {
+        Ok(())
+    } else {
+        Err(UpdateError::ChecksumMismatch {
+            url: file.link.link.to_string(),
+            checksum_type: expected.checksum_type.to_string(),
+            expected: expected.checksum.clone(),
+            actual,
+        })
+    }
+}
+
+fn request_error(url: &str, error: WebError) -> UpdateError {
+    UpdateError::Request {
+        url: url.into(),
+        error,
+    }
+}
+
+pub(crate) fn update_error(path: &Path, error: UpdaterError) -> UpdateError {
+    UpdateError::Update {
+        path: path.to_owned(),
+        error,
+    }
+}
+
+pub(crate) fn io_error(path: &Path, error: std::io::Error) -> UpdateError {
+    UpdateError::Io {
+        path: path.to_owned(),
+        error,
+    }
+}
+
+/// Removes the partially downloaded file that is kept to allow resuming a
+/// failed download.
+fn remove_part_file(part: &Path) {
+    match std::fs::remove_file(part) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            warn!("Unable to remove '{}': {}", part.display(), err);
+        }
+        _ => {}
+    }
+}
+
+fn log_checksum(file: &DownloadedFile) {
+    info!(
+        "Checksum ({}) of '{}' downloaded from '{}': {}",
+        file.checksum_type,
+        file.path.display(),
+        file.link.link,
+        file.checksum
+    );
+}
+
+fn serialize_link<S: Serializer>(link: &LinkElement, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(link.link.as_str())
+}
+
+pub(crate) fn serialize_display<T: Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub(crate) fn get_checksum_type(checksum_type: chocolatey::ChocolateyChecksumType) -> ChecksumType {
+    match checksum_type {
+        chocolatey::ChocolateyChecksumType::Md5 => ChecksumType::Md5,
+        chocolatey::ChocolateyChecksumType::Sha1 => ChecksumType::Sha1,
+        chocolatey::ChocolateyChecksumType::Sha256 => ChecksumType::Sha256,
+        chocolatey::ChocolateyChecksumType::Sha512 => ChecksumType::Sha512,
+    }
+}
+
+/// Reads the links found by the specified parse url, following the link
+/// matched by the regex of the parse url when necessary.
+fn read_parse_url(
+    request: &WebRequest,
+    parse_url: &chocolatey::ChocolateyParseUrl,
+    match_target: MatchTarget,
+    allow_prerelease: bool,
+    version_selector: Option<&VersionSelector>,
+    link_sources: &[LinkSource],
+    resolver: &ResolverContext,
+) -> Result<Vec<LinkElement>, UpdateError> {
+    let with_selector =
+        |response: HtmlResponse| configure_response(response, version_selector, link_sources);
+    let (_, links) = match parse_url {
+        chocolatey::ChocolateyParseUrl::Url(url) => request
+            .get_html_response(url.as_str())
+            .map(with_selector)
+            .and_then(|response| response.read(None))
+            .map_err(|error| request_error(url.as_str(), error))?,
+        chocolatey::ChocolateyParseUrl::UrlWithRegex {
+            url,
+            regex,
+            follow_first,
+            follow_highest,
+            ..
+        } => {
+            info!("Parsing links on '{}' using regex '{}'", url, regex);
+            let response = request.get_html_response(url.as_str());
+            let response = if *follow_first {
+                response
+            } else {
+                response.map(with_selector)
+            };
+            let (parent, urls) = response
+                .and_then(|response| response.read_matching(Some(regex), match_target))
+                .map_err(|error| request_error(url.as_str(), error))?;
+            if *follow_first && !urls.is_empty() {
+                let url = if *follow_highest {
+                    info!(
+                        "{} links found, using the one with the highest version to get links!",
+                        urls.len()
+                    );
+                    select_highest_version(urls.into_iter()).unwrap()
+                } else {
+                    info!("{} links found, using first one to get links!", urls.len());
+                    urls.into_iter().next().unwrap()
+                };
+                info!("Parsing links on '{}'", url.link);
+                request
+                    .get_html_response(url.link.as_str())
+                    .map(with_selector)
+                    .and_then(|response| response.read(None))
+                    .map_err(|error| request_error(url.link.as_str(), error))?
+            } else {
+                (parent, urls)
+            }
+        }
+        chocolatey::ChocolateyParseUrl::UrlWithSelector { url, link_selector } => {
+            info!("Parsing links on '{}' matching '{}'", url, link_selector);
+            request
+                .get_html_response(url.as_str())
+                .map(with_selector)
+                .and_then(|response| response.with_link_selector(link_selector).read(None))
+                .map_err(|error| request_error(url.as_str(), error))?
+        }
+        chocolatey::ChocolateyParseUrl::Json {
+            url,
+            selector,
+            version_field,
+            regex,
+        } => {
+            info!("Selecting links in '{}' using '{}'", url, selector);
+            request
+                .get_json_response(url.as_str(), selector, version_field.as_deref())
+                .and_then(|response| response.read(regex.as_deref()))
+                .map_err(|error| request_error(url.as_str(), error))?
+        }
+        chocolatey::ChocolateyParseUrl::GitHub {
+            owner,
+            repo,
+            token_env,
+        } => {
+            info!(
+                "Getting releases of the GitHub repository '{}/{}'",
+                owner, repo
+            );
+            let releases = request
+                .get_github_releases(owner, repo)
+                .map_err(|error| github_error(owner, repo, token_env.as_deref(), error))?;
+            match select_latest_release(&releases, allow_prerelease) {
+                Some(release) => {
+                    info!("Using the assets of the release '{}'", release.tag_name);
+                    release.links()
+                }
+                None => {
+                    return Err(UpdateError::NoReleases {
+                        owner: owner.clone(),
+                        repo: repo.clone(),
+                    })
+                }
+            }
+        }
+        chocolatey::ChocolateyParseUrl::External { command } => {
+            return read_resolver_links(command, resolver);
+        }
+    };
+
+    Ok(links)
+}
+
+/// The context written as a json document to the standard input of external
+/// resolvers, together with the directory and the timeout the resolvers are
+/// run with.
+#[derive(Debug, Serialize)]
+struct ResolverContext<'a> {
+    id: &'a str,
+    architectures: Vec<&'a str>,
+    allow_prerelease: bool,
+    #[serde(skip)]
+    work_dir: &'a Path,
+    #[serde(skip)]
+    timeout: Option<Duration>,
+}
+
+/// The json document written by an external resolver.
+#[derive(Debug, Deserialize)]
+struct ResolvedLinks {
+    /// The version used for the links that do not have a version.
+    #[serde(default)]
+    version: Option<String>,
+    links: Vec<ResolvedLink>,
+}
+
+/// A single link written by an external resolver, either the url of the link
+/// or an object with the url and the details of the link.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ResolvedLink {
+    Url(String),
+    Link {
+        url: String,
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        title: String,
+        #[serde(default)]
+        version: Option<String>,
+    },
+}
+
+/// Runs the external resolver of a parse url, and reads the links from the
+/// json document the resolver writes to its standard output.
+fn read_resolver_links(
+    command: &str,
+    context: &ResolverContext,
+) -> Result<Vec<LinkElement>, UpdateError> {
+    let input = serde_json::to_string(context).map_err(|err| {
+        UpdateError::Resolver(format!(
+            "Unable to create the input of the resolver '{}': {}",
+            command, err
+        ))
+    })?;
+    let output = run_resolver(command, context.work_dir, &input, context.timeout)
+        .map_err(UpdateError::Resolver)?;
+    let stderr = output.stderr.trim();
+    for line in stderr.lines() {
+        warn!("{}", line);
+    }
+
+    parse_resolver_links(&output.stdout).map_err(|err| {
+        let mut message = format!(
+            "The resolver '{}' did not output a valid json document: {}",
+            command, err
+        );
+        if !stderr.is_empty() {
+            message.push_str(&format!(" ({})", stderr));
+        }
+        UpdateError::Resolver(message)
+    })
+}
+
+/// Parses the json document written by an external resolver into the links
+/// of the document, where the links without a version use the version at the
+/// root of the document.
+fn parse_resolver_links(content: &str) -> Result<Vec<LinkElement>, String> {
+    let resolved: ResolvedLinks = serde_json::from_str(content).map_err(|err| err.to_string())?;
+    let parse_version = |version: &str| {
+        Versions::parse(version.trim())
+            .map_err(|_| format!("the version '{}' could not be parsed", version))
+    };
+    let default_version = resolved.version.as_deref().map(parse_version).transpose()?;
+
+    resolved
+        .links
+        .into_iter()
+        .map(|resolved| {
+            let (url, text, title, version) = match resolved {
+                ResolvedLink::Url(url) => (url, String::new(), String::new(), None),
+                ResolvedLink::Link {
+                    url,
+                    text,
+                    title,
+                    version,
+                } => (url, text, title, version),
+            };
+            let url = Url::parse(url.trim())
+                .map_err(|err| format!("'{}' is not a valid url: {}", url, err))?;
+            let mut link = LinkElement::new(url, LinkType::Unknown);
+            link.text = text;
+            link.title = title;
+            link.version = match version {
+                Some(version) => Some(parse_version(&version)?),
+                None => default_version.clone(),
+            };
+
+            Ok(link)
+        })
+        .collect()
+}
+
+/// Reads the links on the pages followed for each architecture of a parse url
+/// with architecture regexes. The links matched by the regex of the parse url
+/// are narrowed down by the regex of each architecture before one of them is
+/// followed, and pages followed for multiple architectures are only
+/// requested once.
+#[allow(clippy::too_many_arguments)]
+fn read_arch_parse_url(
+    request: &WebRequest,
+    url: &Url,
+    regex: &str,
+    follow_highest: bool,
+    arch_regexes: &HashMap<String, String>,
+    match_target: MatchTarget,
+    version_selector: Option<&VersionSelector>,
+    link_sources: &[LinkSource],
+) -> Result<Vec<(String, Vec<LinkElement>)>, UpdateError> {
+    info!("Parsing links on '{}' using regex '{}'", url, regex);
+    let (_, urls) = request
+        .get_html_response(url.as_str())
+        .and_then(|response| response.read_matching(Some(regex), match_target))
+        .map_err(|error| request_error(url.as_str(), error))?;
+    let mut arch_regexes: Vec<_> = arch_regexes.iter().collect();
+    arch_regexes.sort_by(|(left, _), (right, _)| left.cmp(right));
+    let mut pages: HashMap<String, Vec<LinkElement>> = HashMap::new();
+    let mut found = vec![];
+
+    for (key, arch_regex) in arch_regexes {
+        let re = get_regex(arch_regex).map_err(|error| UpdateError::InvalidRegex {
+            regex: arch_regex.clone(),
+            error,
+        })?;
+        let mut candidates = urls
+            .iter()
+            .filter(|link| re.is_match(&match_target.value(link)))
+            .cloned();
+        let link = if follow_highest {
+            select_highest_version(candidates)
+        } else {
+            candidates.next()
+        };
+        let link = match link {
+            Some(link) => link,
+            None => {
+                warn!(
+                    "None of the {} links matching '{}' matched '{}' for {}!",
+                    urls.len(),
+                    regex,
+                    arch_regex,
+                    key
+                );
+                continue;
+            }
+        };
+
+        info!("Using '{}' to get links for {}", link.link, key);
+        if !pages.contains_key(link.link.as_str()) {
+            info!("Parsing links on '{}'", link.link);
+            let (_, links) = request
+                .get_html_response(link.link.as_str())
+                .map(|response| configure_response(response, version_selector, link_sources))
+                .and_then(|response| response.read(None))
+                .map_err(|error| request_error(link.link.as_str(), error))?;
+            pages.insert(link.link.to_string(), links);
+        }
+        found.push((key.clone(), pages[link.link.as_str()].clone()));
+    }
+
+    Ok(found)
+}
+
+/// Adds the version selector and link sources of the package to the html
+/// response of a page that the links are read from.
+fn configure_response(
+    response: HtmlResponse,
+    version_selector: Option<&VersionSelector>,
+    link_sources: &[LinkSource],
+) -> HtmlResponse {
+    let response = response.with_link_sources(link_sources.to_vec());
+    match version_selector {
+        Some(selector) => response.with_version_selector(selector.clone()),
+        None => response,
+    }
+}
+
+/// Creates the error used when requesting the releases of a GitHub
+/// repository fails, where refused requests mention the environment variable
+/// the token is read from.
+fn github_error(owner: &str, repo: &str, token_env: Option<&str>, error: WebError) -> UpdateError {
+    match error {
+        WebError::GitHubAccessDenied { rate_limited, .. } => UpdateError::GitHubAccessDenied {
+            owner: owner.into(),
+            repo: repo.into(),
+            token_env: token_env.unwrap_or("GITHUB_TOKEN").into(),
+            rate_limited,
+        },
+        error => request_error(&format!("https://github.com/{}/{}", owner, repo), error),
+    }
+}
+
+/// Checks wether the links of a parse url tagged with the specified
+/// architectures should be used for the specified architecture key, the
+/// links of urls without any architectures are used for all architectures.
+fn is_used_for(archs: &[String], key: &str) -> bool {
+    let arch = chocolatey::architecture_name(key);
+
+    archs.is_empty()
+        || archs
+            .iter()
+            .any(|tag| chocolatey::architecture_name(tag) == arch)
+}
+
+/// Finds the links matching the specified regex, including links that are
+/// pre-releases (*see [exclude_prereleases]*). The version of the links is
+/// built from the `version_template` when one is specified.
+fn match_links(
+    urls: &[LinkElement],
+    key: &str,
+    regex: &str,
+    version_template: Option<&str>,
+    match_target: MatchTarget,
+    strict: bool,
+) -> Result<Vec<LinkElement>, UpdateError> {
+    trace!("Filtering {} urls using {}", key, regex);
+    let re = get_regex(regex).map_err(|error| UpdateError::InvalidRegex {
+        regex: regex.into(),
+        error,
+    })?;
+    let mut matched = vec![];
+    let mut warned = HashSet::new();
+
+    for link in urls {
+        let value = match_target.value(link);
+        let capture = match re.captures(&value) {
+            Some(capture) => capture,
+            None => continue,
+        };
+        let mut new_link = link.clone();
+
+        let version = match version_template {
+            Some(template) => expand_template(template, &capture).ok_or_else(|| {
+                format!(
+                    "the capture groups used by the version template '{}' did not all match",
+                    template
+                )
+            }),
+            None => capture
+                .name("version")
+                .map(|v| v.as_str().to_owned())
+                .ok_or_else(|| "the regex do not have a 'version' capture group".to_owned()),
+        };
+        let problem = match version {
+            Ok(version) => match Versions::parse(&version) {
+                Ok(version) => {
+                    new_link.version = Some(version);
+                    None
+                }
+                Err(_) => Some(format!(
+                    "the captured version '{}' could not be parsed",
+                    version
+                )),
+            },
+            Err(problem) => Some(problem),
+        };
+
+        if let (Some(problem), None) = (problem, &new_link.version) {
+            if strict {
+                return Err(UpdateError::NoVersion {
+                    url: link.link.to_string(),
+                    regex: regex.into(),
+                });
+            }
+            if warned.insert(link.link.as_str()) {
+                warn!(
+                    "The regex '{}' matched '{}', but {}!",
+                    regex, link.link, problem
+                );
+            }
+        }
+
+        matched.push(new_link);
+    }
+
+    Ok(matched)
+}
+
+/// Excludes the links matched by the specified regex that are pre-releases,
+/// unless `allow_prerelease` is set.
+fn exclude_prereleases(
+    matched: Vec<LinkElement>,
+    key: &str,
+    regex: &str,
+    allow_prerelease: bool,
+) -> Vec<LinkElement> {
+    let (items, prereleases): (Vec<_>, Vec<_>) = matched
+        .into_iter()
+        .partition(|link| allow_prerelease || !is_prerelease(link));
+    info!("Parsing urls matching '{}' for {}", regex, key);
+
+    for link in &prereleases {
+        trace!("Excluding pre-release link '{}'", link.link);
+    }
+    if items.is_empty() && !prereleases.is_empty() {
+        warn!(
+            "All {} links matching '{}' for {} were pre-releases and have been excluded! \
+                 Set 'allow_prerelease' or use '--pre' to include them.",
+            prereleases.len(),
+            regex,
+            key
+        );
+    }
+
+    items
+}
+
+/// Gets the version of the package from the links found for each
+/// architecture, preferring the version of the `x64` link, then the `x86`
+/// link before falling back to the highest version of any other link.
+fn get_version(architectures: &BTreeMap<String, LinkElement>) -> Option<Versions> {
+    let preferred = [
+        chocolatey::architecture_name("arch64"),
+        chocolatey::architecture_name("arch32"),
+    ];
+
+    preferred
+        .iter()
+        .filter_map(|arch| architectures.get(arch))
+        .find_map(|link| link.version.clone())
+        .or_else(|| {
+            select_highest_version(architectures.values().cloned()).and_then(|link| link.version)
+        })
+}
+
+/// Selects a single link out of the links matched for a key, using the
+/// specified strategy. The links are expected to be in the order they were
+/// found, and only the first of the links with the same url is considered
+/// when selecting by position.
+fn select_link(
+    items: Vec<LinkElement>,
+    selection: chocolatey::ChocolateyLinkSelection,
+) -> Option<LinkElement> {
+    match selection {
+        chocolatey::ChocolateyLinkSelection::HighestVersion => {
+            dedup_links(items).into_iter().next()
+        }
+        chocolatey::ChocolateyLinkSelection::First => unique_links(items).next(),
+        chocolatey::ChocolateyLinkSelection::Last => unique_links(items).last(),
+        chocolatey::ChocolateyLinkSelection::Index(index) => index
+            .checked_sub(1)
+            .and_then(|index| unique_links(items).nth(index)),
+    }
+}
+
+/// Removes the links with the same url as an earlier link, while keeping the
+/// order of the links.
+fn unique_links(items: Vec<LinkElement>) -> impl Iterator<Item = LinkElement> {
+    let mut seen_urls = HashSet::new();
+
+    items
+        .into_iter()
+        .filter(move |link| seen_urls.insert(link.link.to_string()))
+}
+
+/// Selects the link with the highest parsed version. Links without a version
+/// are only selected when none of the links have a version, and links with
+/// the same version will keep their document order.
+fn select_highest_version<I: Iterator<Item = LinkElement>>(links: I) -> Option<LinkElement> {
+    links.fold(None, |current, link| match current {
+        Some(current) if !is_newer_version(&link, &current) => Some(current),
+        _ => Some(link),
+    })
+}
+
+fn is_prerelease(link: &LinkElement) -> bool {
+    link.version
+        .as_ref()
+        .map_or(false, |version| version.is_prerelease())
+}
+
+fn is_newer_version(link: &LinkElement, other: &LinkElement) -> bool {
+    match (&link.version, &other.version) {
+        (Some(version), Some(other_version)) => version > other_version,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Adds the custom headers of a package to the specified request, the url is
+/// only used for reporting when a header is not valid.
+fn add_headers(
+    request: WebRequest,
+    headers: &HashMap<String, String>,
+    url: &str,
+) -> Result<WebRequest, UpdateError> {
+    if headers.is_empty() {
+        return Ok(request);
+    }
+
+    request
+        .with_headers(
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        )
+        .map_err(|err| request_error(url, err))
+}
+
+/// Creates a copy of the specified request that will send the credentials
+/// from the environment variables referenced by the authentication data.
+/// Uses the token in the environment variable configured for any GitHub
+/// parse urls for the requests sent to the GitHub api, which allows
+/// requesting the releases and downloading the assets of private
+/// repositories.
+fn add_github_token(
+    request: WebRequest,
+    parse_urls: &chocolatey::ChocolateyParseUrls,
+) -> Result<WebRequest, UpdateError> {
+    let token_env = parse_urls
+        .entries()
+        .into_iter()
+        .find_map(|entry| match entry.url {
+            chocolatey::ChocolateyParseUrl::GitHub {
+                token_env: Some(ref token_env),
+                ..
+            } => Some(token_env.clone()),
+            _ => None,
+        });
+
+    match token_env {
+        Some(token_env) => {
+            let token = std::env::var(&token_env)
+                .ok()
+                .filter(|token| !token.trim().is_empty())
+                .ok_or(UpdateError::Authentication {
+                    variable: token_env,
+                })?;
+
+            Ok(request.with_github_token(token.trim()))
+        }
+        None => Ok(request),
+    }
+}
+
+fn authenticate_request(
+    request: &WebRequest,
+    authentication: &Authentication,
+) -> Result<WebRequest, UpdateError> {
+    let read_env = |name: &str| {
+        std::env::var(name).map_err(|_| UpdateError::Authentication {
+            variable: name.into(),
+        })
+    };
+
+    let request = request.clone();
+
+    Ok(match authentication {
+        Authentication::Basic {
+            username,
+            password_env,
+        } => request.with_basic_auth(username, &read_env(password_env)?),
+        Authentication::Bearer { token_env } => request.with_bearer_token(&read_env(token_env)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use aer_upd::web::LinkType;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("test.aer.toml", ".")]
+    #[case("packages/test/test.aer.toml", "packages/test")]
+    fn get_package_dir_should_return_parent_directory(#[case] file: &str, #[case] expected: &str) {
+        assert_eq!(get_package_dir(Path::new(file)), Path::new(expected));
+    }
+
+    #[rstest]
+    #[case("{{id}}", None, "test-package")]
+    #[case("{{id}}/{{version}}", Some("1.0.0"), "test-package/1.0.0")]
+    #[case(
+        "packages/{{ id }}/../{{version}}/",
+        Some("1.0.0"),
+        "packages/test-package/1.0.0"
+    )]
+    fn get_work_dir_should_render_output_template(
+        #[case] template: &str,
+        #[case] version: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let options = UpdateOptions {
+            output_dir: PathBuf::from("output"),
+            output_template: template.into(),
+            ..UpdateOptions::default()
+        };
+
+        let actual = get_work_dir(&options, "test-package", version).unwrap();
+
+        assert_eq!(actual, Path::new("output").join(expected));
+    }
+
+    #[test]
+    fn get_work_dir_should_return_error_on_missing_version() {
+        let options = UpdateOptions {
+            output_template: "{{id}}/{{version}}".into(),
+            ..UpdateOptions::default()
+        };
+
+        let actual = get_work_dir(&options, "test-package", None);
+
+        assert!(matches!(actual, Err(UpdateError::Update { .. })));
+    }
+
+    #[rstest]
+    #[case("all-or-nothing", ArchFailurePolicy::AllOrNothing)]
+    #[case(" Best-Effort ", ArchFailurePolicy::BestEffort)]
+    fn arch_failure_policy_should_parse_variants(
+        #[case] value: &str,
+        #[case] expected: ArchFailurePolicy,
+    ) {
+        assert_eq!(value.parse::<ArchFailurePolicy>(), Ok(expected));
+        assert!(ArchFailurePolicy::variants_str().contains(&expected.to_string().as_str()));
+    }
+
+    #[rstest]
+    #[case("skip", CollisionPolicy::Skip)]
+    #[case("Overwrite", CollisionPolicy::Overwrite)]
+    #[case(" error ", CollisionPolicy::Error)]
+    fn collision_policy_should_parse_variants(
+        #[case] value: &str,
+        #[case] expected: CollisionPolicy,
+    ) {
+        assert_eq!(value.parse::<CollisionPolicy>(), Ok(expected));
+        assert!(CollisionPolicy::variants_str().contains(&expected.to_string().as_str()));
+    }
+
+    #[rstest]
+    #[case(&[], "arch64", true)]
+    #[case(&["x64"], "arch64", true)]
+    #[case(&["arch32"], "x64", false)]
+    fn is_used_for_should_resolve_architecture_aliases(
+        #[case] archs: &[&str],
+        #[case] key: &str,
+        #[case] expected: bool,
+    ) {
+        let archs: Vec<String> = archs.iter().map(|arch| (*arch).into()).collect();
+
+        assert_eq!(is_used_for(&archs, key), expected);
+    }
+
+    fn links(urls: &[&str]) -> Vec<LinkElement> {
+        urls.iter()
+            .map(|url| LinkElement::new(Url::parse(url).unwrap(), LinkType::Binary))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(None, "GITHUB_TOKEN")]
+    #[case(Some("PRIVATE_TOKEN"), "PRIVATE_TOKEN")]
+    fn github_error_should_mention_token_env_when_access_is_denied(
+        #[case] token_env: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let error = WebError::GitHubAccessDenied {
+            url: "https://api.github.com/repos/owner/repo/releases".into(),
+            status: 403,
+            rate_limited: false,
+        };
+
+        let actual = github_error("owner", "repo", token_env, error);
+
+        assert!(actual.to_string().contains(&format!("'{}'", expected)));
+        assert_eq!(actual.kind(), "authentication");
+    }
+
+    #[rstest]
+    #[case("https://example.org/setup-1.0.0-x64.exe", "x64")]
+    #[case("https://example.org/setup-1.0.0-x86_64.exe", "x64")]
+    #[case("https://example.org/setup-1.0.0-AMD64.msi", "x64")]
+    #[case("https://example.org/setup-1.0.0-win32.exe", "x86")]
+    #[case("https://example.org/setup-1.0.0-i386.exe", "x86")]
+    #[case("https://example.org/setup-1.0.0-arm64.exe", "arm64")]
+    #[case("https://example.org/x64/setup-1.0.0.exe", "others")]
+    #[case("https://example.org/setup-1.0.0-mix64.exe", "others")]
+    fn detect_architecture_should_use_file_name_heuristics(
+        #[case] url: &str,
+        #[case] expected: &str,
+    ) {
+        let link = LinkElement::new(Url::parse(url).unwrap(), LinkType::Binary);
+        let patterns = chocolatey::ChocolateyUpdaterData::new().arch_patterns();
+
+        assert_eq!(detect_architecture(&link, &patterns).unwrap(), expected);
+    }
+
+    #[test]
+    fn detect_architectures_should_use_first_link_of_each_architecture() {
+        let urls = links(&[
+            "https://example.org/setup-2.0.0-x64.exe",
+            "https://example.org/setup-1.0.0-x64.exe",
+            "https://example.org/setup-2.0.0-x86.exe",
+            "https://example.org/setup-2.0.0.zip",
+        ]);
+        let patterns = chocolatey::ChocolateyUpdaterData::new().arch_patterns();
+        let mut architectures = BTreeMap::new();
+
+        detect_architectures(urls, &patterns, &mut architectures).unwrap();
+        let actual: Vec<(&str, &str)> = architectures
+            .iter()
+            .map(|(arch, link)| (arch.as_str(), link.link.as_str()))
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                ("others", "https://example.org/setup-2.0.0.zip"),
+                ("x64", "https://example.org/setup-2.0.0-x64.exe"),
+                ("x86", "https://example.org/setup-2.0.0-x86.exe"),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(chocolatey::ChocolateyLinkSelection::First, Some("setup-1.0.0.exe"))]
+    #[case(chocolatey::ChocolateyLinkSelection::Last, Some("setup-2.0.0.exe"))]
+    #[case(
+        chocolatey::ChocolateyLinkSelection::HighestVersion,
+        Some("setup-3.0.0.exe")
+    )]
+    #[case(chocolatey::ChocolateyLinkSelection::Index(2), Some("setup-3.0.0.exe"))]
+    #[case(chocolatey::ChocolateyLinkSelection::Index(4), None)]
+    fn select_link_should_use_selection_strategy(
+        #[case] selection: chocolatey::ChocolateyLinkSelection,
+        #[case] expected: Option<&str>,
+    ) {
+        let items = links(&[
+            "https://example.org/setup-1.0.0.exe",
+            "https://example.org/setup-3.0.0.exe",
+            "https://example.org/setup-3.0.0.exe",
+            "https://example.org/setup-2.0.0.exe",
+        ])
+        .into_iter()
+        .map(|mut link| {
+            let name = link.link.path().trim_start_matches("/setup-");
+            link.version = Versions::parse(name.trim_end_matches(".exe")).ok();
+            link
+        })
+        .collect();
+
+        let actual = select_link(items, selection);
+
+        assert_eq!(
+            actual.map(|link| link.link.to_string()),
+            expected.map(|name| format!("https://example.org/{}", name))
+        );
+    }
+
+    #[test]
+    fn match_links_should_keep_links_without_version_when_not_strict() {
+        let urls = links(&[
+            "https://example.org/test-1.0.0.exe",
+            "https://example.org/test-latest.exe",
+        ]);
+
+        let actual = match_links(
+            &urls,
+            "x64",
+            r"test-(?P<version>[^-]+)\.exe$",
+            None,
+            MatchTarget::Link,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].version, Some(Versions::parse("1.0.0").unwrap()));
+        assert_eq!(actual[1].version, None);
+    }
+
+    #[test]
+    fn match_links_should_return_error_on_missing_version_when_strict() {
+        let urls = links(&["https://example.org/test-1.0.0.exe"]);
+
+        let actual = match_links(
+            &urls,
+            "x64",
+            r"test-[\d\.]+\.exe$",
+            None,
+            MatchTarget::Link,
+            true,
+        );
+
+        assert!(matches!(
+            actual,
+            Err(UpdateError::NoVersion { ref url, .. }) if url == "https://example.org/test-1.0.0.exe"
+        ));
+    }
+
+    #[rstest]
+    #[case(
+        "sourceforge",
+        "https://sourceforge.net/projects/astyle/files/astyle/astyle%203.1/AStyle_3.1_win64.zip/download",
+        "x64",
+        Some("3.1")
+    )]
+    #[case(
+        "sourceforge",
+        "https://sourceforge.net/projects/test/files/test/test-win32-1.2.3.exe/download",
+        "x86",
+        Some("1.2.3")
+    )]
+    #[case(
+        "fosshub",
+        "https://www.fosshub.com/Audacity.html?dwl=audacity-win-3.0.2-64bit.exe",
+        "x64",
+        Some("3.0.2")
+    )]
+    #[case(
+        "fosshub",
+        "https://www.fosshub.com/Audacity.html?dwl=audacity-win-3.0.2-32bit.exe",
+        "x86",
+        Some("3.0.2")
+    )]
+    #[case(
+        "github-release",
+        "https://github.com/owner/repo/releases/download/v1.2.0/tool-x86_64-windows.zip",
+        "x64",
+        Some("1.2.0")
+    )]
+    #[case(
+        "github-release",
+        "https://github.com/owner/repo/releases/download/v1.2.0/tool-x86_64-windows.zip",
+        "x86",
+        None
+    )]
+    #[case(
+        "github-release",
+        "https://github.com/owner/repo/releases/download/v1.2.0/tool-i686-windows.zip",
+        "x86",
+        Some("1.2.0")
+    )]
+    fn regex_presets_should_match_links_of_vendor(
+        #[case] name: &str,
+        #[case] url: &str,
+        #[case] arch: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let preset = chocolatey::regex_preset(name).unwrap();
+        let match_target = if preset.decode_link {
+            MatchTarget::DecodedLink
+        } else {
+            MatchTarget::Link
+        };
+        let (_, regexes) = preset.regexes.iter().find(|(key, _)| *key == arch).unwrap();
+        let urls = links(&[url]);
+
+        let actual = regexes.as_slice().iter().find_map(|regex| {
+            match_links(&urls, arch, regex, None, match_target, true)
+                .unwrap()
+                .pop()
+        });
+
+        assert_eq!(
+            actual.map(|link| link.version),
+            expected.map(|version| Some(Versions::parse(version).unwrap()))
+        );
+    }
+
+    #[test]
+    fn match_links_should_build_version_from_template() {
+        let urls = links(&["https://example.org/test-2023_04_01.exe"]);
+
+        let actual = match_links(
+            &urls,
+            "x64",
+            r"test-(?P<year>\d{4})_(?P<month>\d{2})_(?P<day>\d{2})\.exe$",
+            Some("{year}.{month}.{day}"),
+            MatchTarget::Link,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(
+            actual[0].version,
+            Some(Versions::parse("2023.4.1").unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case("1.0.0", Some("1.0.0"), false, true)]
+    #[case("1.0.0", Some("1.0.0"), true, false)]
+    #[case("1.0.0", Some("1.1.0"), false, false)]
+    #[case("1.1.0", Some("1.0.0"), false, true)]
+    #[case("1.1.0", Some("1.0.0"), true, false)]
+    #[case("1.0.0", None, false, false)]
+    fn is_unchanged_should_compare_versions_unless_forced(
+        #[case] current: &str,
+        #[case] version: Option<&str>,
+        #[case] force: bool,
+        #[case] expected: bool,
+    ) {
+        let data = PackageData::new("test");
+        let options = UpdateOptions {
+            force,
+            ..UpdateOptions::default()
+        };
+
+        assert_eq!(is_unchanged(&data, current, version, &options), expected);
+    }
+
+    #[test]
+    fn update_package_should_return_error_on_invalid_data() {
+        let data = PackageData::new("");
+
+        let actual = update_package(
+            &data,
+            Path::new("."),
+            &WebRequest::create(),
+            &UpdateOptions::default(),
+        );
+
+        assert!(matches!(actual, Err(UpdateError::Validation { .. })));
+    }
+
+    #[test]
+    fn update_package_should_return_error_on_invalid_regex() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().set_chocolatey({
+            let mut choco = chocolatey::ChocolateyUpdaterData::new();
+            choco.add_regex("arch64", "[invalid");
+            choco
+        });
+
+        let actual = update_package(
+            &data,
+            Path::new("."),
+            &WebRequest::create(),
+            &UpdateOptions::default(),
+        );
+
+        match actual {
+            Err(UpdateError::PackageRegex { id, violation }) => {
+                assert_eq!(id, "test-package");
+                assert_eq!(violation.key.as_deref(), Some("arch64"));
+            }
+            _ => panic!("Expected an invalid regex error"),
+        }
+    }
+
+    #[test]
+    fn write_changes_should_return_diff_without_writing_during_dry_run() {
+        let path = std::env::temp_dir().join("aer-tests-write-changes.txt");
+        std::fs::write(&path, "version: 1.0.0\n").unwrap();
+
+        let actual = write_changes(&path, "version: 1.0.0\n", "version: 1.1.0\n".into(), true);
+
+        assert_eq!(
+            actual.unwrap(),
+            format!(
+                "--- {0}\n+++ {0}\n@@ -1,1 +1,1 @@\n-version: 1.0.0\n+version: 1.1.0\n",
+                path.display()
+            )
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "version: 1.0.0\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Serves a single file with the content `test` on a local port, and
+    /// returns the url of the file.
+    fn serve_file(name: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/{}", listener.local_addr().unwrap(), name);
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 4096];
+            let _ = stream.read(&mut buffer);
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\n\
+                      test",
+                )
+                .unwrap();
+        });
+
+        url
+    }
+
+    /// Serves the specified html pages keyed by their path, until the test
+    /// finishes. Returns the url of the server, and the received requests.
+    fn serve_pages(
+        pages: HashMap<&'static str, &'static str>,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let received = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buffer = [0; 4096];
+                let size = stream.read(&mut buffer).unwrap_or_default();
+                let request = String::from_utf8_lossy(&buffer[..size]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let response = match pages.get(path) {
+                    Some(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .into()
+                    }
+                };
+                received.lock().unwrap().push(request.to_lowercase());
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn find_links_should_send_accept_language_of_updater() {
+        let mut pages = HashMap::new();
+        pages.insert("/downloads/", r#"<a href="setup-1.0.0.exe">setup</a>"#);
+        let (server, requests) = serve_pages(pages);
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Single(
+            chocolatey::ChocolateyParseUrl::Url(
+                Url::parse(&format!("{}/downloads/", server)).unwrap(),
+            ),
+        ));
+        choco.accept_language = Some("en-US".into());
+        choco.add_regex("arch32", r"setup-(?P<version>[\d\.]+)\.exe$");
+
+        let actual = find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            actual.architectures["x86"].link.as_str(),
+            format!("{}/downloads/setup-1.0.0.exe", server)
+        );
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("accept-language: en-us\r\n"));
+    }
+
+    #[test]
+    fn find_links_should_send_accept_language_of_each_parse_url() {
+        let mut pages = HashMap::new();
+        pages.insert("/x86/", r#"<a href="setup-1.0.0.exe">setup</a>"#);
+        pages.insert("/x64/", r#"<a href="setup-x64-1.0.0.exe">setup</a>"#);
+        let (server, requests) = serve_pages(pages);
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Multiple(vec![
+            chocolatey::ChocolateyParseUrlEntry {
+                archs: vec!["arch32".into()],
+                accept_language: Some("de-DE".into()),
+                ..chocolatey::ChocolateyParseUrlEntry::new(chocolatey::ChocolateyParseUrl::Url(
+                    Url::parse(&format!("{}/x86/", server)).unwrap(),
+                ))
+            },
+            chocolatey::ChocolateyParseUrlEntry {
+                archs: vec!["arch64".into()],
+                ..chocolatey::ChocolateyParseUrlEntry::new(chocolatey::ChocolateyParseUrl::Url(
+                    Url::parse(&format!("{}/x64/", server)).unwrap(),
+                ))
+            },
+        ]));
+        choco.accept_language = Some("en-US".into());
+        choco.add_regex("arch32", r"setup-(?P<version>[\d\.]+)\.exe$");
+        choco.add_regex("arch64", r"setup-x64-(?P<version>[\d\.]+)\.exe$");
+
+        find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        )
+        .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let x86 = requests
+            .iter()
+            .find(|r| r.starts_with("get /x86/ "))
+            .unwrap();
+        let x64 = requests
+            .iter()
+            .find(|r| r.starts_with("get /x64/ "))
+            .unwrap();
+        assert!(x86.contains("accept-language: de-de\r\n"));
+        assert!(!x86.contains("en-us"));
+        assert!(x64.contains("accept-language: en-us\r\n"));
+    }
+
+    #[test]
+    fn find_links_should_follow_links_matching_each_architecture() {
+        let mut pages = HashMap::new();
+        pages.insert(
+            "/downloads/",
+            r#"<a href="/downloads/1.0.0/windows/">x86</a>
+               <a href="/downloads/1.0.0/win64/">x64</a>"#,
+        );
+        pages.insert(
+            "/downloads/1.0.0/windows/",
+            r#"<a href="setup-1.0.0.exe">setup</a>"#,
+        );
+        pages.insert(
+            "/downloads/1.0.0/win64/",
+            r#"<a href="setup-1.0.0.exe">setup</a>"#,
+        );
+        let (server, _) = serve_pages(pages);
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Single(
+            chocolatey::ChocolateyParseUrl::UrlWithRegex {
+                url: Url::parse(&format!("{}/downloads/", server)).unwrap(),
+                regex: r"/(?P<version>[\d\.]+)/win(dows|64)/$".into(),
+                follow_first: true,
+                follow_highest: false,
+                arch_regexes: {
+                    let mut regexes = HashMap::new();
+                    regexes.insert("arch32".to_string(), "/windows/$".to_string());
+                    regexes.insert("arch64".to_string(), "/win64/$".to_string());
+                    regexes
+                },
+            },
+        ));
+        choco.add_regex("arch32", r"setup-(?P<version>[\d\.]+)\.exe$");
+        choco.add_regex("arch64", r"setup-(?P<version>[\d\.]+)\.exe$");
+
+        let actual = find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            actual.architectures["x86"].link.as_str(),
+            format!("{}/downloads/1.0.0/windows/setup-1.0.0.exe", server)
+        );
+        assert_eq!(
+            actual.architectures["x64"].link.as_str(),
+            format!("{}/downloads/1.0.0/win64/setup-1.0.0.exe", server)
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn find_links_should_use_links_of_external_resolver() {
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Single(
+            chocolatey::ChocolateyParseUrl::External {
+                command: r#"grep -q '"id":"test"' && echo '{"version":"1.2.0","links":[
+                    "https://example.org/setup.exe",
+                    {"url":"https://example.org/setup-x64.exe","version":"1.2.1"}
+                ]}'"#
+                    .into(),
+            },
+        ));
+        choco.add_regex("arch32", r"/setup\.exe$");
+        choco.add_regex("arch64", r"/setup-x64\.exe$");
+
+        let actual = find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            actual.architectures["x86"].link.as_str(),
+            "https://example.org/setup.exe"
+        );
+        assert_eq!(
+            actual.architectures["x86"].version,
+            Some(Versions::parse("1.2.0").unwrap())
+        );
+        assert_eq!(
+            actual.architectures["x64"].version,
+            Some(Versions::parse("1.2.1").unwrap())
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn find_links_should_return_keys_of_regexes_without_matching_links() {
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Single(
+            chocolatey::ChocolateyParseUrl::External {
+                command: r#"echo '{"version":"1.2.0","links":["https://example.org/setup.exe"]}'"#
+                    .into(),
+            },
+        ));
+        choco.add_regex("arch32", r"/setup\.exe$");
+        choco.add_regex("arch64", r"/setup-x64\.exe$");
+
+        let actual = find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        )
+        .unwrap();
+
+        assert!(actual.architectures.contains_key("x86"));
+        assert_eq!(actual.unmatched, vec!["arch64".to_string()]);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn find_links_should_not_return_keys_of_regexes_only_matching_prereleases() {
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Single(
+            chocolatey::ChocolateyParseUrl::External {
+                command: r#"echo '{"version":"1.2.0","links":[
+                    "https://example.org/setup.exe",
+                    {"url":"https://example.org/setup-x64.exe","version":"1.3.0-beta"}
+                ]}'"#
+                    .into(),
+            },
+        ));
+        choco.add_regex("arch32", r"/setup\.exe$");
+        choco.add_regex("arch64", r"/setup-x64\.exe$");
+
+        let actual = find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        )
+        .unwrap();
+
+        assert!(actual.architectures.contains_key("x86"));
+        assert!(!actual.architectures.contains_key("x64"));
+        assert!(actual.unmatched.is_empty());
+    }
+
+    #[test]
+    fn check_unmatched_should_record_keys_when_not_strict() {
+        let found = FoundLinks {
+            request: WebRequest::create(),
+            version: None,
+            architectures: BTreeMap::new(),
+            unmatched: vec!["arch64".into()],
+        };
+        let mut outcome = UpdateOutcome::default();
+
+        check_unmatched(
+            &PackageData::new("test"),
+            &found,
+            &UpdateOptions::default(),
+            &mut outcome,
+        )
+        .unwrap();
+        check_unmatched(
+            &PackageData::new("test"),
+            &found,
+            &UpdateOptions::default(),
+            &mut outcome,
+        )
+        .unwrap();
+
+        assert_eq!(outcome.unmatched, vec!["arch64".to_string()]);
+    }
+
+    #[test]
+    fn check_unmatched_should_return_error_when_strict() {
+        let found = FoundLinks {
+            request: WebRequest::create(),
+            version: None,
+            architectures: BTreeMap::new(),
+            unmatched: vec!["arch32".into(), "arch64".into()],
+        };
+        let options = UpdateOptions {
+            strict: true,
+            ..UpdateOptions::default()
+        };
+
+        let actual = check_unmatched(
+            &PackageData::new("test"),
+            &found,
+            &options,
+            &mut UpdateOutcome::default(),
+        );
+
+        assert!(matches!(
+            actual,
+            Err(UpdateError::NoMatch { ref keys, .. }) if keys == &["arch32", "arch64"]
+        ));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn find_links_should_fail_when_external_resolver_fails() {
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(chocolatey::ChocolateyParseUrls::Single(
+            chocolatey::ChocolateyParseUrl::External {
+                command: "echo 'not found' >&2; exit 1".into(),
+            },
+        ));
+        choco.add_regex("arch32", r"\.exe$");
+
+        let actual = find_links(
+            &WebRequest::create(),
+            &PackageData::new("test"),
+            Path::new("."),
+            &choco,
+            &UpdateOptions::default(),
+        );
+
+        let error = actual.err().unwrap();
+        assert_eq!(error.kind(), "resolver");
+        assert!(error.to_string().ends_with(": not found"));
+    }
+
+    #[rstest]
+    #[case("{}")]
+    #[case("not json")]
+    #[case(r#"{"links":["not a url"]}"#)]
+    #[case(r#"{"version":"invalid","links":[]}"#)]
+    fn parse_resolver_links_should_return_error_on_invalid_document(#[case] content: &str) {
+        assert!(parse_resolver_links(content).is_err());
+    }
+
+    /// Downloads the files of a package where the `x64` file can be
+    /// downloaded, while the `x86` file can not.
+    fn download_partial_files(
+        policy: ArchFailurePolicy,
+    ) -> (
+        Result<BTreeMap<String, DownloadedFile>, UpdateError>,
+        BTreeMap<String, String>,
+    ) {
+        let mut architectures = BTreeMap::new();
+        architectures.insert(
+            "x64".to_string(),
+            links(&[&serve_file("setup-x64.exe")]).remove(0),
+        );
+        architectures.insert(
+            "x86".to_string(),
+            links(&["http://127.0.0.1:1/setup-x86.exe"]).remove(0),
+        );
+        let found = FoundLinks {
+            request: WebRequest::create().with_retries(0, Duration::from_millis(1)),
+            version: None,
+            architectures,
+            unmatched: vec![],
+        };
+        let options = UpdateOptions {
+            output_dir: std::env::temp_dir()
+                .join("aer-tests-arch-failure")
+                .join(policy.to_string()),
+            arch_failure: policy,
+            run_hooks: false,
+            checksum_file: Some(ChecksumType::Sha256),
+            ..UpdateOptions::default()
+        };
+        let _ = std::fs::remove_dir_all(&options.output_dir);
+        let mut failed = BTreeMap::new();
+
+        let result = download_files(
+            found,
+            &PackageData::new("test"),
+            &chocolatey::ChocolateyUpdaterData::new(),
+            None,
+            Path::new("."),
+            &options,
+            &mut failed,
+        );
+
+        (result, failed)
+    }
+
+    #[test]
+    fn download_files_should_fail_on_any_failed_architecture_with_all_or_nothing() {
+        let (result, failed) = download_partial_files(ArchFailurePolicy::AllOrNothing);
+
+        assert_eq!(result.unwrap_err().kind(), "download");
+        assert!(failed.is_empty());
+        assert!(!std::env::temp_dir()
+            .join("aer-tests-arch-failure")
+            .join("all-or-nothing")
+            .join("test")
+            .join("SHA256SUMS")
+            .exists());
+    }
+
+    #[test]
+    fn download_files_should_keep_successful_architectures_with_best_effort() {
+        let (result, failed) = download_partial_files(ArchFailurePolicy::BestEffort);
+
+        let files = result.unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["x64"]);
+        assert_eq!(failed.keys().collect::<Vec<_>>(), ["x86"]);
+        assert!(files["x64"].path.with_file_name("SHA256SUMS").exists());
+    }
+
+    #[test]
+    fn get_mirror_links_should_render_mirror_templates() {
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.mirrors.insert(
+            "arch64".into(),
+            vec![
+                "https://mirror.example.org/{{version}}/{{file_name}}".into(),
+                "not a url".into(),
+                "https://archive.example.org/?url={{url}}".into(),
+            ],
+        );
+        let link = links(&["https://example.org/download/setup-x64.exe"]).remove(0);
+
+        let actual: Vec<String> = get_mirror_links(&choco, "x64", &link, Some("1.0.0"))
+            .into_iter()
+            .map(|mirror| mirror.link.to_string())
+            .collect();
+
+        assert_eq!(
+            actual,
+            [
+                "https://mirror.example.org/1.0.0/setup-x64.exe",
+                "https://archive.example.org/?url=https://example.org/download/setup-x64.exe"
+            ]
+        );
+    }
+
+    #[test]
+    fn download_files_should_fall_back_to_mirrors() {
+        let mut architectures = BTreeMap::new();
+        architectures.insert(
+            "x64".to_string(),
+            links(&["http://127.0.0.1:1/setup-x64.exe"]).remove(0),
+        );
+        let found = FoundLinks {
+            request: WebRequest::create().with_retries(0, Duration::from_millis(1)),
+            version: None,
+            architectures,
+            unmatched: vec![],
+        };
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco
+            .mirrors
+            .insert("x64".into(), vec![serve_file("{{file_name}}")]);
+        let options = UpdateOptions {
+            output_dir: std::env::temp_dir().join("aer-tests-mirrors"),
+            run_hooks: false,
+            ..UpdateOptions::default()
+        };
+        let _ = std::fs::remove_dir_all(&options.output_dir);
+        let mut failed = BTreeMap::new();
+
+        let files = download_files(
+            found,
+            &PackageData::new("test"),
+            &choco,
+            None,
+            Path::new("."),
+            &options,
+            &mut failed,
+        )
+        .unwrap();
+
+        assert!(files["x64"].link.link.path().ends_with("/setup-x64.exe"));
+        assert!(failed.is_empty());
+    }
+
+    #[test]
+    fn download_files_should_fail_on_unexpected_content_type() {
+        let mut architectures = BTreeMap::new();
+        architectures.insert(
+            "x64".to_string(),
+            links(&[&serve_file("setup-x64.exe")]).remove(0),
+        );
+        let found = FoundLinks {
+            request: WebRequest::create().with_retries(0, Duration::from_millis(1)),
+            version: None,
+            architectures,
+            unmatched: vec![],
+        };
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco
+            .expected_content_types
+            .insert("x64".into(), "application/x-msdownload".into());
+        let options = UpdateOptions {
+            output_dir: std::env::temp_dir().join("aer-tests-content-type"),
+            run_hooks: false,
+            ..UpdateOptions::default()
+        };
+        let _ = std::fs::remove_dir_all(&options.output_dir);
+        let mut failed = BTreeMap::new();
+
+        let result = download_files(
+            found,
+            &PackageData::new("test"),
+            &choco,
+            None,
+            Path::new("."),
+            &options,
+            &mut failed,
+        );
+
+        assert_eq!(result.unwrap_err().kind(), "download");
+    }
+
+    /// Serves each of the specified raw responses to a single request, in
+    /// order. Returns the url of the served file, and the received requests.
+    fn serve_responses(responses: Vec<&'static [u8]>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/setup-x64.exe", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let received = requests.clone();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; 4096];
+                let size = stream.read(&mut buffer).unwrap_or_default();
+                received
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buffer[..size]).to_lowercase());
+                stream.write_all(response).unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn download_with_retries(
+        url: &str,
+        retries: u32,
+        output_dir: &str,
+    ) -> Result<BTreeMap<String, DownloadedFile>, UpdateError> {
+        let mut architectures = BTreeMap::new();
+        architectures.insert("x64".to_string(), links(&[url]).remove(0));
+        let found = FoundLinks {
+            request: WebRequest::create().with_retries(retries, Duration::from_millis(1)),
+            version: None,
+            architectures,
+            unmatched: vec![],
+        };
+        let options = UpdateOptions {
+            output_dir: std::env::temp_dir().join(output_dir),
+            run_hooks: false,
+            ..UpdateOptions::default()
+        };
+        let _ = std::fs::remove_dir_all(&options.output_dir);
+        let mut failed = BTreeMap::new();
+
+        download_files(
+            found,
+            &PackageData::new("test"),
+            &chocolatey::ChocolateyUpdaterData::new(),
+            None,
+            Path::new("."),
+            &options,
+            &mut failed,
+        )
+    }
+
+    #[test]
+    fn download_files_should_resume_interrupted_download() {
+        let (url, requests) = serve_responses(vec![
+            b"HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nETag: \"abc\"\r\n\
+              Content-Length: 4\r\nConnection: close\r\n\r\nte",
+            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 2-3/4\r\n\
+              Content-Length: 2\r\nConnection: close\r\n\r\nst",
+        ]);
+
+        let result = download_with_retries(&url, 1, "aer-tests-resume").unwrap();
+
+        let path = &result["x64"].path;
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "test");
+        assert!(!part_file_path(path).exists());
+        let requests = requests.lock().unwrap();
+        assert!(requests[1].contains("range: bytes=2-"));
+        assert!(requests[1].contains("if-range: \"abc\""));
+    }
+
+    #[test]
+    fn download_files_should_not_resume_download_without_retries() {
+        let (url, requests) = serve_responses(vec![
+            b"HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\n\
+              Content-Length: 4\r\nConnection: close\r\n\r\nte",
+        ]);
+
+        let result = download_with_retries(&url, 0, "aer-tests-resume-no-retries");
+
+        assert_eq!(result.unwrap_err().kind(), "download");
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(!part_file_path(
+            &std::env::temp_dir()
+                .join("aer-tests-resume-no-retries")
+                .join("test")
+                .join("setup-x64.exe")
+        )
+        .exists());
+    }
+
+    #[test]
+    fn download_files_should_fail_when_resumed_file_have_different_length() {
+        let (url, _) = serve_responses(vec![
+            b"HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\n\
+              Content-Length: 4\r\nConnection: close\r\n\r\nte",
+            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 2-4/5\r\n\
+              Content-Length: 3\r\nConnection: close\r\n\r\nstx",
+        ]);
+
+        let result = download_with_retries(&url, 1, "aer-tests-resume-length");
+
+        let path = std::env::temp_dir()
+            .join("aer-tests-resume-length")
+            .join("test")
+            .join("setup-x64.exe");
+        assert_eq!(result.unwrap_err().kind(), "download");
+        assert!(!path.exists());
+        assert!(!part_file_path(&path).exists());
+    }
+
+    #[rstest]
+    #[case(None, false, Some("text/html"), true)]
+    #[case(None, true, Some("text/html"), false)]
+    #[case(None, true, Some("application/xhtml+xml"), false)]
+    #[case(None, true, None, true)]
+    #[case(Some("application/zip"), false, Some("application/zip"), true)]
+    #[case(Some("Application/Zip"), false, Some("application/zip"), true)]
+    #[case(Some("application/*"), false, Some("application/octet-stream"), true)]
+    #[case(Some("application/*"), false, Some("text/plain"), false)]
+    #[case(Some("application/zip"), false, Some("text/plain"), false)]
+    #[case(Some("application/zip"), false, None, false)]
+    fn content_type_check_should_accept_expected_types(
+        #[case] expected: Option<&str>,
+        #[case] reject_html: bool,
+        #[case] actual: Option<&str>,
+        #[case] accepted: bool,
+    ) {
+        let check = ContentTypeCheck {
+            expected: expected.map(Into::into),
+            reject_html,
+        };
+
+        assert_eq!(check.check(actual).is_ok(), accepted);
+    }
+
+    #[test]
+    fn get_script_values_should_skip_variables_of_failed_architectures() {
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.script_variables.insert(
+            "url".into(),
+            chocolatey::ChocolateyScriptValue::Url("x86".into()),
+        );
+        choco
+            .script_variables
+            .insert("version".into(), chocolatey::ChocolateyScriptValue::Version);
+        let mut failed = BTreeMap::new();
+        failed.insert("x86".to_string(), "Connection refused".to_string());
+
+        let actual = get_script_values(&choco, &BTreeMap::new(), Some("1.0.0"), &failed).unwrap();
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual["version"], "1.0.0");
+    }
+
+    #[test]
+    fn download_files_should_not_download_when_cancelled() {
+        let (url, requests) = serve_responses(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\ntest",
+        ]);
+        let mut architectures = BTreeMap::new();
+        architectures.insert("x64".to_string(), links(&[&url]).remove(0));
+        let found = FoundLinks {
+            request: WebRequest::create(),
+            version: None,
+            architectures,
+            unmatched: vec![],
+        };
+        let options = UpdateOptions {
+            output_dir: std::env::temp_dir().join("aer-tests-cancelled"),
+            run_hooks: false,
+            checksum_file: Some(ChecksumType::Sha256),
+            cancelled: Some(Arc::new(AtomicBool::new(true))),
+            ..UpdateOptions::default()
+        };
+        let _ = std::fs::remove_dir_all(&options.output_dir);
+        let mut failed = BTreeMap::new();
+
+        let result = download_files(
+            found,
+            &PackageData::new("test"),
+            &chocolatey::ChocolateyUpdaterData::new(),
+            None,
+            Path::new("."),
+            &options,
+            &mut failed,
+        );
+
+        assert_eq!(result.unwrap_err().kind(), "timeout");
+        assert!(requests.lock().unwrap().is_empty());
+        assert!(!options.output_dir.join("test").exists());
+    }
+
+    #[test]
+    fn write_sums_file_should_calculate_checksums_of_other_types() {
+        let dir = std::env::temp_dir().join("aer-tests-sums-file");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("setup.exe");
+        std::fs::write(&path, "setup").unwrap();
+        let mut files = BTreeMap::new();
+        files.insert(
+            "x86".to_string(),
+            DownloadedFile {
+                link: LinkElement::new(
+                    Url::parse("https://example.org/setup.exe").unwrap(),
+                    LinkType::Binary,
+                ),
+                path: path.clone(),
+                checksum: ChecksumType::Sha256.generate(&path).unwrap(),
+                checksum_type: ChecksumType::Sha256,
+            },
+        );
+
+        write_sums_file(&dir, &files, ChecksumType::Md5, &UpdateOptions::default()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("MD5SUMS")).unwrap(),
+            format!(
+                "{}  setup.exe\n",
+                ChecksumType::Md5.generate(&path).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn write_sums_file_should_not_write_file_during_dry_run() {
+        let dir = std::env::temp_dir().join("aer-tests-sums-file-dry-run");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("setup.exe");
+        std::fs::write(&path, "setup").unwrap();
+        let mut files = BTreeMap::new();
+        files.insert(
+            "x86".to_string(),
+            DownloadedFile {
+                link: LinkElement::new(
+                    Url::parse("https://example.org/setup.exe").unwrap(),
+                    LinkType::Binary,
+                ),
+                path,
+                checksum: "abcd".into(),
+                checksum_type: ChecksumType::Sha256,
+            },
+        );
+        let options = UpdateOptions {
+            dry_run: true,
+            ..UpdateOptions::default()
+        };
+
+        write_sums_file(&dir, &files, ChecksumType::Sha256, &options).unwrap();
+
+        assert!(!dir.join("SHA256SUMS").exists());
+    }
+
+    #[test]
+    fn write_sums_file_should_not_write_file_when_cancelled() {
+        let dir = std::env::temp_dir().join("aer-tests-sums-file-cancelled");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("setup.exe");
+        std::fs::write(&path, "setup").unwrap();
+        let mut files = BTreeMap::new();
+        files.insert(
+            "x86".to_string(),
+            DownloadedFile {
+                link: LinkElement::new(
+                    Url::parse("https://example.org/setup.exe").unwrap(),
+                    LinkType::Binary,
+                ),
+                path,
+                checksum: "abcd".into(),
+                checksum_type: ChecksumType::Sha256,
+            },
+        );
+        let options = UpdateOptions {
+            cancelled: Some(Arc::new(AtomicBool::new(true))),
+            ..UpdateOptions::default()
+        };
+
+        let result = write_sums_file(&dir, &files, ChecksumType::Sha256, &options);
+
+        assert_eq!(result.unwrap_err().kind(), "timeout");
+        assert!(!dir.join("SHA256SUMS").exists());
+    }
+
+    #[test]
+    fn file_backup_should_restore_changed_and_created_files() {
+        let dir = std::env::temp_dir().join("aer-tests-file-backup");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let changed = dir.join("test.nuspec");
+        let created = dir.join("VERIFICATION.txt");
+        std::fs::write(&changed, "<version>1.0.0</version>").unwrap();
+        let backup = FileBackup::create(vec![changed.clone(), created.clone()]);
+        std::fs::write(&changed, "<version>2.0.0</version>").unwrap();
+        std::fs::write(&created, "Verification").unwrap();
+
+        backup.restore();
+
+        assert_eq!(
+            std::fs::read_to_string(&changed).unwrap(),
+            "<version>1.0.0</version>"
+        );
+        assert!(!created.exists());
+    }
+}
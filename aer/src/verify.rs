@@ -0,0 +1,415 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the verification of the files that have already been published
+//! by a package. The urls and checksums are read from the install script or
+//! manifest of the package, and every file is downloaded again to detect
+//! files that was changed upstream without the url changing.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use aer_upd::data::*;
+use aer_upd::updaters::chocolatey::get_script_variables;
+use aer_upd::updaters::errors::UpdaterError;
+use aer_upd::updaters::scoop::get_manifest_files;
+use aer_upd::web::{LinkElement, LinkType, WebRequest};
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::errors::{ExitCode, UpdateError};
+use crate::update::{
+    configure_request, download_link, get_checksum_type, get_install_script_path,
+    get_manifest_path, io_error, serialize_display, update_error, ContentTypeCheck, Download,
+    DownloadHooks, ExpectedChecksum, UpdateOptions,
+};
+use crate::ChecksumType;
+
+/// A file that have been published by a package, together with the checksum
+/// that was published for the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishedFile {
+    /// The name of the updater the file was published by.
+    pub updater: &'static str,
+    /// The name of the architecture of the file.
+    pub arch: String,
+    pub url: String,
+    pub checksum: String,
+    pub checksum_type: ChecksumType,
+}
+
+/// The result of verifying a single published file.
+#[derive(Debug, Serialize)]
+pub struct VerifiedFile {
+    pub updater: &'static str,
+    pub arch: String,
+    pub url: String,
+    #[serde(serialize_with = "serialize_display")]
+    pub checksum_type: ChecksumType,
+    /// The checksum that was published for the file.
+    pub expected: String,
+    /// The checksum of the file that was downloaded, or [None] if the file
+    /// could not be downloaded.
+    pub actual: Option<String>,
+    pub error: Option<String>,
+    /// The category of the error, if the file could not be downloaded.
+    pub error_kind: Option<&'static str>,
+    #[serde(skip)]
+    pub exit_code: ExitCode,
+}
+
+impl VerifiedFile {
+    /// Checks wether the downloaded file have the published checksum.
+    pub fn matches(&self) -> bool {
+        self.actual
+            .as_ref()
+            .map_or(false, |actual| actual.eq_ignore_ascii_case(&self.expected))
+    }
+
+    /// Checks wether the file was downloaded, but no longer have the
+    /// published checksum.
+    pub fn is_changed(&self) -> bool {
+        self.actual.is_some() && !self.matches()
+    }
+}
+
+/// Verifies the files published by the specified package data, where
+/// `package_dir` is the directory that the paths in the package data are
+/// relative to. Every file is downloaded again, even when it already exists
+/// in the output directory, and the checksum of the downloaded file is
+/// compared against the published checksum.
+///
+/// Files that could not be downloaded are included in the result together
+/// with the error, an error is only returned when the published files could
+/// not be read.
+pub fn verify_package(
+    data: &PackageData,
+    package_dir: &Path,
+    request: &WebRequest,
+    options: &UpdateOptions,
+) -> Result<Vec<VerifiedFile>, UpdateError> {
+    let mut options = options.clone();
+    options.force = true;
+    let work_dir = options.output_dir.join(data.metadata().id());
+    let mut results = vec![];
+
+    if data.updater().has_chocolatey() {
+        let choco = data.updater().chocolatey();
+        let files = get_chocolatey_files(&choco, package_dir)?;
+        let request = package_request(request, &choco)?;
+        for file in files {
+            results.push(verify_file(&request, file, &work_dir, &options));
+        }
+    }
+
+    if data.updater().has_scoop() {
+        let link_data = data.updater().scoop().link_data();
+        let files = get_scoop_files(data, package_dir)?;
+        let request = package_request(request, &link_data)?;
+        for file in files {
+            results.push(verify_file(&request, file, &work_dir, &options));
+        }
+    }
+
+    Ok(results)
+}
+
+/// Reads the urls and checksums published by the chocolatey updater from the
+/// install script. The script variables of the package are used to find the
+/// url and checksum of each architecture, or the default `url`, `checksum`,
+/// `url64` and `checksum64` variables when no script variables have been
+/// specified.
+fn get_chocolatey_files(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    package_dir: &Path,
+) -> Result<Vec<PublishedFile>, UpdateError> {
+    use chocolatey::{architecture_name, ChocolateyScriptValue};
+
+    let path = get_install_script_path(package_dir, choco);
+    if !path.exists() && choco.install_script.is_none() {
+        info!(
+            "No install script found at '{}', no chocolatey files to verify!",
+            path.display()
+        );
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|error| io_error(&path, error))?;
+
+    let mut urls = BTreeMap::new();
+    let mut checksums = BTreeMap::new();
+    let mut checksum_type = None;
+    let defaults = choco.script_variables.is_empty();
+
+    if defaults {
+        urls.insert(architecture_name("arch32"), "url".to_owned());
+        checksums.insert(architecture_name("arch32"), "checksum".to_owned());
+        urls.insert(architecture_name("arch64"), "url64".to_owned());
+        checksums.insert(architecture_name("arch64"), "checksum64".to_owned());
+    } else {
+        for (name, source) in &choco.script_variables {
+            match source {
+                ChocolateyScriptValue::Url(arch) => {
+                    urls.insert(arch.clone(), name.clone());
+                }
+                ChocolateyScriptValue::Checksum(arch) => {
+                    checksums.insert(arch.clone(), name.clone());
+                }
+                ChocolateyScriptValue::ChecksumType => checksum_type = Some(name.clone()),
+                ChocolateyScriptValue::Version => {}
+            }
+        }
+    }
+
+    let default_type = get_checksum_type(choco.checksum_type);
+    let checksum_type = match checksum_type {
+        Some(name) => {
+            let values = get_script_variables(&content, &[name.as_str()])
+                .map_err(|err| update_error(&path, err))?;
+            ChecksumType::from_str(&values[&name]).unwrap_or_else(|err| {
+                warn!(
+                    "The checksum type '{}' in '{}' is not valid ({}), using {} instead!",
+                    values[&name],
+                    path.display(),
+                    err,
+                    default_type
+                );
+                default_type
+            })
+        }
+        None => default_type,
+    };
+    let mut files = vec![];
+
+    for (arch, url_name) in urls {
+        let checksum_name = match checksums.get(&arch) {
+            Some(name) => name,
+            None => {
+                warn!(
+                    "No checksum variable have been specified for the {} url!",
+                    arch
+                );
+                continue;
+            }
+        };
+
+        let mut values =
+            match get_script_variables(&content, &[url_name.as_str(), checksum_name.as_str()]) {
+                Ok(values) => values,
+                Err(UpdaterError::MissingVariable(_)) if defaults => continue,
+                Err(err) => return Err(update_error(&path, err)),
+            };
+
+        files.push(PublishedFile {
+            updater: "chocolatey",
+            arch,
+            url: values.remove(&url_name).unwrap_or_default(),
+            checksum: values.remove(checksum_name).unwrap_or_default(),
+            checksum_type,
+        });
+    }
+
+    Ok(files)
+}
+
+/// Reads the urls and hashes published by the scoop updater from the
+/// manifest. Hashes without a prefix (*ie: `sha512:`*) are `sha256` hashes.
+fn get_scoop_files(
+    data: &PackageData,
+    package_dir: &Path,
+) -> Result<Vec<PublishedFile>, UpdateError> {
+    let path = get_manifest_path(package_dir, data, &data.updater().scoop());
+    if !path.exists() {
+        info!(
+            "No manifest found at '{}', no scoop files to verify!",
+            path.display()
+        );
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|error| io_error(&path, error))?;
+    let files = get_manifest_files(&content).map_err(|error| update_error(&path, error))?;
+
+    Ok(files
+        .into_iter()
+        .map(|(arch, file)| {
+            let mut parts = file.hash.splitn(2, ':');
+            let (checksum_type, checksum) = match (parts.next(), parts.next()) {
+                (Some(prefix), Some(hash)) => {
+                    (ChecksumType::from_str(prefix).unwrap_or_default(), hash)
+                }
+                _ => (ChecksumType::Sha256, file.hash.as_str()),
+            };
+
+            PublishedFile {
+                updater: "scoop",
+                arch,
+                url: file.url.clone(),
+                checksum: checksum.to_owned(),
+                checksum_type,
+            }
+        })
+        .collect())
+}
+
+/// Creates the request used when downloading the files of a package, with
+/// the same settings as when the package is updated.
+fn package_request(
+    request: &WebRequest,
+    choco: &chocolatey::ChocolateyUpdaterData,
+) -> Result<WebRequest, UpdateError> {
+    match choco.parse_url {
+        Some(ref parse_urls) => configure_request(request, choco, parse_urls),
+        None => Ok(request.clone()),
+    }
+}
+
+/// Downloads the specified file, and compares the checksum of the downloaded
+/// file against the published checksum.
+fn verify_file(
+    request: &WebRequest,
+    file: PublishedFile,
+    work_dir: &Path,
+    options: &UpdateOptions,
+) -> VerifiedFile {
+    let mut result = VerifiedFile {
+        updater: file.updater,
+        arch: file.arch.clone(),
+        url: file.url.clone(),
+        checksum_type: file.checksum_type,
+        expected: file.checksum.trim().to_lowercase(),
+        actual: None,
+        error: None,
+        error_kind: None,
+        exit_code: ExitCode::Success,
+    };
+    let link = match Url::parse(file.url.trim()) {
+        Ok(url) => LinkElement::new(url, LinkType::Binary),
+        Err(err) => {
+            result.error = Some(format!("The url '{}' is not valid: {}", file.url, err));
+            result.error_kind = Some("update");
+            result.exit_code = ExitCode::Failure;
+            return result;
+        }
+    };
+    info!("Verifying the {} file '{}'", file.arch, link.link);
+
+    let download = Download {
+        arch: file.arch,
+        link,
+        expected: Some(ExpectedChecksum {
+            checksum: result.expected.clone(),
+            checksum_type: file.checksum_type,
+        }),
+        content_type: ContentTypeCheck::default(),
+        mirrors: vec![],
+    };
+
+    match download_link(
+        request,
+        download,
+        work_dir,
+        file.checksum_type,
+        options,
+        &DownloadHooks::default(),
+    ) {
+        Ok(downloaded) => result.actual = Some(downloaded.checksum),
+        Err(UpdateError::ChecksumMismatch { actual, .. }) => {
+            warn!(
+                "The checksum of '{}' have changed from {} to {}!",
+                result.url, result.expected, actual
+            );
+            result.actual = Some(actual);
+            result.exit_code = ExitCode::ChecksumMismatch;
+        }
+        Err(err) => {
+            result.error = Some(err.to_string());
+            result.error_kind = Some(err.kind());
+            result.exit_code = err.exit_code();
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn create_package_dir(name: &str, script: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("aer-verify-tests").join(name);
+        std::fs::create_dir_all(dir.join("tools")).unwrap();
+        std::fs::write(dir.join("tools/chocolateyInstall.ps1"), script).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn get_chocolatey_files_should_read_default_variables() {
+        let dir = create_package_dir(
+            "default-variables",
+            "$url64 = 'https://example.org/app-x64.exe'\n$checksum64 = 'ABC'\n",
+        );
+        let choco = chocolatey::ChocolateyUpdaterData::new();
+
+        let actual = get_chocolatey_files(&choco, &dir).unwrap();
+
+        assert_eq!(
+            actual,
+            vec![PublishedFile {
+                updater: "chocolatey",
+                arch: "x64".into(),
+                url: "https://example.org/app-x64.exe".into(),
+                checksum: "ABC".into(),
+                checksum_type: ChecksumType::Sha256,
+            }]
+        );
+    }
+
+    #[test]
+    fn get_chocolatey_files_should_use_script_variables() {
+        let dir = create_package_dir(
+            "script-variables",
+            "$packageArgs = @{\n  url = 'https://example.org/app.exe'\n  checksum = 'abc'\n  \
+             checksumType = 'sha512'\n}\n",
+        );
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.script_variables.insert(
+            "url".into(),
+            chocolatey::ChocolateyScriptValue::Url("x86".into()),
+        );
+        choco.script_variables.insert(
+            "checksum".into(),
+            chocolatey::ChocolateyScriptValue::Checksum("x86".into()),
+        );
+        choco.script_variables.insert(
+            "checksumType".into(),
+            chocolatey::ChocolateyScriptValue::ChecksumType,
+        );
+
+        let actual = get_chocolatey_files(&choco, &dir).unwrap();
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].url, "https://example.org/app.exe");
+        assert_eq!(actual[0].checksum_type, ChecksumType::Sha512);
+    }
+
+    #[test]
+    fn verified_file_should_be_changed_when_checksums_differ() {
+        let file = VerifiedFile {
+            updater: "chocolatey",
+            arch: "x64".into(),
+            url: "https://example.org/app.exe".into(),
+            checksum_type: ChecksumType::Sha256,
+            expected: "abc".into(),
+            actual: Some("def".into()),
+            error: None,
+            error_kind: None,
+            exit_code: ExitCode::ChecksumMismatch,
+        };
+
+        assert!(!file.matches());
+        assert!(file.is_changed());
+    }
+}
@@ -0,0 +1,4 @@
+$ErrorActionPreference = 'Stop'
+
+$url64 = 'http://127.0.0.1:1/1.0.0/setup-x64.exe'
+$checksum64 = 'aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa'
@@ -0,0 +1,637 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+use std::path::PathBuf;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use lazy_static::lazy_static;
+use predicates::prelude::*;
+
+lazy_static! {
+    static ref LOG_DIR: PathBuf = std::env::temp_dir();
+}
+
+#[test]
+fn validate_should_succeed_on_valid_package() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-validate-valid.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success().stderr(
+        predicate::str::contains("'test-data/valid-package.aer.toml' is valid!").and(
+            predicate::str::contains("Finished validating 1 package files: 1 valid, 0 invalid!"),
+        ),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_only_output_warnings_when_quiet() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-validate-quiet.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "-q",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("is valid!").not());
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_write_logs_to_stdout_when_requested() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-validate-stdout.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--log-stream",
+        "stdout",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("is valid!"))
+        .stderr(predicate::str::is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_report_all_violations() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-validate-invalid.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "test-data/invalid-package.aer.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().failure().stderr(
+        predicate::str::contains("'test-data/invalid-package.aer.toml' have 4 violation(s):")
+            .and(predicate::str::contains("metadata.id:"))
+            .and(predicate::str::contains("metadata.summary:"))
+            .and(predicate::str::contains("updater.chocolatey.parse_url:"))
+            .and(predicate::str::contains("updater.chocolatey.regexes.arch32:")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn update_should_exit_with_parse_error_code() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-invalid.log");
+
+    cmd.args(&[
+        "update",
+        "test-data/non-existing.aer.toml",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .code(2)
+        .stdout(predicate::str::contains(r#""error_kind": "parse""#));
+
+    Ok(())
+}
+
+#[test]
+fn update_should_write_report_when_packages_fail() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-report.log");
+    let report_path = LOG_DIR.join("aer-tests-update-report.toml");
+    let _ = std::fs::remove_file(&report_path);
+
+    cmd.args(&[
+        "update",
+        "test-data/non-existing.aer.toml",
+        "--report",
+        report_path.to_str().unwrap(),
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(2);
+
+    let report = std::fs::read_to_string(&report_path)?;
+    assert!(report.contains("[[packages]]"));
+    assert!(report.contains(r#"error_kind = "parse""#));
+
+    Ok(())
+}
+
+#[test]
+fn update_should_only_fail_on_new_failures_compared_to_previous_report(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-previous.log");
+    let previous_path = LOG_DIR.join("aer-tests-update-previous.toml");
+    std::fs::write(
+        &previous_path,
+        "[[packages]]\nfile = \"test-data/non-existing.aer.toml\"\nerror = \"failed\"\n",
+    )?;
+
+    cmd.args(&[
+        "update",
+        "test-data/non-existing.aer.toml",
+        "--previous",
+        previous_path.to_str().unwrap(),
+        "--only-new-failures",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn update_should_exit_with_timeout_code_when_deadline_is_exceeded(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-deadline.log");
+
+    cmd.args(&[
+        "update",
+        "test-data/valid-package.aer.toml",
+        "--deadline",
+        "0s",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .code(9)
+        .stdout(predicate::str::contains(r#""error_kind": "timeout""#))
+        .stdout(predicate::str::contains(r#""timed_out": true"#));
+
+    Ok(())
+}
+
+#[test]
+fn update_should_exit_with_no_update_code_when_nothing_was_updated(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-no-update.log");
+
+    cmd.args(&[
+        "update",
+        "test-data/no-updater.aer.toml",
+        "--fail-on-no-update",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(7).stderr(predicate::str::contains(
+        "None of the package files was updated!",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn update_should_succeed_when_nothing_was_updated() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-unchanged.log");
+
+    cmd.args(&[
+        "update",
+        "test-data/no-updater.aer.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn update_should_fail_when_no_packages_are_selected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-skip.log");
+
+    cmd.args(&[
+        "update",
+        "test-data/valid-package.aer.toml",
+        "--skip",
+        "Test-Package",
+        "--only",
+        "unknown-package",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(1).stderr(
+        predicate::str::contains("No package file with the identifier 'unknown-package'")
+            .and(predicate::str::contains("No packages selected")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_find_package_files_in_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-validate-directory.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/packages",
+        "--ignore",
+        "nested",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Finished validating 2 package files: 2 valid, 0 invalid!",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn new_should_not_overwrite_existing_package_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-new-existing.log");
+
+    cmd.args(&[
+        "new",
+        "test-package",
+        "--output",
+        "test-data/valid-package.aer.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("use --force to overwrite it"));
+
+    Ok(())
+}
+
+#[test]
+fn completions_should_output_script_for_shell() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-completions.log");
+
+    cmd.args(&["completions", "bash", "--log", log_path.to_str().unwrap()])
+        .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("_aer()")
+            .and(predicate::str::contains("update"))
+            .and(predicate::str::contains("--log-format")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn new_should_not_create_package_file_during_dry_run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-new-dry-run.log");
+    let output = LOG_DIR
+        .join("aer-tests-new-dry-run")
+        .join("dry-run.aer.toml");
+
+    cmd.args(&[
+        "new",
+        "dry-run",
+        "--output",
+        output.to_str().unwrap(),
+        "--dry-run",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("+id = \"dry-run\""));
+    assert!(!output.exists());
+
+    Ok(())
+}
+
+#[test]
+fn list_should_output_package_information() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-list.log");
+
+    cmd.args(&[
+        "list",
+        "test-data/packages",
+        "--ignore",
+        "nested",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("\"id\": \"first-package\"")
+            .and(predicate::str::contains("\"version\": \"1.2.3\""))
+            .and(predicate::str::contains(
+                "\"parse_url\": \"https://test.com/downloads\"",
+            ))
+            .and(predicate::str::contains("\"chocolatey\"")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn list_should_output_every_package_in_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-list-multiple.log");
+
+    cmd.args(&[
+        "list",
+        "test-data/multiple-packages.aer.toml",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("\"id\": \"first-multiple-package\"")
+            .and(predicate::str::contains(
+                "\"id\": \"second-multiple-package\"",
+            ))
+            .and(predicate::str::contains(
+                "\"parse_url\": \"https://test.com/downloads\"",
+            )),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn list_should_only_output_packages_with_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-list-tags.log");
+
+    cmd.args(&[
+        "list",
+        "test-data/multiple-packages.aer.toml",
+        "--tag",
+        "Browser",
+        "--tag",
+        "monthly",
+        "--all-tags",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("\"id\": \"first-multiple-package\"")
+            .and(predicate::str::contains("\"browser\""))
+            .and(predicate::str::contains("second-multiple-package").not()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn verify_should_report_files_that_could_not_be_downloaded(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-verify.log");
+    let output = std::env::temp_dir().join("aer-tests-verify");
+
+    cmd.args(&[
+        "verify",
+        "test-data/verify",
+        "--output",
+        output.to_str().unwrap(),
+        "--retries",
+        "0",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(4).stdout(
+        predicate::str::contains(r#""id": "verify-package""#)
+            .and(predicate::str::contains(
+                r#""url": "http://127.0.0.1:1/1.0.0/setup-x64.exe""#,
+            ))
+            .and(predicate::str::contains(r#""error_kind": "download""#)),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn check_should_report_unreachable_packages() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-check.log");
+
+    cmd.args(&[
+        "check",
+        "test-data/check",
+        "--fail-on-update",
+        "--retries",
+        "0",
+        "--no-cache",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(4).stdout(
+        predicate::str::contains(r#""id": "check-package""#)
+            .and(predicate::str::contains(r#""error_kind": "network""#)),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn update_should_read_package_data_from_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = assert_cmd::Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-stdin.log");
+
+    cmd.args(&[
+        "update",
+        "-",
+        "--data-format",
+        "toml",
+        "--dry-run",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true")
+    .write_stdin("[metadata\n");
+
+    cmd.assert().code(2).stdout(
+        predicate::str::contains(r#""file": "-""#)
+            .and(predicate::str::contains("Unable to deserialize '<stdin>'")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_use_defaults_from_config_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-config-defaults.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--config",
+        "test-data/config/quiet.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("is valid!").not());
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_prefer_environment_over_config_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-config-env.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true")
+    .env("AER_CONFIG", "test-data/config/quiet.toml")
+    .env("AER_LOG_LEVEL", "info");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("is valid!"));
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_prefer_arguments_over_config_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-config-args.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--config=test-data/config/quiet.toml",
+        "--log-level",
+        "info",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("is valid!"));
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_fail_on_unknown_key_in_config_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-config-unknown.log");
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--config",
+        "test-data/config/unknown-key.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "contains the unknown key(s) 'timout'",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn validate_should_reject_unknown_tls_min_version() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+
+    cmd.args(&[
+        "validate",
+        "test-data/valid-package.aer.toml",
+        "--tls-min-version",
+        "2.0",
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "'2.0' isn't a valid value for '--tls-min-version",
+    ));
+
+    Ok(())
+}
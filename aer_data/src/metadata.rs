@@ -88,6 +88,21 @@ pub struct PackageMetadata {
     #[cfg_attr(feature = "serialize", serde(default))]
     license: LicenseType,
 
+    /// The tags used to group the package together with other packages (*ie:
+    /// `browser` or `monthly`*), allowing a subset of the packages to be
+    /// selected without listing the identifier of each package.
+    ///
+    /// ### Examples
+    ///
+    /// ```toml
+    /// [metadata]
+    /// id = "test-package"
+    /// project_url = "https://some-page.org"
+    /// tags = ["browser", "monthly"]
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    tags: Vec<String>,
+
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
     chocolatey: Option<chocolatey::ChocolateyMetadata>,
@@ -103,6 +118,7 @@ impl PackageMetadata {
             summary: String::new(),
             project_url: Url::parse("https://example-repo.org").unwrap(),
             license: LicenseType::None,
+            tags: vec![],
             #[cfg(feature = "chocolatey")]
             chocolatey: None,
         }
@@ -147,6 +163,17 @@ impl PackageMetadata {
         &self.license
     }
 
+    /// Returns the tags the package is grouped by.
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_slice()
+    }
+
+    /// Checks wether the package is tagged with the specified tag, ignoring
+    /// the case of the tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
     /// Allows setting a new instance of chocolatey metadata and associate it
     /// with the current metadata instance.
     #[cfg(feature = "chocolatey")]
@@ -176,6 +203,13 @@ impl PackageMetadata {
     pub fn set_license(&mut self, license: LicenseType) {
         self.license = license;
     }
+
+    pub fn set_tags<T>(&mut self, vals: &[T])
+    where
+        T: Display,
+    {
+        self.tags = vals.iter().map(|val| val.to_string()).collect();
+    }
 }
 
 impl Default for PackageMetadata {
@@ -196,6 +230,7 @@ mod tests {
             project_url: Url::parse("https://example-repo.org").unwrap(),
             license: LicenseType::None,
             summary: String::new(),
+            tags: vec![],
             #[cfg(feature = "chocolatey")]
             chocolatey: None,
         };
@@ -245,6 +280,17 @@ mod tests {
         assert_eq!(pkg.project_url(), &expected);
     }
 
+    #[test]
+    fn has_tag_should_ignore_case_of_tags() {
+        let mut pkg = PackageMetadata::new("test");
+        pkg.set_tags(&["Browser", "monthly"]);
+
+        assert_eq!(pkg.tags(), ["Browser", "monthly"]);
+        assert!(pkg.has_tag("browser"));
+        assert!(pkg.has_tag("MONTHLY"));
+        assert!(!pkg.has_tag("weekly"));
+    }
+
     #[cfg(feature = "chocolatey")]
     #[test]
     fn chocolatey_should_return_set_data() {
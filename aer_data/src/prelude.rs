@@ -6,7 +6,7 @@ pub use aer_version::{FixVersion, SemVersion, Versions};
 pub use url::Url;
 
 pub use crate::metadata::{Description, PackageMetadata};
-pub use crate::updater::PackageUpdateData;
+pub use crate::updater::{Authentication, Hook, PackageUpdateData, UpdateHooks};
 pub use crate::PackageData;
 
 /// Re-Exports of usable chocolatey types.
@@ -17,6 +17,17 @@ pub mod chocolatey {
 
     pub use crate::metadata::chocolatey::ChocolateyMetadata;
     pub use crate::updater::chocolatey::{
-        ChocolateyParseUrl, ChocolateyUpdaterData, ChocolateyUpdaterType,
+        arch_pattern_regex, architecture_name, ChocolateyChecksumType, ChocolateyExpectedChecksum,
+        ChocolateyLinkSelection, ChocolateyLinkSource, ChocolateyParseUrl, ChocolateyParseUrlEntry,
+        ChocolateyParseUrls, ChocolateyRegexes, ChocolateyScriptValue, ChocolateyUpdaterData,
+        ChocolateyUpdaterType, ChocolateyVerification, ChocolateyVersionSelector,
+        OTHER_ARCHITECTURE,
     };
 }
+
+/// Re-Exports of usable scoop types.
+#[cfg(feature = "scoop")]
+#[cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+pub mod scoop {
+    pub use crate::updater::scoop::ScoopUpdaterData;
+}
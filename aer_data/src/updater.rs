@@ -2,29 +2,158 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
 pub mod chocolatey;
+pub mod scoop;
 
 use std::borrow::Cow;
 
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 
+/// The credentials needed when requesting a remote location. The secret
+/// values are never stored directly, instead the name of the environment
+/// variable holding the secret is specified.
+///
+/// ### Examples
+///
+/// A `TOML` edition of using a bearer token.
+/// ```toml
+/// [updater.chocolatey.authentication]
+/// token_env = "GITHUB_TOKEN"
+/// ```
+///
+/// A `TOML` edition of using basic authentication.
+/// ```toml
+/// [updater.chocolatey.authentication]
+/// username = "my-user"
+/// password_env = "ARTIFACTORY_PASSWORD"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
+pub enum Authentication {
+    /// Uses basic authentication with the specified username, and the
+    /// password stored in the specified environment variable.
+    Basic {
+        username: String,
+        password_env: String,
+    },
+    /// Uses a bearer token stored in the specified environment variable.
+    Bearer { token_env: String },
+}
+
+/// A single command that is run before or after an update.
+///
+/// ### Examples
+///
+/// A `TOML` edition of specifying a command to run with the default shell, and
+/// a command to run with PowerShell.
+/// ```toml
+/// [updater.hooks]
+/// before = ["git pull"]
+/// after = [{ powershell = "Write-Host $env:AER_VERSION" }]
+/// post_download = ["clamscan --no-summary \"$AER_DOWNLOAD_PATH\""]
+/// timeout = 300
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
+pub enum Hook {
+    /// The command is run using the default shell of the system (`sh` on
+    /// unix, and `cmd` on windows).
+    Command(String),
+    /// The command is run using PowerShell.
+    PowerShell { powershell: String },
+}
+
+/// The hooks that should be run around the update of a package. The hooks
+/// are run with the directory of the package file as the working directory.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct UpdateHooks {
+    /// The hooks to run before the update is started, any failure will abort
+    /// the update of the package. These are run once a newer version have
+    /// been found, before any files are downloaded.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub before: Vec<Hook>,
+    /// The hooks to run after the update have successfully completed.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub after: Vec<Hook>,
+    /// The hooks to run before each file is downloaded, with the url and the
+    /// path the file will be downloaded to available in the
+    /// `AER_DOWNLOAD_URL` and `AER_DOWNLOAD_PATH` environment variables. Any
+    /// failure will abort the download of the file.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub pre_download: Vec<Hook>,
+    /// The hooks to run after each file have been downloaded, with the same
+    /// environment variables as the `pre_download` hooks. Any failure will
+    /// reject the downloaded file, and the file will be deleted.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub post_download: Vec<Hook>,
+    /// The maximum number of seconds each hook is allowed to run, before the
+    /// hook is killed and treated as failed. Overrides the timeout used for
+    /// every package.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub timeout: Option<u64>,
+}
+
 #[derive(Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
 #[non_exhaustive]
 pub struct PackageUpdateData {
+    #[cfg_attr(feature = "serialize", serde(default))]
+    hooks: UpdateHooks,
+
+    #[cfg_attr(feature = "serialize", serde(default))]
+    latest_pointer: bool,
+
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
     chocolatey: Option<chocolatey::ChocolateyUpdaterData>,
+
+    #[cfg(feature = "scoop")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+    scoop: Option<scoop::ScoopUpdaterData>,
 }
 
 impl PackageUpdateData {
     pub fn new() -> PackageUpdateData {
         PackageUpdateData {
+            hooks: UpdateHooks::default(),
+            latest_pointer: false,
             #[cfg(feature = "chocolatey")]
             chocolatey: None,
+            #[cfg(feature = "scoop")]
+            scoop: None,
         }
     }
 
+    /// Returns the hooks that should be run around the update.
+    pub fn hooks(&self) -> &UpdateHooks {
+        &self.hooks
+    }
+
+    /// Allows setting the hooks that should be run around the update.
+    pub fn set_hooks(&mut self, hooks: UpdateHooks) {
+        self.hooks = hooks;
+    }
+
+    /// Returns wether a `latest` pointer to the directory the files of the
+    /// newest version was downloaded to should be maintained in the output
+    /// directory. The pointer is only updated when a new version is found.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater]
+    /// latest_pointer = true
+    /// ```
+    pub fn latest_pointer(&self) -> bool {
+        self.latest_pointer
+    }
+
+    /// Allows setting wether the `latest` pointer should be maintained.
+    pub fn set_latest_pointer(&mut self, value: bool) {
+        self.latest_pointer = value;
+    }
+
     /// Returns wether data regarding chocolatey is already set for the updater.
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
@@ -50,12 +179,56 @@ impl PackageUpdateData {
     pub fn set_chocolatey(&mut self, choco: chocolatey::ChocolateyUpdaterData) {
         self.chocolatey = Some(choco);
     }
+
+    /// Returns wether data regarding scoop is already set for the updater.
+    #[cfg(feature = "scoop")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+    pub fn has_scoop(&self) -> bool {
+        self.scoop.is_some()
+    }
+
+    /// Returns the current set scoop updater data, or a new instance if no
+    /// data is already set.
+    #[cfg(feature = "scoop")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+    pub fn scoop(&self) -> Cow<scoop::ScoopUpdaterData> {
+        if let Some(ref scoop) = self.scoop {
+            Cow::Borrowed(scoop)
+        } else {
+            Cow::Owned(scoop::ScoopUpdaterData::new())
+        }
+    }
+
+    /// Allows associating new scoop updater data with the current instance.
+    #[cfg(feature = "scoop")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+    pub fn set_scoop(&mut self, scoop: scoop::ScoopUpdaterData) {
+        self.scoop = Some(scoop);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn should_get_set_hooks() {
+        let expected = UpdateHooks {
+            before: vec![Hook::Command("git pull".into())],
+            after: vec![Hook::PowerShell {
+                powershell: "Write-Host 'Done'".into(),
+            }],
+            pre_download: vec![],
+            post_download: vec![Hook::Command("scan \"$AER_DOWNLOAD_PATH\"".into())],
+            timeout: Some(30),
+        };
+
+        let mut data = PackageUpdateData::new();
+        data.set_hooks(expected.clone());
+
+        assert_eq!(data.hooks(), &expected);
+    }
+
     #[cfg(feature = "chocolatey")]
     #[test]
     fn should_get_set_chocolatey_data() {
@@ -78,4 +251,17 @@ mod tests {
         assert!(!data.has_chocolatey());
         assert_eq!(data.chocolatey(), Cow::Owned(expected));
     }
+
+    #[cfg(feature = "scoop")]
+    #[test]
+    fn should_get_set_scoop_data() {
+        let mut expected = scoop::ScoopUpdaterData::new();
+        expected.add_regex("arch64", "MY REGEX");
+
+        let mut data = PackageUpdateData::new();
+        data.set_scoop(expected.clone());
+
+        assert!(data.has_scoop());
+        assert_eq!(data.scoop(), Cow::Owned(expected));
+    }
 }
@@ -3,12 +3,20 @@
 
 #![cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
 
+use aer_version::Versions;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::updater::Authentication;
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
 pub enum ChocolateyUpdaterType {
@@ -23,11 +31,861 @@ impl Default for ChocolateyUpdaterType {
     }
 }
 
+/// The checksum types that can be used for the files downloaded by a package,
+/// these are the same types that is accepted by `Get-ChocolateyWebFile`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum ChocolateyChecksumType {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Default for ChocolateyChecksumType {
+    fn default() -> Self {
+        Self::Sha256
+    }
+}
+
+/// The checksum that a downloaded file is expected to have, the downloaded
+/// file is verified against this checksum and the update is aborted when the
+/// checksums do not match.
+///
+/// ## Examples
+///
+/// ```toml
+/// [updater.chocolatey.expected_checksums.x64]
+/// checksum = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+/// checksum_type = "sha256"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct ChocolateyExpectedChecksum {
+    /// The checksum the downloaded file is expected to have.
+    pub checksum: String,
+    /// The type of the checksum, when not specified the checksum type of the
+    /// updater is used.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub checksum_type: Option<ChocolateyChecksumType>,
+}
+
+impl ChocolateyExpectedChecksum {
+    /// Creates a new expected checksum of the specified type.
+    pub fn new(checksum: &str, checksum_type: Option<ChocolateyChecksumType>) -> Self {
+        Self {
+            checksum: checksum.into(),
+            checksum_type,
+        }
+    }
+
+    /// Checks wether the specified checksum matches the expected checksum,
+    /// the checksums are compared case insensitively.
+    pub fn matches(&self, checksum: &str) -> bool {
+        self.checksum.trim().eq_ignore_ascii_case(checksum.trim())
+    }
+}
+
+/// The settings used when creating the `VERIFICATION.txt` file of a package,
+/// listing the urls and checksums of the downloaded files.
+///
+/// ## Examples
+///
+/// ```toml
+/// [updater.chocolatey.verification]
+/// path = "legal/VERIFICATION.txt"
+/// template = "templates/VERIFICATION.txt"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct ChocolateyVerification {
+    /// The path to the verification file that should be created, relative to
+    /// the directory of the package file. Defaults to
+    /// `legal/VERIFICATION.txt`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub path: Option<PathBuf>,
+    /// The path to the template used to create the verification file,
+    /// relative to the directory of the package file. The placeholders in the
+    /// template (*ie: `{{url_x64}}`, `{{checksum_x64}}` or
+    /// `{{checksum_type}}`*) are replaced with the values found during the
+    /// update. A built-in template is used when no template is specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub template: Option<PathBuf>,
+}
+
+/// An element and attribute pair of the html pages that links are read from,
+/// specified as `<element>@<attribute>` (*ie: `button@data-url`*).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct ChocolateyLinkSource {
+    /// The name of the element containing the link.
+    pub element: String,
+    /// The attribute of the element containing the link.
+    pub attribute: String,
+}
+
+impl FromStr for ChocolateyLinkSource {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        let mut parts = val.trim().splitn(2, '@');
+
+        match (parts.next(), parts.next()) {
+            (Some(element), Some(attribute))
+                if !element.trim().is_empty() && !attribute.trim().is_empty() =>
+            {
+                Ok(Self {
+                    element: element.trim().to_lowercase(),
+                    attribute: attribute.trim().to_lowercase(),
+                })
+            }
+            _ => Err(format!(
+                "'{}' is not a supported link source, expected '<element>@<attribute>'!",
+                val
+            )),
+        }
+    }
+}
+
+impl Display for ChocolateyLinkSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.element, self.attribute)
+    }
+}
+
+impl TryFrom<String> for ChocolateyLinkSource {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ChocolateyLinkSource> for String {
+    fn from(value: ChocolateyLinkSource) -> Self {
+        value.to_string()
+    }
+}
+
+/// The element of the html page at the parse url that contains the version of
+/// the software, used when the version is not part of the download links.
+/// Every link that do not have a version will inherit the version found in
+/// the element.
+///
+/// ## Examples
+///
+/// ```toml
+/// [updater.chocolatey.version_selector]
+/// selector = "meta[name=version]"
+/// attribute = "content"
+/// regex = '''v(?P<version>[\d\.]+)'''
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct ChocolateyVersionSelector {
+    /// A simple css selector matching the element containing the version
+    /// (*ie: `title`, `h1.version`, `#version` or `meta[name=version]`*).
+    pub selector: String,
+    /// The attribute of the element that contains the version. The text of
+    /// the element is used when not specified, except for `meta` elements
+    /// where the `content` attribute is used.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub attribute: Option<String>,
+    /// The regular expression used to extract the version, either using the
+    /// `version` capture group or the whole match. Defaults to the first value
+    /// that looks like a version.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub regex: Option<String>,
+}
+
+/// Gets the name of the architecture that the specified regex key will be
+/// used for. This is the key in lowercase, except for the `arch32` and
+/// `arch64` keys which are aliases for the `x86` and `x64` architectures.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_data::updater::chocolatey::architecture_name;
+///
+/// assert_eq!(architecture_name("arch32"), "x86");
+/// assert_eq!(architecture_name("ARM64"), "arm64");
+/// ```
+pub fn architecture_name(key: &str) -> String {
+    let key = key.trim().to_lowercase();
+
+    match key.as_str() {
+        "arch32" => "x86".into(),
+        "arch64" => "x64".into(),
+        _ => key,
+    }
+}
+
+/// The filename heuristics used to detect the architecture of links when
+/// `auto_arch` is enabled, in the order they are tried. A link is assigned
+/// to the first architecture where any of the patterns match a whole word of
+/// the file name, ignoring case.
+///
+/// | Architecture | Patterns                                                 |
+/// |--------------|----------------------------------------------------------|
+/// | `arm64`      | `arm64`, `aarch64`                                       |
+/// | `x64`        | `x64`, `x86_64`, `x86-64`, `amd64`, `win64`, `64bit`     |
+/// | `x86`        | `x86`, `i386`, `i686`, `ia32`, `win32`, `32bit`          |
+///
+/// Links that do not match any of the patterns are assigned to
+/// [OTHER_ARCHITECTURE].
+pub const DEFAULT_ARCH_PATTERNS: &[(&str, &[&str])] = &[
+    ("arm64", &["arm64", "aarch64"]),
+    (
+        "x64",
+        &["x64", "x86_64", "x86-64", "amd64", "win64", "64-?bit"],
+    ),
+    ("x86", &["x86", "i[36]86", "ia32", "win32", "32-?bit"]),
+];
+
+/// The architecture that links are assigned to when `auto_arch` is enabled
+/// and none of the architecture patterns match the file name of the link.
+pub const OTHER_ARCHITECTURE: &str = "others";
+
+/// Creates the regex matching a file name where any of the specified
+/// architecture patterns match a whole word, ignoring case.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_data::updater::chocolatey::arch_pattern_regex;
+///
+/// assert_eq!(
+///     arch_pattern_regex(&["x64", "amd64"]),
+///     "(?i)(?:^|[^a-z0-9])(?:x64|amd64)(?:[^a-z0-9]|$)"
+/// );
+/// ```
+pub fn arch_pattern_regex<S: AsRef<str>>(patterns: &[S]) -> String {
+    let patterns: Vec<&str> = patterns.iter().map(|p| p.as_ref()).collect();
+
+    format!(
+        "(?i)(?:^|[^a-z0-9])(?:{})(?:[^a-z0-9]|$)",
+        patterns.join("|")
+    )
+}
+
+/// The regexes used to find the link of a single architecture. This can
+/// either be a single regex, or a list of regexes that are tried in order
+/// until one of them matches any links.
+///
+/// ## Examples
+///
+/// ```toml
+/// [updater.chocolatey.regexes]
+/// x86 = '''-x86\.exe$'''
+/// x64 = ['''-x64\.exe$''', '''-amd64\.exe$''']
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
+pub enum ChocolateyRegexes {
+    /// A single regex to use.
+    Single(String),
+    /// Multiple regexes to try in order.
+    Multiple(Vec<String>),
+}
+
+impl ChocolateyRegexes {
+    /// Returns all of the regexes, in the order they should be tried.
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            Self::Single(regex) => std::slice::from_ref(regex),
+            Self::Multiple(regexes) => regexes,
+        }
+    }
+}
+
+impl From<&str> for ChocolateyRegexes {
+    fn from(value: &str) -> Self {
+        Self::Single(value.into())
+    }
+}
+
+impl From<Vec<String>> for ChocolateyRegexes {
+    fn from(values: Vec<String>) -> Self {
+        Self::Multiple(values)
+    }
+}
+
+/// The names of the built-in regex presets, that can be selected with
+/// `regex_preset` instead of specifying the regexes for vendors that use the
+/// same url patterns for most of their downloads.
+///
+/// | Preset           | Links                                                                |
+/// |------------------|----------------------------------------------------------------------|
+/// | `fosshub`        | `https://www.fosshub.com/<project>.html?dwl=<file>`                  |
+/// | `github-release` | `https://github.com/<owner>/<repo>/releases/download/<tag>/<file>`    |
+/// | `sourceforge`    | `https://sourceforge.net/projects/<project>/files/<path>/<file>/download` |
+pub const REGEX_PRESETS: &[&str] = &["fosshub", "github-release", "sourceforge"];
+
+/// The regexes and the parse mode of a built-in regex preset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChocolateyRegexPreset {
+    /// The name of the preset.
+    pub name: &'static str,
+    /// The regexes of the preset, keyed by the architecture they are used
+    /// for.
+    pub regexes: Vec<(&'static str, ChocolateyRegexes)>,
+    /// Wether the regexes are matched against the decoded link target.
+    pub decode_link: bool,
+}
+
+const PRESET_VERSION: &str = r"(?P<version>\d+(?:\.\d+)+)";
+const PRESET_ARCH64: &str = r"(?:x64|x86[_-]64|amd64|win64|64-?bit)";
+const PRESET_ARCH32: &str = r"(?:x86|i[36]86|win32|32-?bit)";
+const PRESET_EXTENSIONS: &str = r"(?:exe|msi|zip)";
+
+/// Gets the built-in regex preset with the specified name, ignoring case.
+///
+/// The presets provide the `x64` and `x86` regexes matching installers
+/// (*`.exe`, `.msi` or `.zip` files*) where the file name contains the
+/// architecture (*ie: `x64`, `win64` or `32bit`*). The version is captured
+/// from the file name, where it must come before the architecture or
+/// directly after it, except for `github-release` where the version is
+/// captured from the tag of the release.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_data::updater::chocolatey::regex_preset;
+///
+/// let preset = regex_preset("SourceForge").unwrap();
+/// assert_eq!(preset.name, "sourceforge");
+/// assert!(preset.decode_link);
+/// assert!(regex_preset("unknown").is_none());
+/// ```
+pub fn regex_preset(name: &str) -> Option<ChocolateyRegexPreset> {
+    let name = name.trim().to_lowercase();
+
+    let (name, regexes, decode_link) = match name.as_str() {
+        "fosshub" => (
+            REGEX_PRESETS[0],
+            preset_regexes(r"fosshub\.com/[^?]+\?dwl=", "/&=", "$", true),
+            false,
+        ),
+        "github-release" => (
+            REGEX_PRESETS[1],
+            preset_regexes(
+                &format!(
+                    r"github\.com/[^/]+/[^/]+/releases/download/v?{}[^/]*/",
+                    PRESET_VERSION
+                ),
+                "/",
+                "$",
+                false,
+            ),
+            false,
+        ),
+        "sourceforge" => (
+            REGEX_PRESETS[2],
+            preset_regexes(
+                r"sourceforge\.net/projects/[^/]+/files/(?:[^/]+/)*",
+                "/",
+                "/download$",
+                true,
+            ),
+            true,
+        ),
+        _ => return None,
+    };
+
+    Some(ChocolateyRegexPreset {
+        name,
+        regexes,
+        decode_link,
+    })
+}
+
+/// Creates the `x64` and `x86` regexes of a preset, where the file name of
+/// the link follows the `prefix` and is made up of any character except the
+/// `excluded` characters.
+fn preset_regexes(
+    prefix: &str,
+    excluded: &str,
+    suffix: &str,
+    with_version: bool,
+) -> Vec<(&'static str, ChocolateyRegexes)> {
+    let any = format!("[^{}]*?", excluded);
+    let end = format!(r"{}\.{}{}", any, PRESET_EXTENSIONS, suffix);
+    // The 32-bit architecture must not be followed by `_64` or `-64`, as
+    // `x86_64` and `x86-64` are used for 64-bit files.
+    let end32 = format!(
+        r"(?:\.|(?:[_-][^6{excluded}]|[^_\-6.{excluded}]){any}\.){ext}{suffix}",
+        excluded = excluded,
+        any = any,
+        ext = PRESET_EXTENSIONS,
+        suffix = suffix
+    );
+
+    let (x64, x86) = if with_version {
+        (
+            vec![
+                format!(
+                    "(?i){}{}{}{}{}{}",
+                    prefix, any, PRESET_VERSION, any, PRESET_ARCH64, end
+                ),
+                format!(
+                    "(?i){}{}{}{}{}{}",
+                    prefix, any, PRESET_ARCH64, any, PRESET_VERSION, end
+                ),
+            ],
+            vec![
+                format!(
+                    "(?i){}{}{}{}{}{}",
+                    prefix, any, PRESET_VERSION, any, PRESET_ARCH32, end32
+                ),
+                format!(
+                    "(?i){}{}{}[_-]?{}{}",
+                    prefix, any, PRESET_ARCH32, PRESET_VERSION, end
+                ),
+            ],
+        )
+    } else {
+        (
+            vec![format!("(?i){}{}{}{}", prefix, any, PRESET_ARCH64, end)],
+            vec![format!("(?i){}{}{}{}", prefix, any, PRESET_ARCH32, end32)],
+        )
+    };
+
+    vec![("x64", x64.into()), ("x86", x86.into())]
+}
+
+/// The values found during an update that can be written to the variables in
+/// the chocolatey install script.
+///
+/// The values are specified as a string, where the url and checksum of a
+/// downloaded file is referenced by the name of the architecture (*ie:
+/// `url_arm64` or `checksum_arm64`*). The values `url32`, `url64`,
+/// `checksum32` and `checksum64` are also accepted as aliases for the `x86`
+/// and `x64` architectures.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum ChocolateyScriptValue {
+    /// The url of the file downloaded for the specified architecture.
+    Url(String),
+    /// The checksum of the file downloaded for the specified architecture.
+    Checksum(String),
+    /// The type of checksum that was used.
+    ChecksumType,
+    /// The version that was found for the package.
+    Version,
+}
+
+impl FromStr for ChocolateyScriptValue {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        let val = val.trim().to_lowercase();
+
+        match val.as_str() {
+            "url32" => return Ok(Self::Url(architecture_name("arch32"))),
+            "url64" => return Ok(Self::Url(architecture_name("arch64"))),
+            "checksum32" => return Ok(Self::Checksum(architecture_name("arch32"))),
+            "checksum64" => return Ok(Self::Checksum(architecture_name("arch64"))),
+            "checksumtype" | "checksum_type" => return Ok(Self::ChecksumType),
+            "version" => return Ok(Self::Version),
+            _ => {}
+        }
+
+        if let Some(arch) = val.strip_prefix("url_").filter(|arch| !arch.is_empty()) {
+            Ok(Self::Url(architecture_name(arch)))
+        } else if let Some(arch) = val
+            .strip_prefix("checksum_")
+            .filter(|arch| !arch.is_empty())
+        {
+            Ok(Self::Checksum(architecture_name(arch)))
+        } else {
+            Err(format!("'{}' is not a supported script value!", val))
+        }
+    }
+}
+
+impl Display for ChocolateyScriptValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(arch) => write!(f, "url_{}", arch),
+            Self::Checksum(arch) => write!(f, "checksum_{}", arch),
+            Self::ChecksumType => f.write_str("checksum_type"),
+            Self::Version => f.write_str("version"),
+        }
+    }
+}
+
+impl TryFrom<String> for ChocolateyScriptValue {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ChocolateyScriptValue> for String {
+    fn from(value: ChocolateyScriptValue) -> Self {
+        value.to_string()
+    }
+}
+
+/// The strategy used to select the link that is used for a single key of the
+/// regexes, out of all the links matched by the regex of the key.
+///
+/// The strategies are specified as a string, being one of `first`, `last`,
+/// `highest-version` or `index N`, where `N` is the position of the link
+/// (*starting at 1*).
+///
+/// ## Examples
+///
+/// ```toml
+/// [updater.chocolatey.link_selection]
+/// x64 = "last"
+/// installer = "index 2"
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum ChocolateyLinkSelection {
+    /// The first link, in the order the links was found on the parsed pages.
+    First,
+    /// The last link, in the order the links was found on the parsed pages.
+    Last,
+    /// The link with the highest version, this is the default.
+    HighestVersion,
+    /// The link at the specified position (*starting at 1*), in the order
+    /// the links was found on the parsed pages.
+    Index(usize),
+}
+
+impl Default for ChocolateyLinkSelection {
+    fn default() -> Self {
+        Self::HighestVersion
+    }
+}
+
+impl FromStr for ChocolateyLinkSelection {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        let val = val.trim().to_lowercase();
+
+        match val.as_str() {
+            "first" => return Ok(Self::First),
+            "last" => return Ok(Self::Last),
+            "highest-version" | "highest_version" => return Ok(Self::HighestVersion),
+            _ => {}
+        }
+
+        match val
+            .strip_prefix("index")
+            .map(|index| index.trim().parse::<usize>())
+        {
+            Some(Ok(index)) if index > 0 => Ok(Self::Index(index)),
+            _ => Err(format!("'{}' is not a supported link selection!", val)),
+        }
+    }
+}
+
+impl Display for ChocolateyLinkSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::First => f.write_str("first"),
+            Self::Last => f.write_str("last"),
+            Self::HighestVersion => f.write_str("highest-version"),
+            Self::Index(index) => write!(f, "index {}", index),
+        }
+    }
+}
+
+impl TryFrom<String> for ChocolateyLinkSelection {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ChocolateyLinkSelection> for String {
+    fn from(value: ChocolateyLinkSelection) -> Self {
+        value.to_string()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
 pub enum ChocolateyParseUrl {
-    UrlWithRegex { url: Url, regex: String },
+    /// Requests a json document (*ie: a `latest.json` file*) and selects the
+    /// links in the document by using the specified `selector`. The version
+    /// of the links are read from the `version_field` (*if specified*), or
+    /// captured by the `regex` (*if specified*).
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// url = "https://example.org/latest.json"
+    /// selector = "downloads.*"
+    /// version_field = "version"
+    /// ```
+    Json {
+        /// The url of the json document.
+        url: Url,
+        /// The selector used to find the links in the json document, each key
+        /// is separated by a dot and array items are selected with brackets
+        /// (*ie: `assets[*].url`*).
+        selector: String,
+        /// The path to the field holding the version of the links, this is
+        /// first looked up in the object holding the link, and then from the
+        /// root of the document.
+        #[cfg_attr(feature = "serialize", serde(default))]
+        version_field: Option<String>,
+        /// The regular expression that the selected links must match.
+        #[cfg_attr(feature = "serialize", serde(default))]
+        regex: Option<String>,
+    },
+    /// Parses the links on the html page that match the specified `regex`,
+    /// and by default follows the first of the matched links to parse the
+    /// links on that page instead.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// url = "https://sourceforge.net/projects/astyle/files/astyle/"
+    /// regex = 'astyle( |%20)(?P<version>[\d\.]+)/$'
+    /// follow_highest = true
+    /// ```
+    ///
+    /// Pages where the downloads of each architecture are on a separate page,
+    /// where the link to follow is selected for each architecture.
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// url = "https://example.org/downloads/"
+    /// regex = '/(?P<version>[\d\.]+)/(windows|win64)/$'
+    /// arch_regexes = { arch32 = '/windows/$', arch64 = '/win64/$' }
+    /// ```
+    UrlWithRegex {
+        /// The url of the html page to parse.
+        url: Url,
+        /// The regular expression that the links on the page must match.
+        regex: String,
+        /// Wether one of the matched links should be followed to parse the
+        /// links on that page, when disabled the matched links are used
+        /// directly.
+        #[cfg_attr(
+            feature = "serialize",
+            serde(default = "crate::defaults::boolean_true")
+        )]
+        follow_first: bool,
+        /// Wether the matched link with the highest version should be
+        /// followed instead of the first matched link.
+        #[cfg_attr(feature = "serialize", serde(default))]
+        follow_highest: bool,
+        /// The regular expressions that the matched links must also match to
+        /// be followed for a specific architecture, where the keys are the
+        /// same architectures as in the regexes of the updater. A link is
+        /// followed for each architecture, and the links on the followed
+        /// page are only used for that architecture. When empty, a single
+        /// link is followed for every architecture.
+        #[cfg_attr(feature = "serialize", serde(default))]
+        arch_regexes: HashMap<String, String>,
+    },
+    /// Parses the links on the html page that are matched by the css
+    /// `link_selector`, or that are inside an element matched by the
+    /// selector. The regexes of the updater are then matched against the
+    /// selected links as usual.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// url = "https://example.org/downloads"
+    /// link_selector = "table.downloads a"
+    /// ```
+    UrlWithSelector {
+        /// The url of the html page to parse.
+        url: Url,
+        /// The css selector limiting the links of the page, made up of
+        /// element names, ids, classes and attributes separated by
+        /// whitespace to match descendant elements.
+        link_selector: String,
+    },
     Url(Url),
+    /// Uses the GitHub releases api to find the latest release of the
+    /// repository, instead of parsing a html page. The assets of the release
+    /// will be used as the links to match the regexes against, and the
+    /// version is taken from the tag of the release.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// owner = "codecov"
+    /// repo = "codecov-exe"
+    /// ```
+    ///
+    /// A private repository, where the token is read from the `token_env`
+    /// environment variable.
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// owner = "my-org"
+    /// repo = "private-tool"
+    /// token_env = "MY_ORG_GITHUB_TOKEN"
+    /// ```
+    GitHub {
+        /// The owner (*user or organization*) of the repository.
+        owner: String,
+        /// The name of the repository.
+        repo: String,
+        /// The environment variable holding the token used when requesting
+        /// the releases and downloading the assets of the release. The token
+        /// is required for private repositories, and is only sent to the
+        /// GitHub api. When not specified, the `GITHUB_TOKEN` environment
+        /// variable is used for the api requests if it is set.
+        #[cfg_attr(feature = "serialize", serde(default))]
+        token_env: Option<String>,
+    },
+    /// Runs the specified `command` to find the links of the package, for
+    /// sources that can not be parsed by any of the other parse urls. The
+    /// command is run by the shell (*`sh` or `cmd`*) in the directory of the
+    /// package file, and receives a json document with the identifier of the
+    /// package, the architectures of the updater and wether pre-releases are
+    /// allowed on its standard input.
+    ///
+    /// The command must write a json document with the links to its standard
+    /// output, where each link is either a url or an object with the `url`
+    /// and an optional `text`, `title` and `version`. The `version` at the
+    /// root of the document is used for the links without a version. The
+    /// regexes of the updater are then matched against the links as usual.
+    ///
+    /// ```json
+    /// {
+    ///   "version": "1.2.0",
+    ///   "links": [
+    ///     "https://example.org/downloads/setup-x64.exe",
+    ///     { "url": "https://example.org/downloads/setup.exe", "text": "x86" }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.parse_url]
+    /// command = "python resolve.py"
+    /// ```
+    External {
+        /// The command that is run to find the links.
+        command: String,
+    },
+}
+
+/// The urls that should be parsed to find the links of the package, either
+/// a single url or a list of urls where the links found on every url are
+/// merged together before the regexes are matched against them.
+///
+/// ## Examples
+///
+/// A `TOML` edition of a package where the `x86` and `x64` links are found on
+/// different pages.
+/// ```toml
+/// [[updater.chocolatey.parse_url]]
+/// url = "https://example.org/downloads/x86"
+/// archs = ["arch32"]
+///
+/// [[updater.chocolatey.parse_url]]
+/// url = { url = "https://example.org/downloads/x64", regex = '/x64/$' }
+/// archs = ["arch64"]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
+pub enum ChocolateyParseUrls {
+    /// A single url to parse, used for all architectures.
+    Single(ChocolateyParseUrl),
+    /// Multiple urls to parse, where the links of every url are merged.
+    Multiple(Vec<ChocolateyParseUrlEntry>),
+}
+
+impl ChocolateyParseUrls {
+    /// Returns every url that should be parsed, together with the
+    /// architectures the links of the url should be used for and the
+    /// additional headers to send when requesting the url. When no
+    /// architectures are specified, the links are used for all
+    /// architectures.
+    pub fn entries(&self) -> Vec<Cow<ChocolateyParseUrlEntry>> {
+        match self {
+            Self::Single(parse_url) => {
+                vec![Cow::Owned(ChocolateyParseUrlEntry::new(parse_url.clone()))]
+            }
+            Self::Multiple(entries) => entries.iter().map(Cow::Borrowed).collect(),
+        }
+    }
+}
+
+impl From<ChocolateyParseUrl> for ChocolateyParseUrls {
+    fn from(value: ChocolateyParseUrl) -> Self {
+        Self::Single(value)
+    }
+}
+
+/// A single url in a list of urls to parse, optionally tagged with the
+/// architectures the links on the url should be used for.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(deny_unknown_fields)
+)]
+pub struct ChocolateyParseUrlEntry {
+    /// The url to parse, accepting the same values as a single parse url.
+    pub url: ChocolateyParseUrl,
+    /// The architectures (*ie: `arch32`, `x64` or `arm64`*) the links of the
+    /// url should be used for, defaults to all architectures.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub archs: Vec<String>,
+    /// The languages sent in the `Accept-Language` header when requesting the
+    /// url, overriding the language specified for the updater. An
+    /// `Accept-Language` header in `headers` overrides this value.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [[updater.chocolatey.parse_url]]
+    /// url = "https://example.org/downloads"
+    /// accept_language = "en-US,en;q=0.9"
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub accept_language: Option<String>,
+    /// The additional headers to send when requesting the url, these
+    /// override any headers with the same name specified for the updater.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub headers: HashMap<String, String>,
+}
+
+impl ChocolateyParseUrlEntry {
+    pub fn new(url: ChocolateyParseUrl) -> ChocolateyParseUrlEntry {
+        ChocolateyParseUrlEntry {
+            url,
+            archs: vec![],
+            accept_language: None,
+            headers: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -38,9 +896,264 @@ pub struct ChocolateyUpdaterData {
     pub embedded: bool,
     #[cfg_attr(feature = "serialize", serde(default, rename = "type"))]
     pub updater_type: ChocolateyUpdaterType,
-    pub parse_url: Option<ChocolateyParseUrl>,
+    /// The url, or list of urls, that should be parsed to find the links of
+    /// the package.
+    pub parse_url: Option<ChocolateyParseUrls>,
+    /// The credentials to use when requesting the parse url, and when
+    /// downloading any files.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub authentication: Option<Authentication>,
+    /// The user agent to use when requesting the parse url and downloading
+    /// any files, for the few web servers that only serves the expected
+    /// content to browsers.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub user_agent: Option<String>,
+    /// The languages sent in the `Accept-Language` header when requesting the
+    /// parse url and downloading any files (*ie: `en-US,en;q=0.9`*), for web
+    /// servers that serves localized pages with different links. This only
+    /// changes the header of the requests, the links are parsed the same way
+    /// regardless of the language. Overrides the global `--accept-language`
+    /// option, while an `Accept-Language` header in `headers` overrides this
+    /// value.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey]
+    /// parse_url = "https://example.org/downloads"
+    /// accept_language = "en-US,en;q=0.9"
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub accept_language: Option<String>,
+    /// The additional headers to send when requesting the parse url and
+    /// downloading any files. Environment variables can be used in the
+    /// values to avoid storing secrets in the package file.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.headers]
+    /// Accept = "text/html"
+    /// Referer = "https://example.org/"
+    /// X-Api-Key = "${EXAMPLE_API_KEY}"
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub headers: HashMap<String, String>,
+    /// Wether versions marked as pre-releases should be considered when
+    /// finding the latest version. Defaults to `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub allow_prerelease: bool,
+    /// Wether the regular expressions should be matched against the text of
+    /// the links instead of the link target, this is useful for pages where
+    /// the link itself do not contain the version (*ie:
+    /// `Download 64-bit (v3.2.1)`*). Defaults to `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub match_link_text: bool,
+    /// Wether the regexes should be matched against the link target after
+    /// any percent-encoded characters have been decoded, this is useful for
+    /// links that carry an encoded version in the query string (*ie:
+    /// `?ver=3%2E2%2E1`*). Not used when `match_link_text` is enabled.
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub decode_link: bool,
+    /// The elements and attributes of the html pages that links are read
+    /// from in addition to the `href` attribute of anchors, for pages where
+    /// the download links are buttons or media sources. Every source is
+    /// specified as `<element>@<attribute>`. Defaults to only reading
+    /// anchors.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey]
+    /// link_sources = ["button@data-url", "source@src"]
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub link_sources: Vec<ChocolateyLinkSource>,
+    /// The lowest version (*inclusive*) that links are allowed to have to be
+    /// considered when finding the latest version.
+    ///
+    /// When either `min_version` or `max_version` have been specified, links
+    /// where no version could be parsed are ignored.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub min_version: Option<Versions>,
+    /// The version (*exclusive*) that links need to be lower than to be
+    /// considered when finding the latest version, this allows pinning the
+    /// updates below a major version (*ie: `max_version = "4.0"`*).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub max_version: Option<Versions>,
+    /// The element of the html page that contains the version, for pages
+    /// where the version is not part of the download links.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_selector: Option<ChocolateyVersionSelector>,
+    /// The template used to build the version out of the named capture
+    /// groups of the regexes, instead of using the `version` capture group.
+    /// Capture groups are referenced by their name inside braces, and leading
+    /// zeros are removed from numeric values.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey]
+    /// version_template = "{year}.{month}.{day}"
+    ///
+    /// [updater.chocolatey.regexes]
+    /// x64 = '''app-(?P<year>\d{4})_(?P<month>\d{2})_(?P<day>\d{2})\.exe$'''
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_template: Option<String>,
+    /// The maximum number of links on each html page that are processed,
+    /// overriding the limit used for every package. Only needed for the
+    /// rare pages that contain more than 5000 links.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub max_links: Option<usize>,
+    /// Wether the architecture of the links should be detected from their
+    /// file name, instead of requiring a regex for each architecture. When
+    /// enabled, the links matched by regexes that are not keyed by an
+    /// architecture are assigned to an architecture using the
+    /// [DEFAULT_ARCH_PATTERNS], and the link with the highest version is
+    /// used for each of them. Defaults to `false`.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey]
+    /// auto_arch = true
+    ///
+    /// [updater.chocolatey.regexes]
+    /// installer = '''setup-(?P<version>[\d\.]+)[^/]*\.exe$'''
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub auto_arch: bool,
+    /// The patterns that replace the default patterns used to detect the
+    /// architecture of links when `auto_arch` is enabled, keyed by the name
+    /// of the architecture. Architectures without any default patterns are
+    /// tried after the default architectures.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.auto_arch_patterns]
+    /// x86 = ["x86", "win32", "32bit"]
+    /// arm = ["armv7"]
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub auto_arch_patterns: HashMap<String, Vec<String>>,
+    /// The strategy used to select the link of each key of the regexes, when
+    /// the regex of the key matches multiple links. The same aliases as for
+    /// the regexes are accepted, and the link with the highest version is
+    /// selected for keys without a strategy.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub link_selection: HashMap<String, ChocolateyLinkSelection>,
+    /// The type of checksum to calculate for the downloaded files. Defaults
+    /// to `sha256`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub checksum_type: ChocolateyChecksumType,
+    /// The path to the nuspec file that should be updated with the new
+    /// version, relative to the directory of the package file. Defaults to
+    /// `<package id>.nuspec`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub nuspec: Option<PathBuf>,
+    /// The path to the install script that should be updated, relative to the
+    /// directory of the package file. Defaults to
+    /// `tools/chocolateyInstall.ps1`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub install_script: Option<PathBuf>,
+    /// The variables in the install script that should be updated, and which
+    /// value each of them should be updated with. The variables can either be
+    /// normal variable assignments (`$url = '...'`) or entries in a hashtable
+    /// (`url = '...'`).
+    ///
+    /// When no variables are specified, `url`, `checksum`, `url64` and
+    /// `checksum64` will be updated depending on which architecture files was
+    /// found.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.script_variables]
+    /// url = "url32"
+    /// url64bit = "url64"
+    /// urlArm64 = "url_arm64"
+    /// checksum = "checksum32"
+    /// checksum64 = "checksum64"
+    /// checksumArm64 = "checksum_arm64"
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub script_variables: HashMap<String, ChocolateyScriptValue>,
+
+    /// The checksums the downloaded files are expected to have, keyed by the
+    /// name of the architecture. The same aliases as for the regexes are
+    /// accepted.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub expected_checksums: HashMap<String, ChocolateyExpectedChecksum>,
+
+    /// The content types the downloaded files are expected to be served
+    /// with, keyed by the name of the architecture. The same aliases as for
+    /// the regexes are accepted. Any parameters of the content type (*ie:
+    /// `charset`*) are ignored, and `*` can be used to accept any subtype
+    /// (*ie: `application/*`*). The download fails when the server responds
+    /// with a different content type.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.expected_content_types]
+    /// x64 = "application/x-msdownload"
+    /// arm64 = "application/*"
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub expected_content_types: HashMap<String, String>,
+
+    /// Wether downloads should fail when the server responds with a html page
+    /// instead of a file, which usually is an error page or a login page
+    /// served with a successful status. Defaults to `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub reject_html: bool,
+
+    /// The fallback urls that are tried in order when the file of an
+    /// architecture could not be downloaded from the found link, or when the
+    /// downloaded file do not have the expected checksum. The urls are keyed
+    /// by the name of the architecture, using the same aliases as for the
+    /// regexes. `{{url}}`, `{{file_name}}` and `{{version}}` in the urls are
+    /// replaced with the found link, the file name of the found link and the
+    /// found version.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey.mirrors]
+    /// x64 = ["https://mirror.example.org/releases/{{version}}/{{file_name}}"]
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub mirrors: HashMap<String, Vec<String>>,
+
+    /// The settings for creating the `VERIFICATION.txt` file, the file is
+    /// only created when this have been specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub verification: Option<ChocolateyVerification>,
+
+    /// The name of the built-in regex preset (*ie: `sourceforge`*) providing
+    /// the regexes and the parse mode for the links of a vendor, see
+    /// [REGEX_PRESETS]. Regexes specified in `regexes` overrides the regexes
+    /// of the preset for the same architecture.
+    ///
+    /// ## Examples
+    ///
+    /// ```toml
+    /// [updater.chocolatey]
+    /// regex_preset = "github-release"
+    /// parse_url = { owner = "codecov", repo = "codecov-exe" }
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub regex_preset: Option<String>,
 
-    regexes: HashMap<String, String>,
+    /// The regexes used to find the links of each architecture, or any other
+    /// links that should be downloaded. The key is the name of the
+    /// architecture (*ie: `x86`, `x64`, `arm` or `arm64`*), where `arch32`
+    /// and `arch64` can be used as aliases for `x86` and `x64`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    regexes: HashMap<String, ChocolateyRegexes>,
 }
 
 impl ChocolateyUpdaterData {
@@ -49,11 +1162,131 @@ impl ChocolateyUpdaterData {
             embedded: false,
             updater_type: ChocolateyUpdaterType::default(),
             parse_url: None,
+            authentication: None,
+            user_agent: None,
+            accept_language: None,
+            headers: HashMap::new(),
+            allow_prerelease: false,
+            match_link_text: false,
+            decode_link: false,
+            link_sources: vec![],
+            min_version: None,
+            max_version: None,
+            version_selector: None,
+            version_template: None,
+            max_links: None,
+            auto_arch: false,
+            auto_arch_patterns: HashMap::new(),
+            link_selection: HashMap::new(),
+            checksum_type: ChocolateyChecksumType::default(),
+            nuspec: None,
+            install_script: None,
+            script_variables: HashMap::new(),
+            expected_checksums: HashMap::new(),
+            expected_content_types: HashMap::new(),
+            reject_html: false,
+            mirrors: HashMap::new(),
+            verification: None,
+            regex_preset: None,
             regexes: HashMap::new(),
         }
     }
 
-    pub fn regexes(&self) -> &HashMap<String, String> {
+    /// Checks wether the specified version is allowed by the `min_version`
+    /// and `max_version` constraints. When no constraints have been
+    /// specified, every link is allowed, including links without a version.
+    pub fn is_version_allowed(&self, version: Option<&Versions>) -> bool {
+        if self.min_version.is_none() && self.max_version.is_none() {
+            return true;
+        }
+
+        match version {
+            Some(version) => {
+                self.min_version.as_ref().map_or(true, |min| version >= min)
+                    && self.max_version.as_ref().map_or(true, |max| version < max)
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the patterns used to detect the architecture of links when
+    /// `auto_arch` is enabled, in the order they should be tried. The
+    /// [DEFAULT_ARCH_PATTERNS] are used unless they have been overridden by
+    /// `auto_arch_patterns`, followed by any additional architectures sorted
+    /// by their name.
+    pub fn arch_patterns(&self) -> Vec<(String, Vec<String>)> {
+        let mut overrides: Vec<(String, &Vec<String>)> = self
+            .auto_arch_patterns
+            .iter()
+            .map(|(key, patterns)| (architecture_name(key), patterns))
+            .collect();
+        overrides.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let mut patterns: Vec<(String, Vec<String>)> = DEFAULT_ARCH_PATTERNS
+            .iter()
+            .map(|(arch, defaults)| {
+                let values = match overrides.iter().find(|(key, _)| key == arch) {
+                    Some((_, values)) => (*values).clone(),
+                    None => defaults.iter().map(|p| p.to_string()).collect(),
+                };
+                (arch.to_string(), values)
+            })
+            .collect();
+
+        for (arch, values) in overrides {
+            if !patterns.iter().any(|(key, _)| *key == arch) {
+                patterns.push((arch, values.clone()));
+            }
+        }
+
+        patterns
+    }
+
+    /// Gets the strategy used to select the link for the specified key of the
+    /// regexes.
+    pub fn link_selection(&self, key: &str) -> ChocolateyLinkSelection {
+        let arch = architecture_name(key);
+
+        self.link_selection
+            .iter()
+            .find(|(name, _)| architecture_name(name) == arch)
+            .map(|(_, selection)| *selection)
+            .unwrap_or_default()
+    }
+
+    /// Gets the fallback urls of the file for the specified architecture.
+    pub fn mirrors(&self, arch: &str) -> &[String] {
+        let arch = architecture_name(arch);
+
+        self.mirrors
+            .iter()
+            .find(|(key, _)| architecture_name(key) == arch)
+            .map_or(&[], |(_, mirrors)| mirrors.as_slice())
+    }
+
+    /// Gets the checksum that the file downloaded for the specified
+    /// architecture is expected to have, if any.
+    pub fn expected_checksum(&self, arch: &str) -> Option<&ChocolateyExpectedChecksum> {
+        let arch = architecture_name(arch);
+
+        self.expected_checksums
+            .iter()
+            .find(|(key, _)| architecture_name(key) == arch)
+            .map(|(_, checksum)| checksum)
+    }
+
+    /// Gets the content type that the file downloaded for the specified
+    /// architecture is expected to be served with, if any.
+    pub fn expected_content_type(&self, arch: &str) -> Option<&str> {
+        let arch = architecture_name(arch);
+
+        self.expected_content_types
+            .iter()
+            .find(|(key, _)| architecture_name(key) == arch)
+            .map(|(_, content_type)| content_type.as_str())
+    }
+
+    pub fn regexes(&self) -> &HashMap<String, ChocolateyRegexes> {
         &self.regexes
     }
 
@@ -61,13 +1294,49 @@ impl ChocolateyUpdaterData {
         self.regexes.insert(name.into(), value.into());
     }
 
-    pub fn set_regexes(&mut self, values: HashMap<String, String>) {
+    /// Adds multiple regexes for the specified name, that will be tried in
+    /// the specified order until one of them matches.
+    pub fn add_regexes(&mut self, name: &str, values: &[&str]) {
+        let values: Vec<String> = values.iter().map(|v| (*v).into()).collect();
+        self.regexes.insert(name.into(), values.into());
+    }
+
+    pub fn set_regexes(&mut self, values: HashMap<String, ChocolateyRegexes>) {
         self.regexes = values;
     }
+
+    /// Gets the data with the regexes and the parse mode of the selected
+    /// `regex_preset` applied, where the explicitly specified regexes are
+    /// kept for the architectures they are specified for. The data is
+    /// returned unchanged when no preset, or an unknown preset, is selected.
+    pub fn with_regex_preset(&self) -> Cow<ChocolateyUpdaterData> {
+        let preset = match self.regex_preset.as_deref().and_then(regex_preset) {
+            Some(preset) => preset,
+            None => return Cow::Borrowed(self),
+        };
+        let mut data = self.clone();
+
+        for (arch, regexes) in preset.regexes {
+            if !self
+                .regexes
+                .keys()
+                .any(|key| architecture_name(key) == arch)
+            {
+                data.regexes.insert(arch.into(), regexes);
+            }
+        }
+        if preset.decode_link && !self.match_link_text {
+            data.decode_link = true;
+        }
+
+        Cow::Owned(data)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
 
     #[test]
@@ -76,6 +1345,32 @@ mod tests {
             embedded: false,
             updater_type: ChocolateyUpdaterType::default(),
             parse_url: None,
+            authentication: None,
+            user_agent: None,
+            accept_language: None,
+            headers: HashMap::new(),
+            allow_prerelease: false,
+            match_link_text: false,
+            decode_link: false,
+            link_sources: vec![],
+            min_version: None,
+            max_version: None,
+            version_selector: None,
+            version_template: None,
+            max_links: None,
+            auto_arch: false,
+            auto_arch_patterns: HashMap::new(),
+            link_selection: HashMap::new(),
+            checksum_type: ChocolateyChecksumType::default(),
+            nuspec: None,
+            install_script: None,
+            script_variables: HashMap::new(),
+            expected_checksums: HashMap::new(),
+            expected_content_types: HashMap::new(),
+            reject_html: false,
+            mirrors: HashMap::new(),
+            verification: None,
+            regex_preset: None,
             regexes: HashMap::new(),
         };
 
@@ -84,11 +1379,211 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    #[case("arch64")]
+    #[case("x64")]
+    #[case("X64")]
+    fn expected_checksum_should_resolve_architecture_aliases(#[case] arch: &str) {
+        let mut data = ChocolateyUpdaterData::new();
+        let expected = ChocolateyExpectedChecksum::new("ABC", None);
+        data.expected_checksums
+            .insert("arch64".into(), expected.clone());
+
+        assert_eq!(data.expected_checksum(arch), Some(&expected));
+        assert_eq!(data.expected_checksum("x86"), None);
+    }
+
+    #[rstest]
+    #[case("arch32")]
+    #[case("x86")]
+    fn expected_content_type_should_resolve_architecture_aliases(#[case] arch: &str) {
+        let mut data = ChocolateyUpdaterData::new();
+        data.expected_content_types
+            .insert("x86".into(), "application/x-msdownload".into());
+
+        assert_eq!(
+            data.expected_content_type(arch),
+            Some("application/x-msdownload")
+        );
+        assert_eq!(data.expected_content_type("x64"), None);
+    }
+
+    #[rstest]
+    #[case("abc123", true)]
+    #[case(" ABC123 ", true)]
+    #[case("abc124", false)]
+    fn expected_checksum_matches_should_ignore_case(
+        #[case] checksum: &str,
+        #[case] expected: bool,
+    ) {
+        let expected_checksum = ChocolateyExpectedChecksum::new("ABC123", None);
+
+        assert_eq!(expected_checksum.matches(checksum), expected);
+    }
+
+    #[rstest]
+    #[case(None, None, Some("1.0.0"), true)]
+    #[case(None, None, None, true)]
+    #[case(Some("2.0.0"), None, Some("1.9.9"), false)]
+    #[case(Some("2.0.0"), None, Some("2.0.0"), true)]
+    #[case(None, Some("4.0.0"), Some("4.0.0"), false)]
+    #[case(None, Some("4.0.0"), Some("3.9.1"), true)]
+    #[case(Some("2.0.0"), Some("4.0.0"), Some("4.1.0"), false)]
+    #[case(Some("2.0.0"), None, None, false)]
+    fn is_version_allowed_should_apply_constraints(
+        #[case] min_version: Option<&str>,
+        #[case] max_version: Option<&str>,
+        #[case] version: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let mut data = ChocolateyUpdaterData::new();
+        data.min_version = min_version.map(|v| Versions::parse(v).unwrap());
+        data.max_version = max_version.map(|v| Versions::parse(v).unwrap());
+        let version = version.map(|v| Versions::parse(v).unwrap());
+
+        assert_eq!(data.is_version_allowed(version.as_ref()), expected);
+    }
+
+    #[test]
+    fn arch_patterns_should_replace_overridden_defaults() {
+        let mut data = ChocolateyUpdaterData::new();
+        data.auto_arch_patterns
+            .insert("arch32".into(), vec!["win32".into()]);
+        data.auto_arch_patterns
+            .insert("arm".into(), vec!["armv7".into()]);
+
+        let actual = data.arch_patterns();
+        let archs: Vec<&str> = actual.iter().map(|(arch, _)| arch.as_str()).collect();
+
+        assert_eq!(archs, vec!["arm64", "x64", "x86", "arm"]);
+        assert_eq!(actual[2].1, vec!["win32".to_string()]);
+        assert_eq!(
+            actual[0].1,
+            vec!["arm64".to_string(), "aarch64".to_string()]
+        );
+    }
+
+    #[test]
+    fn checksum_type_default_should_be_sha256() {
+        assert_eq!(
+            ChocolateyChecksumType::default(),
+            ChocolateyChecksumType::Sha256
+        );
+    }
+
+    #[rstest]
+    #[case("arch32", "x86")]
+    #[case("arch64", "x64")]
+    #[case("ARM64", "arm64")]
+    #[case(" arm ", "arm")]
+    #[case("x64", "x64")]
+    fn architecture_name_should_return_expected_name(#[case] key: &str, #[case] expected: &str) {
+        assert_eq!(architecture_name(key), expected);
+    }
+
+    #[rstest]
+    #[case("url32", ChocolateyScriptValue::Url("x86".into()))]
+    #[case("url64", ChocolateyScriptValue::Url("x64".into()))]
+    #[case("checksum32", ChocolateyScriptValue::Checksum("x86".into()))]
+    #[case("Checksum64", ChocolateyScriptValue::Checksum("x64".into()))]
+    #[case("url_arm64", ChocolateyScriptValue::Url("arm64".into()))]
+    #[case("checksum_arch32", ChocolateyScriptValue::Checksum("x86".into()))]
+    #[case("checksumtype", ChocolateyScriptValue::ChecksumType)]
+    #[case("version", ChocolateyScriptValue::Version)]
+    fn script_value_from_str_should_create_expected_value(
+        #[case] value: &str,
+        #[case] expected: ChocolateyScriptValue,
+    ) {
+        assert_eq!(value.parse(), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("url_")]
+    #[case("unknown")]
+    fn script_value_from_str_should_error_on_unknown_value(#[case] value: &str) {
+        assert!(value.parse::<ChocolateyScriptValue>().is_err());
+    }
+
+    #[rstest]
+    #[case("first", ChocolateyLinkSelection::First)]
+    #[case("Last", ChocolateyLinkSelection::Last)]
+    #[case("highest-version", ChocolateyLinkSelection::HighestVersion)]
+    #[case("index 2", ChocolateyLinkSelection::Index(2))]
+    fn link_selection_should_parse_strategies(
+        #[case] value: &str,
+        #[case] expected: ChocolateyLinkSelection,
+    ) {
+        let actual: ChocolateyLinkSelection = value.parse().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn link_source_should_parse_element_and_attribute() {
+        let actual: ChocolateyLinkSource = "Button@data-url".parse().unwrap();
+
+        assert_eq!(
+            actual,
+            ChocolateyLinkSource {
+                element: "button".into(),
+                attribute: "data-url".into()
+            }
+        );
+        assert_eq!(actual.to_string(), "button@data-url");
+    }
+
+    #[rstest]
+    #[case("button")]
+    #[case("@src")]
+    #[case("source@")]
+    fn link_source_should_reject_missing_parts(#[case] value: &str) {
+        assert!(value.parse::<ChocolateyLinkSource>().is_err());
+    }
+
+    #[rstest]
+    #[case("index 0")]
+    #[case("index")]
+    #[case("second")]
+    fn link_selection_should_reject_unsupported_strategies(#[case] value: &str) {
+        assert!(value.parse::<ChocolateyLinkSelection>().is_err());
+    }
+
+    #[test]
+    fn mirrors_should_resolve_architecture_aliases() {
+        let mut data = ChocolateyUpdaterData::new();
+        data.mirrors.insert(
+            "arch64".into(),
+            vec!["https://mirror.example.org/{{file_name}}".into()],
+        );
+
+        assert_eq!(
+            data.mirrors("x64"),
+            ["https://mirror.example.org/{{file_name}}"]
+        );
+        assert!(data.mirrors("x86").is_empty());
+    }
+
+    #[test]
+    fn link_selection_should_resolve_architecture_aliases() {
+        let mut data = ChocolateyUpdaterData::new();
+        data.link_selection
+            .insert("x64".into(), ChocolateyLinkSelection::Last);
+
+        assert_eq!(data.link_selection("arch64"), ChocolateyLinkSelection::Last);
+        assert_eq!(
+            data.link_selection("arch32"),
+            ChocolateyLinkSelection::HighestVersion
+        );
+    }
+
     #[test]
     fn set_regexes_should_set_expected_values() {
         let mut expected = HashMap::new();
-        expected.insert("arch32".to_string(), "test-regex-1".to_string());
-        expected.insert("arch64".to_string(), "test-regex-2".to_string());
+        expected.insert("arch32".to_string(), "test-regex-1".into());
+        expected.insert(
+            "arch64".to_string(),
+            vec!["test-regex-2".to_string(), "test-regex-3".to_string()].into(),
+        );
 
         let mut data = ChocolateyUpdaterData::new();
         data.set_regexes(expected.clone());
@@ -99,11 +1594,79 @@ mod tests {
     #[test]
     fn add_regex_should_include_new_regex() {
         let mut expected = HashMap::new();
-        expected.insert("some".to_string(), "test-addition-regex".to_string());
+        expected.insert("some".to_string(), "test-addition-regex".into());
 
         let mut data = ChocolateyUpdaterData::new();
         data.add_regex("some", "test-addition-regex");
 
         assert_eq!(data.regexes(), &expected);
     }
+
+    #[test]
+    fn add_regexes_should_include_all_regexes_in_order() {
+        let mut data = ChocolateyUpdaterData::new();
+        data.add_regexes("x64", &["first", "second"]);
+
+        assert_eq!(
+            data.regexes()["x64"].as_slice(),
+            &["first".to_string(), "second".to_string()]
+        );
+    }
+
+    #[test]
+    fn as_slice_should_return_single_regex() {
+        let regexes = ChocolateyRegexes::from("single");
+
+        assert_eq!(regexes.as_slice(), &["single".to_string()]);
+    }
+
+    #[rstest]
+    #[case("fosshub", "fosshub", false)]
+    #[case("GitHub-Release", "github-release", false)]
+    #[case(" sourceforge ", "sourceforge", true)]
+    fn regex_preset_should_get_preset_ignoring_case(
+        #[case] name: &str,
+        #[case] expected: &str,
+        #[case] decode_link: bool,
+    ) {
+        let preset = regex_preset(name).unwrap();
+
+        assert_eq!(preset.name, expected);
+        assert_eq!(preset.decode_link, decode_link);
+        assert_eq!(
+            preset
+                .regexes
+                .iter()
+                .map(|(arch, _)| *arch)
+                .collect::<Vec<_>>(),
+            ["x64", "x86"]
+        );
+    }
+
+    #[test]
+    fn with_regex_preset_should_keep_explicit_regexes() {
+        let mut data = ChocolateyUpdaterData::new();
+        data.regex_preset = Some("sourceforge".into());
+        data.add_regex("arch64", "explicit");
+
+        let actual = data.with_regex_preset();
+
+        assert_eq!(
+            actual.regexes()["arch64"],
+            ChocolateyRegexes::from("explicit")
+        );
+        assert!(!actual.regexes().contains_key("x64"));
+        assert!(actual.regexes().contains_key("x86"));
+        assert!(actual.decode_link);
+    }
+
+    #[test]
+    fn with_regex_preset_should_return_unchanged_data_on_unknown_preset() {
+        let mut data = ChocolateyUpdaterData::new();
+        data.regex_preset = Some("unknown".into());
+
+        let actual = data.with_regex_preset();
+
+        assert!(matches!(actual, Cow::Borrowed(_)));
+    }
 }
@@ -0,0 +1,233 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+#![cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use aer_version::Versions;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::updater::chocolatey::{
+    ChocolateyChecksumType, ChocolateyExpectedChecksum, ChocolateyLinkSelection,
+    ChocolateyLinkSource, ChocolateyParseUrls, ChocolateyRegexes, ChocolateyUpdaterData,
+    ChocolateyVersionSelector,
+};
+use crate::updater::Authentication;
+
+/// The data used when updating a Scoop manifest. The links of the manifest
+/// are found in the same way as for the chocolatey updater, and the version,
+/// urls and hashes of the found files are written to the manifest.
+///
+/// ## Examples
+///
+/// ```toml
+/// [updater.scoop]
+/// parse_url = "https://example.org/downloads"
+/// manifest = "bucket/test-package.json"
+///
+/// [updater.scoop.regexes]
+/// x64 = '''test-package-(?P<version>[\d\.]+)-x64\.zip$'''
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub struct ScoopUpdaterData {
+    /// The url, or list of urls, that should be parsed to find the links of
+    /// the manifest.
+    pub parse_url: Option<ChocolateyParseUrls>,
+    /// The credentials to use when requesting the parse url, and when
+    /// downloading any files.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub authentication: Option<Authentication>,
+    /// The user agent to use when requesting the parse url and downloading
+    /// any files.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub user_agent: Option<String>,
+    /// The languages sent in the `Accept-Language` header when requesting the
+    /// parse url and downloading any files, this only changes the header of
+    /// the requests and not how the links are parsed.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub accept_language: Option<String>,
+    /// The additional headers to send when requesting the parse url and
+    /// downloading any files.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub headers: HashMap<String, String>,
+    /// Wether versions marked as pre-releases should be considered when
+    /// finding the latest version. Defaults to `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub allow_prerelease: bool,
+    /// Wether the regular expressions should be matched against the text of
+    /// the links instead of the link target. Defaults to `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub match_link_text: bool,
+    /// Wether the regexes should be matched against the link target after
+    /// any percent-encoded characters have been decoded. Defaults to
+    /// `false`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub decode_link: bool,
+    /// The elements and attributes of the html pages that links are read
+    /// from in addition to anchors, in the same way as for the chocolatey
+    /// updater.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub link_sources: Vec<ChocolateyLinkSource>,
+    /// The lowest version (*inclusive*) that links are allowed to have to be
+    /// considered when finding the latest version.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub min_version: Option<Versions>,
+    /// The version (*exclusive*) that links need to be lower than to be
+    /// considered when finding the latest version.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub max_version: Option<Versions>,
+    /// The element of the html page that contains the version, for pages
+    /// where the version is not part of the download links.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_selector: Option<ChocolateyVersionSelector>,
+    /// The template used to build the version out of the named capture
+    /// groups of the regexes, in the same way as for the chocolatey updater.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_template: Option<String>,
+    /// The maximum number of links on each html page that are processed,
+    /// overriding the limit used for every package.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub max_links: Option<usize>,
+    /// Wether the architecture of the links should be detected from their
+    /// file name, in the same way as for the chocolatey updater. Links where
+    /// no architecture could be detected can not be written to the manifest.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub auto_arch: bool,
+    /// The patterns that replace the default patterns used to detect the
+    /// architecture of links when `auto_arch` is enabled.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub auto_arch_patterns: HashMap<String, Vec<String>>,
+    /// The strategy used to select the link of each key of the regexes, in
+    /// the same way as for the chocolatey updater.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub link_selection: HashMap<String, ChocolateyLinkSelection>,
+    /// The path to the manifest that should be updated, relative to the
+    /// directory of the package file. Defaults to `<package id>.json`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub manifest: Option<PathBuf>,
+    /// The sha256 checksums the downloaded files are expected to have, keyed
+    /// by the name of the architecture.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub expected_checksums: HashMap<String, ChocolateyExpectedChecksum>,
+    /// The content types the downloaded files are expected to be served
+    /// with, keyed by the name of the architecture.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub expected_content_types: HashMap<String, String>,
+    /// Wether downloads should fail when the server responds with a html page
+    /// instead of a file.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub reject_html: bool,
+    /// The fallback urls of the files of each architecture, in the same way
+    /// as for the chocolatey updater.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub mirrors: HashMap<String, Vec<String>>,
+
+    /// The regexes used to find the links of each architecture, using the
+    /// same names and aliases as the chocolatey updater.
+    regexes: HashMap<String, ChocolateyRegexes>,
+}
+
+impl ScoopUpdaterData {
+    pub fn new() -> ScoopUpdaterData {
+        ScoopUpdaterData {
+            parse_url: None,
+            authentication: None,
+            user_agent: None,
+            accept_language: None,
+            headers: HashMap::new(),
+            allow_prerelease: false,
+            match_link_text: false,
+            decode_link: false,
+            link_sources: vec![],
+            min_version: None,
+            max_version: None,
+            version_selector: None,
+            version_template: None,
+            max_links: None,
+            auto_arch: false,
+            auto_arch_patterns: HashMap::new(),
+            link_selection: HashMap::new(),
+            manifest: None,
+            expected_checksums: HashMap::new(),
+            expected_content_types: HashMap::new(),
+            reject_html: false,
+            mirrors: HashMap::new(),
+            regexes: HashMap::new(),
+        }
+    }
+
+    pub fn regexes(&self) -> &HashMap<String, ChocolateyRegexes> {
+        &self.regexes
+    }
+
+    pub fn add_regex(&mut self, name: &str, value: &str) {
+        self.regexes.insert(name.into(), value.into());
+    }
+
+    pub fn set_regexes(&mut self, values: HashMap<String, ChocolateyRegexes>) {
+        self.regexes = values;
+    }
+
+    /// Returns the settings used to find the links of the manifest as
+    /// chocolatey updater data, to allow the selection of links and versions
+    /// to be shared between the updaters. Scoop manifests always use `sha256`
+    /// checksums.
+    pub fn link_data(&self) -> ChocolateyUpdaterData {
+        ChocolateyUpdaterData {
+            parse_url: self.parse_url.clone(),
+            authentication: self.authentication.clone(),
+            user_agent: self.user_agent.clone(),
+            accept_language: self.accept_language.clone(),
+            headers: self.headers.clone(),
+            allow_prerelease: self.allow_prerelease,
+            match_link_text: self.match_link_text,
+            decode_link: self.decode_link,
+            link_sources: self.link_sources.clone(),
+            min_version: self.min_version.clone(),
+            max_version: self.max_version.clone(),
+            version_selector: self.version_selector.clone(),
+            version_template: self.version_template.clone(),
+            max_links: self.max_links,
+            auto_arch: self.auto_arch,
+            auto_arch_patterns: self.auto_arch_patterns.clone(),
+            link_selection: self.link_selection.clone(),
+            checksum_type: ChocolateyChecksumType::Sha256,
+            expected_checksums: self.expected_checksums.clone(),
+            expected_content_types: self.expected_content_types.clone(),
+            reject_html: self.reject_html,
+            mirrors: self.mirrors.clone(),
+            regexes: self.regexes.clone(),
+            ..ChocolateyUpdaterData::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::updater::chocolatey::ChocolateyParseUrl;
+
+    #[test]
+    fn link_data_should_copy_link_settings() {
+        let mut data = ScoopUpdaterData::new();
+        data.parse_url =
+            Some(ChocolateyParseUrl::Url(url::Url::parse("https://example.org").unwrap()).into());
+        data.allow_prerelease = true;
+        data.accept_language = Some("en-US".into());
+        data.add_regex("arch64", "x64\\.zip$");
+
+        let actual = data.link_data();
+
+        assert_eq!(actual.parse_url, data.parse_url);
+        assert!(actual.allow_prerelease);
+        assert_eq!(actual.accept_language.as_deref(), Some("en-US"));
+        assert_eq!(actual.regexes(), data.regexes());
+        assert_eq!(actual.checksum_type, ChocolateyChecksumType::Sha256);
+        assert_eq!(actual.nuspec, None);
+    }
+}
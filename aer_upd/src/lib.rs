@@ -14,13 +14,19 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub mod parsers;
+pub mod regexes;
 pub mod runners;
+pub mod updaters;
+pub mod validators;
 
 pub mod data {
     pub use aer_data::prelude::*;
 }
 
 pub mod web {
-    pub use aer_web::response::ResponseType;
-    pub use aer_web::{errors, LinkElement, LinkType, WebRequest, WebResponse};
+    pub use aer_web::response::{part_file_path, HtmlResponse, ResponseType};
+    pub use aer_web::{
+        dedup_links, errors, github, partial, HostOverride, LinkElement, LinkSource, LinkType,
+        MatchTarget, TlsVersion, VersionSelector, WebRequest, WebRequestBuilder, WebResponse,
+    };
 }
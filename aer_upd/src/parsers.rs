@@ -1,16 +1,21 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+use std::fmt::{self, Display};
 use std::fs::File;
 use std::io::{BufReader, Error as IoError, ErrorKind, Read};
 use std::path::Path;
+use std::str::FromStr;
 
 use aer_data::prelude::*;
 use log::warn;
 
+pub mod env;
 pub mod errors;
 #[cfg(feature = "toml_data")]
 pub mod toml;
+#[cfg(feature = "yaml_data")]
+pub mod yaml;
 
 /// Parsers implementing this trait are able to read and transform a specific
 /// structure to the [PackageData] type.
@@ -19,49 +24,101 @@ pub trait DataReader {
     /// file (usually by file extension).
     fn can_handle_file(&self, path: &Path) -> bool;
 
+    /// Function to decide if the implemented structure can handle the
+    /// specified content. This is used when the type of a file can not be
+    /// decided by its path (*ie: when the file do not have an extension*).
+    fn can_handle_content(&self, _content: &str) -> bool {
+        false
+    }
+
     /// Read and Deserialize the specified file, calling the implemented
     /// structure that handle the Deserialization.
     fn read_file(&self, path: &Path) -> Result<PackageData, errors::ParserError> {
-        if !self.can_handle_file(path) {
-            let error = IoError::new(
-                ErrorKind::InvalidData,
-                format!("The file '{}' is not a supported type.", path.display()),
-            );
-            warn!("{}", error);
-            return Err(errors::ParserError::Loading(error));
-        }
+        let mut buffer = open_file(self, path)?;
 
-        if !path.exists() {
-            let error = IoError::new(
-                ErrorKind::NotFound,
-                format!("The file '{}' could not be found!", path.display()),
-            );
-            warn!("{}", error);
-            return Err(errors::ParserError::Loading(error));
-        }
+        self.read_data(&mut buffer).map_err(|err| err.with_path(path))
+    }
 
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(error) => return Err(errors::ParserError::Loading(error)),
-        };
-        let mut buffer = BufReader::new(file);
+    /// Read and Deserialize every package definition in the specified file,
+    /// in the same way as [read_file](DataReader::read_file).
+    fn read_file_packages(&self, path: &Path) -> Result<Vec<PackageData>, errors::ParserError> {
+        let mut buffer = open_file(self, path)?;
 
-        self.read_data(&mut buffer)
+        self.read_packages_data(&mut buffer)
+            .map_err(|err| err.with_path(path))
     }
 
     /// Read the specifed buffer and return either the parsed package data, or
     /// an error if one occurs.
     fn read_data<T: Read>(&self, reader: &mut T) -> Result<PackageData, errors::ParserError>;
+
+    /// Read the specified buffer and return every package definition in the
+    /// buffer. Parsers supporting multiple package definitions in a single
+    /// document override this, by default the buffer is read as a single
+    /// package definition.
+    fn read_packages_data<T: Read>(
+        &self,
+        reader: &mut T,
+    ) -> Result<Vec<PackageData>, errors::ParserError> {
+        self.read_data(reader).map(|data| vec![data])
+    }
 }
 
-#[cfg(any(feature = "toml_data"))]
-#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data"))))]
+/// Opens the specified file for reading, if the file exists and is supported
+/// by the reader.
+fn open_file<R: DataReader + ?Sized>(
+    reader: &R,
+    path: &Path,
+) -> Result<BufReader<File>, errors::ParserError> {
+    if !reader.can_handle_file(path) {
+        let error = IoError::new(
+            ErrorKind::InvalidData,
+            format!("The file '{}' is not a supported type.", path.display()),
+        );
+        warn!("{}", error);
+        return Err(errors::ParserError::Loading(error));
+    }
+
+    if !path.exists() {
+        let error = IoError::new(
+            ErrorKind::NotFound,
+            format!("The file '{}' could not be found!", path.display()),
+        );
+        warn!("{}", error);
+        return Err(errors::ParserError::Loading(error));
+    }
+
+    match File::open(path) {
+        Ok(f) => Ok(BufReader::new(f)),
+        Err(error) => Err(errors::ParserError::Loading(error)),
+    }
+}
+
+/// Reads all of the text in the specified reader.
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+fn read_text<T: Read>(reader: &mut T) -> Result<String, errors::ParserError> {
+    let mut text = String::new();
+
+    match reader.read_to_string(&mut text) {
+        Err(err) => {
+            log::error!("Failed to read data: {:?}", err);
+            Err(errors::ParserError::Loading(err))
+        }
+        Ok(size) => {
+            log::debug!("Read {} bytes!", size);
+            Ok(text)
+        }
+    }
+}
+
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
 macro_rules! call_parsers {
-    ($path:ident,$($parser:expr=>$feature:literal),+) => {
+    ($method:ident,$path:ident,$($parser:expr=>$feature:literal),+) => {
         $(
             #[cfg(feature = $feature)]
             {
-                let data = $parser.read_file($path);
+                let data = $parser.$method($path);
                 if let Ok(data) = data {
                     return Ok(data);
                 } else if $parser.can_handle_file($path) {
@@ -72,10 +129,306 @@ macro_rules! call_parsers {
     };
 }
 
-#[cfg(any(feature = "toml_data"))]
-#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data"))))]
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
+macro_rules! call_content_parsers {
+    ($method:ident,$path:ident,$content:ident,$($parser:expr=>$feature:literal),+) => {
+        $(
+            #[cfg(feature = $feature)]
+            {
+                if $parser.can_handle_content(&$content) {
+                    return $parser
+                        .$method(&mut $content.as_bytes())
+                        .map_err(|err| err.with_path($path));
+                }
+            }
+        )*
+    };
+}
+
+/// Reads the package data in the specified file, using the first parser that
+/// can handle the file. When the file do not have an extension, the content of
+/// the file will be used to decide which parser to use instead.
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
 pub fn read_file(path: &Path) -> Result<PackageData, errors::ParserError> {
-    call_parsers!(path, toml::TomlParser => "toml_data");
+    call_parsers!(read_file, path, toml::TomlParser => "toml_data", yaml::YamlParser => "yaml_data");
+
+    if path.extension().is_none() && path.is_file() {
+        let content = std::fs::read_to_string(path).map_err(errors::ParserError::Loading)?;
+        call_content_parsers!(
+            read_data,
+            path,
+            content,
+            toml::TomlParser => "toml_data",
+            yaml::YamlParser => "yaml_data"
+        );
+    }
+
+    Err(errors::ParserError::NoParsers(path.to_owned()))
+}
+
+/// Reads every package definition in the specified file, in the same way as
+/// [read_file]. Files can contain multiple package definitions as a list of
+/// `packages`, where the values in the `defaults` of the file are shared by
+/// every package definition. Files containing a single package definition
+/// returns that definition.
+///
+/// ## Examples
+///
+/// ```toml
+/// [defaults.metadata]
+/// project_url = "https://example.org"
+///
+/// [[packages]]
+/// metadata = { id = "example-cli", summary = "The command line tools" }
+///
+/// [[packages]]
+/// metadata = { id = "example-gui", summary = "The graphical user interface" }
+/// ```
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
+pub fn read_packages(path: &Path) -> Result<Vec<PackageData>, errors::ParserError> {
+    call_parsers!(
+        read_file_packages,
+        path,
+        toml::TomlParser => "toml_data",
+        yaml::YamlParser => "yaml_data"
+    );
+
+    if path.extension().is_none() && path.is_file() {
+        let content = std::fs::read_to_string(path).map_err(errors::ParserError::Loading)?;
+        call_content_parsers!(
+            read_packages_data,
+            path,
+            content,
+            toml::TomlParser => "toml_data",
+            yaml::YamlParser => "yaml_data"
+        );
+    }
 
     Err(errors::ParserError::NoParsers(path.to_owned()))
 }
+
+/// The formats that package data can be read as, used when the format can not
+/// be decided from the path of a file (*ie: when reading from stdin*).
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataFormat {
+    /// The package data is a `TOML` document.
+    #[cfg(feature = "toml_data")]
+    Toml,
+    /// The package data is a `YAML` document.
+    #[cfg(feature = "yaml_data")]
+    Yaml,
+}
+
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+impl FromStr for DataFormat {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            #[cfg(feature = "toml_data")]
+            "toml" => Ok(DataFormat::Toml),
+            #[cfg(feature = "yaml_data")]
+            "yaml" | "yml" => Ok(DataFormat::Yaml),
+            _ => Err("The value is not a supported data format!"),
+        }
+    }
+}
+
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+impl Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "toml_data")]
+            DataFormat::Toml => f.write_str("toml"),
+            #[cfg(feature = "yaml_data")]
+            DataFormat::Yaml => f.write_str("yaml"),
+        }
+    }
+}
+
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+impl DataFormat {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &[
+            #[cfg(feature = "toml_data")]
+            "toml",
+            #[cfg(feature = "yaml_data")]
+            "yaml",
+        ];
+
+        VARIANTS
+    }
+}
+
+/// Reads the package data in the specified content, using the parser of the
+/// specified format. When no format is specified, the content is used to
+/// decide which parser to use in the same way as for files without an
+/// extension. The `name` is only used to identify the content in errors
+/// (*ie: `<stdin>`*).
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
+pub fn read_content(
+    name: &Path,
+    content: &str,
+    format: Option<DataFormat>,
+) -> Result<PackageData, errors::ParserError> {
+    let data = match format {
+        #[cfg(feature = "toml_data")]
+        Some(DataFormat::Toml) => toml::TomlParser.read_data(&mut content.as_bytes()),
+        #[cfg(feature = "yaml_data")]
+        Some(DataFormat::Yaml) => yaml::YamlParser.read_data(&mut content.as_bytes()),
+        None => {
+            call_content_parsers!(
+                read_data,
+                name,
+                content,
+                toml::TomlParser => "toml_data",
+                yaml::YamlParser => "yaml_data"
+            );
+
+            return Err(errors::ParserError::NoParsers(name.to_owned()));
+        }
+    };
+
+    data.map_err(|err| err.with_path(name))
+}
+
+/// Reads every package definition in the specified content, in the same way
+/// as [read_content] and [read_packages].
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
+pub fn read_content_packages(
+    name: &Path,
+    content: &str,
+    format: Option<DataFormat>,
+) -> Result<Vec<PackageData>, errors::ParserError> {
+    let data = match format {
+        #[cfg(feature = "toml_data")]
+        Some(DataFormat::Toml) => toml::TomlParser.read_packages_data(&mut content.as_bytes()),
+        #[cfg(feature = "yaml_data")]
+        Some(DataFormat::Yaml) => yaml::YamlParser.read_packages_data(&mut content.as_bytes()),
+        None => {
+            call_content_parsers!(
+                read_packages_data,
+                name,
+                content,
+                toml::TomlParser => "toml_data",
+                yaml::YamlParser => "yaml_data"
+            );
+
+            return Err(errors::ParserError::NoParsers(name.to_owned()));
+        }
+    };
+
+    data.map_err(|err| err.with_path(name))
+}
+
+/// Checks wether any of the available parsers can handle the specified file,
+/// based on the path of the file (*usually the file extension*).
+#[cfg(any(feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data", feature = "yaml_data"))))]
+pub fn is_supported_file(path: &Path) -> bool {
+    #[cfg(feature = "toml_data")]
+    {
+        if toml::TomlParser.can_handle_file(path) {
+            return true;
+        }
+    }
+    #[cfg(feature = "yaml_data")]
+    {
+        if yaml::YamlParser.can_handle_file(path) {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(all(test, feature = "toml_data"))]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn read_file_should_detect_toml_content_without_extension() {
+        let path = PathBuf::from("test-data/no-extension-package");
+
+        let actual = read_file(&path).unwrap();
+
+        assert_eq!(actual.metadata().id(), "test-package");
+    }
+
+    #[test]
+    fn read_file_should_include_path_and_location_in_error() {
+        let path = PathBuf::from("test-data/invalid-data.aer.toml");
+
+        let actual = read_file(&path).unwrap_err().to_string();
+
+        assert!(actual.starts_with("Unable to deserialize 'test-data/invalid-data.aer.toml': "));
+        assert!(actual.ends_with(" at line 3 column 11"));
+    }
+
+    #[cfg(feature = "yaml_data")]
+    #[test]
+    fn read_file_should_read_yaml_files() {
+        let path = PathBuf::from("test-data/basic-metadata.aer.yaml");
+
+        let actual = read_file(&path).unwrap();
+
+        assert_eq!(actual.metadata().id(), "test-package");
+    }
+
+    #[cfg(feature = "yaml_data")]
+    #[test]
+    fn is_supported_file_should_accept_yaml_files() {
+        assert!(is_supported_file(Path::new("packages/test.aer.yaml")));
+        assert!(is_supported_file(Path::new("packages/test.aer.yml")));
+    }
+
+    #[test]
+    fn read_content_should_use_specified_format() {
+        let content = std::fs::read_to_string("test-data/no-extension-package").unwrap();
+
+        let actual = read_content(Path::new("<stdin>"), &content, Some(DataFormat::Toml)).unwrap();
+
+        assert_eq!(actual.metadata().id(), "test-package");
+    }
+
+    #[test]
+    fn read_content_should_detect_format_of_content() {
+        let content = std::fs::read_to_string("test-data/no-extension-package").unwrap();
+
+        let actual = read_content(Path::new("<stdin>"), &content, None).unwrap();
+
+        assert_eq!(actual.metadata().id(), "test-package");
+    }
+
+    #[test]
+    fn read_content_should_include_name_in_error() {
+        let actual = read_content(Path::new("<stdin>"), "[metadata\n", Some(DataFormat::Toml))
+            .unwrap_err()
+            .to_string();
+
+        assert!(actual.starts_with("Unable to deserialize '<stdin>': "));
+    }
+
+    #[rstest::rstest]
+    #[case("toml", DataFormat::Toml)]
+    #[case(" TOML ", DataFormat::Toml)]
+    #[cfg_attr(feature = "yaml_data", case("yml", DataFormat::Yaml))]
+    fn data_format_should_parse_supported_values(#[case] value: &str, #[case] expected: DataFormat) {
+        assert_eq!(value.parse::<DataFormat>(), Ok(expected));
+    }
+
+    #[test]
+    fn is_supported_file_should_accept_toml_files() {
+        assert!(is_supported_file(Path::new("packages/test.aer.toml")));
+        assert!(!is_supported_file(Path::new("packages/README.md")));
+    }
+}
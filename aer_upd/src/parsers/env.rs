@@ -0,0 +1,105 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for substituting environment variables in the string
+//! values of package data files, to allow secrets and environment specific
+//! values (*like the base url of a server*) to be kept out of the files.
+//!
+//! Variables are specified as `${NAME}`, or as `${NAME:-default}` when a
+//! default value should be used if the variable is not defined.
+//!
+//! ## Examples
+//!
+//! ```toml
+//! [updater.chocolatey]
+//! parse_url = "${DOWNLOAD_SERVER:-https://example.org}/downloads"
+//! ```
+
+/// Replaces all environment variables in the specified value with the value
+/// of the variable in the current environment.
+///
+/// The name of the variable is returned as the error when a variable is not
+/// defined, and no default value have been specified.
+pub fn substitute(value: &str) -> Result<String, String> {
+    substitute_with(value, |name| std::env::var(name).ok())
+}
+
+/// Replaces all variables in the specified value with the value returned by
+/// the `lookup` function.
+///
+/// The name of the variable is returned as the error when the `lookup`
+/// function do not return a value, and no default value have been
+/// specified.
+pub fn substitute_with<F>(value: &str, lookup: F) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+
+        let variable = &rest[start + 2..end];
+        let (name, default) = match variable.find(":-") {
+            Some(index) => (&variable[..index], Some(&variable[index + 2..])),
+            None => (variable, None),
+        };
+
+        match (lookup(name), default) {
+            (Some(value), _) => result.push_str(&value),
+            (None, Some(default)) => result.push_str(default),
+            (None, None) => return Err(name.to_owned()),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "SERVER" => Some("https://example.org".into()),
+            "TOKEN" => Some("secret".into()),
+            _ => None,
+        }
+    }
+
+    #[rstest]
+    #[case("https://example.org/downloads", "https://example.org/downloads")]
+    #[case("${SERVER}/downloads", "https://example.org/downloads")]
+    #[case("${SERVER}/${TOKEN}", "https://example.org/secret")]
+    #[case("${MISSING:-https://test.com}/downloads", "https://test.com/downloads")]
+    #[case(
+        "${SERVER:-https://test.com}/downloads",
+        "https://example.org/downloads"
+    )]
+    #[case("${MISSING:-}", "")]
+    #[case(r"-(?P<version>[\d\.]+)\.exe$", r"-(?P<version>[\d\.]+)\.exe$")]
+    #[case("unclosed ${SERVER", "unclosed ${SERVER")]
+    fn substitute_with_should_replace_variables(#[case] value: &str, #[case] expected: &str) {
+        let actual = substitute_with(value, lookup);
+
+        assert_eq!(actual, Ok(expected.to_owned()));
+    }
+
+    #[test]
+    fn substitute_with_should_return_name_of_undefined_variable() {
+        let actual = substitute_with("${SERVER}/${MISSING}", lookup);
+
+        assert_eq!(actual, Err("MISSING".to_owned()));
+    }
+}
@@ -3,13 +3,29 @@
 
 use std::error::Error;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub enum ParserError {
     NoParsers(PathBuf),
     Loading(std::io::Error),
     Deserialize(String),
+    /// The data could not be deserialized because of an error at a specific
+    /// location. The path is only available when the data was read from a
+    /// file.
+    InvalidData {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// An environment variable used in the data is not defined, and no
+    /// default value was specified for the variable. The path is only
+    /// available when the data was read from a file.
+    UndefinedVariable {
+        path: Option<PathBuf>,
+        name: String,
+    },
     Other { inner: Box<dyn Error> },
 }
 
@@ -18,6 +34,24 @@ impl fmt::Display for ParserError {
         match self {
             ParserError::Loading(err) => err.fmt(f),
             ParserError::Deserialize(s) => s.fmt(f),
+            ParserError::InvalidData {
+                path: Some(path),
+                message,
+                ..
+            } => write!(f, "Unable to deserialize '{}': {}", path.display(), message),
+            ParserError::InvalidData { message, .. } => message.fmt(f),
+            ParserError::UndefinedVariable {
+                path: Some(path),
+                name,
+            } => write!(
+                f,
+                "The environment variable '{}' used in '{}' is not defined!",
+                name,
+                path.display()
+            ),
+            ParserError::UndefinedVariable { name, .. } => {
+                write!(f, "The environment variable '{}' is not defined!", name)
+            }
             ParserError::Other { inner } => inner.fmt(f),
             ParserError::NoParsers(path) => {
                 write!(
@@ -30,6 +64,31 @@ impl fmt::Display for ParserError {
     }
 }
 
+impl ParserError {
+    /// Sets the path of the file the error occurred in, if the error is
+    /// related to the content of that file.
+    pub fn with_path(self, path: &Path) -> ParserError {
+        match self {
+            ParserError::InvalidData {
+                line,
+                column,
+                message,
+                ..
+            } => ParserError::InvalidData {
+                path: Some(path.to_owned()),
+                line,
+                column,
+                message,
+            },
+            ParserError::UndefinedVariable { name, .. } => ParserError::UndefinedVariable {
+                path: Some(path.to_owned()),
+                name,
+            },
+            err => err,
+        }
+    }
+}
+
 impl Error for ParserError {}
 
 impl PartialEq for ParserError {
@@ -47,6 +106,32 @@ impl PartialEq for ParserError {
             (ParserError::NoParsers(path), ParserError::NoParsers(other_path)) => {
                 path.eq(other_path)
             }
+            (
+                ParserError::InvalidData {
+                    path,
+                    line,
+                    column,
+                    message,
+                },
+                ParserError::InvalidData {
+                    path: other_path,
+                    line: other_line,
+                    column: other_column,
+                    message: other_message,
+                },
+            ) => {
+                path.eq(other_path)
+                    && line.eq(other_line)
+                    && column.eq(other_column)
+                    && message.eq(other_message)
+            }
+            (
+                ParserError::UndefinedVariable { path, name },
+                ParserError::UndefinedVariable {
+                    path: other_path,
+                    name: other_name,
+                },
+            ) => path.eq(other_path) && name.eq(other_name),
             _ => false,
         }
     }
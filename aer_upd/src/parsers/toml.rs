@@ -9,7 +9,7 @@ use std::path::Path;
 use aer_data::PackageData;
 use log::{debug, error};
 
-use crate::parsers::{errors, DataReader};
+use crate::parsers::{env, errors, read_text, DataReader};
 
 pub struct TomlParser;
 
@@ -18,11 +18,15 @@ pub struct TomlParser;
 /// See enhancement issue: #1
 impl DataReader for TomlParser {
     fn can_handle_file(&self, path: &Path) -> bool {
-        if let Some(path) = path.to_str() {
-            path.ends_with(".aer.toml")
-        } else {
-            false
-        }
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| ext.eq_ignore_ascii_case("toml"))
+    }
+
+    /// Checks if the content is a valid `TOML` document, this does not check
+    /// if the document contains valid package data.
+    fn can_handle_content(&self, content: &str) -> bool {
+        content.parse::<toml::Value>().is_ok()
     }
 
     /// Reads and deserializes a `TOML` document in the specified reader passed
@@ -31,36 +35,135 @@ impl DataReader for TomlParser {
     where
         T: Read,
     {
-        let config_data: PackageData = {
-            let mut config_text = String::new();
+        let config_text = read_text(reader)?;
 
-            match reader.read_to_string(&mut config_text) {
-                Err(err) => {
-                    error!("Failed to read data: {:?}", err);
-                    return Err(errors::ParserError::Loading(err));
-                }
-                Ok(size) => debug!("Read {} bytes!", size),
+        deserialize(&config_text)
+    }
+
+    /// Reads and deserializes a `TOML` document in the specified reader, where
+    /// the document can contain multiple package definitions in a `packages`
+    /// array. The values in the `defaults` table are used for every package
+    /// definition that do not set the same value.
+    fn read_packages_data<T>(&self, reader: &mut T) -> Result<Vec<PackageData>, errors::ParserError>
+    where
+        T: Read,
+    {
+        let config_text = read_text(reader)?;
+        let mut value: toml::Value = toml::from_str(&config_text).map_err(map_error)?;
+        let table = match value.as_table_mut() {
+            Some(table) if table.contains_key("packages") => table,
+            _ => return deserialize(&config_text).map(|data| vec![data]),
+        };
+
+        let packages = match table.remove("packages") {
+            Some(toml::Value::Array(packages)) => packages,
+            _ => {
+                return Err(errors::ParserError::Deserialize(
+                    "The packages must be an array of package definitions".into(),
+                ))
             }
+        };
+        let defaults = table
+            .remove("defaults")
+            .unwrap_or_else(|| toml::Value::Table(toml::value::Table::new()));
+
+        debug!("Deserializing {} TOML Package definitions", packages.len());
+        packages
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut package)| {
+                merge_defaults(&mut package, &defaults);
+                if config_text.contains("${") {
+                    substitute_variables(&mut package)?;
+                }
 
-            debug!("Deserializing TOML Package data");
-            match toml::from_str(&config_text) {
-                Err(err) => {
+                package.try_into().map_err(|err: toml::de::Error| {
                     error!("Failed to deserialize package data: {:?}", err);
-                    let fmt = err.to_string();
-                    return Err(errors::ParserError::Deserialize(fmt));
+                    errors::ParserError::Deserialize(format!(
+                        "Unable to deserialize package definition {}: {}",
+                        index + 1,
+                        err
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+fn deserialize(config_text: &str) -> Result<PackageData, errors::ParserError> {
+    debug!("Deserializing TOML Package data");
+    let data = if config_text.contains("${") {
+        debug!("Substituting environment variables in TOML data");
+        let mut value: toml::Value = toml::from_str(config_text).map_err(map_error)?;
+        substitute_variables(&mut value)?;
+        value.try_into()
+    } else {
+        toml::from_str(config_text)
+    };
+    let config_data = data.map_err(map_error)?;
+
+    debug!("Package TOML data deserialized, returning package data!");
+
+    Ok(config_data)
+}
+
+/// Merges the specified default values into the value, values that are
+/// already set takes precedence over the default values.
+fn merge_defaults(value: &mut toml::Value, defaults: &toml::Value) {
+    if let (toml::Value::Table(table), toml::Value::Table(defaults)) = (value, defaults) {
+        for (key, default) in defaults {
+            match table.get_mut(key) {
+                Some(value) => merge_defaults(value, default),
+                None => {
+                    table.insert(key.clone(), default.clone());
                 }
-                Ok(data) => data,
             }
-        };
+        }
+    }
+}
 
-        debug!("Package TOML data deserialized, returning package data!");
+/// Replaces the environment variables in all string values of the specified
+/// `TOML` value.
+fn substitute_variables(value: &mut toml::Value) -> Result<(), errors::ParserError> {
+    match value {
+        toml::Value::String(text) => {
+            *text = env::substitute(text)
+                .map_err(|name| errors::ParserError::UndefinedVariable { path: None, name })?;
+        }
+        toml::Value::Array(values) => {
+            for value in values {
+                substitute_variables(value)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                substitute_variables(value)?;
+            }
+        }
+        _ => {}
+    }
 
-        Ok(config_data)
+    Ok(())
+}
+
+fn map_error(err: toml::de::Error) -> errors::ParserError {
+    error!("Failed to deserialize package data: {:?}", err);
+    let fmt = err.to_string();
+
+    match err.line_col() {
+        Some((line, column)) => errors::ParserError::InvalidData {
+            path: None,
+            line: line + 1,
+            column: column + 1,
+            message: fmt,
+        },
+        None => errors::ParserError::Deserialize(fmt),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::io::{BufReader, Error, ErrorKind};
     use std::path::PathBuf;
     use std::str::FromStr;
@@ -82,7 +185,6 @@ mod tests {
     }
 
     #[rstest]
-    #[case("test-package.toml")]
     #[case("test-package.aer.yml")]
     #[case("test-package.xml")]
     fn read_file_should_error_for_non_aer_toml_files(#[case] file: &str) {
@@ -100,6 +202,31 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case("test-package.toml")]
+    #[case("test-package.aer.toml")]
+    #[case("TEST-PACKAGE.TOML")]
+    fn can_handle_file_should_accept_toml_extensions(#[case] file: &str) {
+        let path = PathBuf::from(file);
+        let parser = TomlParser;
+
+        assert!(parser.can_handle_file(&path));
+    }
+
+    #[rstest]
+    #[case("[metadata]\nid = \"test\"", true)]
+    #[case("", true)]
+    #[case("<package></package>", false)]
+    #[case("{ \"metadata\": {} }", false)]
+    fn can_handle_content_should_detect_toml_documents(
+        #[case] content: &str,
+        #[case] expected: bool,
+    ) {
+        let parser = TomlParser;
+
+        assert_eq!(parser.can_handle_content(content), expected);
+    }
+
     #[test]
     fn read_file_should_error_for_non_existing_file() {
         let path = PathBuf::from("test-file.aer.toml");
@@ -150,6 +277,142 @@ mod tests {
         let _ = parser.read_data(&mut reader).unwrap();
     }
 
+    #[test]
+    fn read_data_should_return_line_and_column_of_invalid_data() {
+        const VAL: &[u8] = b"[metadata]\nid = test-package";
+        let mut reader = BufReader::new(VAL);
+        let parser = TomlParser;
+
+        let err = parser.read_data(&mut reader).unwrap_err();
+
+        match err {
+            errors::ParserError::InvalidData {
+                path, line, column, ..
+            } => {
+                assert_eq!(path, None);
+                assert_eq!((line, column), (2, 6));
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn read_data_should_substitute_environment_variables() {
+        const VAL: &[u8] = br#"[metadata]
+id = "test-package"
+project_url = "${AER_TEST_TOML_SERVER}/project"
+summary = "${AER_TEST_TOML_UNDEFINED:-Some summary}"
+"#;
+        std::env::set_var("AER_TEST_TOML_SERVER", "https://example.org");
+        let mut reader = BufReader::new(VAL);
+        let parser = TomlParser;
+
+        let actual = parser.read_data(&mut reader).unwrap();
+
+        assert_eq!(
+            actual.metadata().project_url().as_str(),
+            "https://example.org/project"
+        );
+        assert_eq!(actual.metadata().summary, "Some summary");
+    }
+
+    #[test]
+    fn read_file_should_read_custom_headers() {
+        std::env::set_var("AER_TEST_TOML_API_KEY", "secret-key");
+        let path = PathBuf::from("test-data/custom-headers.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let choco = actual.updater().chocolatey();
+
+        assert_eq!(choco.headers.len(), 2);
+        assert_eq!(choco.headers["Referer"], "https://example.org/");
+        assert_eq!(choco.headers["X-Api-Key"], "secret-key");
+        assert_eq!(
+            choco.parse_url,
+            Some(ChocolateyParseUrls::Multiple(vec![
+                ChocolateyParseUrlEntry {
+                    url: ChocolateyParseUrl::Url(
+                        Url::parse("https://example.org/downloads").unwrap()
+                    ),
+                    archs: vec![],
+                    accept_language: Some("de-DE".into()),
+                    headers: {
+                        let mut headers = HashMap::new();
+                        headers.insert("Accept".to_string(), "application/xhtml+xml".to_string());
+                        headers
+                    },
+                }
+            ]))
+        );
+    }
+
+    #[test]
+    fn read_file_should_name_undefined_variable_and_file() {
+        let path = PathBuf::from("test-data/undefined-variable.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The environment variable 'AER_TEST_UNDEFINED_VARIABLE' used in \
+             'test-data/undefined-variable.aer.toml' is not defined!"
+        );
+    }
+
+    #[test]
+    fn read_file_packages_should_merge_defaults_into_every_package() {
+        let path = PathBuf::from("test-data/multiple-packages.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file_packages(&path).unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].metadata().id(), "example-cli");
+        assert_eq!(
+            actual[0].metadata().project_url().as_str(),
+            "https://example.org/"
+        );
+        assert_eq!(actual[1].metadata().id(), "example-gui");
+        assert_eq!(
+            actual[1].metadata().project_url().as_str(),
+            "https://gui.example.org/"
+        );
+        for package in &actual {
+            assert_eq!(package.metadata().maintainers(), ["AdmiringWorm"]);
+            assert!(package.updater().chocolatey().parse_url.is_some());
+        }
+    }
+
+    #[test]
+    fn read_file_packages_should_return_single_package_definition() {
+        let path = PathBuf::from("test-data/basic-metadata.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file_packages(&path).unwrap();
+
+        assert_eq!(actual, [parser.read_file(&path).unwrap()]);
+    }
+
+    #[test]
+    fn read_packages_data_should_name_invalid_package_definition() {
+        const VAL: &[u8] = br#"[[packages]]
+metadata = { id = "first", summary = "The first package" }
+
+[[packages]]
+metadata = { id = "second" }
+"#;
+        let mut reader = BufReader::new(VAL);
+        let parser = TomlParser;
+
+        let actual = parser.read_packages_data(&mut reader).unwrap_err();
+
+        assert!(actual
+            .to_string()
+            .starts_with("Unable to deserialize package definition 2:"));
+    }
+
     #[test]
     fn read_data_should_succeed_on_required_values_defined() {
         let path = PathBuf::from("test-data/basic-metadata.aer.toml");
@@ -214,6 +477,18 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn read_data_should_accept_tags() {
+        let path = PathBuf::from("test-data/metadata-tags.aer.toml");
+        let parser = TomlParser;
+        let mut expected = PackageData::new("test-package");
+        expected.metadata_mut().set_tags(&["browser", "monthly"]);
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn read_data_should_accept_license_in_seperate_section() {
         let path = PathBuf::from("test-data/license-long.aer.toml");
@@ -254,6 +529,280 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn read_data_should_accept_github_parse_url() {
+        let path = PathBuf::from("test-data/github-parse-url.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().parse_url,
+            Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::GitHub {
+                owner: "codecov".into(),
+                repo: "codecov-exe".into(),
+                token_env: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_external_parse_url() {
+        let path = PathBuf::from("test-data/external-parse-url.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().parse_url,
+            Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::External {
+                command: "python resolve.py".into(),
+            }))
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_regex_preset_with_overridden_regexes() {
+        let path = PathBuf::from("test-data/regex-preset.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let choco = actual.updater().chocolatey();
+        let choco = choco.with_regex_preset();
+
+        assert_eq!(choco.regex_preset.as_deref(), Some("sourceforge"));
+        assert!(choco.decode_link);
+        assert_eq!(
+            choco.regexes()["arch32"],
+            ChocolateyRegexes::from(r"AStyle_(?P<version>[\d\.]+)_windows\.zip/download$")
+        );
+        assert!(!choco.regexes().contains_key("x86"));
+        assert!(choco.regexes().contains_key("x64"));
+    }
+
+    #[test]
+    fn read_data_should_accept_parse_url_with_link_selector() {
+        let path = PathBuf::from("test-data/link-selector-parse-url.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().parse_url,
+            Some(ChocolateyParseUrls::Single(
+                ChocolateyParseUrl::UrlWithSelector {
+                    url: Url::parse("https://example.org/downloads").unwrap(),
+                    link_selector: "table.downloads a".into(),
+                }
+            ))
+        );
+        assert_eq!(actual.updater().chocolatey().max_links, Some(20000));
+    }
+
+    #[test]
+    fn read_data_should_accept_link_sources() {
+        let path = PathBuf::from("test-data/link-sources.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().link_sources,
+            [
+                ChocolateyLinkSource {
+                    element: "button".into(),
+                    attribute: "data-url".into()
+                },
+                ChocolateyLinkSource {
+                    element: "source".into(),
+                    attribute: "src".into()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_parse_url_with_arch_regexes() {
+        const VAL: &[u8] = br#"[metadata]
+id = "test-package"
+summary = "Some summary"
+
+[updater.chocolatey.parse_url]
+url = "https://example.org/downloads/"
+regex = '/(?P<version>[\d\.]+)/(windows|win64)/$'
+arch_regexes = { arch32 = '/windows/$', arch64 = '/win64/$' }
+"#;
+        let mut reader = BufReader::new(VAL);
+        let parser = TomlParser;
+
+        let actual = parser.read_data(&mut reader).unwrap();
+
+        match actual.updater().chocolatey().parse_url {
+            Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::UrlWithRegex {
+                ref arch_regexes,
+                follow_first,
+                ..
+            })) => {
+                assert!(follow_first);
+                assert_eq!(arch_regexes.len(), 2);
+                assert_eq!(arch_regexes["arch64"], "/win64/$");
+            }
+            ref parse_url => panic!("Unexpected parse url: {:?}", parse_url),
+        }
+    }
+
+    #[test]
+    fn read_data_should_accept_parse_url_without_following_links() {
+        let path = PathBuf::from("test-data/url-with-regex-no-follow.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().parse_url,
+            Some(ChocolateyParseUrls::Single(
+                ChocolateyParseUrl::UrlWithRegex {
+                    url: Url::parse("https://example.org/downloads").unwrap(),
+                    regex: r"-(?P<version>[\d\.]+)\.exe$".into(),
+                    follow_first: false,
+                    follow_highest: false,
+                    arch_regexes: HashMap::new(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_multiple_parse_urls() {
+        let path = PathBuf::from("test-data/multiple-parse-urls.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().parse_url,
+            Some(ChocolateyParseUrls::Multiple(vec![
+                ChocolateyParseUrlEntry {
+                    url: ChocolateyParseUrl::Url(
+                        Url::parse("https://example.org/downloads/x86").unwrap()
+                    ),
+                    archs: vec!["arch32".into()],
+                    accept_language: None,
+                    headers: HashMap::new(),
+                },
+                ChocolateyParseUrlEntry {
+                    url: ChocolateyParseUrl::UrlWithRegex {
+                        url: Url::parse("https://example.org/downloads").unwrap(),
+                        regex: "/x64/$".into(),
+                        follow_first: true,
+                        follow_highest: false,
+                        arch_regexes: HashMap::new(),
+                    },
+                    archs: vec!["arch64".into()],
+                    accept_language: None,
+                    headers: HashMap::new(),
+                },
+            ]))
+        );
+    }
+
+    #[cfg(feature = "scoop")]
+    #[test]
+    fn read_data_should_accept_scoop_updater() {
+        let path = PathBuf::from("test-data/scoop-updater.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let scoop = actual.updater().scoop();
+
+        assert!(!actual.updater().has_chocolatey());
+        assert!(actual.updater().has_scoop());
+        assert_eq!(
+            scoop.parse_url,
+            Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org/downloads").unwrap()
+            )))
+        );
+        assert_eq!(scoop.manifest, Some(PathBuf::from("bucket/example.json")));
+        assert_eq!(
+            scoop.regexes().get("arch64"),
+            Some(&r"example-(?P<version>[\d\.]+)-x64\.zip$".into())
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_latest_pointer() {
+        let path = PathBuf::from("test-data/latest-pointer.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert!(actual.updater().latest_pointer());
+    }
+
+    #[test]
+    fn read_data_should_accept_download_hooks() {
+        let path = PathBuf::from("test-data/download-hooks.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().hooks(),
+            &UpdateHooks {
+                before: vec![Hook::Command("git pull".into())],
+                after: vec![],
+                pre_download: vec![Hook::Command("echo $AER_DOWNLOAD_URL".into())],
+                post_download: vec![Hook::PowerShell {
+                    powershell: "Get-MpThreat -Path $env:AER_DOWNLOAD_PATH".into()
+                }],
+                timeout: Some(120),
+            }
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_multiple_regexes_per_architecture() {
+        let path = PathBuf::from("test-data/multiple-regexes.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let choco = actual.updater().chocolatey();
+
+        assert_eq!(
+            choco.regexes()["arch32"],
+            ChocolateyRegexes::from(r"-x86\.exe$")
+        );
+        assert_eq!(
+            choco.regexes()["x64"].as_slice(),
+            &[r"-x64\.exe$".to_string(), r"-amd64\.exe$".to_string()]
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_expected_checksums() {
+        let path = PathBuf::from("test-data/expected-checksums.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let choco = actual.updater().chocolatey();
+
+        assert_eq!(
+            choco.expected_checksum("x86"),
+            Some(&ChocolateyExpectedChecksum::new(
+                "ab66430167ceb33784387abe71cf7c7d",
+                Some(ChocolateyChecksumType::Md5)
+            ))
+        );
+        assert_eq!(
+            choco.expected_checksum("x64"),
+            Some(&ChocolateyExpectedChecksum::new(
+                "856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839",
+                None
+            ))
+        );
+    }
+
     #[test]
     fn read_data_should_deserialize_all_data() {
         let path = PathBuf::from("test-data/deserialize-full.aer.toml");
@@ -300,11 +849,16 @@ mod tests {
                 let mut choco = ChocolateyUpdaterData::new();
                 choco.embedded = true;
                 choco.updater_type = ChocolateyUpdaterType::Archive;
-                choco.parse_url = Some(ChocolateyParseUrl::UrlWithRegex {
-                    url: Url::parse("https://sourceforge.net/projects/astyle/files/astyle/")
-                        .unwrap(),
-                    regex: r"astyle( |%20)(?P<version>[\d\.]+)/$".into(),
-                });
+                choco.parse_url = Some(ChocolateyParseUrls::Single(
+                    ChocolateyParseUrl::UrlWithRegex {
+                        url: Url::parse("https://sourceforge.net/projects/astyle/files/astyle/")
+                            .unwrap(),
+                        regex: r"astyle( |%20)(?P<version>[\d\.]+)/$".into(),
+                        follow_first: true,
+                        follow_highest: false,
+                        arch_regexes: HashMap::new(),
+                    },
+                ));
                 choco.add_regex("arch32", r"windows\.zip/download$");
                 choco
             });
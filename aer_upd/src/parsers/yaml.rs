@@ -0,0 +1,291 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+#![cfg_attr(docsrs, doc(cfg(feature = "yaml_data")))]
+
+use std::io::Read;
+use std::path::Path;
+
+use aer_data::PackageData;
+use log::{debug, error};
+
+use crate::parsers::{env, errors, read_text, DataReader};
+
+pub struct YamlParser;
+
+/// Implements the trait necessary for reading files that are stored in the
+/// `YAML` language. Anchors and aliases can be used to share common values,
+/// like regexes, between different parts of the document.
+impl DataReader for YamlParser {
+    fn can_handle_file(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| {
+                ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml")
+            })
+    }
+
+    /// Checks if the content is a `YAML` document containing a mapping or a
+    /// sequence at the root, this does not check if the document contains
+    /// valid package data.
+    fn can_handle_content(&self, content: &str) -> bool {
+        serde_yaml::from_str::<serde_yaml::Mapping>(content).is_ok()
+            || serde_yaml::from_str::<serde_yaml::Sequence>(content).is_ok()
+    }
+
+    /// Reads and deserializes a `YAML` document in the specified reader passed
+    /// to the function.
+    fn read_data<T>(&self, reader: &mut T) -> Result<PackageData, errors::ParserError>
+    where
+        T: Read,
+    {
+        let config_text = read_text(reader)?;
+
+        deserialize(&config_text)
+    }
+
+    /// Reads and deserializes a `YAML` document in the specified reader, where
+    /// the document can contain multiple package definitions either as a
+    /// sequence at the root, or in a `packages` sequence. The values in the
+    /// `defaults` mapping are used for every package definition that do not
+    /// set the same value.
+    fn read_packages_data<T>(&self, reader: &mut T) -> Result<Vec<PackageData>, errors::ParserError>
+    where
+        T: Read,
+    {
+        let config_text = read_text(reader)?;
+        let value: serde_yaml::Value = serde_yaml::from_str(&config_text).map_err(map_error)?;
+        let packages_key = serde_yaml::Value::String("packages".into());
+
+        let (packages, defaults) = match value {
+            serde_yaml::Value::Sequence(packages) => (packages, serde_yaml::Value::Null),
+            serde_yaml::Value::Mapping(mut mapping) if mapping.contains_key(&packages_key) => {
+                let defaults = mapping
+                    .remove(&serde_yaml::Value::String("defaults".into()))
+                    .unwrap_or(serde_yaml::Value::Null);
+                match mapping.remove(&packages_key) {
+                    Some(serde_yaml::Value::Sequence(packages)) => (packages, defaults),
+                    _ => {
+                        return Err(errors::ParserError::Deserialize(
+                            "The packages must be a sequence of package definitions".into(),
+                        ))
+                    }
+                }
+            }
+            _ => return deserialize(&config_text).map(|data| vec![data]),
+        };
+
+        debug!("Deserializing {} YAML Package definitions", packages.len());
+        packages
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut package)| {
+                merge_defaults(&mut package, &defaults);
+                if config_text.contains("${") {
+                    substitute_variables(&mut package)?;
+                }
+
+                serde_yaml::from_value(package).map_err(|err| {
+                    error!("Failed to deserialize package data: {:?}", err);
+                    errors::ParserError::Deserialize(format!(
+                        "Unable to deserialize package definition {}: {}",
+                        index + 1,
+                        err
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+fn deserialize(config_text: &str) -> Result<PackageData, errors::ParserError> {
+    debug!("Deserializing YAML Package data");
+    let config_data = if config_text.contains("${") {
+        debug!("Substituting environment variables in YAML data");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(config_text).map_err(map_error)?;
+        substitute_variables(&mut value)?;
+        serde_yaml::from_value(value)
+    } else {
+        serde_yaml::from_str(config_text)
+    };
+    let config_data = config_data.map_err(map_error)?;
+
+    debug!("Package YAML data deserialized, returning package data!");
+
+    Ok(config_data)
+}
+
+/// Merges the specified default values into the value, values that are
+/// already set takes precedence over the default values.
+fn merge_defaults(value: &mut serde_yaml::Value, defaults: &serde_yaml::Value) {
+    if let (serde_yaml::Value::Mapping(mapping), serde_yaml::Value::Mapping(defaults)) =
+        (value, defaults)
+    {
+        for (key, default) in defaults {
+            match mapping.get_mut(key) {
+                Some(value) => merge_defaults(value, default),
+                None => {
+                    mapping.insert(key.clone(), default.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the environment variables in all string values of the specified
+/// `YAML` value.
+fn substitute_variables(value: &mut serde_yaml::Value) -> Result<(), errors::ParserError> {
+    match value {
+        serde_yaml::Value::String(text) => {
+            *text = env::substitute(text)
+                .map_err(|name| errors::ParserError::UndefinedVariable { path: None, name })?;
+        }
+        serde_yaml::Value::Sequence(values) => {
+            for value in values {
+                substitute_variables(value)?;
+            }
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            for (_, value) in mapping.iter_mut() {
+                substitute_variables(value)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn map_error(err: serde_yaml::Error) -> errors::ParserError {
+    error!("Failed to deserialize package data: {:?}", err);
+    let fmt = err.to_string();
+
+    match err.location() {
+        Some(location) => errors::ParserError::InvalidData {
+            path: None,
+            line: location.line(),
+            column: location.column(),
+            message: fmt,
+        },
+        None => errors::ParserError::Deserialize(fmt),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aer_data::prelude::chocolatey::*;
+    use aer_data::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("test.aer.yaml")]
+    #[case("test.aer.yml")]
+    #[case("test.YAML")]
+    fn can_handle_file_should_accept_yaml_extensions(#[case] file: &str) {
+        let parser = YamlParser;
+
+        assert!(parser.can_handle_file(Path::new(file)));
+    }
+
+    #[test]
+    fn can_handle_file_should_not_accept_toml_files() {
+        let parser = YamlParser;
+
+        assert!(!parser.can_handle_file(Path::new("test.aer.toml")));
+    }
+
+    #[test]
+    fn read_data_should_succeed_on_required_values_defined() {
+        let path = PathBuf::from("test-data/basic-metadata.aer.yaml");
+        let parser = YamlParser;
+        let expected = {
+            let mut pkg = PackageData::new("test-package");
+            pkg.metadata_mut().set_license(LicenseType::None);
+            pkg.metadata_mut().set_maintainers(&["AdmiringWorm"]);
+            pkg.metadata_mut().set_project_url("https://test.com");
+            pkg.metadata_mut().summary =
+                "Some kind of summary (or description in some packages)".to_owned();
+            pkg
+        };
+
+        let result = parser.read_file(&path).unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn read_data_should_resolve_anchors_and_aliases() {
+        let path = PathBuf::from("test-data/anchors.aer.yml");
+        let parser = YamlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let choco = actual.updater().chocolatey();
+
+        assert_eq!(
+            choco.regexes()["arch32"],
+            ChocolateyRegexes::from(r"-(?P<version>[\d\.]+)\.exe$")
+        );
+        assert_eq!(choco.regexes()["arch32"], choco.regexes()["arch64"]);
+    }
+
+    #[test]
+    fn read_file_packages_should_read_root_sequence() {
+        let path = PathBuf::from("test-data/multiple-packages.aer.yaml");
+        let parser = YamlParser;
+
+        let actual = parser.read_file_packages(&path).unwrap();
+
+        let ids: Vec<_> = actual.iter().map(|pkg| pkg.metadata().id()).collect();
+        assert_eq!(ids, ["example-cli", "example-gui"]);
+    }
+
+    #[test]
+    fn read_packages_data_should_merge_defaults_into_every_package() {
+        const VAL: &[u8] = b"defaults:
+  metadata:
+    project_url: https://example.org
+    summary: The shared summary
+packages:
+  - metadata:
+      id: first
+  - metadata:
+      id: second
+      summary: The second package
+";
+        let parser = YamlParser;
+
+        let actual = parser.read_packages_data(&mut &VAL[..]).unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].metadata().summary, "The shared summary");
+        assert_eq!(actual[1].metadata().summary, "The second package");
+        assert_eq!(
+            actual[1].metadata().project_url().as_str(),
+            "https://example.org/"
+        );
+    }
+
+    #[test]
+    fn read_data_should_return_line_and_column_of_invalid_data() {
+        let path = PathBuf::from("test-data/invalid-data.aer.yaml");
+        let parser = YamlParser;
+
+        let actual = parser.read_file(&path).unwrap_err();
+
+        match actual {
+            errors::ParserError::InvalidData {
+                path: Some(path),
+                line,
+                ..
+            } => {
+                assert_eq!(path, PathBuf::from("test-data/invalid-data.aer.yaml"));
+                assert_eq!(line, 3);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains a cache of compiled regular expressions, to avoid compiling the
+//! same pattern multiple times when the pattern is used by multiple
+//! architectures or package files during the same run. Also contains the
+//! functions used to build a version out of the captures of a regex.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, Regex>> = Mutex::new(HashMap::new());
+}
+
+/// Gets the compiled regular expression for the specified pattern, the
+/// pattern is only compiled the first time it is requested. Patterns that
+/// fail to compile are not cached.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::regexes::get_regex;
+///
+/// let re = get_regex(r"-x64\.exe$").unwrap();
+///
+/// assert!(re.is_match("https://example.org/app-x64.exe"));
+/// ```
+pub fn get_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+
+    if let Some(re) = cache.get(pattern) {
+        return Ok(re.clone());
+    }
+
+    let re = Regex::new(pattern)?;
+    cache.insert(pattern.to_owned(), re.clone());
+
+    Ok(re)
+}
+
+/// A single part of a version template, either text that is used as is or
+/// the name of a capture group.
+#[derive(Debug, PartialEq)]
+enum TemplatePart<'a> {
+    Text(&'a str),
+    Capture(&'a str),
+}
+
+fn parse_template(template: &str) -> Option<Vec<TemplatePart<'_>>> {
+    let mut parts = vec![];
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(TemplatePart::Text(&rest[..start]));
+        }
+        let end = start + rest[start..].find('}')?;
+        let name = rest[start + 1..end].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        parts.push(TemplatePart::Capture(name));
+        rest = &rest[end + 1..];
+    }
+
+    if rest.contains('}') {
+        return None;
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Text(rest));
+    }
+
+    Some(parts)
+}
+
+/// Gets the names of the capture groups referenced in the specified version
+/// template, or [None] if the template is not valid. Capture groups are
+/// referenced by their name inside braces (*ie: `{year}.{month}.{day}`*).
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::regexes::template_captures;
+///
+/// assert_eq!(
+///     template_captures("{year}.{month}.{day}"),
+///     Some(vec!["year", "month", "day"])
+/// );
+/// assert_eq!(template_captures("{year.{month}"), None);
+/// ```
+pub fn template_captures(template: &str) -> Option<Vec<&str>> {
+    let parts = parse_template(template)?;
+
+    Some(
+        parts
+            .into_iter()
+            .filter_map(|part| match part {
+                TemplatePart::Capture(name) => Some(name),
+                TemplatePart::Text(_) => None,
+            })
+            .collect(),
+    )
+}
+
+/// Builds a version by replacing every capture group referenced in the
+/// specified template with the value captured by the regex. Leading zeros
+/// are removed from numeric values (*ie: `04` becomes `4`*), to allow dates
+/// to be used as versions.
+///
+/// ## Returns
+///
+/// The expanded version, or [None] if the template is not valid or if any
+/// of the referenced capture groups did not capture a value.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::regexes::{expand_template, get_regex};
+///
+/// let re = get_regex(r"(?P<year>\d{4})_(?P<month>\d{2})_(?P<day>\d{2})").unwrap();
+/// let captures = re.captures("app-2023_04_01.exe").unwrap();
+///
+/// assert_eq!(
+///     expand_template("{year}.{month}.{day}", &captures),
+///     Some("2023.4.1".into())
+/// );
+/// ```
+pub fn expand_template(template: &str, captures: &Captures<'_>) -> Option<String> {
+    let mut result = String::with_capacity(template.len());
+
+    for part in parse_template(template)? {
+        match part {
+            TemplatePart::Text(text) => result.push_str(text),
+            TemplatePart::Capture(name) => {
+                let value = captures.name(name)?.as_str();
+                if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+                    let trimmed = value.trim_start_matches('0');
+                    result.push_str(if trimmed.is_empty() { "0" } else { trimmed });
+                } else {
+                    result.push_str(value);
+                }
+            }
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_regex_should_cache_compiled_pattern() {
+        let pattern = r"cached-(?P<version>[\d\.]+)\.exe$";
+
+        let first = get_regex(pattern).unwrap();
+        let second = get_regex(pattern).unwrap();
+
+        assert_eq!(first.as_str(), second.as_str());
+        assert!(CACHE.lock().unwrap().contains_key(pattern));
+    }
+
+    #[test]
+    fn get_regex_should_not_cache_invalid_pattern() {
+        let pattern = r"invalid-(";
+
+        assert!(get_regex(pattern).is_err());
+        assert!(!CACHE.lock().unwrap().contains_key(pattern));
+    }
+
+    #[test]
+    fn template_captures_should_return_none_on_unbalanced_braces() {
+        assert_eq!(template_captures("{major}.{minor"), None);
+        assert_eq!(template_captures("major}.{minor}"), None);
+        assert_eq!(template_captures("{}.{minor}"), None);
+    }
+
+    #[test]
+    fn expand_template_should_use_values_of_named_captures() {
+        let re = get_regex(r"v(?P<major>\d+)-(?P<minor>\d+)-(?P<patch>\d+)").unwrap();
+        let captures = re.captures("https://example.org/app-v3-2-10.zip").unwrap();
+
+        let actual = expand_template("{major}.{minor}.{patch}", &captures);
+
+        assert_eq!(actual, Some("3.2.10".into()));
+    }
+
+    #[test]
+    fn expand_template_should_return_none_on_missing_capture() {
+        let re = get_regex(r"v(?P<major>\d+)(?:-(?P<minor>\d+))?").unwrap();
+        let captures = re.captures("app-v3.zip").unwrap();
+
+        assert_eq!(expand_template("{major}.{minor}", &captures), None);
+    }
+}
@@ -9,6 +9,8 @@ use aer_data::prelude::*;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+pub mod external;
+pub mod hooks;
 #[cfg(feature = "powershell")]
 pub mod powershell;
 
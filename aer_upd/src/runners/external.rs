@@ -0,0 +1,179 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the running of external resolvers, which are commands that find
+//! the links of a package for sources that can not be parsed by aer itself.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::{debug, info};
+
+use super::hooks::{create_shell_command, wait_for_exit};
+
+/// The output captured from an external resolver that exited successfully.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolverOutput {
+    /// Everything the resolver wrote to its standard output.
+    pub stdout: String,
+    /// Everything the resolver wrote to its standard error.
+    pub stderr: String,
+}
+
+/// Runs the specified command as an external resolver in the specified
+/// working directory, where the `input` is written to the standard input of
+/// the command. The standard output and standard error of the command are
+/// captured, and returned when the command exits successfully.
+///
+/// When a timeout is specified, the command is killed when it have not
+/// finished within the timeout.
+///
+/// ## Errors
+///
+/// An error is returned if the command could not be started, if the command
+/// exits with a non-zero exit code, or if the command was killed. The
+/// captured standard error is included in the error when the command fails.
+pub fn run_resolver(
+    command: &str,
+    work_dir: &Path,
+    input: &str,
+    timeout: Option<Duration>,
+) -> Result<ResolverOutput, String> {
+    info!("Running external resolver: {}", command);
+
+    let mut child = create_shell_command(command)
+        .current_dir(work_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Unable to run the resolver '{}': {}", command, err))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_owned();
+        // The input is written on a separate thread, as the resolver may fill
+        // its output before reading all of the input.
+        thread::spawn(move || {
+            if let Err(err) = stdin.write_all(input.as_bytes()) {
+                debug!("Unable to write the input of the resolver: {}", err);
+            }
+        });
+    }
+
+    let stdout = capture_output(child.stdout.take());
+    let stderr = capture_output(child.stderr.take());
+
+    let status = wait_for_exit(&mut child, timeout)
+        .map_err(|err| format!("Unable to wait for the resolver '{}': {}", command, err))?;
+
+    let status = match status {
+        Some(status) => status,
+        None => {
+            let _ = child.kill();
+            let _ = child.wait();
+
+            return Err(format!(
+                "The resolver '{}' did not finish within {} seconds, and was killed!",
+                command,
+                timeout.unwrap_or_default().as_secs_f64()
+            ));
+        }
+    };
+
+    let output = ResolverOutput {
+        stdout: join_output(stdout),
+        stderr: join_output(stderr),
+    };
+
+    if status.success() {
+        Ok(output)
+    } else if output.stderr.trim().is_empty() {
+        Err(format!(
+            "The resolver '{}' failed with {}!",
+            command, status
+        ))
+    } else {
+        Err(format!(
+            "The resolver '{}' failed with {}: {}",
+            command,
+            status,
+            output.stderr.trim()
+        ))
+    }
+}
+
+/// Reads everything from the specified output of the resolver process on a
+/// separate thread.
+fn capture_output<R: Read + Send + 'static>(output: Option<R>) -> Option<JoinHandle<Vec<u8>>> {
+    let mut output = output?;
+
+    Some(thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = output.read_to_end(&mut buffer);
+        buffer
+    }))
+}
+
+fn join_output(reader: Option<JoinHandle<Vec<u8>>>) -> String {
+    reader
+        .and_then(|reader| reader.join().ok())
+        .map(|buffer| String::from_utf8_lossy(&buffer).into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn run_resolver_should_pass_input_and_capture_output() {
+        let result = run_resolver(
+            "cat; echo warning >&2",
+            &PathBuf::from("."),
+            "{\"id\":\"test\"}",
+            None,
+        );
+
+        assert_eq!(
+            result,
+            Ok(ResolverOutput {
+                stdout: "{\"id\":\"test\"}".into(),
+                stderr: "warning\n".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn run_resolver_should_return_error_with_stderr_on_non_zero_exit_code() {
+        let result = run_resolver("echo failed >&2; exit 2", &PathBuf::from("."), "", None);
+
+        assert_eq!(
+            result,
+            Err("The resolver 'echo failed >&2; exit 2' failed with exit status: 2: failed".into())
+        );
+    }
+
+    #[test]
+    fn run_resolver_should_kill_resolver_after_timeout() {
+        let started = Instant::now();
+
+        let result = run_resolver(
+            "sleep 5",
+            &PathBuf::from("."),
+            "",
+            Some(Duration::from_millis(200)),
+        );
+
+        assert_eq!(
+            result,
+            Err("The resolver 'sleep 5' did not finish within 0.2 seconds, and was killed!".into())
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use aer_data::prelude::*;
+use log::{info, log, Level};
+
+/// The interval the hook process is checked for completion at, when the hook
+/// is run with a timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs the specified hook with the specified working directory, and with the
+/// environment variables set in addition to the current environment. The
+/// output of the hook is logged while the hook is running, with the standard
+/// output logged as information and the standard error logged as warnings.
+///
+/// When a timeout is specified, the hook is killed when it have not finished
+/// within the timeout. On windows any process started by the hook is killed
+/// as well.
+///
+/// An error is returned if the hook could not be started, if the hook
+/// exits with a non-zero exit code, or if the hook was killed.
+pub fn run_hook(
+    hook: &Hook,
+    work_dir: &Path,
+    env: &HashMap<String, String>,
+    timeout: Option<Duration>,
+) -> Result<(), String> {
+    let (mut cmd, command) = match hook {
+        Hook::Command(command) => (create_shell_command(command), command),
+        Hook::PowerShell { powershell } => (create_powershell_command(powershell)?, powershell),
+    };
+
+    info!("Running hook: {}", command);
+
+    let mut child = cmd
+        .current_dir(work_dir)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Unable to run the hook '{}': {}", command, err))?;
+    let job = job::Job::assign(&child);
+
+    let readers = vec![
+        log_output(child.stdout.take(), Level::Info),
+        log_output(child.stderr.take(), Level::Warn),
+    ];
+
+    let status = wait_for_exit(&mut child, timeout)
+        .map_err(|err| format!("Unable to wait for the hook '{}': {}", command, err))?;
+
+    let status = match status {
+        Some(status) => status,
+        None => {
+            let killed = job.as_ref().map_or(false, job::Job::terminate);
+            if !killed {
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+
+            // The output is not waited on, as processes started by the hook
+            // may still be keeping it open.
+            return Err(format!(
+                "The hook '{}' did not finish within {} seconds, and was killed!",
+                command,
+                timeout.unwrap_or_default().as_secs_f64()
+            ));
+        }
+    };
+
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("The hook '{}' failed with {}!", command, status))
+    }
+}
+
+/// Waits for the process to exit, returning `None` when the process is still
+/// running after the specified timeout.
+pub(super) fn wait_for_exit(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<ExitStatus>> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return child.wait().map(Some),
+    };
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Logs every line read from the specified output of the hook process on a
+/// separate thread, using the specified log level.
+fn log_output<R: Read + Send + 'static>(output: Option<R>, level: Level) -> Option<JoinHandle<()>> {
+    let mut reader = BufReader::new(output?);
+
+    Some(thread::spawn(move || {
+        let mut line = Vec::new();
+        while let Ok(read) = reader.read_until(b'\n', &mut line) {
+            if read == 0 {
+                break;
+            }
+            log!(level, "{}", String::from_utf8_lossy(&line).trim_end());
+            line.clear();
+        }
+    }))
+}
+
+pub(super) fn create_shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(&["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(&["-c", command]);
+        cmd
+    }
+}
+
+#[cfg(feature = "powershell")]
+fn create_powershell_command(command: &str) -> Result<Command, String> {
+    let path = super::powershell::get_powershell_path();
+
+    if !path.is_file() {
+        return Err("No powershell executable was found!".into());
+    }
+
+    let mut cmd = Command::new(path);
+    cmd.env("POWERSHELL_TELEMETRY_OPTOUT", "1").args(&[
+        "-ExecutionPolicy",
+        "Bypass",
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        command,
+    ]);
+
+    Ok(cmd)
+}
+
+#[cfg(not(feature = "powershell"))]
+fn create_powershell_command(_command: &str) -> Result<Command, String> {
+    Err("Running PowerShell hooks requires the 'powershell' feature!".into())
+}
+
+#[cfg(windows)]
+mod job {
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+    use std::ptr;
+
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::jobapi2::{AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject};
+    use winapi::um::winnt::HANDLE;
+
+    /// A job object the process of a hook is assigned to, which allows the
+    /// processes started by the hook to be killed together with the hook.
+    pub struct Job(HANDLE);
+
+    impl Job {
+        pub fn assign(child: &Child) -> Option<Job> {
+            // SAFETY: The job object is only used while the handle is open,
+            // and the handle is closed when the job is dropped.
+            unsafe {
+                let handle = CreateJobObjectW(ptr::null_mut(), ptr::null());
+                if handle.is_null() {
+                    return None;
+                }
+
+                let job = Job(handle);
+                if AssignProcessToJobObject(job.0, child.as_raw_handle() as HANDLE) == 0 {
+                    return None;
+                }
+
+                Some(job)
+            }
+        }
+
+        pub fn terminate(&self) -> bool {
+            // SAFETY: The handle is open for as long as the job exists.
+            unsafe { TerminateJobObject(self.0, 1) != 0 }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: The handle is open, and is not used after this call.
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod job {
+    use std::process::Child;
+
+    /// Processes started by hooks are only killed together with the hook on
+    /// windows.
+    pub struct Job;
+
+    impl Job {
+        pub fn assign(_child: &Child) -> Option<Job> {
+            None
+        }
+
+        pub fn terminate(&self) -> bool {
+            false
+        }
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn run_hook_should_succeed_on_zero_exit_code() {
+        let hook = Hook::Command("exit 0".into());
+
+        let result = run_hook(&hook, &PathBuf::from("."), &HashMap::new(), None);
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn run_hook_should_pass_environment_variables() {
+        let hook = Hook::Command("test \"$AER_VERSION\" = \"1.0.0\"".into());
+        let mut env = HashMap::new();
+        env.insert("AER_VERSION".to_string(), "1.0.0".to_string());
+
+        let result = run_hook(&hook, &PathBuf::from("."), &env, None);
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn run_hook_should_return_error_on_non_zero_exit_code() {
+        let hook = Hook::Command("exit 3".into());
+
+        let result = run_hook(&hook, &PathBuf::from("."), &HashMap::new(), None);
+
+        assert_eq!(
+            result,
+            Err("The hook 'exit 3' failed with exit status: 3!".into())
+        );
+    }
+
+    #[test]
+    fn run_hook_should_kill_hook_after_timeout() {
+        let hook = Hook::Command("sleep 5".into());
+        let started = Instant::now();
+
+        let result = run_hook(
+            &hook,
+            &PathBuf::from("."),
+            &HashMap::new(),
+            Some(Duration::from_millis(200)),
+        );
+
+        assert_eq!(
+            result,
+            Err("The hook 'sleep 5' did not finish within 0.2 seconds, and was killed!".into())
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn run_hook_should_not_kill_hook_finishing_before_timeout() {
+        let hook = Hook::Command("echo test".into());
+
+        let result = run_hook(
+            &hook,
+            &PathBuf::from("."),
+            &HashMap::new(),
+            Some(Duration::from_secs(10)),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -31,7 +31,7 @@ lazy_static! {
     };
 }
 
-fn get_powershell_path() -> &'static Path {
+pub(crate) fn get_powershell_path() -> &'static Path {
     POWERSHELL_EXEC.as_path()
 }
 
@@ -0,0 +1,11 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the functions used to write the information found during an update
+//! back to the files of a package.
+
+pub mod chocolatey;
+pub mod errors;
+#[cfg(feature = "scoop")]
+#[cfg_attr(docsrs, doc(cfg(feature = "scoop")))]
+pub mod scoop;
@@ -0,0 +1,493 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Functions for updating the files that is part of a chocolatey package.
+
+use std::collections::HashMap;
+
+use regex::{Captures, Regex};
+
+use crate::updaters::errors::UpdaterError;
+
+/// Replaces the value of the `<version>` element inside the `<metadata>`
+/// element of the specified nuspec content. Everything else in the content is
+/// kept as is.
+///
+/// ## Returns
+///
+/// The updated content, or [None] if the nuspec already contains the specified
+/// version.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::updaters::chocolatey::update_nuspec_version;
+///
+/// let nuspec = "<package><metadata><version>1.0.0</version></metadata></package>";
+///
+/// let updated = update_nuspec_version(nuspec, "2.0.0").unwrap();
+///
+/// assert_eq!(
+///     updated,
+///     Some("<package><metadata><version>2.0.0</version></metadata></package>".into())
+/// );
+/// assert_eq!(update_nuspec_version(nuspec, "1.0.0").unwrap(), None);
+/// ```
+pub fn update_nuspec_version(content: &str, version: &str) -> Result<Option<String>, UpdaterError> {
+    let (version_start, version_end) = find_nuspec_version(content)?;
+
+    if content[version_start..version_end].trim() == version {
+        return Ok(None);
+    }
+
+    let mut result = String::with_capacity(content.len() + version.len());
+    result.push_str(&content[..version_start]);
+    result.push_str(version);
+    result.push_str(&content[version_end..]);
+
+    Ok(Some(result))
+}
+
+/// Returns the value of the `<version>` element inside the `<metadata>`
+/// element of the specified nuspec content.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::updaters::chocolatey::get_nuspec_version;
+///
+/// let nuspec = "<package><metadata><version> 1.0.0 </version></metadata></package>";
+///
+/// assert_eq!(get_nuspec_version(nuspec).unwrap(), "1.0.0");
+/// ```
+pub fn get_nuspec_version(content: &str) -> Result<String, UpdaterError> {
+    let (version_start, version_end) = find_nuspec_version(content)?;
+
+    Ok(content[version_start..version_end].trim().to_owned())
+}
+
+/// Returns the start and end index of the value in the `<version>` element
+/// inside the `<metadata>` element.
+fn find_nuspec_version(content: &str) -> Result<(usize, usize), UpdaterError> {
+    let metadata_start = find_start_tag(content, "metadata", 0)
+        .ok_or_else(|| UpdaterError::MissingElement("metadata".into()))?;
+    let missing_version = || UpdaterError::MissingElement("version".into());
+    let version_start =
+        find_start_tag(content, "version", metadata_start).ok_or_else(missing_version)?;
+    let version_end = content[version_start..]
+        .find("</version>")
+        .map(|index| index + version_start)
+        .ok_or_else(missing_version)?;
+
+    Ok((version_start, version_end))
+}
+
+/// Replaces the values assigned to the specified variables in the content of a
+/// PowerShell script. Both normal variable assignments (`$url = '...'`) and
+/// entries in a hashtable (`url = '...'`) will be updated, as long as the value
+/// is a quoted string. Everything except the quoted values is kept as is.
+///
+/// ## Returns
+///
+/// The updated content, or [None] if all of the variables already have the
+/// expected values. An error is returned if any of the variables could not be
+/// found.
+///
+/// ## Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use aer_upd::updaters::chocolatey::update_script_variables;
+///
+/// let script = "$url = 'https://example.org/old.exe'\n$checksum = \"abc\"\n";
+/// let mut values = HashMap::new();
+/// values.insert("url".to_string(), "https://example.org/new.exe".to_string());
+/// values.insert("checksum".to_string(), "def".to_string());
+///
+/// let updated = update_script_variables(script, &values).unwrap();
+///
+/// assert_eq!(
+///     updated,
+///     Some("$url = 'https://example.org/new.exe'\n$checksum = \"def\"\n".into())
+/// );
+/// ```
+pub fn update_script_variables(
+    content: &str,
+    values: &HashMap<String, String>,
+) -> Result<Option<String>, UpdaterError> {
+    let mut result = content.to_owned();
+
+    for (name, value) in values {
+        let re = variable_regex(name);
+
+        if !re.is_match(&result) {
+            return Err(UpdaterError::MissingVariable(name.clone()));
+        }
+
+        result = re
+            .replace_all(&result, |captures: &Captures| {
+                if captures.get(2).is_some() {
+                    format!("{}'{}'", &captures[1], value.replace('\'', "''"))
+                } else {
+                    format!(
+                        "{}\"{}\"",
+                        &captures[1],
+                        value
+                            .replace('`', "``")
+                            .replace('"', "`\"")
+                            .replace('$', "`$")
+                    )
+                }
+            })
+            .into_owned();
+    }
+
+    if result == content {
+        Ok(None)
+    } else {
+        Ok(Some(result))
+    }
+}
+
+/// Reads the values assigned to the specified variables in the content of a
+/// PowerShell script, in the same way as the variables are found by
+/// [update_script_variables]. Only the first assignment of each variable is
+/// used, and any escaped characters in the value are unescaped.
+///
+/// ## Returns
+///
+/// The values keyed by the name of their variable, or an error if any of the
+/// variables could not be found.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::updaters::chocolatey::get_script_variables;
+///
+/// let script = "$url = 'https://example.org/app.exe'\n$checksum = \"abc\"\n";
+///
+/// let values = get_script_variables(script, &["url", "checksum"]).unwrap();
+///
+/// assert_eq!(values["url"], "https://example.org/app.exe");
+/// assert_eq!(values["checksum"], "abc");
+/// ```
+pub fn get_script_variables(
+    content: &str,
+    names: &[&str],
+) -> Result<HashMap<String, String>, UpdaterError> {
+    let mut values = HashMap::with_capacity(names.len());
+
+    for name in names {
+        let captures = variable_regex(name)
+            .captures(content)
+            .ok_or_else(|| UpdaterError::MissingVariable((*name).to_owned()))?;
+
+        let value = match (captures.get(2), captures.get(3)) {
+            (Some(value), _) => value.as_str().replace("''", "'"),
+            (None, Some(value)) => unescape_double_quoted(value.as_str()),
+            (None, None) => String::new(),
+        };
+        values.insert((*name).to_owned(), value);
+    }
+
+    Ok(values)
+}
+
+/// Creates the regular expression used to find the assignment of the
+/// specified variable, where the second capture group holds the value of a
+/// single quoted string and the third capture group the value of a double
+/// quoted string.
+fn variable_regex(name: &str) -> Regex {
+    Regex::new(&format!(
+        r#"(?mi)^(\s*\$?{}\s*=\s*)(?:'([^']*)'|"([^"]*)")"#,
+        regex::escape(name.trim_start_matches('$'))
+    ))
+    .expect("The variable regex should always be valid")
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch == '`' {
+            if let Some(escaped) = chars.next() {
+                result.push(escaped);
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+/// The template used when creating the `legal/VERIFICATION.txt` file, when no
+/// template have been configured for the package.
+pub const DEFAULT_VERIFICATION_TEMPLATE: &str = "VERIFICATION
+Verification is intended to assist the Chocolatey moderators and community
+in verifying that this package's contents are trustworthy.
+
+The embedded software have been downloaded from the listed download
+location(s) on <{{project_url}}>
+and can be verified by doing the following:
+
+1. Download the following:
+{{urls}}
+2. You can use one of the following methods to obtain the checksum(s):
+  - Use powershell function 'Get-FileHash'
+  - Use chocolatey utility 'checksum.exe'
+
+  checksum type: {{checksum_type}}
+{{checksums}}
+";
+
+/// Renders the specified template, by replacing every placeholder in the
+/// template (*ie: `{{checksum_x64}}`*) with the value of the same name.
+/// Whitespace around the name of the placeholder is ignored.
+///
+/// ## Returns
+///
+/// The rendered content, or an error if a value for one of the placeholders
+/// could not be found.
+///
+/// ## Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use aer_upd::updaters::chocolatey::render_template;
+///
+/// let mut values = HashMap::new();
+/// values.insert("url_x64".to_string(), "https://example.org/app.exe".to_string());
+///
+/// let rendered = render_template("x64: <{{ url_x64 }}>", &values).unwrap();
+///
+/// assert_eq!(rendered, "x64: <https://example.org/app.exe>");
+/// ```
+pub fn render_template(
+    template: &str,
+    values: &HashMap<String, String>,
+) -> Result<String, UpdaterError> {
+    let re = Regex::new(r"\{\{\s*([\w\-]+)\s*\}\}")
+        .expect("The placeholder regex should always be valid");
+    let mut missing = None;
+
+    let rendered = re.replace_all(template, |captures: &Captures| {
+        match values.get(&captures[1]) {
+            Some(value) => value.clone(),
+            None => {
+                missing.get_or_insert_with(|| captures[1].to_owned());
+                String::new()
+            }
+        }
+    });
+
+    match missing {
+        Some(name) => Err(UpdaterError::MissingVariable(name)),
+        None => Ok(rendered.into_owned()),
+    }
+}
+
+/// Returns the index directly after the start tag with the specified name, if
+/// the tag is found at or after the `offset`.
+fn find_start_tag(content: &str, name: &str, offset: usize) -> Option<usize> {
+    let tag = format!("<{}", name);
+    let mut offset = offset;
+
+    while let Some(index) = content[offset..].find(&tag) {
+        let after_name = offset + index + tag.len();
+        let rest = &content[after_name..];
+
+        if rest.starts_with('>') {
+            return Some(after_name + 1);
+        } else if rest.starts_with(char::is_whitespace) {
+            return rest.find('>').map(|end| after_name + end + 1);
+        }
+
+        offset = after_name;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const NUSPEC: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://schemas.microsoft.com/packaging/2015/06/nuspec.xsd">
+  <metadata>
+    <id>astyle</id>
+    <version>3.1.0</version>
+    <!-- <version>0.0.1</version> -->
+    <versionNotes>Not a version</versionNotes>
+  </metadata>
+</package>
+"#;
+
+    #[test]
+    fn update_nuspec_version_should_only_replace_version() {
+        let expected = NUSPEC.replace("<version>3.1.0</version>", "<version>3.1.1</version>");
+
+        let actual = update_nuspec_version(NUSPEC, "3.1.1").unwrap();
+
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn get_nuspec_version_should_return_current_version() {
+        let actual = get_nuspec_version(NUSPEC).unwrap();
+
+        assert_eq!(actual, "3.1.0");
+    }
+
+    #[rstest(version, case("3.1.0"), case(" 3.1.0 "))]
+    fn update_nuspec_version_should_return_none_when_up_to_date(version: &str) {
+        let content = NUSPEC.replace("3.1.0", version);
+
+        let actual = update_nuspec_version(&content, "3.1.0").unwrap();
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn update_nuspec_version_should_handle_element_attributes() {
+        let content = "<package><metadata minClientVersion=\"0.10\"><version \
+                       >1.0</version></metadata></package>";
+
+        let actual = update_nuspec_version(content, "1.1").unwrap();
+
+        assert_eq!(
+            actual,
+            Some(
+                "<package><metadata minClientVersion=\"0.10\"><version \
+                 >1.1</version></metadata></package>"
+                    .into()
+            )
+        );
+    }
+
+    const SCRIPT: &str = concat!(
+        "$ErrorActionPreference = 'Stop'\r\n",
+        "$url = \"https://example.org/1.0/app.exe\"\r\n",
+        "\r\n",
+        "$packageArgs = @{\r\n",
+        "  packageName = $env:ChocolateyPackageName\r\n",
+        "  url64bit    = 'https://example.org/1.0/app64.exe'\r\n",
+        "  Checksum64  = 'abc'\r\n",
+        "}\r\n"
+    );
+
+    #[test]
+    fn update_script_variables_should_only_replace_variable_values() {
+        let mut values = HashMap::new();
+        values.insert(
+            "url".to_string(),
+            "https://example.org/2.0/app.exe".to_string(),
+        );
+        values.insert(
+            "url64bit".to_string(),
+            "https://example.org/2.0/app64.exe".to_string(),
+        );
+        values.insert("checksum64".to_string(), "def".to_string());
+        let expected = SCRIPT.replace("1.0", "2.0").replace("'abc'", "'def'");
+
+        let actual = update_script_variables(SCRIPT, &values).unwrap();
+
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn update_script_variables_should_escape_quotes_in_values() {
+        let mut values = HashMap::new();
+        values.insert("$url".to_string(), "it's a \"$test\"".to_string());
+        let content = "$url = 'old'\n$Url = \"old\"\n";
+
+        let actual = update_script_variables(content, &values).unwrap();
+
+        assert_eq!(
+            actual,
+            Some("$url = 'it''s a \"$test\"'\n$Url = \"it's a `\"`$test`\"\"\n".into())
+        );
+    }
+
+    #[test]
+    fn update_script_variables_should_return_none_when_up_to_date() {
+        let mut values = HashMap::new();
+        values.insert("checksum64".to_string(), "abc".to_string());
+
+        let actual = update_script_variables(SCRIPT, &values).unwrap();
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn update_script_variables_should_error_on_missing_variable() {
+        let mut values = HashMap::new();
+        values.insert("checksum".to_string(), "abc".to_string());
+
+        let actual = update_script_variables(SCRIPT, &values).unwrap_err();
+
+        assert_eq!(actual, UpdaterError::MissingVariable("checksum".into()));
+    }
+
+    #[test]
+    fn get_script_variables_should_read_values_of_variables() {
+        let actual = get_script_variables(SCRIPT, &["url64bit", "checksum64"]).unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual["url64bit"], "https://example.org/1.0/app64.exe");
+        assert_eq!(actual["checksum64"], "abc");
+    }
+
+    #[test]
+    fn get_script_variables_should_unescape_values() {
+        let content = "$url = 'it''s'\n$checksum = \"a `\"`$test`\"\"\n";
+
+        let actual = get_script_variables(content, &["url", "checksum"]).unwrap();
+
+        assert_eq!(actual["url"], "it's");
+        assert_eq!(actual["checksum"], "a \"$test\"");
+    }
+
+    #[rstest(
+        content,
+        element,
+        case("<package></package>", "metadata"),
+        case("<package><metadata><id>test</id></metadata></package>", "version"),
+        case("<package><metadata><version>1.0</metadata></package>", "version")
+    )]
+    fn update_nuspec_version_should_error_on_missing_element(content: &str, element: &str) {
+        let actual = update_nuspec_version(content, "1.0").unwrap_err();
+
+        assert_eq!(actual, UpdaterError::MissingElement(element.into()));
+    }
+
+    #[test]
+    fn render_template_should_replace_all_placeholders() {
+        let mut values = HashMap::new();
+        values.insert("checksum_type".to_string(), "sha256".to_string());
+        values.insert("checksum_x86".to_string(), "abc".to_string());
+
+        let actual = render_template(
+            "type: {{checksum_type}}\nx86: {{ checksum_x86 }} ({{checksum_type}})",
+            &values,
+        )
+        .unwrap();
+
+        assert_eq!(actual, "type: sha256\nx86: abc (sha256)");
+    }
+
+    #[test]
+    fn render_template_should_error_on_missing_value() {
+        let values = HashMap::new();
+
+        let actual = render_template("x64: {{url_x64}}", &values).unwrap_err();
+
+        assert_eq!(actual, UpdaterError::MissingVariable("url_x64".into()));
+    }
+}
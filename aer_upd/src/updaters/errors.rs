@@ -0,0 +1,44 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum UpdaterError {
+    /// The xml element with the specified name could not be found.
+    MissingElement(String),
+    /// The variable with the specified name could not be found.
+    MissingVariable(String),
+    /// The field with the specified name could not be found.
+    MissingField(String),
+    /// The manifest could not be parsed or written.
+    InvalidManifest(String),
+    /// The architecture with the specified name is not supported by the
+    /// package manager.
+    UnsupportedArchitecture(String),
+}
+
+impl fmt::Display for UpdaterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpdaterError::MissingElement(name) => {
+                write!(f, "The element '<{}>' could not be found!", name)
+            }
+            UpdaterError::MissingVariable(name) => {
+                write!(f, "The variable '{}' could not be found!", name)
+            }
+            UpdaterError::MissingField(name) => {
+                write!(f, "The field '{}' could not be found!", name)
+            }
+            UpdaterError::InvalidManifest(message) => {
+                write!(f, "The manifest is not valid: {}", message)
+            }
+            UpdaterError::UnsupportedArchitecture(arch) => {
+                write!(f, "The architecture '{}' is not supported!", arch)
+            }
+        }
+    }
+}
+
+impl Error for UpdaterError {}
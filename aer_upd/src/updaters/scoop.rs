@@ -0,0 +1,335 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Functions for updating the manifest of a scoop package.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Map, Serializer, Value};
+
+use crate::updaters::errors::UpdaterError;
+
+/// The url and hash of a single file in a scoop manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestFile {
+    pub url: String,
+    pub hash: String,
+}
+
+/// Gets the name scoop uses in the `architecture` object of a manifest for
+/// the specified architecture, or [None] if scoop do not support the
+/// architecture.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::updaters::scoop::manifest_architecture;
+///
+/// assert_eq!(manifest_architecture("x64"), Some("64bit"));
+/// assert_eq!(manifest_architecture("arm64"), Some("arm64"));
+/// assert_eq!(manifest_architecture("installer"), None);
+/// ```
+pub fn manifest_architecture(arch: &str) -> Option<&'static str> {
+    match arch {
+        "x86" => Some("32bit"),
+        "x64" => Some("64bit"),
+        "arm64" => Some("arm64"),
+        _ => None,
+    }
+}
+
+/// Returns the value of the `version` field of the specified manifest
+/// content.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::updaters::scoop::get_manifest_version;
+///
+/// let manifest = r#"{ "version": "1.0.0", "url": "https://example.org/test.zip" }"#;
+///
+/// assert_eq!(get_manifest_version(manifest).unwrap(), "1.0.0");
+/// ```
+pub fn get_manifest_version(content: &str) -> Result<String, UpdaterError> {
+    let manifest = parse_manifest(content)?;
+
+    manifest
+        .get("version")
+        .and_then(|version| version.as_str())
+        .map(|version| version.trim().to_owned())
+        .ok_or_else(|| UpdaterError::MissingField("version".into()))
+}
+
+/// Returns the url and hash of every file in the specified manifest content,
+/// keyed by the name of their architecture (*ie: `x64` for the `64bit`
+/// entry*). The top level file of manifests without an `architecture` object
+/// is keyed as `default`. Entries where the url or hash is not a single
+/// string are ignored.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::updaters::scoop::get_manifest_files;
+///
+/// let manifest = r#"{ "architecture": { "64bit": { "url": "https://example.org/x64.zip", "hash": "abc" } } }"#;
+///
+/// let files = get_manifest_files(manifest).unwrap();
+///
+/// assert_eq!(files["x64"].url, "https://example.org/x64.zip");
+/// assert_eq!(files["x64"].hash, "abc");
+/// ```
+pub fn get_manifest_files(content: &str) -> Result<BTreeMap<String, ManifestFile>, UpdaterError> {
+    let manifest = parse_manifest(content)?;
+    let mut files = BTreeMap::new();
+
+    if let Some(file) = get_file(&manifest) {
+        files.insert("default".to_owned(), file);
+    }
+
+    if let Some(Value::Object(architectures)) = manifest.get("architecture") {
+        for arch in &["x86", "x64", "arm64"] {
+            let entry = manifest_architecture(arch).and_then(|name| architectures.get(name));
+            if let Some(file) = entry.and_then(|entry| entry.as_object()).and_then(get_file) {
+                files.insert((*arch).to_owned(), file);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Updates the version, and the url and hash of each of the specified files
+/// in the manifest content. The files are keyed by the name of their
+/// architecture (*ie: `x64`*).
+///
+/// When the manifest do not have an `architecture` object and only a single
+/// file is specified, the top level `url` and `hash` fields are updated.
+/// Otherwise the files are written to the `architecture` object, which is
+/// created if it do not already exist. Every other field is kept as is.
+///
+/// ## Returns
+///
+/// The updated content, or [None] if the manifest already contains the
+/// specified values.
+///
+/// ## Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use aer_upd::updaters::scoop::{update_manifest, ManifestFile};
+///
+/// let manifest = "{\n    \"version\": \"1.0.0\",\n    \"url\": \"https://example.org/1.0.0.zip\",\n    \"hash\": \"abc\"\n}\n";
+/// let mut files = BTreeMap::new();
+/// files.insert(
+///     "x64".to_string(),
+///     ManifestFile {
+///         url: "https://example.org/2.0.0.zip".into(),
+///         hash: "def".into(),
+///     },
+/// );
+///
+/// let updated = update_manifest(manifest, "2.0.0", &files).unwrap();
+///
+/// assert_eq!(
+///     updated,
+///     Some("{\n    \"version\": \"2.0.0\",\n    \"url\": \"https://example.org/2.0.0.zip\",\n    \"hash\": \"def\"\n}\n".into())
+/// );
+/// ```
+pub fn update_manifest(
+    content: &str,
+    version: &str,
+    files: &BTreeMap<String, ManifestFile>,
+) -> Result<Option<String>, UpdaterError> {
+    let original = parse_manifest(content)?;
+    let mut manifest = original.clone();
+    manifest.insert("version".into(), Value::String(version.into()));
+
+    if files.len() == 1 && !manifest.contains_key("architecture") {
+        let file = files.values().next().unwrap();
+        set_file(&mut manifest, file);
+    } else {
+        let architectures = manifest
+            .entry("architecture")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| {
+                UpdaterError::InvalidManifest("'architecture' is not an object".into())
+            })?;
+
+        for (arch, file) in files {
+            let name = manifest_architecture(arch)
+                .ok_or_else(|| UpdaterError::UnsupportedArchitecture(arch.clone()))?;
+            let entry = architectures
+                .entry(name)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| {
+                    UpdaterError::InvalidManifest(format!(
+                        "'architecture.{}' is not an object",
+                        name
+                    ))
+                })?;
+            set_file(entry, file);
+        }
+    }
+
+    if manifest == original {
+        return Ok(None);
+    }
+
+    let mut result = Vec::with_capacity(content.len());
+    let mut serializer =
+        Serializer::with_formatter(&mut result, PrettyFormatter::with_indent(b"    "));
+    manifest
+        .serialize(&mut serializer)
+        .map_err(|err| UpdaterError::InvalidManifest(err.to_string()))?;
+    let mut result =
+        String::from_utf8(result).map_err(|err| UpdaterError::InvalidManifest(err.to_string()))?;
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+
+    Ok(Some(result))
+}
+
+fn parse_manifest(content: &str) -> Result<Map<String, Value>, UpdaterError> {
+    match serde_json::from_str(content) {
+        Ok(Value::Object(manifest)) => Ok(manifest),
+        Ok(_) => Err(UpdaterError::InvalidManifest(
+            "The manifest is not a json object".into(),
+        )),
+        Err(err) => Err(UpdaterError::InvalidManifest(err.to_string())),
+    }
+}
+
+fn get_file(entry: &Map<String, Value>) -> Option<ManifestFile> {
+    Some(ManifestFile {
+        url: entry.get("url")?.as_str()?.to_owned(),
+        hash: entry.get("hash")?.as_str()?.to_owned(),
+    })
+}
+
+fn set_file(entry: &mut Map<String, Value>, file: &ManifestFile) {
+    entry.insert("url".into(), Value::String(file.url.clone()));
+    entry.insert("hash".into(), Value::String(file.hash.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+    "version": "3.1.0",
+    "description": "A test manifest",
+    "architecture": {
+        "64bit": {
+            "url": "https://example.org/3.1.0/test-x64.zip",
+            "hash": "abc",
+            "extract_dir": "test"
+        }
+    },
+    "bin": "test.exe"
+}
+"#;
+
+    fn file(url: &str, hash: &str) -> ManifestFile {
+        ManifestFile {
+            url: url.into(),
+            hash: hash.into(),
+        }
+    }
+
+    #[test]
+    fn update_manifest_should_update_architecture_entries() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "x64".to_string(),
+            file("https://example.org/3.2.0/test-x64.zip", "def"),
+        );
+        files.insert(
+            "x86".to_string(),
+            file("https://example.org/3.2.0/test-x86.zip", "ghi"),
+        );
+        let expected = r#"{
+    "version": "3.2.0",
+    "description": "A test manifest",
+    "architecture": {
+        "64bit": {
+            "url": "https://example.org/3.2.0/test-x64.zip",
+            "hash": "def",
+            "extract_dir": "test"
+        },
+        "32bit": {
+            "url": "https://example.org/3.2.0/test-x86.zip",
+            "hash": "ghi"
+        }
+    },
+    "bin": "test.exe"
+}
+"#;
+
+        let actual = update_manifest(MANIFEST, "3.2.0", &files).unwrap();
+
+        assert_eq!(actual.as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn update_manifest_should_return_none_when_up_to_date() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "x64".to_string(),
+            file("https://example.org/3.1.0/test-x64.zip", "abc"),
+        );
+
+        let actual = update_manifest(MANIFEST, "3.1.0", &files).unwrap();
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn update_manifest_should_return_error_on_unsupported_architecture() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "arm".to_string(),
+            file("https://example.org/arm.zip", "abc"),
+        );
+
+        let actual = update_manifest(MANIFEST, "3.2.0", &files);
+
+        assert_eq!(
+            actual,
+            Err(UpdaterError::UnsupportedArchitecture("arm".into()))
+        );
+    }
+
+    #[test]
+    fn get_manifest_files_should_return_top_level_file() {
+        let manifest = r#"{ "url": "https://example.org/a.zip", "hash": "abc" }"#;
+
+        let actual = get_manifest_files(manifest).unwrap();
+
+        assert_eq!(
+            actual.get("default"),
+            Some(&file("https://example.org/a.zip", "abc"))
+        );
+    }
+
+    #[test]
+    fn get_manifest_files_should_ignore_lists_of_urls() {
+        let manifest = r#"{ "url": ["https://example.org/a.zip"], "hash": ["abc"] }"#;
+
+        let actual = get_manifest_files(manifest).unwrap();
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn get_manifest_version_should_return_error_without_version() {
+        let actual = get_manifest_version("{}");
+
+        assert_eq!(actual, Err(UpdaterError::MissingField("version".into())));
+    }
+}
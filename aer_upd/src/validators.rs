@@ -0,0 +1,1334 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the rules used to validate package data before any update is
+//! started, to be able to report problems in the data without needing to
+//! do any web requests first.
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use aer_data::prelude::*;
+
+use crate::regexes::{get_regex, template_captures};
+
+/// The available set of rules that package data can be validated against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleSet {
+    /// The rules that is necessary for the update process to be able to run.
+    Core,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::Core
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "core" => Ok(RuleSet::Core),
+            _ => Err("The value is not a supported rule set!"),
+        }
+    }
+}
+
+impl Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleSet::Core => f.write_str("core"),
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["core"];
+
+        VARIANTS
+    }
+}
+
+/// A single problem that was found when validating package data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The path to the field that the violation is for (*ie:
+    /// `metadata.id`*).
+    pub field: String,
+    /// The message describing the violation.
+    pub message: String,
+}
+
+impl Violation {
+    fn new(field: &str, message: &str) -> Violation {
+        Violation {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// A regular expression in the package data that could not be compiled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexViolation {
+    /// The path to the field that the regex is located in (*ie:
+    /// `updater.chocolatey.regexes.arch64`*).
+    pub field: String,
+    /// The architecture key the regex is used for, or [None] if the regex is
+    /// part of a parse url.
+    pub key: Option<String>,
+    /// The regex that could not be compiled.
+    pub regex: String,
+    /// The error returned when compiling the regex.
+    pub error: regex::Error,
+}
+
+#[cfg(feature = "chocolatey")]
+impl RegexViolation {
+    fn check(field: String, key: Option<&str>, regex: &str) -> Option<RegexViolation> {
+        get_regex(regex).err().map(|error| RegexViolation {
+            field,
+            key: key.map(|key| key.to_owned()),
+            regex: regex.into(),
+            error,
+        })
+    }
+}
+
+impl Display for RegexViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.key {
+            Some(ref key) => write!(
+                f,
+                "{} (architecture key '{}'): The regex '{}' is not valid: {}",
+                self.field, key, self.regex, self.error
+            ),
+            None => write!(
+                f,
+                "{}: The regex '{}' is not valid: {}",
+                self.field, self.regex, self.error
+            ),
+        }
+    }
+}
+
+impl From<RegexViolation> for Violation {
+    fn from(violation: RegexViolation) -> Violation {
+        Violation {
+            field: violation.field,
+            message: format!("The regex is not valid: {}", violation.error),
+        }
+    }
+}
+
+/// Validates the package data against the specified rule set, and returns all
+/// of the violations that was found. An empty list means the data is valid.
+pub fn validate(data: &PackageData, rule_set: RuleSet) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    match rule_set {
+        RuleSet::Core => {
+            validate_core_metadata(data, &mut violations);
+            #[cfg(feature = "chocolatey")]
+            validate_core_chocolatey(data, &mut violations);
+            #[cfg(feature = "scoop")]
+            validate_core_scoop(data, &mut violations);
+        }
+    }
+
+    violations
+}
+
+/// Finds every regex in the package data that can not be compiled, both the
+/// regexes used for each architecture and the regexes of the parse urls.
+///
+/// Unlike [validate], only regexes are checked, which allows the caller to
+/// report every invalid regex with the architecture key it belongs to.
+pub fn find_invalid_regexes(data: &PackageData) -> Vec<RegexViolation> {
+    #[allow(unused_mut)]
+    let mut found = vec![];
+
+    #[cfg(feature = "chocolatey")]
+    if data.updater().has_chocolatey() {
+        find_invalid_link_regexes(&data.updater().chocolatey(), "chocolatey", &mut found);
+    }
+    #[cfg(feature = "scoop")]
+    if data.updater().has_scoop() {
+        find_invalid_link_regexes(&data.updater().scoop().link_data(), "scoop", &mut found);
+    }
+
+    found
+}
+
+#[cfg(feature = "chocolatey")]
+fn find_invalid_link_regexes(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    updater: &str,
+    found: &mut Vec<RegexViolation>,
+) {
+    let prefix = format!("updater.{}", updater);
+
+    match choco.parse_url {
+        Some(chocolatey::ChocolateyParseUrls::Single(ref parse_url)) => {
+            found.extend(check_parse_url_regex(
+                parse_url,
+                &format!("{}.parse_url", prefix),
+            ));
+        }
+        Some(chocolatey::ChocolateyParseUrls::Multiple(ref entries)) => {
+            for (index, entry) in entries.iter().enumerate() {
+                found.extend(check_parse_url_regex(
+                    &entry.url,
+                    &format!("{}.parse_url[{}].url", prefix, index),
+                ));
+            }
+        }
+        None => {}
+    }
+
+    found.extend(check_arch_regexes(choco, &prefix));
+}
+
+/// Checks the regexes used for each architecture and the patterns used to
+/// detect the architecture of links, sorted by their key.
+#[cfg(feature = "chocolatey")]
+fn check_arch_regexes(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    prefix: &str,
+) -> Vec<RegexViolation> {
+    let mut regexes: Vec<_> = choco.regexes().iter().collect();
+    regexes.sort_by(|(left, _), (right, _)| left.cmp(right));
+    let mut found = vec![];
+
+    for (key, values) in regexes {
+        let is_list = matches!(values, chocolatey::ChocolateyRegexes::Multiple(_));
+
+        for (index, regex) in values.as_slice().iter().enumerate() {
+            let field = if is_list {
+                format!("{}.regexes.{}[{}]", prefix, key, index)
+            } else {
+                format!("{}.regexes.{}", prefix, key)
+            };
+            found.extend(RegexViolation::check(field, Some(key), regex));
+        }
+    }
+
+    let mut patterns: Vec<_> = choco.auto_arch_patterns.iter().collect();
+    patterns.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    for (key, values) in patterns {
+        for (index, pattern) in values.iter().enumerate() {
+            let field = format!("{}.auto_arch_patterns.{}[{}]", prefix, key, index);
+            let regex = chocolatey::arch_pattern_regex(&[pattern]);
+            found.extend(
+                RegexViolation::check(field, Some(key), &regex).map(|violation| RegexViolation {
+                    regex: pattern.clone(),
+                    ..violation
+                }),
+            );
+        }
+    }
+
+    found
+}
+
+#[cfg(feature = "chocolatey")]
+fn check_parse_url_regex(
+    parse_url: &chocolatey::ChocolateyParseUrl,
+    field: &str,
+) -> Vec<RegexViolation> {
+    match parse_url {
+        chocolatey::ChocolateyParseUrl::UrlWithRegex {
+            regex,
+            arch_regexes,
+            ..
+        } => {
+            let mut found: Vec<RegexViolation> =
+                RegexViolation::check(format!("{}.regex", field), None, regex)
+                    .into_iter()
+                    .collect();
+            let mut arch_regexes: Vec<_> = arch_regexes.iter().collect();
+            arch_regexes.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+            for (key, regex) in arch_regexes {
+                found.extend(RegexViolation::check(
+                    format!("{}.arch_regexes.{}", field, key),
+                    Some(key),
+                    regex,
+                ));
+            }
+            found
+        }
+        chocolatey::ChocolateyParseUrl::Json {
+            regex: Some(regex), ..
+        } => RegexViolation::check(format!("{}.regex", field), None, regex)
+            .into_iter()
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn validate_core_metadata(data: &PackageData, violations: &mut Vec<Violation>) {
+    let metadata = data.metadata();
+
+    if metadata.id().trim().is_empty() {
+        violations.push(Violation::new(
+            "metadata.id",
+            "The package identifier can not be empty!",
+        ));
+    } else if metadata.id().contains(char::is_whitespace) {
+        violations.push(Violation::new(
+            "metadata.id",
+            "The package identifier can not contain whitespace!",
+        ));
+    }
+
+    if metadata.maintainers().iter().all(|m| m.trim().is_empty()) {
+        violations.push(Violation::new(
+            "metadata.maintainers",
+            "At least one maintainer must be specified!",
+        ));
+    }
+
+    if metadata.summary.trim().is_empty() {
+        violations.push(Violation::new(
+            "metadata.summary",
+            "The summary can not be empty!",
+        ));
+    }
+}
+
+#[cfg(feature = "chocolatey")]
+fn validate_core_chocolatey(data: &PackageData, violations: &mut Vec<Violation>) {
+    if !data.updater().has_chocolatey() {
+        return;
+    }
+
+    validate_link_data(&data.updater().chocolatey(), "chocolatey", violations);
+}
+
+#[cfg(feature = "scoop")]
+fn validate_core_scoop(data: &PackageData, violations: &mut Vec<Violation>) {
+    if !data.updater().has_scoop() {
+        return;
+    }
+
+    validate_link_data(&data.updater().scoop().link_data(), "scoop", violations);
+}
+
+/// Validates that regexes have been specified for the specified
+/// architecture, where aliases of the architecture are treated as the same
+/// architecture (*ie: `arch64` and `x64`*).
+#[cfg(feature = "chocolatey")]
+fn validate_arch_regexes(
+    arch: &str,
+    regexes: &std::collections::HashMap<String, chocolatey::ChocolateyRegexes>,
+    field: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let arch = chocolatey::architecture_name(arch);
+
+    if !regexes
+        .keys()
+        .any(|key| chocolatey::architecture_name(key) == arch)
+    {
+        violations.push(Violation {
+            field: field.into(),
+            message: format!(
+                "No regexes have been specified for the {} architecture!",
+                arch
+            ),
+        });
+    }
+}
+
+/// Validates that the names of the specified headers only contain the
+/// characters allowed in a http header name.
+#[cfg(feature = "chocolatey")]
+fn validate_headers(
+    headers: &std::collections::HashMap<String, String>,
+    field: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let mut names: Vec<_> = headers.keys().collect();
+    names.sort();
+
+    for name in names {
+        let is_valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+
+        if !is_valid {
+            violations.push(Violation {
+                field: format!("{}.{}", field, name),
+                message: "The header name is not valid!".into(),
+            });
+        }
+    }
+}
+
+/// Validates the settings used to find the links of a package, which are
+/// shared between the chocolatey and scoop updaters. The name of the updater
+/// is used as part of the field of the violations.
+#[cfg(feature = "chocolatey")]
+fn validate_link_data(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    updater: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let prefix = format!("updater.{}", updater);
+
+    if let Some(ref name) = choco.regex_preset {
+        if chocolatey::regex_preset(name).is_none() {
+            violations.push(Violation {
+                field: format!("{}.regex_preset", prefix),
+                message: format!(
+                    "The regex preset '{}' does not exist, the available presets are: {}!",
+                    name,
+                    chocolatey::REGEX_PRESETS.join(", ")
+                ),
+            });
+        }
+    }
+    let preset_data = choco.with_regex_preset();
+    let choco = preset_data.as_ref();
+
+    match choco.parse_url {
+        None => violations.push(Violation {
+            field: format!("{}.parse_url", prefix),
+            message: format!(
+                "A url to parse must be specified when the {} updater is used!",
+                updater
+            ),
+        }),
+        Some(chocolatey::ChocolateyParseUrls::Single(ref parse_url)) => {
+            validate_parse_url(parse_url, &format!("{}.parse_url", prefix), violations);
+        }
+        Some(chocolatey::ChocolateyParseUrls::Multiple(ref entries)) => {
+            if entries.is_empty() {
+                violations.push(Violation {
+                    field: format!("{}.parse_url", prefix),
+                    message: "At least one url to parse must be specified!".into(),
+                });
+            }
+
+            for (index, entry) in entries.iter().enumerate() {
+                let field = format!("{}.parse_url[{}]", prefix, index);
+                validate_parse_url(&entry.url, &format!("{}.url", field), violations);
+                validate_headers(&entry.headers, &format!("{}.headers", field), violations);
+
+                if !choco.auto_arch {
+                    for arch in &entry.archs {
+                        validate_arch_regexes(
+                            arch,
+                            choco.regexes(),
+                            &format!("{}.archs", field),
+                            violations,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    validate_headers(&choco.headers, &format!("{}.headers", prefix), violations);
+
+    violations.extend(
+        check_arch_regexes(choco, &prefix)
+            .into_iter()
+            .map(Violation::from),
+    );
+    validate_version_template(choco, &prefix, violations);
+
+    if let (Some(min_version), Some(max_version)) = (&choco.min_version, &choco.max_version) {
+        if min_version >= max_version {
+            violations.push(Violation {
+                field: format!("{}.min_version", prefix),
+                message: "The minimum version must be lower than the maximum version!".into(),
+            });
+        }
+    }
+
+    let mut checksums: Vec<_> = choco.expected_checksums.iter().collect();
+    checksums.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    for (key, expected) in checksums {
+        let field = format!("{}.expected_checksums.{}", prefix, key);
+
+        if !choco.auto_arch {
+            validate_arch_regexes(key, choco.regexes(), &field, violations);
+        }
+
+        let checksum = expected.checksum.trim();
+        let checksum_type = expected.checksum_type.unwrap_or(choco.checksum_type);
+        let (name, expected_len) = match checksum_type {
+            chocolatey::ChocolateyChecksumType::Md5 => ("md5", 32),
+            chocolatey::ChocolateyChecksumType::Sha1 => ("sha1", 40),
+            chocolatey::ChocolateyChecksumType::Sha256 => ("sha256", 64),
+            chocolatey::ChocolateyChecksumType::Sha512 => ("sha512", 128),
+        };
+
+        if checksum.is_empty() {
+            violations.push(Violation {
+                field: format!("{}.checksum", field),
+                message: "The checksum can not be empty!".into(),
+            });
+        } else if checksum.len() != expected_len || !checksum.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            violations.push(Violation {
+                field: format!("{}.checksum", field),
+                message: format!("The checksum is not a valid {} checksum!", name),
+            });
+        }
+    }
+
+    let mut content_types: Vec<_> = choco.expected_content_types.iter().collect();
+    content_types.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    for (key, content_type) in content_types {
+        let field = format!("{}.expected_content_types.{}", prefix, key);
+
+        if !choco.auto_arch {
+            validate_arch_regexes(key, choco.regexes(), &field, violations);
+        }
+
+        let mut parts = content_type.trim().splitn(2, '/');
+        let is_valid = matches!(
+            (parts.next(), parts.next()),
+            (Some(kind), Some(subtype)) if !kind.is_empty() && !subtype.is_empty()
+        );
+
+        if !is_valid {
+            violations.push(Violation {
+                field,
+                message: "The content type must be in the format 'type/subtype'!".into(),
+            });
+        }
+    }
+}
+
+/// Validates that the version template can be parsed, and that every capture
+/// group referenced by the template is defined in each of the regexes of the
+/// architectures.
+#[cfg(feature = "chocolatey")]
+fn validate_version_template(
+    choco: &chocolatey::ChocolateyUpdaterData,
+    prefix: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let template = match choco.version_template {
+        Some(ref template) => template,
+        None => return,
+    };
+    let field = format!("{}.version_template", prefix);
+    let names = match template_captures(template) {
+        Some(names) if !names.is_empty() => names,
+        Some(_) => {
+            violations.push(Violation {
+                field,
+                message: "The version template must reference at least one capture group!".into(),
+            });
+            return;
+        }
+        None => {
+            violations.push(Violation {
+                field,
+                message: "The version template is not valid, capture groups must be referenced \
+                          as '{name}'!"
+                    .into(),
+            });
+            return;
+        }
+    };
+
+    let mut regexes: Vec<_> = choco.regexes().iter().collect();
+    regexes.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    for (key, regexes) in regexes {
+        for regex in regexes.as_slice() {
+            let re = match get_regex(regex) {
+                Ok(re) => re,
+                Err(_) => continue,
+            };
+
+            for name in &names {
+                if !re.capture_names().flatten().any(|capture| capture == *name) {
+                    violations.push(Violation {
+                        field: field.clone(),
+                        message: format!(
+                            "The capture group '{}' is not defined in the regex '{}' of '{}'!",
+                            name, regex, key
+                        ),
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "chocolatey")]
+fn validate_parse_url(
+    parse_url: &chocolatey::ChocolateyParseUrl,
+    field: &str,
+    violations: &mut Vec<Violation>,
+) {
+    match parse_url {
+        chocolatey::ChocolateyParseUrl::UrlWithSelector { link_selector, .. } => {
+            if link_selector.trim().is_empty() {
+                violations.push(Violation {
+                    field: format!("{}.link_selector", field),
+                    message: "The selector can not be empty!".into(),
+                });
+            }
+        }
+        chocolatey::ChocolateyParseUrl::Json { selector, .. } => {
+            if selector.trim().is_empty() {
+                violations.push(Violation {
+                    field: format!("{}.selector", field),
+                    message: "The selector can not be empty!".into(),
+                });
+            }
+        }
+        chocolatey::ChocolateyParseUrl::GitHub {
+            owner,
+            repo,
+            token_env,
+        } => {
+            if owner.trim().is_empty() || repo.trim().is_empty() {
+                violations.push(Violation {
+                    field: field.into(),
+                    message: "Both the owner and the repository must be specified when using \
+                              GitHub!"
+                        .into(),
+                });
+            }
+            if token_env
+                .as_ref()
+                .map_or(false, |env| env.trim().is_empty())
+            {
+                violations.push(Violation {
+                    field: format!("{}.token_env", field),
+                    message: "The environment variable of the token can not be empty!".into(),
+                });
+            }
+        }
+        chocolatey::ChocolateyParseUrl::UrlWithRegex {
+            follow_first,
+            arch_regexes,
+            ..
+        } => {
+            if !follow_first && !arch_regexes.is_empty() {
+                violations.push(Violation {
+                    field: format!("{}.arch_regexes", field),
+                    message: "The architecture regexes are only used when the matched links are \
+                              followed!"
+                        .into(),
+                });
+            }
+        }
+        chocolatey::ChocolateyParseUrl::External { command } => {
+            if command.trim().is_empty() {
+                violations.push(Violation {
+                    field: format!("{}.command", field),
+                    message: "The command of the external resolver can not be empty!".into(),
+                });
+            }
+        }
+        chocolatey::ChocolateyParseUrl::Url(_) => {}
+    }
+
+    violations.extend(
+        check_parse_url_regex(parse_url, field)
+            .into_iter()
+            .map(Violation::from),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use aer_data::prelude::chocolatey::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn create_valid_data() -> PackageData {
+        let mut data = PackageData::new("test-package");
+        data.metadata_mut().summary = "Some summary".into();
+
+        data
+    }
+
+    #[rstest(
+        test,
+        expected,
+        case("core", RuleSet::Core),
+        case("Core", RuleSet::Core),
+        case(" CORE ", RuleSet::Core)
+    )]
+    fn from_str_should_create_expected_rule_set(test: &str, expected: RuleSet) {
+        assert_eq!(RuleSet::from_str(test), Ok(expected));
+    }
+
+    #[test]
+    fn from_str_should_return_error_on_unknown_value() {
+        assert_eq!(
+            RuleSet::from_str("unknown"),
+            Err("The value is not a supported rule set!")
+        );
+    }
+
+    #[test]
+    fn default_should_be_core() {
+        assert_eq!(RuleSet::default(), RuleSet::Core);
+    }
+
+    #[test]
+    fn validate_should_not_return_violations_for_valid_data() {
+        let data = create_valid_data();
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn validate_should_return_all_metadata_violations() {
+        let mut data = PackageData::new("");
+        data.metadata_mut().set_maintainers::<&str>(&[]);
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new("metadata.id", "The package identifier can not be empty!"),
+                Violation::new(
+                    "metadata.maintainers",
+                    "At least one maintainer must be specified!"
+                ),
+                Violation::new("metadata.summary", "The summary can not be empty!"),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_whitespace_in_id() {
+        let mut data = create_valid_data();
+        *data.metadata_mut() = {
+            let mut metadata = PackageMetadata::new("test package");
+            metadata.summary = "Some summary".into();
+            metadata
+        };
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "metadata.id",
+                "The package identifier can not contain whitespace!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_missing_parse_url() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey(ChocolateyUpdaterData::new());
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.parse_url",
+                "A url to parse must be specified when the chocolatey updater is used!"
+            )]
+        );
+    }
+
+    #[cfg(feature = "scoop")]
+    #[test]
+    fn validate_should_return_violations_for_scoop_updater() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_scoop({
+            let mut scoop = aer_data::prelude::scoop::ScoopUpdaterData::new();
+            scoop.add_regex("arch64", "(invalid");
+            scoop
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(
+            fields,
+            vec!["updater.scoop.parse_url", "updater.scoop.regexes.arch64"]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violations_on_invalid_json_parse_url() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Json {
+                url: Url::parse("https://example.org/latest.json").unwrap(),
+                selector: " ".into(),
+                version_field: None,
+                regex: Some("(unclosed".into()),
+            }));
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "updater.chocolatey.parse_url.selector",
+                "updater.chocolatey.parse_url.regex"
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_should_compile_all_regexes_in_list() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.add_regexes("x64", &[r"-x64\.exe$", "[invalid", r"-amd64\.exe$"]);
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["updater.chocolatey.regexes.x64[1]"]);
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_empty_github_repository() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::GitHub {
+                owner: "codecov".into(),
+                repo: "".into(),
+                token_env: None,
+            }));
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.parse_url",
+                "Both the owner and the repository must be specified when using GitHub!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_empty_external_command() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::External {
+                command: " ".into(),
+            }));
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.parse_url.command",
+                "The command of the external resolver can not be empty!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_unknown_regex_preset() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.regex_preset = Some("unknown".into());
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.regex_preset",
+                "The regex preset 'unknown' does not exist, the available presets are: fosshub, \
+                 github-release, sourceforge!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_use_regexes_of_regex_preset() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Multiple(vec![
+                ChocolateyParseUrlEntry {
+                    archs: vec!["arch64".into()],
+                    ..ChocolateyParseUrlEntry::new(ChocolateyParseUrl::Url(
+                        Url::parse("https://example.org").unwrap(),
+                    ))
+                },
+            ]));
+            choco.regex_preset = Some("fosshub".into());
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_empty_github_token_env() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::GitHub {
+                owner: "codecov".into(),
+                repo: "codecov-exe".into(),
+                token_env: Some(" ".into()),
+            }));
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.parse_url.token_env",
+                "The environment variable of the token can not be empty!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violations_for_all_invalid_regexes() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(
+                ChocolateyParseUrl::UrlWithRegex {
+                    url: Url::parse("https://example.org").unwrap(),
+                    regex: "(unclosed".into(),
+                    follow_first: true,
+                    follow_highest: false,
+                    arch_regexes: HashMap::new(),
+                },
+            ));
+            choco.add_regex("arch32", r"\.exe$");
+            choco.add_regex("arch64", "[invalid");
+            choco.add_regex("other", "(?P<version");
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "updater.chocolatey.parse_url.regex",
+                "updater.chocolatey.regexes.arch64",
+                "updater.chocolatey.regexes.other"
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violations_for_arch_regexes_without_following() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(
+                ChocolateyParseUrl::UrlWithRegex {
+                    url: Url::parse("https://example.org").unwrap(),
+                    regex: "/downloads/".into(),
+                    follow_first: false,
+                    follow_highest: false,
+                    arch_regexes: {
+                        let mut regexes = HashMap::new();
+                        regexes.insert("arch64".to_string(), "[invalid".to_string());
+                        regexes
+                    },
+                },
+            ));
+            choco.add_regex("arch64", r"\.exe$");
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "updater.chocolatey.parse_url.arch_regexes",
+                "updater.chocolatey.parse_url.arch_regexes.arch64"
+            ]
+        );
+    }
+
+    #[cfg(feature = "scoop")]
+    #[test]
+    fn find_invalid_regexes_should_return_regexes_of_all_updaters() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(
+                ChocolateyParseUrl::UrlWithRegex {
+                    url: Url::parse("https://example.org").unwrap(),
+                    regex: "(unclosed".into(),
+                    follow_first: true,
+                    follow_highest: false,
+                    arch_regexes: HashMap::new(),
+                },
+            ));
+            choco.add_regex("arch32", r"\.exe$");
+            choco.add_regex("arch64", "[invalid");
+            choco
+        });
+        data.updater_mut().set_scoop({
+            let mut scoop = aer_data::prelude::scoop::ScoopUpdaterData::new();
+            scoop.add_regex("x64", "(?P<version");
+            scoop
+        });
+
+        let actual = find_invalid_regexes(&data);
+        let found: Vec<(&str, Option<&str>, &str)> = actual
+            .iter()
+            .map(|v| (v.field.as_str(), v.key.as_deref(), v.regex.as_str()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                ("updater.chocolatey.parse_url.regex", None, "(unclosed"),
+                (
+                    "updater.chocolatey.regexes.arch64",
+                    Some("arch64"),
+                    "[invalid"
+                ),
+                ("updater.scoop.regexes.x64", Some("x64"), "(?P<version"),
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_violation_should_include_key_and_regex() {
+        let violation = RegexViolation {
+            field: "updater.chocolatey.regexes.arch64".into(),
+            key: Some("arch64".into()),
+            regex: "[invalid".into(),
+            error: regex::Regex::new("[invalid").unwrap_err(),
+        };
+
+        assert_eq!(
+            violation.to_string(),
+            format!(
+                "updater.chocolatey.regexes.arch64 (architecture key 'arch64'): The regex \
+                 '[invalid' is not valid: {}",
+                regex::Regex::new("[invalid").unwrap_err()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_should_allow_missing_architecture_regexes_with_auto_arch() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.auto_arch = true;
+            choco.add_regex("installer", r"setup-(?P<version>[\d\.]+)");
+            choco
+                .auto_arch_patterns
+                .insert("arm".into(), vec!["armv7".into(), "(unclosed".into()]);
+            choco.expected_checksums.insert(
+                "x64".into(),
+                ChocolateyExpectedChecksum {
+                    checksum: "a".repeat(64),
+                    checksum_type: None,
+                },
+            );
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["updater.chocolatey.auto_arch_patterns.arm[1]"]);
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_capture_missing_from_version_template_regex() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.version_template = Some("{year}.{month}.{day}".into());
+            choco.add_regex(
+                "x64",
+                r"(?P<year>\d{4})_(?P<month>\d{2})_(?P<day>\d{2})-x64",
+            );
+            choco.add_regex("x86", r"(?P<year>\d{4})_(?P<month>\d{2})-x86");
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.version_template",
+                r"The capture group 'day' is not defined in the regex '(?P<year>\d{4})_(?P<month>\d{2})-x86' of 'x86'!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violation_on_invalid_version_template() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.version_template = Some("{major.{minor}".into());
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["updater.chocolatey.version_template"]);
+    }
+
+    #[test]
+    fn validate_should_return_violations_for_invalid_parse_url_entries() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Multiple(vec![
+                ChocolateyParseUrlEntry::new(ChocolateyParseUrl::Url(
+                    Url::parse("https://example.org").unwrap(),
+                )),
+                ChocolateyParseUrlEntry {
+                    url: ChocolateyParseUrl::UrlWithRegex {
+                        url: Url::parse("https://example.org").unwrap(),
+                        regex: "(unclosed".into(),
+                        follow_first: true,
+                        follow_highest: false,
+                        arch_regexes: HashMap::new(),
+                    },
+                    archs: vec!["arch32".into(), "arm64".into()],
+                    accept_language: None,
+                    headers: HashMap::new(),
+                },
+            ]));
+            choco.add_regex("x86", r"\.exe$");
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "updater.chocolatey.parse_url[1].url.regex",
+                    &format!(
+                        "The regex is not valid: {}",
+                        regex::Regex::new("(unclosed").unwrap_err()
+                    )
+                ),
+                Violation::new(
+                    "updater.chocolatey.parse_url[1].archs",
+                    "No regexes have been specified for the arm64 architecture!"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violation_when_min_version_is_not_lower_than_max_version() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.min_version = Some(Versions::parse("4.0.0").unwrap());
+            choco.max_version = Some(Versions::parse("3.0.0").unwrap());
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "updater.chocolatey.min_version",
+                "The minimum version must be lower than the maximum version!"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violations_for_invalid_header_names() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            let mut entry = ChocolateyParseUrlEntry::new(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            ));
+            entry.headers.insert("Invalid Name".into(), "test".into());
+            choco.parse_url = Some(ChocolateyParseUrls::Multiple(vec![entry]));
+            choco
+                .headers
+                .insert("X-Api-Key".into(), "${API_KEY}".into());
+            choco.headers.insert("Invalid:Name".into(), "test".into());
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "updater.chocolatey.parse_url[0].headers.Invalid Name",
+                    "The header name is not valid!"
+                ),
+                Violation::new(
+                    "updater.chocolatey.headers.Invalid:Name",
+                    "The header name is not valid!"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violations_for_invalid_expected_checksums() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.add_regex("arch32", r"\.exe$");
+            choco.add_regex("arch64", r"\.exe$");
+            choco.expected_checksums.insert(
+                "x86".into(),
+                ChocolateyExpectedChecksum::new(&"a".repeat(64), None),
+            );
+            choco.expected_checksums.insert(
+                "x64".into(),
+                ChocolateyExpectedChecksum::new("abc", Some(ChocolateyChecksumType::Md5)),
+            );
+            choco
+                .expected_checksums
+                .insert("arm64".into(), ChocolateyExpectedChecksum::new("", None));
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "updater.chocolatey.expected_checksums.arm64",
+                    "No regexes have been specified for the arm64 architecture!"
+                ),
+                Violation::new(
+                    "updater.chocolatey.expected_checksums.arm64.checksum",
+                    "The checksum can not be empty!"
+                ),
+                Violation::new(
+                    "updater.chocolatey.expected_checksums.x64.checksum",
+                    "The checksum is not a valid md5 checksum!"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_should_return_violations_for_invalid_expected_content_types() {
+        let mut data = create_valid_data();
+        data.updater_mut().set_chocolatey({
+            let mut choco = ChocolateyUpdaterData::new();
+            choco.parse_url = Some(ChocolateyParseUrls::Single(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org").unwrap(),
+            )));
+            choco.add_regex("arch32", r"\.exe$");
+            choco.add_regex("arch64", r"\.exe$");
+            choco
+                .expected_content_types
+                .insert("x86".into(), "application/*".into());
+            choco
+                .expected_content_types
+                .insert("x64".into(), "application".into());
+            choco
+                .expected_content_types
+                .insert("arm64".into(), "application/zip".into());
+            choco
+        });
+
+        let violations = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "updater.chocolatey.expected_content_types.arm64",
+                    "No regexes have been specified for the arm64 architecture!"
+                ),
+                Violation::new(
+                    "updater.chocolatey.expected_content_types.x64",
+                    "The content type must be in the format 'type/subtype'!"
+                ),
+            ]
+        );
+    }
+}
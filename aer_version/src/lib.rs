@@ -4,6 +4,7 @@
 
 mod versions;
 
+use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt::Display;
 
@@ -59,6 +60,51 @@ impl Versions {
         }
     }
 
+    /// Parses the specified version the same way as [parse](Versions::parse),
+    /// except that any numeric parts after the fourth part of the version
+    /// are dropped (*ie: `3.2.1.0.5` is parsed as `3.2.1.0`*).
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn parse_truncated(val: &str) -> Result<Versions, Box<dyn std::error::Error>> {
+        let numeric_len = val
+            .find(|ch: char| !ch.is_digit(10) && ch != '.')
+            .unwrap_or_else(|| val.len());
+        let (numeric, rest) = val.split_at(numeric_len);
+        let parts: Vec<&str> = numeric.split('.').take(4).collect();
+
+        Versions::parse(&format!("{}{}", parts.join("."), rest))
+    }
+
+    /// Normalizes the version to the `major.minor.patch[.revision][-prerelease]`
+    /// format accepted by chocolatey. Missing parts are padded with `0`, the
+    /// revision is only included when it is not `0` and any pre-release
+    /// identifiers are kept in the format used by chocolatey (*ie:
+    /// `2.0.0-beta.5` becomes `2.0.0-beta0005`*).
+    ///
+    /// Unlike [to_choco](Versions::to_choco), numbers higher than `255` are
+    /// kept as they are, which allows date based versions (*ie:
+    /// `2021.10.14`*).
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn to_chocolatey(&self) -> String {
+        let [major, minor, patch, revision] = match self {
+            Versions::SemVer(semver) => [semver.major, semver.minor, semver.patch, 0],
+            Versions::Choco(ver) => ver.numeric_parts(),
+        };
+
+        let mut result = format!("{}.{}.{}", major, minor, patch);
+        if revision > 0 {
+            result.push_str(&format!(".{}", revision));
+        }
+
+        let choco = self.to_choco().to_string();
+        if let Some(index) = choco.find('-') {
+            result.push_str(&choco[index..]);
+        }
+
+        result
+    }
+
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
     pub fn to_choco(&self) -> chocolatey::ChocoVersion {
@@ -68,6 +114,17 @@ impl Versions {
         }
     }
 
+    /// Returns wether the version is a pre-release version. Any build metadata
+    /// of the version (*ie: `+build.5`*) is not considered to be a
+    /// pre-release.
+    pub fn is_prerelease(&self) -> bool {
+        match self {
+            Versions::SemVer(semver) => semver.is_prerelease(),
+            #[cfg(feature = "chocolatey")]
+            Versions::Choco(ver) => ver.is_prerelease(),
+        }
+    }
+
     pub fn to_semver(&self) -> SemVersion {
         match self {
             Versions::SemVer(semver) => semver.clone(),
@@ -78,6 +135,19 @@ impl Versions {
     }
 }
 
+/// Compares two versions, if both versions are of the same type the versions
+/// are compared directly. Otherwise both versions are converted to a
+/// chocolatey version before being compared.
+impl PartialOrd for Versions {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Versions::SemVer(left), Versions::SemVer(right)) => left.partial_cmp(right),
+            #[cfg(feature = "chocolatey")]
+            (left, right) => left.to_choco().partial_cmp(&right.to_choco()),
+        }
+    }
+}
+
 impl Display for Versions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         match self {
@@ -198,6 +268,58 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    #[case("1.9.4", false)]
+    #[case("2.0.0-beta3", true)]
+    #[case("2.0.0-rc.1", true)]
+    #[case("1.2.3+build.5", false)]
+    #[cfg_attr(feature = "chocolatey", case("2.5-alpha", true))]
+    #[cfg_attr(feature = "chocolatey", case("2.5.1.3", false))]
+    fn is_prerelease_should_detect_prerelease_versions(#[case] test: &str, #[case] expected: bool) {
+        let version = Versions::parse(test).unwrap();
+
+        assert_eq!(version.is_prerelease(), expected);
+    }
+
+    #[rstest]
+    #[case("1.0.0", "2.0.0")]
+    #[case("1.9.4", "1.10.0")]
+    #[case("2.0.0-beta.1", "2.0.0")]
+    #[cfg_attr(feature = "chocolatey", case("3.2", "3.10.1"))]
+    #[cfg_attr(feature = "chocolatey", case("5.1.0", "5.1.0.1"))]
+    fn partial_cmp_should_order_versions(#[case] lower: &str, #[case] higher: &str) {
+        let lower = Versions::parse(lower).unwrap();
+        let higher = Versions::parse(higher).unwrap();
+
+        assert!(lower < higher);
+        assert!(higher > lower);
+    }
+
+    #[rstest]
+    #[case("3.2", "3.2.0")]
+    #[case("3.2.1", "3.2.1")]
+    #[case("3.2.1.0", "3.2.1")]
+    #[case("3.2.1.4", "3.2.1.4")]
+    #[case("01.02.03", "1.2.3")]
+    #[case("3.2.1.0.5", "3.2.1")]
+    #[case("3.2.1.4.5-beta", "3.2.1.4-beta")]
+    #[case("2021.10.14", "2021.10.14")]
+    #[case("2.0.0-beta.5", "2.0.0-beta0005")]
+    #[case("1.2.3+build.5", "1.2.3")]
+    #[case("5.2.1.6-beta-0005", "5.2.1.6-beta0005")]
+    #[cfg(feature = "chocolatey")]
+    fn to_chocolatey_should_normalize_version(#[case] test: &str, #[case] expected: &str) {
+        let version = Versions::parse_truncated(test).unwrap();
+
+        assert_eq!(version.to_chocolatey(), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "chocolatey")]
+    fn parse_truncated_should_return_error_on_invalid_version() {
+        assert!(Versions::parse_truncated("invalid").is_err());
+    }
+
     #[rstest]
     #[case("4.2.1-alpha.5+6", "4.2.1-alpha.5+6")]
     #[cfg_attr(feature = "chocolatey", case("3.2", "3.2"))]
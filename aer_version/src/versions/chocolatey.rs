@@ -146,6 +146,12 @@ impl ChocoVersion {
         Ok(result)
     }
 
+    /// Returns wether the version contains any pre-release identifiers (*ie:
+    /// `alpha`, `beta`, `rc`, etc.*).
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre_release.is_empty()
+    }
+
     /// Specifically sets the patch version (third part of the version).
     pub fn set_patch(&mut self, patch: u8) {
         self.patch = Some(patch);
@@ -160,6 +166,17 @@ impl ChocoVersion {
         self.build = Some(build);
     }
 
+    /// Returns the numeric parts of the version (`major.minor.patch.build`),
+    /// where any missing part is returned as `0`.
+    pub(crate) fn numeric_parts(&self) -> [u64; 4] {
+        [
+            self.major as u64,
+            self.minor as u64,
+            self.patch.unwrap_or(0) as u64,
+            self.build.unwrap_or(0) as u64,
+        ]
+    }
+
     /// Sets and replaces the pre-release part of the version, without doing any
     /// parsing.
     pub fn set_prerelease(&mut self, pre: Vec<Identifier>) {
@@ -698,6 +715,21 @@ mod tests {
         let _ = ChocoVersion::parse(val).unwrap();
     }
 
+    #[rstest(
+        val,
+        expected,
+        case("1.0", false),
+        case("3.5.0.2342", false),
+        case("3.3-alpha001", true),
+        case("1.0rc1", true),
+        case("2.0.0-beta", true)
+    )]
+    fn is_prerelease_should_detect_prerelease_versions(val: &str, expected: bool) {
+        let version = ChocoVersion::parse(val).unwrap();
+
+        assert_eq!(version.is_prerelease(), expected);
+    }
+
     #[test]
     fn from_should_create_choco_version() {
         let expected = ChocoVersion {
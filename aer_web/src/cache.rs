@@ -0,0 +1,144 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for storing the body of html responses on disk, to
+//! allow sending conditional requests the next time the same url is
+//! requested.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use log::{trace, warn};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::WebError;
+
+/// A cache of responses stored in a directory on disk, where each response
+/// is stored in a separate file keyed by the url that was requested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+/// A single response that have been stored in the cache.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CacheEntry {
+    /// The url that was requested.
+    pub url: String,
+    /// The etag returned by the server, if any.
+    pub etag: Option<String>,
+    /// The last modified date returned by the server, if any.
+    pub last_modified: Option<String>,
+    /// The body of the response.
+    pub body: String,
+}
+
+impl ResponseCache {
+    /// Creates a new cache that will store the responses in the specified
+    /// directory. The directory will be created when the first response is
+    /// stored.
+    pub fn new<P: AsRef<Path>>(dir: P) -> ResponseCache {
+        ResponseCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the directory the responses are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Gets the response that have previously been stored for the specified
+    /// url. Any entries that can not be read are ignored.
+    pub fn get(&self, url: &Url) -> Option<CacheEntry> {
+        let path = self.get_path(url);
+        let content = std::fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str::<CacheEntry>(&content) {
+            Ok(entry) if entry.url == url.as_str() => {
+                trace!(
+                    "Found cached response for '{}' in '{}'",
+                    url,
+                    path.display()
+                );
+                Some(entry)
+            }
+            Ok(_) => None,
+            Err(err) => {
+                warn!(
+                    "Ignoring invalid cached response in '{}': {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Stores the specified entry in the cache, replacing any entry that
+    /// have previously been stored for the same url.
+    pub fn store(&self, entry: &CacheEntry) -> Result<(), WebError> {
+        let url = Url::parse(&entry.url).map_err(|err| WebError::Other(err.to_string()))?;
+        let path = self.get_path(&url);
+        let content =
+            serde_json::to_string(entry).map_err(|err| WebError::Other(err.to_string()))?;
+
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, content)?;
+        trace!("Stored response for '{}' in '{}'", url, path.display());
+
+        Ok(())
+    }
+
+    fn get_path(&self, url: &Url) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.as_str().hash(&mut hasher);
+
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_entry(url: &str) -> CacheEntry {
+        CacheEntry {
+            url: url.into(),
+            etag: Some("\"abc\"".into()),
+            last_modified: None,
+            body: "<html></html>".into(),
+        }
+    }
+
+    #[test]
+    fn get_should_return_stored_entry() {
+        let cache = ResponseCache::new(std::env::temp_dir().join("aer-web-cache-tests"));
+        let url = Url::parse("https://example.org/cache-test-stored").unwrap();
+        let entry = create_entry(url.as_str());
+
+        cache.store(&entry).unwrap();
+
+        assert_eq!(cache.get(&url), Some(entry));
+    }
+
+    #[test]
+    fn get_should_return_none_for_unknown_url() {
+        let cache = ResponseCache::new(std::env::temp_dir().join("aer-web-cache-tests"));
+        let url = Url::parse("https://example.org/cache-test-unknown").unwrap();
+
+        assert_eq!(cache.get(&url), None);
+    }
+
+    #[test]
+    fn get_should_ignore_invalid_entries() {
+        let cache = ResponseCache::new(std::env::temp_dir().join("aer-web-cache-tests"));
+        let url = Url::parse("https://example.org/cache-test-invalid").unwrap();
+        std::fs::create_dir_all(cache.dir()).unwrap();
+        std::fs::write(cache.get_path(&url), "not json").unwrap();
+
+        assert_eq!(cache.get(&url), None);
+    }
+}
@@ -4,10 +4,15 @@
 //! Contains information gathered when parsing an html page, or during manual
 //! creation.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 
 use aer_version::Versions;
+use lazy_static::lazy_static;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
 use reqwest::Url;
 
 /// Defines what type (MIME or extension) the current link
@@ -114,6 +119,14 @@ impl LinkElement {
     pub fn is_binary(&self) -> bool {
         self.link_type == LinkType::Binary
     }
+
+    /// Returns the url of the link, where any percent-encoded characters
+    /// (*ie: `%2E` in `?ver=3%2E2%2E1`*) have been decoded. Sequences that do
+    /// not decode to valid `UTF-8` are replaced with the replacement
+    /// character.
+    pub fn decoded_link(&self) -> Cow<str> {
+        percent_decode_str(self.link.as_str()).decode_utf8_lossy()
+    }
 }
 
 impl Default for LinkElement {
@@ -129,3 +142,41 @@ impl Default for LinkElement {
         }
     }
 }
+
+/// Removes duplicate links, and sorts the remaining links by their version in
+/// descending order. Links without a version are placed last, and links with
+/// the same version keep their original order.
+///
+/// Links are considered duplicates when they have the same url, or when the
+/// file name of the link only differ by the version (*ie: the same file
+/// hosted on multiple mirrors, or older releases of the same file*). Only the
+/// link with the highest version is kept for each distinct file name.
+pub fn dedup_links(mut links: Vec<LinkElement>) -> Vec<LinkElement> {
+    links.sort_by(|a, b| b.version.partial_cmp(&a.version).unwrap_or(Ordering::Equal));
+
+    let mut seen_urls = HashSet::new();
+    let mut seen_patterns = HashSet::new();
+
+    links
+        .into_iter()
+        .filter(|link| {
+            seen_urls.insert(link.link.to_string())
+                && (link.version.is_none() || seen_patterns.insert(file_name_pattern(&link.link)))
+        })
+        .collect()
+}
+
+/// Gets the file name of the url, where any version numbers have been
+/// replaced by a placeholder.
+fn file_name_pattern(url: &Url) -> String {
+    lazy_static! {
+        static ref VERSION: Regex = Regex::new(r"\d+(?:\.\d+)+").unwrap();
+    }
+
+    let name = url
+        .path_segments()
+        .and_then(|segments| segments.last())
+        .unwrap_or_default();
+
+    VERSION.replace_all(name, "{version}").into_owned()
+}
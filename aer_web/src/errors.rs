@@ -5,6 +5,8 @@
 
 use std::error::Error;
 use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Common error collector for different errors that can be found in the
 /// library.
@@ -14,10 +16,114 @@ pub enum WebError {
     Request(reqwest::Error),
     /// An error that occurred while reading or writing to the file system
     IoError(std::io::Error),
+    /// The request to a web server did not finish within the configured
+    /// timeout.
+    Timeout {
+        /// The url that was requested.
+        url: String,
+        /// The timeout that was exceeded.
+        timeout: Duration,
+    },
+    /// The response was expected to be a json document, but could not be
+    /// parsed as one.
+    NotJson {
+        /// The url that was requested.
+        url: String,
+        /// The reason the response could not be parsed.
+        message: String,
+    },
+    /// The selector used on a json document did not match any links.
+    NoSelectorMatch {
+        /// The url that was requested.
+        url: String,
+        /// The selector that did not match anything.
+        selector: String,
+    },
+    /// The field holding the version could not be found in a json document.
+    MissingVersionField {
+        /// The url that was requested.
+        url: String,
+        /// The path to the version field that was missing.
+        field: String,
+    },
+    /// There is not enough free disk space available to download a file.
+    InsufficientSpace {
+        /// The path the file would be downloaded to.
+        path: PathBuf,
+        /// The number of bytes needed to download the file.
+        required: u64,
+        /// The number of bytes available on the disk.
+        available: u64,
+    },
+    /// The number of bytes received for a download do not match the length
+    /// advertised by the web server.
+    IncompleteDownload {
+        /// The url that was downloaded.
+        url: String,
+        /// The number of bytes advertised by the web server.
+        expected: u64,
+        /// The number of bytes that was received.
+        actual: u64,
+    },
+    /// A custom header could not be used, as either the name or the value is
+    /// not valid in a http header.
+    InvalidHeader {
+        /// The name of the header.
+        name: String,
+        /// The reason the header is not valid.
+        message: String,
+    },
+    /// The GitHub api refused the request, either because the rate limit was
+    /// exceeded or because the token do not have access to the repository.
+    GitHubAccessDenied {
+        /// The url that was requested.
+        url: String,
+        /// The status code returned by the GitHub api.
+        status: u16,
+        /// Wether the request was refused because of the rate limit.
+        rate_limited: bool,
+    },
+    /// The web server responded with a client or server error status.
+    Status {
+        /// The final url that was requested, after any redirects have been
+        /// followed.
+        url: String,
+        /// The status code of the response.
+        status: u16,
+        /// The start of the response body, with any whitespace collapsed.
+        /// Only the first characters of the body are included, to avoid
+        /// outputting entire pages.
+        body: String,
+    },
+    /// The web server responded with a content encoding that can not be
+    /// decoded, only `gzip`, `deflate` and `br` (*brotli*) are supported.
+    UnsupportedEncoding {
+        /// The url that was requested.
+        url: String,
+        /// The content encoding of the response.
+        encoding: String,
+    },
+    /// The minimum version of TLS can not be required by the TLS backend of
+    /// the current platform.
+    UnsupportedTlsVersion(String),
     /// Any other type of error not covered by the other types.
     Other(String),
 }
 
+impl WebError {
+    /// Checks wether the error is caused by a temporary network failure (*ie:
+    /// the connection was closed before the download was finished*), where
+    /// sending the request again may succeed. Failures of the local file
+    /// system, like running out of disk space, are never temporary.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            WebError::Request(err) => err.is_connect() || err.is_timeout() || err.is_body(),
+            WebError::Timeout { .. } | WebError::IncompleteDownload { .. } => true,
+            _ => false,
+        }
+    }
+}
+
 impl Error for WebError {}
 
 impl Display for WebError {
@@ -25,6 +131,85 @@ impl Display for WebError {
         match self {
             WebError::Request(err) => err.fmt(f),
             WebError::IoError(err) => err.fmt(f),
+            WebError::Timeout { url, timeout } => write!(
+                f,
+                "The request to '{}' timed out after {} seconds!",
+                url,
+                timeout.as_secs_f64()
+            ),
+            WebError::NotJson { url, message } => write!(
+                f,
+                "The response from '{}' is not a valid json document: {}",
+                url, message
+            ),
+            WebError::NoSelectorMatch { url, selector } => write!(
+                f,
+                "The selector '{}' did not match any links in the json document from '{}'!",
+                selector, url
+            ),
+            WebError::MissingVersionField { url, field } => write!(
+                f,
+                "The version field '{}' could not be found in the json document from '{}'!",
+                field, url
+            ),
+            WebError::InsufficientSpace {
+                path,
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough disk space to download '{}', {} bytes are required but only {} bytes \
+                 are available!",
+                path.display(),
+                required,
+                available
+            ),
+            WebError::IncompleteDownload {
+                url,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The download of '{}' is incomplete, expected {} bytes but received {} bytes!",
+                url, expected, actual
+            ),
+            WebError::InvalidHeader { name, message } => {
+                write!(f, "The header '{}' is not valid: {}", name, message)
+            }
+            WebError::GitHubAccessDenied {
+                url,
+                status,
+                rate_limited: true,
+            } => write!(
+                f,
+                "The GitHub api rate limit was exceeded when requesting '{}' (status {})!",
+                url, status
+            ),
+            WebError::GitHubAccessDenied { url, status, .. } => write!(
+                f,
+                "The GitHub api denied access to '{}' (status {})!",
+                url, status
+            ),
+            WebError::Status { url, status, body } if body.is_empty() => write!(
+                f,
+                "The web server responded with status {} for '{}'!",
+                status, url
+            ),
+            WebError::Status { url, status, body } => write!(
+                f,
+                "The web server responded with status {} for '{}': {}",
+                status, url, body
+            ),
+            WebError::UnsupportedEncoding { url, encoding } => write!(
+                f,
+                "The response from '{}' uses the content encoding '{}', which can not be decoded!",
+                url, encoding
+            ),
+            WebError::UnsupportedTlsVersion(version) => write!(
+                f,
+                "TLS {} can not be required as the minimum version on this platform!",
+                version
+            ),
             WebError::Other(val) => f.write_str(&val),
         }
     }
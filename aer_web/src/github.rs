@@ -0,0 +1,275 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the structures used when reading releases from the GitHub api,
+//! as well as the functions necessary to convert the release assets to
+//! links that can be used the same way as links parsed from a html page.
+
+use aer_version::Versions;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::{LinkElement, LinkType};
+
+/// The name of the link attribute holding the api url of a release asset,
+/// which is used in place of the download url when downloading the assets
+/// of private repositories.
+pub const API_URL_ATTRIBUTE: &str = "api_url";
+
+/// A single release returned by the GitHub releases api.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GitHubRelease {
+    /// The tag name the release was created from (*ie: `v1.2.0`*).
+    pub tag_name: String,
+    /// The name (title) of the release, if one have been set.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Wether the release is a draft, and have not been published yet.
+    #[serde(default)]
+    pub draft: bool,
+    /// Wether the release have been marked as a pre-release.
+    #[serde(default)]
+    pub prerelease: bool,
+    /// The url of the html page of the release.
+    pub html_url: String,
+    /// The files that have been uploaded to the release.
+    #[serde(default)]
+    pub assets: Vec<GitHubAsset>,
+}
+
+/// A single file that have been uploaded to a GitHub release.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GitHubAsset {
+    /// The file name of the asset.
+    pub name: String,
+    /// The url that can be used to download the asset.
+    pub browser_download_url: String,
+    /// The api url of the asset, which needs to be used together with a
+    /// token to download the assets of private repositories.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// The mime type of the asset, as reported by GitHub.
+    #[serde(default)]
+    pub content_type: String,
+}
+
+impl GitHubRelease {
+    /// Parses the version of the release from the tag name, any leading `v`
+    /// in the tag name is ignored (*ie: both `v1.2.0` and `1.2.0` is parsed
+    /// as `1.2.0`*).
+    pub fn version(&self) -> Option<Versions> {
+        let tag = self.tag_name.trim();
+        let tag = tag
+            .strip_prefix('v')
+            .or_else(|| tag.strip_prefix('V'))
+            .unwrap_or(tag);
+
+        Versions::parse(tag).ok()
+    }
+
+    /// Creates a parent link of the release html page, and a link for each
+    /// asset in the release. All of the links will have the version of the
+    /// release set, and the name of the asset set as the text of the link.
+    /// The api url of each asset is stored in the [API_URL_ATTRIBUTE]
+    /// attribute of the link.
+    pub fn links(&self) -> (LinkElement, Vec<LinkElement>) {
+        let version = self.version();
+        let mut parent = match Url::parse(&self.html_url) {
+            Ok(url) => LinkElement::new(url, LinkType::Html),
+            Err(_) => LinkElement::default(),
+        };
+        parent.title = self.name.clone().unwrap_or_default();
+        parent.text = self.tag_name.clone();
+        parent.version = version.clone();
+
+        let links = self
+            .assets
+            .iter()
+            .filter_map(|asset| {
+                let url = Url::parse(&asset.browser_download_url).ok()?;
+                let mut link = LinkElement::new(url, get_link_type(asset));
+                link.text = asset.name.clone();
+                link.version = version.clone();
+                if let Some(ref api_url) = asset.url {
+                    link.attributes
+                        .insert(API_URL_ATTRIBUTE.into(), api_url.clone());
+                }
+
+                Some(link)
+            })
+            .collect();
+
+        (parent, links)
+    }
+}
+
+/// Selects the latest release that is not a draft from the specified
+/// releases, pre-releases are only considered when `allow_prerelease` is
+/// `true`.
+///
+/// The releases are expected to be in the same order as returned by the
+/// GitHub api (*newest releases first*).
+pub fn select_latest_release(
+    releases: &[GitHubRelease],
+    allow_prerelease: bool,
+) -> Option<&GitHubRelease> {
+    releases
+        .iter()
+        .filter(|release| !release.draft)
+        .find(|release| allow_prerelease || !release.prerelease)
+}
+
+fn get_link_type(asset: &GitHubAsset) -> LinkType {
+    let name = asset.name.to_lowercase();
+
+    if asset.content_type == "application/json" || name.ends_with(".json") {
+        LinkType::Json
+    } else if asset.content_type == "text/plain" || name.ends_with(".txt") {
+        LinkType::Text
+    } else {
+        LinkType::Binary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn create_release(tag_name: &str, draft: bool, prerelease: bool) -> GitHubRelease {
+        GitHubRelease {
+            tag_name: tag_name.into(),
+            name: None,
+            draft,
+            prerelease,
+            html_url: format!("https://github.com/owner/repo/releases/tag/{}", tag_name),
+            assets: vec![],
+        }
+    }
+
+    #[rstest]
+    #[case("v1.2.0", "1.2.0")]
+    #[case("V1.2.0", "1.2.0")]
+    #[case("1.2.0", "1.2.0")]
+    #[case("v2.0.0-beta.1", "2.0.0-beta.1")]
+    fn version_should_strip_v_prefix(#[case] tag: &str, #[case] expected: &str) {
+        let release = create_release(tag, false, false);
+
+        assert_eq!(release.version(), Some(Versions::parse(expected).unwrap()));
+    }
+
+    #[test]
+    fn version_should_return_none_on_invalid_tag() {
+        let release = create_release("latest", false, false);
+
+        assert_eq!(release.version(), None);
+    }
+
+    #[test]
+    fn select_latest_release_should_ignore_drafts() {
+        let releases = [
+            create_release("v3.0.0", true, false),
+            create_release("v2.0.0", false, false),
+        ];
+
+        let release = select_latest_release(&releases, false).unwrap();
+
+        assert_eq!(release.tag_name, "v2.0.0");
+    }
+
+    #[rstest]
+    #[case(false, "v2.0.0")]
+    #[case(true, "v3.0.0-beta")]
+    fn select_latest_release_should_only_use_prereleases_when_allowed(
+        #[case] allow_prerelease: bool,
+        #[case] expected: &str,
+    ) {
+        let releases = [
+            create_release("v3.0.0-beta", false, true),
+            create_release("v2.0.0", false, false),
+        ];
+
+        let release = select_latest_release(&releases, allow_prerelease).unwrap();
+
+        assert_eq!(release.tag_name, expected);
+    }
+
+    #[test]
+    fn links_should_create_versioned_links_of_assets() {
+        let releases: Vec<GitHubRelease> = serde_json::from_str(
+            r#"[{
+                "tag_name": "v1.13.0",
+                "name": "Release 1.13.0",
+                "draft": false,
+                "prerelease": false,
+                "html_url": "https://github.com/codecov/codecov-exe/releases/tag/1.13.0",
+                "assets": [
+                    {
+                        "name": "codecov-win7-x64.zip",
+                        "browser_download_url": "https://github.com/codecov/codecov-exe/releases/download/1.13.0/codecov-win7-x64.zip",
+                        "content_type": "application/x-zip-compressed"
+                    },
+                    {
+                        "name": "checksums.txt",
+                        "browser_download_url": "https://github.com/codecov/codecov-exe/releases/download/1.13.0/checksums.txt",
+                        "content_type": "text/plain"
+                    }
+                ]
+            }]"#,
+        )
+        .unwrap();
+        let version = Versions::parse("1.13.0").unwrap();
+
+        let (parent, links) = releases[0].links();
+
+        assert_eq!(parent.link_type, LinkType::Html);
+        assert_eq!(parent.title, "Release 1.13.0");
+        assert_eq!(
+            links,
+            [
+                LinkElement {
+                    link: Url::parse("https://github.com/codecov/codecov-exe/releases/download/1.13.0/codecov-win7-x64.zip").unwrap(),
+                    text: "codecov-win7-x64.zip".into(),
+                    version: Some(version.clone()),
+                    link_type: LinkType::Binary,
+                    ..Default::default()
+                },
+                LinkElement {
+                    link: Url::parse("https://github.com/codecov/codecov-exe/releases/download/1.13.0/checksums.txt").unwrap(),
+                    text: "checksums.txt".into(),
+                    version: Some(version),
+                    link_type: LinkType::Text,
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+    #[test]
+    fn links_should_store_api_url_of_assets() {
+        let release: GitHubRelease = serde_json::from_str(
+            r#"{
+                "tag_name": "v1.0.0",
+                "html_url": "https://github.com/owner/repo/releases/tag/v1.0.0",
+                "assets": [
+                    {
+                        "name": "tool-x64.zip",
+                        "browser_download_url": "https://github.com/owner/repo/releases/download/v1.0.0/tool-x64.zip",
+                        "url": "https://api.github.com/repos/owner/repo/releases/assets/1234"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let (_, links) = release.links();
+
+        assert_eq!(
+            links[0]
+                .attributes
+                .get(API_URL_ATTRIBUTE)
+                .map(|url| url.as_str()),
+            Some("https://api.github.com/repos/owner/repo/releases/assets/1234")
+        );
+    }
+}
@@ -24,10 +24,14 @@
 
 mod elements;
 
+pub mod cache;
 pub mod errors;
+pub mod github;
+pub mod limiter;
+pub mod partial;
 pub mod request;
 pub mod response;
 
-pub use elements::{LinkElement, LinkType};
-pub use request::WebRequest;
-pub use response::WebResponse;
+pub use elements::{dedup_links, LinkElement, LinkType};
+pub use request::{HostOverride, TlsVersion, WebRequest, WebRequestBuilder};
+pub use response::{LinkSource, MatchTarget, VersionSelector, WebResponse};
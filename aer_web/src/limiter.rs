@@ -0,0 +1,249 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Section responsible for limiting the rate of requests that are sent to
+//! each host, as well as the number of requests that are sent at the same
+//! time.
+
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use log::trace;
+use reqwest::Url;
+
+/// Limits the number of requests per second that are sent to a single host,
+/// and the number of requests that can be in progress at the same time, both
+/// in total and to a single host.
+///
+/// Hosts are identified by their registrable domain, as such requests to
+/// `www.example.org` and `downloads.example.org` share the same limit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Option<Duration>,
+    max_concurrent: Option<usize>,
+    max_per_host: Option<usize>,
+    state: Mutex<LimiterState>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    /// The number of requests that are currently in progress.
+    active: usize,
+    /// The number of requests to each host that are currently in progress.
+    active_hosts: HashMap<String, usize>,
+    /// The earliest time the next request to each host is allowed to be sent.
+    next_request: HashMap<String, Instant>,
+}
+
+/// Holds a reservation in the [RateLimiter] while a request is in progress,
+/// the reservation is released when the permit is dropped.
+#[derive(Debug)]
+pub struct RequestPermit<'a> {
+    limiter: &'a RateLimiter,
+    host: String,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter, that allows at most `requests_per_second`
+    /// requests to each host, at most `max_concurrent` requests in progress
+    /// at the same time and at most `max_per_host` requests to each host in
+    /// progress at the same time. Each limit is disabled when it is [None].
+    pub fn new(
+        requests_per_second: Option<f64>,
+        max_concurrent: Option<usize>,
+        max_per_host: Option<usize>,
+    ) -> RateLimiter {
+        RateLimiter {
+            interval: requests_per_second
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            max_concurrent: max_concurrent.map(|max| max.max(1)),
+            max_per_host: max_per_host.map(|max| max.max(1)),
+            state: Mutex::new(LimiterState::default()),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Waits until a request to the specified url is allowed to be sent by
+    /// the configured limits, and returns the permit that must be held while
+    /// the request is in progress.
+    pub fn acquire(&self, url: &Url) -> RequestPermit<'_> {
+        let host = registrable_domain(url);
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        loop {
+            let host_active = state.active_hosts.get(&host).copied().unwrap_or_default();
+            if self.max_concurrent.map_or(false, |max| state.active >= max) {
+                trace!(
+                    "Throttling the request to '{}', {} requests are already in progress",
+                    url,
+                    state.active
+                );
+            } else if self.max_per_host.map_or(false, |max| host_active >= max) {
+                trace!(
+                    "Throttling the request to '{}', {} requests to '{}' are already in progress",
+                    url,
+                    host_active,
+                    host
+                );
+            } else {
+                break;
+            }
+
+            state = self
+                .available
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+        state.active += 1;
+        *state.active_hosts.entry(host.clone()).or_default() += 1;
+
+        let delay = match self.interval {
+            Some(interval) => {
+                let now = Instant::now();
+                let next = state.next_request.entry(host.clone()).or_insert(now);
+                let start = (*next).max(now);
+                *next = start + interval;
+
+                start - now
+            }
+            None => Duration::from_secs(0),
+        };
+        drop(state);
+
+        if delay > Duration::from_secs(0) {
+            trace!(
+                "Throttling the request to '{}' for {} ms to respect the rate limit of the host",
+                url,
+                delay.as_millis()
+            );
+            std::thread::sleep(delay);
+        }
+
+        RequestPermit {
+            limiter: self,
+            host,
+        }
+    }
+}
+
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self
+            .limiter
+            .state
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        state.active -= 1;
+        if let Some(active) = state.active_hosts.get_mut(&self.host) {
+            *active -= 1;
+            if *active == 0 {
+                state.active_hosts.remove(&self.host);
+            }
+        }
+        // Every waiting request is notified, as the released reservation may
+        // only allow a request to the same host to be sent.
+        self.limiter.available.notify_all();
+    }
+}
+
+/// Returns the registrable domain of the host in the specified url (*ie:
+/// `example.co.uk` for `www.example.co.uk`*). The host itself is returned
+/// for ip addresses and hosts where no registrable domain could be found.
+pub fn registrable_domain(url: &Url) -> String {
+    let host = match url.host_str() {
+        Some(host) => host.trim_end_matches('.').to_lowercase(),
+        None => return String::new(),
+    };
+
+    if url.domain().is_none() {
+        return host;
+    }
+
+    psl::domain_str(&host)
+        .map(|domain| domain.to_owned())
+        .unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("https://www.example.org/downloads", "example.org")]
+    #[case("https://downloads.example.co.uk", "example.co.uk")]
+    #[case("https://EXAMPLE.org", "example.org")]
+    #[case("http://127.0.0.1:8080/test", "127.0.0.1")]
+    #[case("http://localhost/test", "localhost")]
+    fn registrable_domain_should_return_expected_domain(#[case] url: &str, #[case] expected: &str) {
+        let url = Url::parse(url).unwrap();
+
+        assert_eq!(registrable_domain(&url), expected);
+    }
+
+    #[test]
+    fn acquire_should_delay_requests_to_the_same_host() {
+        let limiter = RateLimiter::new(Some(5.0), None, None);
+        let first = Url::parse("https://www.example.org").unwrap();
+        let second = Url::parse("https://downloads.example.org").unwrap();
+        let started = Instant::now();
+
+        drop(limiter.acquire(&first));
+        drop(limiter.acquire(&second));
+
+        assert!(started.elapsed() >= Duration::from_millis(190));
+    }
+
+    #[test]
+    fn acquire_should_not_delay_requests_to_different_hosts() {
+        let limiter = RateLimiter::new(Some(0.5), None, None);
+        let started = Instant::now();
+
+        drop(limiter.acquire(&Url::parse("https://example.org").unwrap()));
+        drop(limiter.acquire(&Url::parse("https://example.com").unwrap()));
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn acquire_should_track_requests_in_progress() {
+        let limiter = RateLimiter::new(None, Some(2), None);
+        let url = Url::parse("https://example.org").unwrap();
+
+        let first = limiter.acquire(&url);
+        let second = limiter.acquire(&url);
+        assert_eq!(limiter.state.lock().unwrap().active, 2);
+
+        drop(first);
+        drop(second);
+        assert_eq!(limiter.state.lock().unwrap().active, 0);
+    }
+
+    #[test]
+    fn acquire_should_limit_requests_in_progress_to_the_same_host() {
+        let limiter = std::sync::Arc::new(RateLimiter::new(None, None, Some(1)));
+        let url = Url::parse("https://www.example.org").unwrap();
+
+        let first = limiter.acquire(&url);
+        let other = limiter.acquire(&Url::parse("https://example.com").unwrap());
+        let waiting = {
+            let limiter = limiter.clone();
+            std::thread::spawn(move || {
+                let url = Url::parse("https://downloads.example.org").unwrap();
+                drop(limiter.acquire(&url));
+                Instant::now()
+            })
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        let released = Instant::now();
+        drop(first);
+
+        assert!(waiting.join().unwrap() >= released);
+        drop(other);
+        assert!(limiter.state.lock().unwrap().active_hosts.is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Keeps track of the files that are currently being downloaded, to allow
+//! the partially downloaded files to be removed when the process is
+//! interrupted before the downloads are finished.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref PARTIAL_FILES: Mutex<Vec<PathBuf>> = Mutex::new(vec![]);
+}
+
+/// Marks a file as being downloaded until it is dropped.
+#[derive(Debug)]
+pub(crate) struct PartialFile {
+    path: PathBuf,
+}
+
+impl PartialFile {
+    pub(crate) fn track(path: &Path) -> PartialFile {
+        partial_files().push(path.to_path_buf());
+
+        PartialFile {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        let mut files = partial_files();
+
+        if let Some(index) = files.iter().position(|path| path == &self.path) {
+            files.remove(index);
+        }
+    }
+}
+
+fn partial_files() -> MutexGuard<'static, Vec<PathBuf>> {
+    PARTIAL_FILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Removes every file that is currently being downloaded, and returns the
+/// paths of the files that was removed. Files that have finished downloading
+/// are never removed.
+///
+/// This is meant to be called when the process is interrupted, as the
+/// downloads are not stopped.
+pub fn remove_partial_files() -> Vec<PathBuf> {
+    remove_files(&mut partial_files())
+}
+
+fn remove_files(files: &mut Vec<PathBuf>) -> Vec<PathBuf> {
+    files
+        .drain(..)
+        .filter(|path| std::fs::remove_file(path).is_ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_should_mark_file_as_downloading_until_dropped() {
+        let path = std::env::temp_dir().join("aer-partial-tests-tracked.bin");
+
+        let partial = PartialFile::track(&path);
+        assert!(partial_files().contains(&path));
+        drop(partial);
+
+        assert!(!partial_files().contains(&path));
+    }
+
+    #[test]
+    fn remove_files_should_remove_existing_files() {
+        let path = std::env::temp_dir().join("aer-partial-tests-removed.bin");
+        let missing = std::env::temp_dir().join("aer-partial-tests-missing.bin");
+        std::fs::write(&path, "partial").unwrap();
+        let mut files = vec![path.clone(), missing];
+
+        let actual = remove_files(&mut files);
+
+        assert_eq!(actual, [path.clone()]);
+        assert!(files.is_empty());
+        assert!(!path.exists());
+    }
+}
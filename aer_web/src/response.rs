@@ -8,15 +8,19 @@
 mod binary;
 /// Contains code related to handling html responses.
 mod html;
+/// Contains code related to handling json responses.
+mod json;
 
 use std::collections::HashMap;
 use std::path::Path;
 
-pub use binary::BinaryResponse;
-pub use html::HtmlResponse;
+pub(crate) use binary::get_range_start;
+pub use binary::{part_file_path, BinaryResponse, FileInfo};
+pub use html::{HtmlResponse, LinkSource, MatchTarget, VersionSelector, DEFAULT_MAX_LINKS};
+pub use json::JsonResponse;
 use lazy_static::lazy_static;
 use reqwest::blocking::Response;
-use reqwest::StatusCode;
+use reqwest::{StatusCode, Url};
 
 use crate::elements::LinkType;
 use crate::errors::WebError;
@@ -34,6 +38,34 @@ lazy_static! {
     };
 }
 
+/// Gets the type of the specified link, based on the extension of the path in
+/// the url. Returns [LinkType::Unknown] if the extension is not recognized.
+fn get_link_type(url: &Url) -> LinkType {
+    let path = url.path();
+
+    if path.ends_with(".html") {
+        LinkType::Html
+    } else if path.ends_with(".json") {
+        LinkType::Json
+    } else if path.ends_with(".css") {
+        LinkType::Css
+    } else if path.ends_with(".txt") {
+        LinkType::Text
+    } else if path.ends_with(".zip")
+        || path.ends_with(".7z")
+        || path.ends_with(".exe")
+        || path.ends_with(".msi")
+        || path.ends_with(".tar")
+        || path.ends_with(".tar.gz")
+        || path.ends_with(".tar.bz2")
+        || path.ends_with(".nupkg")
+    {
+        LinkType::Binary
+    } else {
+        LinkType::Unknown
+    }
+}
+
 /// A simple enumerator that holds information of wether the response returned
 /// by a server said the content is up to date, or if there is new content
 /// available.
@@ -97,6 +129,8 @@ impl ResponseType<BinaryResponse> {
 ///   generally for aquiring links on a web page_.
 /// - [BinaryResponse](BinaryResponse): _Responsible for downloading a remote
 ///   file to a specified location_
+/// - [JsonResponse](JsonResponse): _Responsible of selecting links from json
+///   documents_.
 pub trait WebResponse {
     /// The response content that will be returned by any implementation of
     /// [WebResponse]. This can be anything that would be expected by the
@@ -128,6 +162,26 @@ pub trait WebResponse {
         self.response().status()
     }
 
+    /// Returns the final url of the response, after any redirects have been
+    /// followed.
+    fn url(&self) -> &Url {
+        self.response().url()
+    }
+
+    /// Returns the location the web server redirected to, when redirects are
+    /// not followed and the server responded with a redirect. Relative
+    /// locations are resolved against the [url](WebResponse::url) of the
+    /// response.
+    fn redirect_location(&self) -> Option<Url> {
+        let response = self.response();
+        if !response.status().is_redirection() {
+            return None;
+        }
+
+        let location = response.headers().get(reqwest::header::LOCATION)?;
+        response.url().join(location.to_str().ok()?).ok()
+    }
+
     /// Reads the current response content, and if successful returns the a
     /// structure holding the necessary items found. This may return an
     /// error if the status code is a success code, or if the reading of the
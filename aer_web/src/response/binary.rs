@@ -1,16 +1,18 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
-use std::fs::File;
-use std::io::BufWriter;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use log::{info, warn};
 use reqwest::blocking::Response;
 use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::{header, Url};
+use reqwest::{header, StatusCode, Url};
 
+use crate::partial::PartialFile;
 use crate::response::WebError;
 use crate::WebResponse;
 
@@ -24,6 +26,12 @@ pub struct BinaryResponse {
     response: Response,
     url: Url,
     work_dir: PathBuf,
+    /// The number of bytes already downloaded to the partial file, when the
+    /// response continues a previous download.
+    resume_from: Option<u64>,
+    /// The length of the complete file, that the partial file must have
+    /// before it is renamed to the output path.
+    complete_length: Option<u64>,
 }
 
 impl PartialEq for BinaryResponse {
@@ -41,6 +49,18 @@ impl BinaryResponse {
             response,
             url,
             work_dir: PathBuf::new(),
+            resume_from: None,
+            complete_length: None,
+        }
+    }
+
+    /// Creates a new instance of the [BinaryResponse] structure, where the
+    /// response holds the content of the file after the `offset` number of
+    /// bytes that have already been downloaded to the partial file.
+    pub(crate) fn resuming(response: Response, url: Url, offset: u64) -> BinaryResponse {
+        BinaryResponse {
+            resume_from: Some(offset),
+            ..BinaryResponse::new(response, url)
         }
     }
 
@@ -52,6 +72,37 @@ impl BinaryResponse {
         self.work_dir = PathBuf::from(path);
     }
 
+    /// Sets the length of the complete file, which the downloaded file must
+    /// have before the download is successful. This is used when resuming a
+    /// download, to verify that the file have the length advertised when the
+    /// download was first started.
+    ///
+    /// When not set, the length advertised by the current response is used
+    /// instead (*ie: the length in the `Content-Range` header of a resumed
+    /// download*).
+    pub fn set_complete_length(&mut self, length: Option<u64>) {
+        self.complete_length = length;
+    }
+
+    /// Gets the length of the complete file, either as set by
+    /// [set_complete_length](Self::set_complete_length) or as advertised by
+    /// the web server.
+    fn get_complete_length(&self) -> Option<u64> {
+        if self.complete_length.is_some() {
+            return self.complete_length;
+        }
+
+        if self.resume_from.is_some() {
+            self.response
+                .headers()
+                .get(header::CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(get_range_length)
+        } else {
+            self.response.content_length()
+        }
+    }
+
     /// Tries to get the name of the remote file by either reading the
     /// disposition header, or checking the url if it contains an extension.
     pub fn file_name(&self) -> Option<String> {
@@ -63,6 +114,336 @@ impl BinaryResponse {
             None
         }
     }
+
+    /// Gets the media type of the content type header of the response, in
+    /// lowercase and without any parameters (*ie: `text/html` for
+    /// `text/html; charset=utf-8`*).
+    pub fn content_type(&self) -> Option<String> {
+        self.response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Wether the web server supports requesting parts of the file, which
+    /// allows a failed download to be resumed by using
+    /// [get_binary_range_response](crate::WebRequest::get_binary_range_response).
+    pub fn accepts_ranges(&self) -> bool {
+        self.response.status() == StatusCode::PARTIAL_CONTENT
+            || self
+                .response
+                .headers()
+                .get(header::ACCEPT_RANGES)
+                .and_then(|value| value.to_str().ok())
+                .map_or(false, |value| {
+                    value
+                        .split(',')
+                        .any(|unit| unit.trim().eq_ignore_ascii_case("bytes"))
+                })
+    }
+
+    /// Gets the value that identifies the version of the remote file, which
+    /// is the strong `ETag` header when available and otherwise the
+    /// `Last-Modified` header of the response. Used as the `If-Range` header
+    /// when resuming a download, to avoid combining parts of different files.
+    pub fn validator(&self) -> Option<String> {
+        let headers = self.response.headers();
+        let get_header = |name: header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_owned())
+                .filter(|value| !value.is_empty())
+        };
+
+        get_header(header::ETAG)
+            .filter(|etag| !etag.starts_with("W/"))
+            .or_else(|| get_header(header::LAST_MODIFIED))
+    }
+
+    /// Downloads the response content the same way as
+    /// [read](WebResponse::read), while also passing every downloaded byte to
+    /// the specified `inspector`. This allows calculating a checksum of the
+    /// file without needing to read the file again after it is downloaded.
+    ///
+    /// The content is first written to a partial file (*see
+    /// [part_file_path]*), which is renamed to the output path when the
+    /// download succeeds. When the response continues a previous download,
+    /// the content is appended to the partial file and the bytes that was
+    /// already downloaded are passed to the `inspector` first.
+    ///
+    /// When the web server advertises the length of the file, the download
+    /// is not started unless there is enough free disk space available, and
+    /// the download fails if the received number of bytes do not match the
+    /// advertised length. The length of the partial file is also verified
+    /// against the [length of the complete file](Self::set_complete_length)
+    /// before it is renamed, where the partial file is always removed when
+    /// the lengths do not match. Otherwise the partial file is removed when
+    /// the download fails, unless the web server
+    /// [accepts ranges](Self::accepts_ranges) and part of the file was
+    /// downloaded. The partial file is always
+    /// removed when the process is interrupted and
+    /// [remove_partial_files](crate::partial::remove_partial_files) is
+    /// called.
+    pub fn read_with_inspector<W: Write>(
+        self,
+        output: Option<&str>,
+        inspector: &mut W,
+    ) -> Result<PathBuf, WebError> {
+        let output = if let Some(output) = output {
+            output.into()
+        } else {
+            self.file_name()
+                .ok_or_else(|| WebError::Other("Unable to extract file name request".into()))?
+        };
+
+        let output = self.work_dir.join(output);
+        let part = part_file_path(&output);
+        let accepts_ranges = self.accepts_ranges();
+        let complete_length = self.get_complete_length();
+
+        let mut response = self.response;
+        let content_length = response.content_length();
+
+        if let Some(required) = content_length {
+            check_available_space(&self.work_dir, &output, required)?;
+        }
+
+        let file = match self.resume_from {
+            Some(offset) => {
+                let existing = part.metadata().map_or(0, |metadata| metadata.len());
+                if existing != offset {
+                    return Err(WebError::Other(format!(
+                        "The partial file '{}' have {} bytes, while {} bytes was expected!",
+                        part.display(),
+                        existing,
+                        offset
+                    )));
+                }
+
+                info!(
+                    "Resuming the download of '{}' to '{}' after {} bytes",
+                    self.url,
+                    output.display(),
+                    offset
+                );
+                let mut existing = File::open(&part).map_err(WebError::IoError)?;
+                std::io::copy(&mut existing, inspector).map_err(WebError::IoError)?;
+                OpenOptions::new()
+                    .append(true)
+                    .open(&part)
+                    .map_err(WebError::IoError)?
+            }
+            None => {
+                info!("Downloading '{}' to '{}'", self.url, output.display());
+                File::create(&part).map_err(WebError::IoError)?
+            }
+        };
+        let partial = PartialFile::track(&part);
+        let mut writer = InspectingWriter {
+            writer: BufWriter::new(&file),
+            inspector,
+        };
+
+        let result = match response.copy_to(&mut writer) {
+            Err(err) => Err(WebError::Request(err)),
+            Ok(written) => match writer.flush() {
+                Err(err) => Err(WebError::IoError(err)),
+                Ok(()) => match content_length {
+                    Some(expected) if expected != written => Err(WebError::IncompleteDownload {
+                        url: self.url.to_string(),
+                        expected,
+                        actual: written,
+                    }),
+                    _ => Ok(()),
+                },
+            },
+        };
+        drop(writer);
+        drop(file);
+        drop(partial);
+
+        let downloaded = part.metadata().map_or(0, |metadata| metadata.len());
+        let mut discard = false;
+        let result = result.and_then(|()| match complete_length {
+            Some(expected) if expected != downloaded => {
+                discard = true;
+                Err(WebError::IncompleteDownload {
+                    url: self.url.to_string(),
+                    expected,
+                    actual: downloaded,
+                })
+            }
+            _ => std::fs::rename(&part, &output).map_err(WebError::IoError),
+        });
+
+        match result {
+            Err(err) => {
+                warn!("Failed to download '{}'", self.url);
+                if accepts_ranges && downloaded > 0 && !discard {
+                    info!(
+                        "Keeping the partial file '{}' with {} bytes to resume the download",
+                        part.display(),
+                        downloaded
+                    );
+                } else if let Err(err) = std::fs::remove_file(&part) {
+                    warn!(
+                        "Unable to remove the partial file '{}': {}",
+                        part.display(),
+                        err
+                    );
+                }
+                Err(err)
+            }
+            Ok(()) => {
+                info!("Successfully downloaded '{}'", output.display());
+                Ok(output)
+            }
+        }
+    }
+}
+
+/// The information about a remote file, that was requested without
+/// downloading the content of the file.
+///
+/// Created by calling [get_file_info](crate::WebRequest::get_file_info).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileInfo {
+    /// The final url of the file, after any redirects have been followed.
+    pub url: Url,
+    /// The status the web server responded with. This is `206` when the web
+    /// server did not support `HEAD` requests, and responded with part of the
+    /// file instead.
+    pub status: u16,
+    /// The size of the remote file, when advertised by the web server.
+    pub content_length: Option<u64>,
+    /// The content type of the remote file, when advertised by the web
+    /// server.
+    pub content_type: Option<String>,
+    /// The name of the remote file, either from the disposition header or
+    /// from the url when it contains an extension.
+    pub file_name: Option<String>,
+    /// When the remote file was last modified, as returned by the web server.
+    pub last_modified: Option<String>,
+}
+
+impl FileInfo {
+    /// Creates the information about the remote file from the headers of the
+    /// specified response, without reading the body of the response. The
+    /// size of the file is read from the `Content-Range` header of partial
+    /// responses.
+    pub(crate) fn from_response(response: &Response) -> FileInfo {
+        let headers = response.headers();
+        let get_header = |name: header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_owned())
+                .filter(|value| !value.is_empty())
+        };
+        let content_length = if response.status() == StatusCode::PARTIAL_CONTENT {
+            get_header(header::CONTENT_RANGE).and_then(|range| get_range_length(&range))
+        } else {
+            get_header(header::CONTENT_LENGTH).and_then(|length| length.parse().ok())
+        };
+
+        FileInfo {
+            url: response.url().clone(),
+            status: response.status().as_u16(),
+            content_length,
+            content_type: get_header(header::CONTENT_TYPE),
+            file_name: get_from_disposition(headers).or_else(|| get_from_url(response.url())),
+            last_modified: get_header(header::LAST_MODIFIED),
+        }
+    }
+}
+
+/// Gets the path of the partial file that the file at the specified path is
+/// downloaded to, before it is renamed to the path when the download
+/// succeeds (*ie: `setup.exe.part` for `setup.exe`*).
+pub fn part_file_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".part");
+
+    path.with_file_name(name)
+}
+
+/// Gets the offset of the first byte from a `Content-Range` header (*ie: `512`
+/// from `bytes 512-1023/1024`*), returns [None] when the header is not valid.
+pub(crate) fn get_range_start(range: &str) -> Option<u64> {
+    range
+        .trim()
+        .strip_prefix("bytes")?
+        .trim_start()
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Gets the complete length of the file from a `Content-Range` header (*ie:
+/// `4096` from `bytes 0-0/4096`*), returns [None] when the length is unknown.
+fn get_range_length(range: &str) -> Option<u64> {
+    range.rsplit('/').next()?.trim().parse().ok()
+}
+
+/// Writes all data to the underlying writer, and passes any data that was
+/// successfully written on to the inspector.
+struct InspectingWriter<'a, T: Write, W: Write> {
+    writer: T,
+    inspector: &'a mut W,
+}
+
+impl<'a, T: Write, W: Write> Write for InspectingWriter<'a, T, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.inspector.write_all(&buf[..written])?;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        self.inspector.flush()
+    }
+}
+
+/// Checks that the disk containing the work directory have at least the
+/// `required` number of bytes available. An existing file at the output path
+/// is counted as available space, as it will be replaced by the download.
+fn check_available_space(work_dir: &Path, output: &Path, required: u64) -> Result<(), WebError> {
+    let dir = if work_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        work_dir
+    };
+
+    let available = match fs2::available_space(dir) {
+        Ok(available) => available,
+        Err(err) => {
+            warn!(
+                "Unable to check the available disk space of '{}': {}",
+                dir.display(),
+                err
+            );
+            return Ok(());
+        }
+    };
+    let existing = output.metadata().map_or(0, |metadata| metadata.len());
+
+    if available.saturating_add(existing) < required {
+        Err(WebError::InsufficientSpace {
+            path: output.to_owned(),
+            required,
+            available,
+        })
+    } else {
+        Ok(())
+    }
 }
 
 fn get_from_url(url: &Url) -> Option<String> {
@@ -142,32 +523,7 @@ impl WebResponse for BinaryResponse {
     /// The `output` argument will be combined with the previously set work
     /// directory.
     fn read(self, output: Option<&str>) -> Result<Self::ResponseContent, WebError> {
-        let output = if let Some(output) = output {
-            output.into()
-        } else {
-            self.file_name()
-                .ok_or_else(|| WebError::Other("Unable to extract file name request".into()))?
-        };
-
-        let output = self.work_dir.join(output);
-
-        let mut response = self.response;
-
-        info!("Downloading '{}' to '{}'", self.url, output.display());
-
-        let file = File::create(output.clone()).map_err(WebError::IoError)?;
-        let mut writer = BufWriter::new(&file);
-
-        match response.copy_to(&mut writer) {
-            Err(err) => {
-                warn!("Failed to download '{}'", self.url);
-                Err(WebError::Request(err))
-            }
-            Ok(_) => {
-                info!("Successfully downloaded '{}'", output.display());
-                Ok(output)
-            }
-        }
+        self.read_with_inspector(output, &mut std::io::sink())
     }
 }
 
@@ -242,6 +598,39 @@ mod tests {
         assert_eq!(file_name, Some(expected.into()))
     }
 
+    #[rstest(
+        range,
+        expected,
+        case("bytes 0-0/4096", Some(4096)),
+        case("bytes 0-0/*", None),
+        case("invalid", None)
+    )]
+    fn get_range_length_should_return_complete_length(range: &str, expected: Option<u64>) {
+        assert_eq!(get_range_length(range), expected);
+    }
+
+    #[rstest(
+        range,
+        expected,
+        case("bytes 512-1023/1024", Some(512)),
+        case("bytes 0-0/*", Some(0)),
+        case("bytes */1024", None),
+        case("invalid", None)
+    )]
+    fn get_range_start_should_return_first_byte(range: &str, expected: Option<u64>) {
+        assert_eq!(get_range_start(range), expected);
+    }
+
+    #[test]
+    fn part_file_path_should_append_part_extension() {
+        let path = PathBuf::from("downloads").join("setup.exe");
+
+        assert_eq!(
+            part_file_path(&path),
+            PathBuf::from("downloads").join("setup.exe.part")
+        );
+    }
+
     #[test]
     fn get_from_url_should_return_none_on_no_file_name() {
         let url = Url::parse("https://www.codeblocks.org/downloads/binaries/").unwrap();
@@ -275,4 +664,47 @@ mod tests {
 
         let _ = std::fs::remove_file(expected);
     }
+
+    #[test]
+    fn check_available_space_should_succeed_when_enough_space_is_available() {
+        let work_dir = std::env::temp_dir();
+        let output = work_dir.join("aer-space-test.bin");
+
+        let actual = check_available_space(&work_dir, &output, 0);
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn check_available_space_should_error_when_not_enough_space_is_available() {
+        let work_dir = std::env::temp_dir();
+        let output = work_dir.join("aer-space-test.bin");
+
+        let actual = check_available_space(&work_dir, &output, u64::MAX);
+
+        assert!(matches!(
+            actual,
+            Err(WebError::InsufficientSpace { required, .. }) if required == u64::MAX
+        ));
+    }
+
+    #[test]
+    fn read_with_inspector_should_pass_downloaded_content_to_inspector() {
+        let work_dir = std::env::temp_dir();
+        let request = WebRequest::create();
+        let mut response = request
+            .get_binary_response("https://httpbin.org/bytes/512", None, None)
+            .unwrap();
+        response.set_work_dir(&work_dir);
+        let mut inspected = vec![];
+        let path = response
+            .read_with_inspector(Some("aer-inspector-test.bin"), &mut inspected)
+            .unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(inspected.len(), 512);
+        assert_eq!(inspected, content);
+    }
 }
@@ -1,16 +1,35 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::Chars;
+
 use aer_version::Versions;
+use log::{info, warn};
 use regex::{Captures, Regex};
 use reqwest::blocking::Response;
-use reqwest::{header, Url};
+use reqwest::{header, StatusCode, Url};
 use select::document::Document;
-use select::predicate::Name;
+use select::node::Node;
+use select::predicate::{Any, Name};
 
-use crate::response::{WebError, MIME_TYPES};
+use crate::cache::{CacheEntry, ResponseCache};
+use crate::response::{get_link_type, WebError, MIME_TYPES};
 use crate::{LinkElement, LinkType, WebResponse};
 
+/// The url schemes of links that should not be returned when reading the
+/// links of a html page.
+const SKIPPED_SCHEMES: &[&str] = &["mailto", "javascript"];
+
+/// The regular expression used to find the version on a page when no regular
+/// expression have been specified in the [VersionSelector].
+const DEFAULT_VERSION_REGEX: &str = r"\d+(?:\.\d+)+(?:-[0-9A-Za-z\.\-]+)?";
+
+/// The maximum number of links on a html page that are processed when no
+/// other maximum have been configured.
+pub const DEFAULT_MAX_LINKS: usize = 5000;
+
 /// Contains functions and structure for holding a single html response, and
 /// extracting any necessary information out of the html page.
 ///
@@ -19,13 +38,445 @@ use crate::{LinkElement, LinkType, WebResponse};
 #[derive(Debug)]
 pub struct HtmlResponse {
     response: Response,
+    /// The cache to store the body in, and the url it should be stored for.
+    cache: Option<(ResponseCache, Url)>,
+    cached: Option<CacheEntry>,
+    version_selector: Option<VersionSelector>,
+    link_selector: Option<String>,
+    /// The elements and attributes that links are read from in addition to
+    /// anchors.
+    link_sources: Vec<LinkSource>,
+    /// The maximum number of links on the page that will be processed.
+    max_links: usize,
+}
+
+/// Describes the element of a html page that contains the version of the
+/// software, for pages where the version is not part of the download links
+/// (*ie: when the version is only shown in the title or a heading*). Every
+/// link without a version will inherit the version found in the element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionSelector {
+    /// A simple css selector matching the element containing the version.
+    /// The selector may contain an element name, an id, classes and
+    /// attributes (*ie: `title`, `h1.version`, `#version` or
+    /// `meta[name=version]`*), but combinators are not supported.
+    pub selector: String,
+    /// The attribute of the element that contains the version. The text of
+    /// the element is used when no attribute is specified, except for `meta`
+    /// elements where the `content` attribute is used.
+    pub attribute: Option<String>,
+    /// The regular expression used to extract the version, either using the
+    /// `version` capture group or the whole match. Defaults to matching the
+    /// first value that looks like a version (*ie: `3.2.1`*).
+    pub regex: Option<String>,
+}
+
+impl VersionSelector {
+    /// Creates a new version selector for the elements matching the specified
+    /// `selector`.
+    pub fn new(selector: &str) -> VersionSelector {
+        VersionSelector {
+            selector: selector.into(),
+            attribute: None,
+            regex: None,
+        }
+    }
+}
+
+/// An element and attribute pair that links are read from in addition to the
+/// `href` attribute of anchors, for pages where the download links are not
+/// exposed as anchors (*ie: `button@data-url` or `source@src`*).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkSource {
+    /// The name of the element containing the link.
+    pub element: String,
+    /// The attribute of the element containing the link.
+    pub attribute: String,
+}
+
+impl LinkSource {
+    /// Creates a new link source reading the links from the specified
+    /// `attribute` of the elements with the specified name.
+    pub fn new(element: &str, attribute: &str) -> LinkSource {
+        LinkSource {
+            element: element.trim().to_lowercase(),
+            attribute: attribute.trim().to_lowercase(),
+        }
+    }
+}
+
+/// The parsed representation of the selector in a [VersionSelector].
+#[derive(Debug, Default, PartialEq)]
+struct SimpleSelector {
+    name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl SimpleSelector {
+    fn parse(selector: &str) -> Result<SimpleSelector, WebError> {
+        let invalid = || WebError::Other(format!("The selector '{}' is not supported!", selector));
+        let mut result = SimpleSelector::default();
+        let mut chars = selector.trim().chars().peekable();
+
+        let name = read_ident(&mut chars);
+        if !name.is_empty() {
+            result.name = Some(name.to_lowercase());
+        }
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '#' | '.' => {
+                    let ident = read_ident(&mut chars);
+                    if ident.is_empty() {
+                        return Err(invalid());
+                    } else if ch == '#' {
+                        result.id = Some(ident);
+                    } else {
+                        result.classes.push(ident);
+                    }
+                }
+                '[' => {
+                    let mut content = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(ch) => content.push(ch),
+                            None => return Err(invalid()),
+                        }
+                    }
+                    let mut parts = content.splitn(2, '=');
+                    let name = parts.next().unwrap_or_default().trim().to_lowercase();
+                    if name.is_empty() {
+                        return Err(invalid());
+                    }
+                    let value = parts.next().map(|value| {
+                        value
+                            .trim()
+                            .trim_matches(|c: char| c == '"' || c == '\'')
+                            .into()
+                    });
+                    result.attributes.push((name, value));
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        if result == SimpleSelector::default() {
+            Err(invalid())
+        } else {
+            Ok(result)
+        }
+    }
+
+    fn matches(&self, node: &Node<'_>) -> bool {
+        let name = match node.name() {
+            Some(name) => name,
+            None => return false,
+        };
+
+        if self.name.as_ref().map_or(false, |n| n != name)
+            || (self.id.is_some() && node.attr("id") != self.id.as_deref())
+        {
+            return false;
+        }
+
+        let classes: Vec<&str> = node
+            .attr("class")
+            .map(|classes| classes.split_whitespace().collect())
+            .unwrap_or_default();
+
+        self.classes
+            .iter()
+            .all(|class| classes.contains(&class.as_str()))
+            && self
+                .attributes
+                .iter()
+                .all(|(name, value)| match (node.attr(name), value) {
+                    (Some(actual), Some(expected)) => actual == expected,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                })
+    }
+}
+
+/// A selector made up of simple selectors separated by whitespace, where
+/// each simple selector must match an ancestor of the element matched by the
+/// following simple selector (*ie: `table.downloads a`*).
+#[derive(Debug, PartialEq)]
+struct DescendantSelector {
+    parts: Vec<SimpleSelector>,
+}
+
+impl DescendantSelector {
+    fn parse(selector: &str) -> Result<DescendantSelector, WebError> {
+        let mut parts = vec![];
+        let mut part = String::new();
+        let mut in_attribute = false;
+
+        for ch in selector.trim().chars() {
+            match ch {
+                '[' => in_attribute = true,
+                ']' => in_attribute = false,
+                _ => {}
+            }
+
+            if ch.is_whitespace() && !in_attribute {
+                if !part.is_empty() {
+                    parts.push(SimpleSelector::parse(&part)?);
+                    part.clear();
+                }
+            } else {
+                part.push(ch);
+            }
+        }
+
+        if part.is_empty() && parts.is_empty() {
+            return Err(WebError::Other(format!(
+                "The selector '{}' is not supported!",
+                selector
+            )));
+        } else if !part.is_empty() {
+            parts.push(SimpleSelector::parse(&part)?);
+        }
+
+        Ok(DescendantSelector { parts })
+    }
+
+    fn matches(&self, node: &Node<'_>) -> bool {
+        let mut parts = self.parts.iter().rev();
+
+        match parts.next() {
+            Some(last) if last.matches(node) => {}
+            _ => return false,
+        }
+
+        let mut ancestor = node.parent();
+        for part in parts {
+            loop {
+                match ancestor {
+                    Some(current) => {
+                        ancestor = current.parent();
+                        if part.matches(&current) {
+                            break;
+                        }
+                    }
+                    None => return false,
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Checks wether the specified node, or any of its ancestors, is matched
+    /// by the selector.
+    fn contains(&self, node: &Node<'_>) -> bool {
+        let mut current = Some(*node);
+
+        while let Some(node) = current {
+            if self.matches(&node) {
+                return true;
+            }
+            current = node.parent();
+        }
+
+        false
+    }
+}
+
+fn read_ident(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut ident = String::new();
+
+    while let Some(ch) = chars.peek() {
+        if ch.is_alphanumeric() || *ch == '-' || *ch == '_' {
+            ident.push(*ch);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+
+    ident
+}
+
+/// The part of a link that a regular expression should be matched against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchTarget {
+    /// Matches the regular expression against the target (`href`) of the link.
+    Link,
+    /// Matches the regular expression against the inner text of the link.
+    Text,
+    /// Matches the regular expression against the target (`href`) of the
+    /// link, after any percent-encoded characters have been decoded.
+    DecodedLink,
+}
+
+impl MatchTarget {
+    /// Returns the part of the link that a regular expression should be
+    /// matched against.
+    pub fn value<'a>(&self, link: &'a LinkElement) -> Cow<'a, str> {
+        match self {
+            Self::Link => Cow::Borrowed(link.link.as_str()),
+            Self::Text => Cow::Borrowed(&link.text),
+            Self::DecodedLink => link.decoded_link(),
+        }
+    }
+}
+
+impl Default for MatchTarget {
+    fn default() -> Self {
+        Self::Link
+    }
 }
 
 impl HtmlResponse {
     /// Creates a new instance of the [HtmlResponse] structe to hold the current
     /// response, and allow reading the content from that response.
     pub fn new(response: Response) -> HtmlResponse {
-        HtmlResponse { response }
+        HtmlResponse {
+            response,
+            cache: None,
+            cached: None,
+            version_selector: None,
+            link_selector: None,
+            link_sources: vec![],
+            max_links: DEFAULT_MAX_LINKS,
+        }
+    }
+
+    /// Sets the maximum number of links on the page that will be processed
+    /// when the response is read, instead of the default of 5000 links. Any
+    /// links after the maximum are ignored, and a warning is logged.
+    pub fn with_max_links(mut self, max_links: usize) -> HtmlResponse {
+        self.max_links = max_links;
+        self
+    }
+
+    /// Sets the css selector that the links on the page are limited to when
+    /// the response is read. Only links matching the selector, or links
+    /// inside an element matching the selector are returned (*ie:
+    /// `table.downloads a` or `#downloads`*).
+    ///
+    /// Every part of the selector may contain an element name, an id,
+    /// classes and attributes, and the parts are separated by whitespace to
+    /// match descendant elements. Other combinators are not supported.
+    pub fn with_link_selector(mut self, selector: &str) -> HtmlResponse {
+        self.link_selector = Some(selector.into());
+        self
+    }
+
+    /// Sets the elements and attributes that links are read from when the
+    /// response is read, in addition to the `href` attribute of anchors. The
+    /// regular expression is matched against these links in the same way as
+    /// for anchors.
+    pub fn with_link_sources(mut self, sources: Vec<LinkSource>) -> HtmlResponse {
+        self.link_sources = sources;
+        self
+    }
+
+    /// Sets the selector used to find the version on the page when the
+    /// response is read. The found version is used for every link that do
+    /// not have a version.
+    pub fn with_version_selector(mut self, selector: VersionSelector) -> HtmlResponse {
+        self.version_selector = Some(selector);
+        self
+    }
+
+    /// Sets the cache that the body of the response will be stored in for the
+    /// requested url when read, and the entry previously stored in the cache
+    /// for the same url. The body of the cached entry is used when the server
+    /// responds with a not modified status.
+    pub(crate) fn with_cache(
+        mut self,
+        cache: ResponseCache,
+        url: Url,
+        cached: Option<CacheEntry>,
+    ) -> HtmlResponse {
+        self.cache = Some((cache, url));
+        self.cached = cached;
+        self
+    }
+
+    /// Reads the current response the same way as [read](HtmlResponse::read),
+    /// except that the regular expression is matched against the specified
+    /// `target` of each link. Any version captured by the regular expression
+    /// will be parsed from the same target.
+    pub fn read_matching(
+        self,
+        re: Option<&str>,
+        target: MatchTarget,
+    ) -> Result<<Self as WebResponse>::ResponseContent, WebError> {
+        let response_url = self.response.url().clone();
+
+        let mut parent_link = get_parent_link_element(&self);
+        let version_selector = self.version_selector;
+        let link_selector = self.link_selector;
+        let link_sources = self.link_sources;
+        let max_links = self.max_links;
+
+        let body = match self.cached {
+            Some(cached) if self.response.status() == StatusCode::NOT_MODIFIED => {
+                info!("Using cached response for '{}'", response_url);
+                parent_link.link_type = LinkType::Html;
+                cached.body
+            }
+            _ => {
+                let etag = get_header(&self.response, header::ETAG);
+                let last_modified = get_header(&self.response, header::LAST_MODIFIED);
+                let body = self.response.text().map_err(WebError::Request)?;
+
+                if let Some((cache, url)) = self.cache {
+                    if etag.is_some() || last_modified.is_some() {
+                        let entry = CacheEntry {
+                            url: url.to_string(),
+                            etag,
+                            last_modified,
+                            body,
+                        };
+                        if let Err(err) = cache.store(&entry) {
+                            warn!(
+                                "Unable to cache the response of '{}': {}",
+                                response_url, err
+                            );
+                        }
+                        entry.body
+                    } else {
+                        body
+                    }
+                } else {
+                    body
+                }
+            }
+        };
+        let page_version = match version_selector {
+            Some(ref selector) => get_page_version(&body, selector)?,
+            None => None,
+        };
+        let mut links = get_link_elements(
+            body,
+            response_url,
+            re,
+            target,
+            link_selector.as_deref(),
+            &link_sources,
+            max_links,
+        )?;
+
+        if let Some(version) = page_version {
+            info!("Found the version {} on the page", version);
+            for link in links.iter_mut().filter(|link| link.version.is_none()) {
+                link.version = Some(version.clone());
+            }
+        } else if let Some(ref selector) = version_selector {
+            warn!(
+                "No version was found in the elements matching '{}'",
+                selector.selector
+            );
+        }
+
+        Ok((parent_link, links))
     }
 }
 
@@ -46,17 +497,18 @@ impl WebResponse for HtmlResponse {
     /// response do not have a successful status code, or if the reading of the
     /// body fails.
     fn read(self, re: Option<&str>) -> Result<Self::ResponseContent, WebError> {
-        let response_url = self.response.url().clone();
-
-        let parent_link = get_parent_link_element(&self);
-
-        let body = self.response.text().map_err(WebError::Request)?;
-        let links = get_link_elements(body, response_url, re)?;
-
-        Ok((parent_link, links))
+        self.read_matching(re, MatchTarget::Link)
     }
 }
 
+fn get_header(response: &Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
 fn get_parent_link_element<T: WebResponse>(content: &T) -> LinkElement {
     let headers = content.get_headers();
     let url = content.response().url();
@@ -77,8 +529,13 @@ fn get_link_elements(
     text: String,
     parent_url: Url,
     re: Option<&str>,
+    target: MatchTarget,
+    selector: Option<&str>,
+    sources: &[LinkSource],
+    max_links: usize,
 ) -> Result<Vec<LinkElement>, WebError> {
     let document = Document::from(text.as_str());
+    let selector = selector.map(DescendantSelector::parse).transpose()?;
 
     let re = if let Some(re) = re {
         Some(Regex::new(&re).map_err(|err| WebError::Other(err.to_string()))?)
@@ -86,41 +543,51 @@ fn get_link_elements(
         None
     };
 
-    let results = document
-        .find(Name("a"))
-        .filter_map(|n| {
+    let base_url = get_base_url(&document, parent_url.clone());
+
+    let mut anchors = document
+        .find(Any)
+        .filter_map(|n| get_link_attribute(&n, sources).map(|attribute| (n, attribute)))
+        .filter(|(n, _)| {
+            selector
+                .as_ref()
+                .map_or(true, |selector| selector.contains(n))
+        });
+    let results = anchors
+        .by_ref()
+        .take(max_links)
+        .filter_map(|(n, attribute)| {
             let mut link = {
-                let href = match n.attr("href") {
-                    Some(n) => {
-                        if n.is_empty() {
-                            return None;
-                        } else {
-                            n
-                        }
-                    }
+                let href = match n.attr(attribute) {
+                    Some(n) => n.trim(),
                     _ => return None,
                 };
 
-                let href =
-                    if href.starts_with('/') || href.starts_with('.') || href.starts_with('#') {
-                        parent_url.join(&href)
-                    } else {
-                        Url::parse(href)
-                    }
-                    .ok()?;
+                if href.is_empty() || href.starts_with('#') {
+                    return None;
+                }
+
+                let href = base_url.join(href).ok()?;
+                if SKIPPED_SCHEMES.contains(&href.scheme()) {
+                    return None;
+                }
+
                 LinkElement::new(href, LinkType::Unknown)
             };
 
+            link.text = n.text().trim().into();
+
             if let Some(re) = &re {
-                let capture = re.captures(link.link.as_str())?;
-                link.version = parse_version(capture);
+                let version = {
+                    let value = target.value(&link);
+                    parse_version(re.captures(&value)?)
+                };
+                link.version = version;
             }
 
-            link.text = n.text().trim().into();
-
             for (key, val) in n.attrs() {
                 let key = key.to_lowercase();
-                if key == "href" {
+                if key == attribute {
                     continue;
                 } else if key == "title" {
                     link.title = val.into();
@@ -129,34 +596,75 @@ fn get_link_elements(
                 }
             }
 
-            let path = link.link.path();
-            if path.ends_with(".html") {
-                link.link_type = LinkType::Html;
-            } else if path.ends_with(".json") {
-                link.link_type = LinkType::Json;
-            } else if path.ends_with(".css") {
-                link.link_type = LinkType::Css;
-            } else if path.ends_with(".txt") {
-                link.link_type = LinkType::Text;
-            } else if path.ends_with(".zip")
-                || path.ends_with(".7z")
-                || path.ends_with(".exe")
-                || path.ends_with(".msi")
-                || path.ends_with(".tar")
-                || path.ends_with(".tar.gz")
-                || path.ends_with(".tar.bz2")
-                || path.ends_with(".nupkg")
-            {
-                link.link_type = LinkType::Binary;
-            }
+            link.link_type = get_link_type(&link.link);
 
             Some(link)
         })
         .collect();
 
+    if anchors.next().is_some() {
+        warn!(
+            "The page '{}' contains more than {} links, only the first {} links are used!",
+            parent_url, max_links, max_links
+        );
+    }
+
     Ok(results)
 }
 
+/// Gets the name of the attribute containing the link of the specified node,
+/// when the node is an anchor or an element of one of the link sources.
+fn get_link_attribute<'a>(node: &Node<'_>, sources: &'a [LinkSource]) -> Option<&'a str> {
+    let name = node.name()?;
+
+    if name == "a" {
+        return Some("href");
+    }
+
+    sources
+        .iter()
+        .find(|source| source.element == name && node.attr(&source.attribute).is_some())
+        .map(|source| source.attribute.as_str())
+}
+
+/// Gets the version from the first element in the specified html `text` that
+/// is matched by the selector, and contains a value matching the regular
+/// expression of the selector.
+fn get_page_version(text: &str, selector: &VersionSelector) -> Result<Option<Versions>, WebError> {
+    let simple = SimpleSelector::parse(&selector.selector)?;
+    let re = Regex::new(selector.regex.as_deref().unwrap_or(DEFAULT_VERSION_REGEX))
+        .map_err(|err| WebError::Other(err.to_string()))?;
+    let document = Document::from(text);
+
+    let version = document
+        .find(Any)
+        .filter(|node| simple.matches(node))
+        .filter_map(|node| match selector.attribute {
+            Some(ref attribute) => node.attr(attribute).map(|value| value.to_string()),
+            None if node.name() == Some("meta") => node.attr("content").map(|v| v.to_string()),
+            None => Some(node.text()),
+        })
+        .find_map(|value| {
+            let captures = re.captures(&value)?;
+            let version = captures.name("version").or_else(|| captures.get(0))?;
+
+            Versions::parse(version.as_str().trim()).ok()
+        });
+
+    Ok(version)
+}
+
+/// Gets the url that all relative links should be resolved against. This is
+/// the url specified in the first `<base href>` element if one exists,
+/// otherwise the url of the response (*after any redirects*).
+fn get_base_url(document: &Document, parent_url: Url) -> Url {
+    document
+        .find(Name("base"))
+        .find_map(|n| n.attr("href"))
+        .and_then(|href| parent_url.join(href.trim()).ok())
+        .unwrap_or(parent_url)
+}
+
 fn parse_version(captures: Captures<'_>) -> Option<Versions> {
     Versions::parse(captures.name("version")?.as_str()).ok()
 }
@@ -228,6 +736,424 @@ mod tests {
         ])
     }
 
+    #[test]
+    fn read_matching_should_extract_version_from_link_text() {
+        let request = WebRequest::create();
+        let response = request
+            .get_html_response("https://github.com/MASGAU/MASGAU/releases/tag/v.1.0.6")
+            .unwrap();
+
+        let links = response
+            .read_matching(
+                Some(r"^MASGAU v\.(?P<version>[\d\.]+) for Windows$"),
+                MatchTarget::Text,
+            )
+            .unwrap()
+            .1;
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].text, "MASGAU v.1.0.6 for Windows");
+        assert_eq!(links[0].version, Some(Versions::parse("1.0.6").unwrap()));
+    }
+
+    #[test]
+    fn get_link_elements_should_match_against_link_text() {
+        let html = r#"<html><body>
+            <a href="/download?id=1">Download 32-bit (v3.2.1)</a>
+            <a href="/download?id=2">Download 64-bit (v3.2.1)</a>
+            <a href="/about">About</a>
+        </body></html>"#;
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            Some(r"64-bit \(v(?P<version>[\d\.]+)\)$"),
+            MatchTarget::Text,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert_eq!(
+            links,
+            [LinkElement {
+                link: Url::parse("https://example.org/download?id=2").unwrap(),
+                text: "Download 64-bit (v3.2.1)".into(),
+                version: Some(Versions::parse("3.2.1").unwrap()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn get_link_elements_should_not_match_link_text_when_targeting_link() {
+        let html = r#"<a href="/download?id=2">Download 64-bit (v3.2.1)</a>"#;
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            Some(r"64-bit"),
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert!(links.is_empty());
+    }
+
+    #[test]
+    fn get_link_elements_should_match_against_decoded_link() {
+        let html = include_str!("../../test-data/encoded-links.html");
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            Some(r"app-x64\.[a-z]+(?:&ver=|#version=)(?P<version>[\d\.]+)$"),
+            MatchTarget::DecodedLink,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|l| (l.link.to_string(), l.version))
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            links,
+            [
+                (
+                    "https://example.org/download?file=app-x64.exe&ver=3%2E2%2E1".into(),
+                    Some(Versions::parse("3.2.1").unwrap())
+                ),
+                (
+                    "https://example.org/app-x64.zip#version%3D3.1.0".into(),
+                    Some(Versions::parse("3.1.0").unwrap())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_link_elements_should_not_decode_link_when_targeting_link() {
+        let html = include_str!("../../test-data/encoded-links.html");
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            Some(r"&ver=(?P<version>[\d\.]+)$"),
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert!(links.is_empty());
+    }
+
+    #[test]
+    fn get_link_elements_should_resolve_relative_links() {
+        let html = include_str!("../../test-data/relative-links.html");
+        let parent = Url::parse("https://example.org/software/index.html").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            None,
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|l| l.link.to_string())
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            links,
+            [
+                "https://example.org/downloads/app-3.2.msi",
+                "https://example.org/software/app-3.2.zip",
+                "https://example.org/app-3.1.zip",
+                "https://cdn.example.org/app-3.2.exe",
+            ]
+        );
+    }
+
+    #[test]
+    fn get_link_elements_should_only_return_links_matching_selector() {
+        let html = r#"<html><body>
+            <a href="/news/app-4.0.0.exe">News</a>
+            <table class="downloads">
+                <tr><td><a href="/files/app-3.2.1.exe">Download</a></td></tr>
+            </table>
+            <table class="archive">
+                <tr><td><a href="/files/app-3.1.0.exe">Download</a></td></tr>
+            </table>
+        </body></html>"#;
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent.clone(),
+            None,
+            MatchTarget::Link,
+            Some("table.downloads a"),
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+        let container_links = get_link_elements(
+            html.into(),
+            parent,
+            None,
+            MatchTarget::Link,
+            Some("table.downloads"),
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].link.as_str(),
+            "https://example.org/files/app-3.2.1.exe"
+        );
+        assert_eq!(container_links, links);
+    }
+
+    #[test]
+    fn get_link_elements_should_return_error_on_unsupported_selector() {
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let result = get_link_elements(
+            "<a href=\"/\">Home</a>".into(),
+            parent,
+            None,
+            MatchTarget::Link,
+            Some("table > a"),
+            &[],
+            DEFAULT_MAX_LINKS,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn dedup_links_should_remove_duplicate_and_older_links() {
+        let html = include_str!("../../test-data/duplicate-links.html");
+        let parent = Url::parse("https://example.org/").unwrap();
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            Some(r"app-(?P<version>[\d\.]+)-x(86|64)\.exe$"),
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        let links: Vec<String> = crate::dedup_links(links)
+            .into_iter()
+            .map(|link| link.link.to_string())
+            .collect();
+
+        assert_eq!(
+            links,
+            [
+                "https://example.org/files/app-3.2.1-x64.exe",
+                "https://example.org/files/app-3.2.1-x86.exe"
+            ]
+        );
+    }
+
+    #[test]
+    fn dedup_links_should_keep_links_without_version_last() {
+        let html = include_str!("../../test-data/duplicate-links.html");
+        let parent = Url::parse("https://example.org/").unwrap();
+        let mut links = get_link_elements(
+            html.into(),
+            parent,
+            None,
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+        links[5].version = Some(Versions::parse("2.9.4").unwrap());
+
+        let links = crate::dedup_links(links);
+
+        assert_eq!(links.len(), 5);
+        assert_eq!(
+            links[0].link.as_str(),
+            "https://example.org/files/app-2.9.4-x86.exe"
+        );
+        assert_eq!(
+            links[4].link.as_str(),
+            "https://example.org/files/app-3.2.1-x86.exe"
+        );
+    }
+
+    #[test]
+    fn get_page_version_should_use_text_of_title() {
+        let html = "<html><head><title>App 3.2.1 - Download</title></head></html>";
+
+        let version = get_page_version(html, &VersionSelector::new("title")).unwrap();
+
+        assert_eq!(version, Some(Versions::parse("3.2.1").unwrap()));
+    }
+
+    #[test]
+    fn get_page_version_should_use_content_of_meta_element() {
+        let html = r#"<html><head>
+            <meta name="description" content="Version 1.0.0">
+            <meta name="version" content="4.5.0">
+            </head></html>"#;
+
+        let version = get_page_version(html, &VersionSelector::new("meta[name=version]")).unwrap();
+
+        assert_eq!(version, Some(Versions::parse("4.5.0").unwrap()));
+    }
+
+    #[test]
+    fn get_page_version_should_use_attribute_and_regex() {
+        let html = r#"<h1 class="release title" data-release="release-v2.1.0-beta">App</h1>"#;
+        let selector = VersionSelector {
+            selector: "h1.title".into(),
+            attribute: Some("data-release".into()),
+            regex: Some(r"v(?P<version>[\d\.]+)".into()),
+        };
+
+        let version = get_page_version(html, &selector).unwrap();
+
+        assert_eq!(version, Some(Versions::parse("2.1.0").unwrap()));
+    }
+
+    #[test]
+    fn get_page_version_should_return_none_without_matching_element() {
+        let html = "<html><body><h1>App 3.2.1</h1></body></html>";
+
+        let version = get_page_version(html, &VersionSelector::new("#version")).unwrap();
+
+        assert_eq!(version, None);
+    }
+
+    #[test]
+    fn get_page_version_should_return_error_on_unsupported_selector() {
+        let html = "<html></html>";
+
+        assert!(get_page_version(html, &VersionSelector::new("body > h1")).is_err());
+        assert!(get_page_version(html, &VersionSelector::new("meta[name")).is_err());
+    }
+
+    #[test]
+    fn get_link_elements_should_resolve_relative_links_against_base_element() {
+        let html = r#"<html><head><base href="https://mirror.example.org/files/"></head>
+            <body><a href="app-3.2.msi">Download</a></body></html>"#;
+        let parent = Url::parse("https://example.org/software/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            None,
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].link.as_str(),
+            "https://mirror.example.org/files/app-3.2.msi"
+        );
+    }
+
+    #[test]
+    fn get_link_elements_should_read_links_from_link_sources() {
+        let html = include_str!("../../test-data/link-sources.html");
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            Some(r"app-(?P<version>[\d\.]+)-"),
+            MatchTarget::Link,
+            None,
+            &[
+                LinkSource::new("button", "data-url"),
+                LinkSource::new("source", "src"),
+            ],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert_eq!(
+            links.iter().map(|l| l.link.to_string()).collect::<Vec<_>>(),
+            [
+                "https://example.org/app-3.2.1-x86.exe",
+                "https://example.org/app-3.2.1-x64.exe",
+                "https://cdn.example.org/app-3.2.1-demo.mp4",
+            ]
+        );
+        assert_eq!(links[1].text, "Download 64-bit");
+        assert_eq!(
+            links[1].attributes.get("class"),
+            Some(&"download".to_string())
+        );
+        assert!(!links[1].attributes.contains_key("data-url"));
+    }
+
+    #[test]
+    fn get_link_elements_should_only_read_anchors_by_default() {
+        let html = include_str!("../../test-data/link-sources.html");
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(
+            html.into(),
+            parent,
+            None,
+            MatchTarget::Link,
+            None,
+            &[],
+            DEFAULT_MAX_LINKS,
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].link.as_str(),
+            "https://example.org/app-3.2.1-x86.exe"
+        );
+    }
+
+    #[test]
+    fn get_link_elements_should_stop_processing_when_reaching_max_links() {
+        let html = r#"<a href="/1">1</a><a href="/2">2</a><a href="/3">3</a>"#;
+        let parent = Url::parse("https://example.org/").unwrap();
+
+        let links = get_link_elements(html.into(), parent, None, MatchTarget::Link, None, &[], 2)
+            .unwrap()
+            .into_iter()
+            .map(|l| l.link.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(links, ["https://example.org/1", "https://example.org/2"]);
+    }
+
     #[test]
     fn read_should_only_return_links_matching_specified_regex() {
         let request = WebRequest::create();
@@ -0,0 +1,395 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use aer_version::Versions;
+use regex::Regex;
+use reqwest::blocking::Response;
+use reqwest::Url;
+use serde_json::Value;
+
+use crate::response::{get_link_type, WebError};
+use crate::{LinkElement, LinkType, WebResponse};
+
+/// Contains functions and structure for holding a single json response, and
+/// extracting versioned links out of the json document.
+///
+/// The links are selected by using a simple JSONPath like selector, where
+/// each key is separated by a dot, and array items are selected by using
+/// brackets (*ie: `downloads.*`, `assets[*].url` or `$.files[0].url`*). A
+/// `*` selects all items in an array, or all values in an object.
+///
+/// Implements the [WebResponse] trait, and are not meant to be created directly
+/// by a user.
+#[derive(Debug)]
+pub struct JsonResponse {
+    response: Response,
+    selector: String,
+    version_field: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl JsonResponse {
+    /// Creates a new instance of the [JsonResponse] structure to hold the
+    /// current response, the `selector` used to find the links, and the
+    /// optional path to the field holding the version of the links.
+    pub fn new(response: Response, selector: &str, version_field: Option<&str>) -> JsonResponse {
+        JsonResponse {
+            response,
+            selector: selector.into(),
+            version_field: version_field.map(|field| field.into()),
+        }
+    }
+}
+
+impl WebResponse for JsonResponse {
+    /// Sets the response type that will be returned when calling the
+    /// [read](JsonResponse::read) function. The first item is the link the
+    /// response came from, and the second item holds a vector of the links
+    /// that were selected in the json document.
+    type ResponseContent = (LinkElement, Vec<LinkElement>);
+
+    fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Reads the current response, and extracts the links matching the
+    /// selector of the response. If a regular expression is specified, only
+    /// links matching the regular expression will be returned, and any
+    /// version captured by the regular expression will be used instead of
+    /// the version field.
+    ///
+    /// This function will return an error if the response is not a valid
+    /// json document, if the selector do not match any values, or if the
+    /// version field could not be found for one of the selected links.
+    fn read(self, re: Option<&str>) -> Result<Self::ResponseContent, WebError> {
+        let response_url = self.response.url().clone();
+        let parent_link = LinkElement::new(response_url.clone(), LinkType::Json);
+
+        let body = self.response.text().map_err(WebError::Request)?;
+        let links = get_json_links(
+            &body,
+            &response_url,
+            &self.selector,
+            self.version_field.as_deref(),
+            re,
+        )?;
+
+        Ok((parent_link, links))
+    }
+}
+
+fn get_json_links(
+    body: &str,
+    parent_url: &Url,
+    selector: &str,
+    version_field: Option<&str>,
+    re: Option<&str>,
+) -> Result<Vec<LinkElement>, WebError> {
+    let document: Value = serde_json::from_str(body).map_err(|err| WebError::NotJson {
+        url: parent_url.to_string(),
+        message: err.to_string(),
+    })?;
+    let segments = parse_selector(selector)?;
+    let version_segments = match version_field {
+        Some(field) => Some(parse_selector(field)?),
+        None => None,
+    };
+    let re = match re {
+        Some(re) => Some(Regex::new(re).map_err(|err| WebError::Other(err.to_string()))?),
+        None => None,
+    };
+
+    let selected: Vec<_> = select(&document, &segments)
+        .into_iter()
+        .filter_map(|(parent, value)| {
+            let url = parent_url.join(value.as_str()?.trim()).ok()?;
+            Some((parent, value, url))
+        })
+        .collect();
+
+    if selected.is_empty() {
+        return Err(WebError::NoSelectorMatch {
+            url: parent_url.to_string(),
+            selector: selector.into(),
+        });
+    }
+
+    let mut links = Vec::with_capacity(selected.len());
+
+    for (parent, value, url) in selected {
+        let mut link = LinkElement::new(url, LinkType::Unknown);
+        link.link_type = get_link_type(&link.link);
+        if let Some(Value::Object(map)) = parent {
+            if let Some((key, _)) = map.iter().find(|(_, v)| std::ptr::eq(*v, value)) {
+                link.text = key.clone();
+            }
+        }
+
+        if let Some(ref re) = re {
+            let capture = match re.captures(link.link.as_str()) {
+                Some(capture) => capture,
+                None => continue,
+            };
+            link.version = capture
+                .name("version")
+                .and_then(|version| Versions::parse(version.as_str()).ok());
+        }
+
+        if link.version.is_none() {
+            if let Some(ref version_segments) = version_segments {
+                let version = parent
+                    .and_then(|parent| select(parent, version_segments).into_iter().next())
+                    .or_else(|| select(&document, version_segments).into_iter().next())
+                    .map(|(_, version)| version);
+                link.version = match version {
+                    Some(Value::String(version)) => Versions::parse(version.trim()).ok(),
+                    Some(Value::Number(version)) => Versions::parse(&version.to_string()).ok(),
+                    _ => {
+                        return Err(WebError::MissingVersionField {
+                            url: parent_url.to_string(),
+                            field: version_field.unwrap_or_default().into(),
+                        });
+                    }
+                };
+            }
+        }
+
+        links.push(link);
+    }
+
+    Ok(links)
+}
+
+fn parse_selector(selector: &str) -> Result<Vec<Segment>, WebError> {
+    let invalid = |reason: &str| {
+        WebError::Other(format!(
+            "The selector '{}' is not valid: {}",
+            selector, reason
+        ))
+    };
+    let value = selector.trim();
+    let value = value.strip_prefix('$').unwrap_or(value);
+    let mut segments = vec![];
+    let mut key = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => push_key(&mut segments, &mut key),
+            '[' => {
+                push_key(&mut segments, &mut key);
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => index.push(c),
+                        None => return Err(invalid("missing closing bracket")),
+                    }
+                }
+                let index = index.trim();
+
+                if index == "*" {
+                    segments.push(Segment::Wildcard);
+                } else if index.len() > 1
+                    && (index.starts_with('\'') && index.ends_with('\'')
+                        || index.starts_with('"') && index.ends_with('"'))
+                {
+                    segments.push(Segment::Key(index[1..index.len() - 1].into()));
+                } else {
+                    let index = index
+                        .parse()
+                        .map_err(|_| invalid("the index must be a number, '*' or a quoted key"))?;
+                    segments.push(Segment::Index(index));
+                }
+            }
+            ']' => return Err(invalid("missing opening bracket")),
+            c => key.push(c),
+        }
+    }
+    push_key(&mut segments, &mut key);
+
+    Ok(segments)
+}
+
+fn push_key(segments: &mut Vec<Segment>, key: &mut String) {
+    let value = key.trim();
+
+    if value == "*" {
+        segments.push(Segment::Wildcard);
+    } else if !value.is_empty() {
+        segments.push(Segment::Key(value.into()));
+    }
+
+    key.clear();
+}
+
+/// Selects all values matching the specified segments, each selected value is
+/// returned together with the value that contains it (*if any*).
+fn select<'a>(root: &'a Value, segments: &[Segment]) -> Vec<(Option<&'a Value>, &'a Value)> {
+    let mut current = vec![(None, root)];
+
+    for segment in segments {
+        current = current
+            .into_iter()
+            .flat_map(|(_, value)| {
+                let children: Vec<&Value> = match (segment, value) {
+                    (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Segment::Index(index), Value::Array(items)) => {
+                        items.get(*index).into_iter().collect()
+                    }
+                    (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    _ => vec![],
+                };
+
+                children.into_iter().map(move |child| (Some(value), child))
+            })
+            .collect();
+    }
+
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const LATEST: &str = r#"{
+        "version": "3.2.1",
+        "downloads": {
+            "win32": "https://example.org/app-3.2.1-x86.exe",
+            "win64": "/app-3.2.1-x64.exe"
+        },
+        "releases": [
+            { "version": "3.2.1", "url": "https://example.org/app-3.2.1.zip" },
+            { "version": "3.1.0", "url": "https://example.org/app-3.1.0.zip" }
+        ]
+    }"#;
+
+    fn parent_url() -> Url {
+        Url::parse("https://example.org/latest.json").unwrap()
+    }
+
+    #[rstest]
+    #[case("downloads.*", vec![Segment::Key("downloads".into()), Segment::Wildcard])]
+    #[case("$.releases[*].url", vec![Segment::Key("releases".into()), Segment::Wildcard, Segment::Key("url".into())])]
+    #[case("releases[1]['url']", vec![Segment::Key("releases".into()), Segment::Index(1), Segment::Key("url".into())])]
+    #[case("$", vec![])]
+    fn parse_selector_should_create_expected_segments(
+        #[case] selector: &str,
+        #[case] expected: Vec<Segment>,
+    ) {
+        let actual = parse_selector(selector).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("releases[*")]
+    #[case("releases]")]
+    #[case("releases[first]")]
+    fn parse_selector_should_error_on_invalid_selector(#[case] selector: &str) {
+        let actual = parse_selector(selector);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn get_json_links_should_use_version_field_of_document() {
+        let links =
+            get_json_links(LATEST, &parent_url(), "downloads.*", Some("version"), None).unwrap();
+
+        assert_eq!(
+            links,
+            [
+                LinkElement {
+                    link: Url::parse("https://example.org/app-3.2.1-x86.exe").unwrap(),
+                    text: "win32".into(),
+                    version: Some(Versions::parse("3.2.1").unwrap()),
+                    link_type: LinkType::Binary,
+                    ..Default::default()
+                },
+                LinkElement {
+                    link: Url::parse("https://example.org/app-3.2.1-x64.exe").unwrap(),
+                    text: "win64".into(),
+                    version: Some(Versions::parse("3.2.1").unwrap()),
+                    link_type: LinkType::Binary,
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn get_json_links_should_prefer_version_field_of_containing_object() {
+        let links = get_json_links(
+            LATEST,
+            &parent_url(),
+            "releases[*].url",
+            Some("version"),
+            None,
+        )
+        .unwrap();
+        let versions: Vec<_> = links.into_iter().map(|l| l.version.unwrap()).collect();
+
+        assert_eq!(
+            versions,
+            [
+                Versions::parse("3.2.1").unwrap(),
+                Versions::parse("3.1.0").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn get_json_links_should_only_return_links_matching_regex() {
+        let links = get_json_links(
+            LATEST,
+            &parent_url(),
+            "releases[*].url",
+            None,
+            Some(r"app-(?P<version>3\.1[\d\.]*)\.zip$"),
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].version, Some(Versions::parse("3.1.0").unwrap()));
+    }
+
+    #[test]
+    fn get_json_links_should_error_on_invalid_json() {
+        let err =
+            get_json_links("<html></html>", &parent_url(), "downloads.*", None, None).unwrap_err();
+
+        assert!(matches!(err, WebError::NotJson { .. }));
+    }
+
+    #[test]
+    fn get_json_links_should_error_when_selector_matches_nothing() {
+        let err = get_json_links(LATEST, &parent_url(), "assets[*].url", None, None).unwrap_err();
+
+        assert!(matches!(err, WebError::NoSelectorMatch { .. }));
+    }
+
+    #[test]
+    fn get_json_links_should_error_on_missing_version_field() {
+        let err = get_json_links(
+            LATEST,
+            &parent_url(),
+            "downloads.*",
+            Some("release.version"),
+            None,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, WebError::MissingVersionField { .. }));
+    }
+}